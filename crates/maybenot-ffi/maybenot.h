@@ -18,6 +18,9 @@ enum MaybenotEventType {
   MaybenotEventType_BlockingEnd = 7,
   MaybenotEventType_TimerBegin = 8,
   MaybenotEventType_TimerEnd = 9,
+  MaybenotEventType_IncomingBlockingBegin = 10,
+  MaybenotEventType_IncomingBlockingEnd = 11,
+  MaybenotEventType_Idle = 12,
 };
 typedef uint32_t MaybenotEventType;
 
@@ -64,6 +67,14 @@ enum MaybenotTimer {
    * Apply to all timers.
    */
   MaybenotTimer_All = 2,
+  /**
+   * The scheduled timer for actions with a timeout, only if padding is pending.
+   */
+  MaybenotTimer_ActionPadding = 3,
+  /**
+   * The scheduled timer for actions with a timeout, only if blocking or holding is pending.
+   */
+  MaybenotTimer_ActionBlocking = 4,
 };
 typedef uint32_t MaybenotTimer;
 
@@ -82,6 +93,12 @@ typedef struct MaybenotEvent {
    * The ID of the machine that triggered the event, if any.
    */
   uintptr_t machine;
+  /**
+   * The blocking lane of the event, in `[0, 4)`, for `BlockingBegin` and `BlockingEnd`: the
+   * lane of the `BlockOutgoing` action that started the blocking. Ignored for other events.
+   * Integrations without classes of outgoing traffic use lane 0 for all traffic.
+   */
+  uint8_t lane;
 } MaybenotEvent;
 
 typedef struct MaybenotDuration {
@@ -115,6 +132,14 @@ enum MaybenotAction_Tag {
    * Update the timer duration for a machine.
    */
   MaybenotAction_UpdateTimer = 3,
+  /**
+   * Schedule blocking of incoming traffic after the given timeout for a machine.
+   */
+  MaybenotAction_BlockIncoming = 4,
+  /**
+   * Schedule holding of outgoing traffic after the given timeout for a machine.
+   */
+  MaybenotAction_HoldOutgoing = 5,
 };
 typedef uint32_t MaybenotAction_Tag;
 
@@ -154,6 +179,11 @@ typedef struct MaybenotAction_BlockOutgoing_Body {
    * How long to block.
    */
   struct MaybenotDuration duration;
+  /**
+   * The lane of outgoing traffic to block, in `[0, 4)`: the integration maps each lane to a
+   * class of outgoing traffic, blocked independently of the others.
+   */
+  uint8_t lane;
 } MaybenotAction_BlockOutgoing_Body;
 
 typedef struct MaybenotAction_UpdateTimer_Body {
@@ -162,6 +192,41 @@ typedef struct MaybenotAction_UpdateTimer_Body {
   bool replace;
 } MaybenotAction_UpdateTimer_Body;
 
+typedef struct MaybenotAction_BlockIncoming_Body {
+  /**
+   * The machine that generated the action.
+   */
+  uintptr_t machine;
+  /**
+   * The time to wait before blocking.
+   */
+  struct MaybenotDuration timeout;
+  bool replace;
+  /**
+   * How long to block.
+   */
+  struct MaybenotDuration duration;
+} MaybenotAction_BlockIncoming_Body;
+
+typedef struct MaybenotAction_HoldOutgoing_Body {
+  /**
+   * The machine that generated the action.
+   */
+  uintptr_t machine;
+  /**
+   * The time to wait before holding.
+   */
+  struct MaybenotDuration timeout;
+  /**
+   * How long to hold.
+   */
+  struct MaybenotDuration duration;
+  /**
+   * The probability of holding each outgoing non-padding packet.
+   */
+  double probability;
+} MaybenotAction_HoldOutgoing_Body;
+
 typedef struct MaybenotAction {
   MaybenotAction_Tag tag;
   union {
@@ -169,6 +234,8 @@ typedef struct MaybenotAction {
     MaybenotAction_SendPadding_Body send_padding;
     MaybenotAction_BlockOutgoing_Body block_outgoing;
     MaybenotAction_UpdateTimer_Body update_timer;
+    MaybenotAction_BlockIncoming_Body block_incoming;
+    MaybenotAction_HoldOutgoing_Body hold_outgoing;
   };
 } MaybenotAction;
 
@@ -42,6 +42,11 @@ pub struct MaybenotEvent {
 
     /// The ID of the machine that triggered the event, if any.
     pub machine: usize,
+
+    /// The blocking lane of the event, in `[0, 4)`, for `BlockingBegin` and `BlockingEnd`: the
+    /// lane of the `BlockOutgoing` action that started the blocking. Ignored for other events.
+    /// Integrations without classes of outgoing traffic use lane 0 for all traffic.
+    pub lane: u8,
 }
 
 #[repr(C)]
@@ -71,6 +76,11 @@ pub enum MaybenotEventType {
 
     TimerBegin = 8,
     TimerEnd = 9,
+
+    IncomingBlockingBegin = 10,
+    IncomingBlockingEnd = 11,
+
+    Idle = 12,
 }
 
 /// The action to be taken by the framework user.
@@ -110,6 +120,10 @@ pub enum MaybenotAction {
 
         /// How long to block.
         duration: MaybenotDuration,
+
+        /// The lane of outgoing traffic to block, in `[0, 4)`: the integration maps each lane to a
+        /// class of outgoing traffic, blocked independently of the others.
+        lane: u8,
     } = 2,
 
     /// Update the timer duration for a machine.
@@ -120,6 +134,35 @@ pub enum MaybenotAction {
 
         replace: bool,
     } = 3,
+
+    /// Schedule blocking of incoming traffic after the given timeout for a machine.
+    BlockIncoming {
+        /// The machine that generated the action.
+        machine: usize,
+
+        /// The time to wait before blocking.
+        timeout: MaybenotDuration,
+
+        replace: bool,
+
+        /// How long to block.
+        duration: MaybenotDuration,
+    } = 4,
+
+    /// Schedule holding of outgoing traffic after the given timeout for a machine.
+    HoldOutgoing {
+        /// The machine that generated the action.
+        machine: usize,
+
+        /// The time to wait before holding.
+        timeout: MaybenotDuration,
+
+        /// How long to hold.
+        duration: MaybenotDuration,
+
+        /// The probability of holding each outgoing non-padding packet.
+        probability: f64,
+    } = 5,
 }
 
 /// The different types of timers used by a [Machine].
@@ -135,6 +178,12 @@ pub enum MaybenotTimer {
 
     /// Apply to all timers.
     All = 2,
+
+    /// The scheduled timer for actions with a timeout, only if padding is pending.
+    ActionPadding = 3,
+
+    /// The scheduled timer for actions with a timeout, only if blocking or holding is pending.
+    ActionBlocking = 4,
 }
 
 impl MaybenotFramework {
@@ -220,12 +269,14 @@ fn convert_action(action: &maybenot::TriggerAction) -> MaybenotAction {
             duration,
             bypass,
             replace,
+            lane,
             machine,
         } => MaybenotAction::BlockOutgoing {
             timeout: timeout.into(),
             duration: duration.into(),
             replace,
             bypass,
+            lane,
             machine: machine.into_raw(),
         },
         maybenot::TriggerAction::UpdateTimer {
@@ -237,6 +288,28 @@ fn convert_action(action: &maybenot::TriggerAction) -> MaybenotAction {
             replace,
             machine: machine.into_raw(),
         },
+        maybenot::TriggerAction::BlockIncoming {
+            timeout,
+            duration,
+            replace,
+            machine,
+        } => MaybenotAction::BlockIncoming {
+            timeout: timeout.into(),
+            duration: duration.into(),
+            replace,
+            machine: machine.into_raw(),
+        },
+        maybenot::TriggerAction::HoldOutgoing {
+            timeout,
+            duration,
+            probability,
+            machine,
+        } => MaybenotAction::HoldOutgoing {
+            timeout: timeout.into(),
+            duration: duration.into(),
+            probability,
+            machine: machine.into_raw(),
+        },
     }
 }
 
@@ -252,11 +325,19 @@ fn convert_event(event: MaybenotEvent) -> TriggerEvent {
         MaybenotEventType::PaddingSent => TriggerEvent::PaddingSent { machine },
         MaybenotEventType::TunnelSent => TriggerEvent::TunnelSent,
 
-        MaybenotEventType::BlockingBegin => TriggerEvent::BlockingBegin { machine },
-        MaybenotEventType::BlockingEnd => TriggerEvent::BlockingEnd,
+        MaybenotEventType::BlockingBegin => TriggerEvent::BlockingBegin {
+            machine,
+            lane: event.lane,
+        },
+        MaybenotEventType::BlockingEnd => TriggerEvent::BlockingEnd { lane: event.lane },
 
         MaybenotEventType::TimerBegin => TriggerEvent::TimerBegin { machine },
         MaybenotEventType::TimerEnd => TriggerEvent::TimerEnd { machine },
+
+        MaybenotEventType::IncomingBlockingBegin => TriggerEvent::IncomingBlockingBegin { machine },
+        MaybenotEventType::IncomingBlockingEnd => TriggerEvent::IncomingBlockingEnd,
+
+        MaybenotEventType::Idle => TriggerEvent::Idle,
     }
 }
 
@@ -276,6 +357,8 @@ impl From<maybenot::Timer> for MaybenotTimer {
             maybenot::Timer::Action => MaybenotTimer::Action,
             maybenot::Timer::Internal => MaybenotTimer::Internal,
             maybenot::Timer::All => MaybenotTimer::All,
+            maybenot::Timer::ActionPadding => MaybenotTimer::ActionPadding,
+            maybenot::Timer::ActionBlocking => MaybenotTimer::ActionBlocking,
         }
     }
 }
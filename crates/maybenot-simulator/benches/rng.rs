@@ -147,6 +147,7 @@ fn sample_uniform<R: RngCore>(rng: &mut R, n: usize) {
         },
         start: 20.0 * 1000.0,
         max: 0.0,
+        ..Default::default()
     };
 
     for _ in 0..n {
@@ -162,6 +163,7 @@ fn sample_normal<R: RngCore>(rng: &mut R, n: usize) {
         },
         start: 20.0 * 1000.0,
         max: 0.0,
+        ..Default::default()
     };
 
     for _ in 0..n {
@@ -178,6 +180,7 @@ fn sample_skew_normal<R: RngCore>(rng: &mut R, n: usize) {
         },
         start: 20.0 * 1000.0,
         max: 0.0,
+        ..Default::default()
     };
 
     for _ in 0..n {
@@ -193,6 +196,7 @@ fn sample_log_normal<R: RngCore>(rng: &mut R, n: usize) {
         },
         start: 20.0 * 1000.0,
         max: 0.0,
+        ..Default::default()
     };
 
     for _ in 0..n {
@@ -208,6 +212,7 @@ fn sample_binomial<R: RngCore>(rng: &mut R, n: usize) {
         },
         start: 20.0 * 1000.0,
         max: 0.0,
+        ..Default::default()
     };
 
     for _ in 0..n {
@@ -220,6 +225,7 @@ fn sample_geometric<R: RngCore>(rng: &mut R, n: usize) {
         dist: DistType::Geometric { probability: 0.5 },
         start: 20.0 * 1000.0,
         max: 0.0,
+        ..Default::default()
     };
 
     for _ in 0..n {
@@ -235,6 +241,7 @@ fn sample_pareto<R: RngCore>(rng: &mut R, n: usize) {
         },
         start: 20.0 * 1000.0,
         max: 0.0,
+        ..Default::default()
     };
 
     for _ in 0..n {
@@ -247,6 +254,7 @@ fn sample_poisson<R: RngCore>(rng: &mut R, n: usize) {
         dist: DistType::Poisson { lambda: 2.0 },
         start: 20.0 * 1000.0,
         max: 0.0,
+        ..Default::default()
     };
 
     for _ in 0..n {
@@ -262,6 +270,7 @@ fn sample_weibull<R: RngCore>(rng: &mut R, n: usize) {
         },
         start: 20.0 * 1000.0,
         max: 0.0,
+        ..Default::default()
     };
 
     for _ in 0..n {
@@ -277,6 +286,7 @@ fn sample_gamma<R: RngCore>(rng: &mut R, n: usize) {
         },
         start: 20.0 * 1000.0,
         max: 0.0,
+        ..Default::default()
     };
 
     for _ in 0..n {
@@ -292,6 +302,7 @@ fn sample_beta<R: RngCore>(rng: &mut R, n: usize) {
         },
         start: 20.0 * 1000.0,
         max: 0.0,
+        ..Default::default()
     };
 
     for _ in 0..n {
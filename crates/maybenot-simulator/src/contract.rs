@@ -0,0 +1,136 @@
+//! Checks of the contracts between the framework and its integration in the
+//! simulator, enabled by
+//! [`SimulatorArgs::check_contracts`](crate::SimulatorArgs::check_contracts).
+//! A violation is a bug in a machine, the framework, the simulator, or the
+//! events put in the queue: it panics, describing the violation.
+
+use std::{fmt, time::Duration};
+
+use maybenot::{
+    constants::{
+        BLOCKING_LANES, MAX_SAMPLED_BLOCK_DURATION, MAX_SAMPLED_TIMEOUT, MAX_SAMPLED_TIMER_DURATION,
+    },
+    Machine, TriggerAction, TriggerEvent,
+};
+
+use crate::{RngSource, SimState};
+
+fn violation(is_client: bool, msg: impl fmt::Display) -> ! {
+    let side = if is_client { "client" } else { "server" };
+    panic!("contract violation @{}: {}", side, msg);
+}
+
+/// Check an event before it is triggered: it must refer to an existing
+/// machine and lane.
+pub(crate) fn check_event<M: AsRef<[Machine]>>(
+    state: &SimState<M, RngSource>,
+    event: &TriggerEvent,
+    is_client: bool,
+) {
+    let (machine, lane) = match event {
+        TriggerEvent::PaddingSent { machine }
+        | TriggerEvent::TimerBegin { machine }
+        | TriggerEvent::TimerEnd { machine }
+        | TriggerEvent::IncomingBlockingBegin { machine } => (Some(machine), None),
+        TriggerEvent::BlockingBegin { machine, lane } => (Some(machine), Some(*lane)),
+        TriggerEvent::BlockingEnd { lane } => (None, Some(*lane)),
+        _ => (None, None),
+    };
+    if let Some(machine) = machine {
+        if machine.into_raw() >= state.framework.num_machines() {
+            violation(
+                is_client,
+                format!("event {:?} for a machine that does not exist", event),
+            );
+        }
+    }
+    if lane.is_some_and(|lane| lane as usize >= BLOCKING_LANES) {
+        violation(
+            is_client,
+            format!("event {:?} for a lane that does not exist", event),
+        );
+    }
+}
+
+/// Check the actions returned by one call to trigger events: at most one per
+/// machine, only for existing and enabled machines, with timeouts and
+/// durations within the caps of sampled values.
+pub(crate) fn check_actions<M: AsRef<[Machine]>>(
+    state: &SimState<M, RngSource>,
+    actions: &[TriggerAction],
+    is_client: bool,
+) {
+    let mut seen = vec![false; state.framework.num_machines()];
+    for action in actions {
+        let (timeout, duration, duration_cap) = match action {
+            TriggerAction::Cancel { .. } => (None, None, 0.0),
+            TriggerAction::SendPadding { timeout, .. } => (Some(timeout), None, 0.0),
+            TriggerAction::BlockOutgoing {
+                timeout, duration, ..
+            }
+            | TriggerAction::BlockIncoming {
+                timeout, duration, ..
+            }
+            | TriggerAction::HoldOutgoing {
+                timeout, duration, ..
+            } => (Some(timeout), Some(duration), MAX_SAMPLED_BLOCK_DURATION),
+            TriggerAction::UpdateTimer { duration, .. } => {
+                (None, Some(duration), MAX_SAMPLED_TIMER_DURATION)
+            }
+        };
+
+        let mi = action.machine().into_raw();
+        if mi >= seen.len() {
+            violation(
+                is_client,
+                format!("action {:?} for a machine that does not exist", action),
+            );
+        }
+        if seen[mi] {
+            violation(
+                is_client,
+                format!("more than one action for machine {}", mi),
+            );
+        }
+        seen[mi] = true;
+        if !state.framework.is_enabled(action.machine()) {
+            violation(
+                is_client,
+                format!("action {:?} for a disabled machine", action),
+            );
+        }
+        if timeout.is_some_and(|t| exceeds(*t, MAX_SAMPLED_TIMEOUT)) {
+            violation(
+                is_client,
+                format!("action {:?} with a timeout above the cap", action),
+            );
+        }
+        if duration.is_some_and(|d| exceeds(*d, duration_cap)) {
+            violation(
+                is_client,
+                format!("action {:?} with a duration above the cap", action),
+            );
+        }
+    }
+}
+
+/// Check the state at the end of a simulation that ran until there was
+/// nothing left to simulate: any blocking must have ended.
+pub(crate) fn check_end<M: AsRef<[Machine]>>(state: &SimState<M, RngSource>, is_client: bool) {
+    for lane in 0..BLOCKING_LANES as u8 {
+        if let Some(machine) = state.framework.blocking_owner(lane) {
+            violation(
+                is_client,
+                format!(
+                    "blocking of lane {} by machine {} without BlockingEnd",
+                    lane,
+                    machine.into_raw()
+                ),
+            );
+        }
+    }
+}
+
+fn exceeds(d: Duration, cap: f64) -> bool {
+    d > Duration::from_micros(cap as u64)
+}
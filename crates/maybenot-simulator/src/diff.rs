@@ -0,0 +1,114 @@
+//! Functions for comparing simulated traces, e.g., of the same base trace
+//! before and after changing a machine.
+
+use std::{collections::HashMap, time::Instant};
+
+use maybenot::{event::Event, TriggerEvent};
+
+use crate::SimEvent;
+
+/// The differences between two simulated traces, see [`diff_traces`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TraceDiff {
+    /// Padding packets in the new trace but not in the old one, in order.
+    pub added_padding: Vec<SimEvent>,
+    /// Padding packets in the old trace but not in the new one, in order.
+    pub removed_padding: Vec<SimEvent>,
+    /// Normal packets at a different time in the new trace, as pairs of the
+    /// packet in the old and the new trace, in order.
+    pub shifted: Vec<(SimEvent, SimEvent)>,
+}
+
+impl TraceDiff {
+    /// Returns true if the traces have the same packets at the same times.
+    pub fn is_empty(&self) -> bool {
+        self.added_padding.is_empty() && self.removed_padding.is_empty() && self.shifted.is_empty()
+    }
+
+    /// The change in the number of padding packets, from the old to the new
+    /// trace.
+    pub fn padding_delta(&self) -> i64 {
+        self.added_padding.len() as i64 - self.removed_padding.len() as i64
+    }
+
+    /// The change in the number of bytes of padding, from the old to the new
+    /// trace.
+    pub fn padding_bytes_delta(&self) -> i64 {
+        let sum = |v: &[SimEvent]| v.iter().map(|e| e.size as i64).sum::<i64>();
+        sum(&self.added_padding) - sum(&self.removed_padding)
+    }
+}
+
+/// Compare two simulated traces, typically of the same base trace simulated
+/// with different machines. Only packets, i.e., [`TriggerEvent::TunnelSent`]
+/// and [`TriggerEvent::TunnelRecv`], are compared.
+///
+/// Padding packets cannot be told apart, so a padding packet is unchanged if
+/// there is one of the same size, in the same direction and at the same time
+/// in both traces. Normal packets are matched in order, per client or server
+/// and direction, and reported if their time differs. Normal packets without a
+/// match, e.g., since one trace was cut short, are ignored.
+pub fn diff_traces(old: &[SimEvent], new: &[SimEvent]) -> TraceDiff {
+    TraceDiff {
+        added_padding: unmatched_padding(new, old),
+        removed_padding: unmatched_padding(old, new),
+        shifted: shifted_normal(old, new),
+    }
+}
+
+type PaddingKey = (bool, bool, Instant, u64);
+
+fn packets(trace: &[SimEvent]) -> impl Iterator<Item = &SimEvent> {
+    trace
+        .iter()
+        .filter(|e| e.event.is_event(Event::TunnelSent) || e.event.is_event(Event::TunnelRecv))
+}
+
+fn padding_key(e: &SimEvent) -> PaddingKey {
+    (
+        e.client,
+        e.event == TriggerEvent::TunnelSent,
+        e.time,
+        e.size,
+    )
+}
+
+// padding packets in trace without a match in other
+fn unmatched_padding(trace: &[SimEvent], other: &[SimEvent]) -> Vec<SimEvent> {
+    let mut counts: HashMap<PaddingKey, usize> = HashMap::new();
+    for e in packets(other).filter(|e| e.contains_padding) {
+        *counts.entry(padding_key(e)).or_default() += 1;
+    }
+
+    let mut unmatched = vec![];
+    for e in packets(trace).filter(|e| e.contains_padding) {
+        match counts.get_mut(&padding_key(e)) {
+            Some(count) if *count > 0 => *count -= 1,
+            _ => unmatched.push(e.clone()),
+        }
+    }
+    unmatched
+}
+
+fn shifted_normal(old: &[SimEvent], new: &[SimEvent]) -> Vec<(SimEvent, SimEvent)> {
+    let mut shifted = vec![];
+    for client in [true, false] {
+        for sent in [true, false] {
+            let normal = |trace| {
+                packets(trace).filter(move |e| {
+                    !e.contains_padding
+                        && e.client == client
+                        && (e.event == TriggerEvent::TunnelSent) == sent
+                })
+            };
+            for (o, n) in normal(old).zip(normal(new)) {
+                if o.time != n.time {
+                    shifted.push((o.clone(), n.clone()));
+                }
+            }
+        }
+    }
+    // in order of the old trace
+    shifted.sort_by(|a, b| a.0.cmp(&b.0));
+    shifted
+}
@@ -102,8 +102,11 @@
 //! // received a normal packet at 9420 ms
 //! ```
 
+mod contract;
+pub mod diff;
 pub mod integration;
 pub mod network;
+pub mod overhead;
 pub mod peek;
 pub mod queue;
 
@@ -117,7 +120,10 @@ use log::debug;
 use network::{Network, NetworkBottleneck, WindowCount};
 use queue::SimQueue;
 
-use maybenot::{Framework, Machine, MachineId, Timer, TriggerAction, TriggerEvent};
+use maybenot::{
+    action::Action, constants::BLOCKING_LANES, Framework, Machine, MachineId, Timer, TriggerAction,
+    TriggerEvent,
+};
 use rand::{rngs::ThreadRng, RngCore};
 use rand_xoshiro::rand_core::SeedableRng;
 use rand_xoshiro::Xoshiro256StarStar;
@@ -170,6 +176,11 @@ impl RngCore for RngSource {
 /// SimEvent represents an event in the simulator. It is used internally to
 /// represent events that are to be processed by the simulator (in SimQueue) and
 /// events that are produced by the simulator (the resulting trace).
+///
+/// Events are totally ordered by time, then by type (see the order in
+/// `event_to_usize()`), and last by the order they were pushed to the
+/// SimQueue, such that events that are otherwise equal are processed in FIFO
+/// order.
 #[derive(PartialEq, Hash, Eq, Clone, Debug)]
 pub struct SimEvent {
     /// the actual event
@@ -182,6 +193,10 @@ pub struct SimEvent {
     pub client: bool,
     /// flag to track padding or normal packet
     pub contains_padding: bool,
+    /// the size of the packet in bytes: from the base trace for normal
+    /// packets, [`SimulatorArgs::padding_size`] for padding, and zero for
+    /// events that are not packets
+    pub size: u64,
     /// internal flag to mark event as bypass
     bypass: bool,
     /// internal flag to mark event as replace
@@ -189,6 +204,8 @@ pub struct SimEvent {
     /// internal duration to propagate base trace delay from one party to the
     /// other due to bottleneck and blocking
     propagate_base_delay: Option<Duration>,
+    /// internal sequence number, set when pushed to the SimQueue
+    seq: u64,
 }
 
 /// Helper function to convert a TriggerEvent to a usize for sorting purposes.
@@ -203,9 +220,12 @@ fn event_to_usize(e: &TriggerEvent) -> usize {
         TriggerEvent::PaddingRecv => 5,
         // begin before end
         TriggerEvent::BlockingBegin { .. } => 6,
-        TriggerEvent::BlockingEnd => 7,
+        TriggerEvent::BlockingEnd { .. } => 7,
         TriggerEvent::TimerBegin { .. } => 8,
         TriggerEvent::TimerEnd { .. } => 9,
+        TriggerEvent::IncomingBlockingBegin { .. } => 10,
+        TriggerEvent::IncomingBlockingEnd => 11,
+        TriggerEvent::Idle => 12,
     }
 }
 
@@ -216,6 +236,7 @@ impl Ord for SimEvent {
         self.time
             .cmp(&other.time)
             .then_with(|| event_to_usize(&self.event).cmp(&event_to_usize(&other.event)))
+            .then_with(|| self.seq.cmp(&other.seq))
             .reverse()
     }
 }
@@ -243,33 +264,113 @@ pub struct SimState<M, R> {
     scheduled_action: Vec<Option<ScheduledAction>>,
     /// scheduled internal timers
     scheduled_internal_timer: Vec<Option<Instant>>,
-    /// blocking until time, active is set
-    blocking_until: Option<Instant>,
-    /// whether the active blocking bypassable or not
-    blocking_bypassable: bool,
+    /// blocking until time of each lane, if active: the simulated traffic is
+    /// in lane 0, and machines blocking other lanes are rejected, so other
+    /// lanes are only blocked by events in the queue
+    blocking_until: [Option<Instant>; BLOCKING_LANES],
+    /// whether the active blocking of each lane is bypassable or not
+    blocking_bypassable: [bool; BLOCKING_LANES],
+    /// blocking of incoming traffic until time, if active
+    incoming_blocking_until: Option<Instant>,
+    /// holding of outgoing normal packets until time, with the probability of
+    /// holding each packet, if active
+    holding: Option<(Instant, f64)>,
+    /// the source of randomness for holding packets, separate from the
+    /// framework to not affect its random stream
+    hold_rng: RngSource,
+    /// time of the last normal packet sent into the tunnel, if it has not
+    /// already replaced padding
+    last_normal_sent: Option<Instant>,
     /// integration aspects for this state
     integration: Option<Integration>,
+    /// the size of padding packets in bytes
+    padding_size: u64,
+    /// the factor to scale integration delays and the timeouts and durations
+    /// of actions by
+    time_scale: f64,
+    /// if the contracts of the framework are checked, see
+    /// [`SimulatorArgs::check_contracts`]
+    check_contracts: bool,
+}
+
+/// The configuration of a [`SimState`], of the client or the server, as set
+/// for each by [`SimulatorArgs`].
+#[derive(Clone, Debug)]
+pub struct SimStateConfig {
+    /// The maximum fraction of padding for the instance of the framework.
+    pub max_padding_frac: f64,
+    /// The maximum fraction of blocking for the instance of the framework.
+    pub max_blocking_frac: f64,
+    /// Optional integration delays.
+    pub integration: Option<Integration>,
+    /// The seed for the deterministic (insecure) RNG, see
+    /// [`SimulatorArgs::insecure_rng_seed`].
+    pub insecure_rng_seed: Option<u64>,
+    /// The size of padding packets in bytes.
+    pub padding_size: u64,
+    /// The factor to scale time by, see [`SimulatorArgs::time_scale`].
+    pub time_scale: f64,
+    /// If true, the contracts of the framework are checked, see
+    /// [`SimulatorArgs::check_contracts`].
+    pub check_contracts: bool,
+}
+
+impl Default for SimStateConfig {
+    fn default() -> Self {
+        Self {
+            max_padding_frac: 0.0,
+            max_blocking_frac: 0.0,
+            integration: None,
+            insecure_rng_seed: None,
+            padding_size: DEFAULT_PADDING_SIZE,
+            time_scale: 1.0,
+            check_contracts: false,
+        }
+    }
 }
 
 impl<M> SimState<M, RngSource>
 where
     M: AsRef<[Machine]>,
 {
-    pub fn new(
-        machines: M,
-        current_time: Instant,
-        max_padding_frac: f64,
-        max_blocking_frac: f64,
-        integration: Option<Integration>,
-        insecure_rng_seed: Option<u64>,
-    ) -> Self {
+    pub fn new(machines: M, current_time: Instant, config: SimStateConfig) -> Self {
+        let SimStateConfig {
+            max_padding_frac,
+            max_blocking_frac,
+            integration,
+            insecure_rng_seed,
+            padding_size,
+            time_scale,
+            check_contracts,
+        } = config;
         let rng = match insecure_rng_seed {
             // deterministic, insecure RNG
             Some(seed) => RngSource::Xoshiro(Xoshiro256StarStar::seed_from_u64(seed)),
             // secure RNG, default
             None => RngSource::Thread(rand::thread_rng()),
         };
+        let hold_rng = match insecure_rng_seed {
+            // a non-overlapping stream with the same seed
+            Some(seed) => {
+                let mut rng = Xoshiro256StarStar::seed_from_u64(seed);
+                rng.jump();
+                RngSource::Xoshiro(rng)
+            }
+            None => RngSource::Thread(rand::thread_rng()),
+        };
 
+        // the simulated traffic has no classes, so all of it is in lane 0
+        for (i, m) in machines.as_ref().iter().enumerate() {
+            let other_lane = m
+                .states
+                .iter()
+                .any(|s| matches!(s.action, Some(Action::BlockOutgoing { lane, .. }) if lane != 0));
+            assert!(
+                !other_lane,
+                "machine {} blocks a lane other than 0, not supported by the simulator",
+                i
+            );
+        }
         let num_machines = machines.as_ref().len();
 
         Self {
@@ -283,32 +384,58 @@ where
             .unwrap(),
             scheduled_action: vec![None; num_machines],
             scheduled_internal_timer: vec![None; num_machines],
-            blocking_until: None,
-            blocking_bypassable: false,
+            blocking_until: [None; BLOCKING_LANES],
+            blocking_bypassable: [false; BLOCKING_LANES],
+            incoming_blocking_until: None,
+            holding: None,
+            hold_rng,
+            last_normal_sent: None,
             integration,
+            padding_size,
+            time_scale,
+            check_contracts,
         }
     }
 
     pub fn reporting_delay(&self) -> Duration {
-        self.integration
-            .as_ref()
-            .map(|i| i.reporting_delay())
-            .unwrap_or(Duration::from_micros(0))
+        self.scale(
+            self.integration
+                .as_ref()
+                .map(|i| i.reporting_delay())
+                .unwrap_or(Duration::from_micros(0)),
+        )
     }
 
     pub fn action_delay(&self) -> Duration {
-        self.integration
-            .as_ref()
-            .map(|i| i.action_delay())
-            .unwrap_or(Duration::from_micros(0))
+        self.scale(
+            self.integration
+                .as_ref()
+                .map(|i| i.action_delay())
+                .unwrap_or(Duration::from_micros(0)),
+        )
     }
 
     pub fn trigger_delay(&self) -> Duration {
-        self.integration
-            .as_ref()
-            .map(|i| i.trigger_delay())
-            .unwrap_or(Duration::from_micros(0))
+        self.scale(
+            self.integration
+                .as_ref()
+                .map(|i| i.trigger_delay())
+                .unwrap_or(Duration::from_micros(0)),
+        )
+    }
+
+    /// Scale a duration by the time scale of the simulation.
+    fn scale(&self, d: Duration) -> Duration {
+        scale_duration(d, self.time_scale)
+    }
+}
+
+// exact for the default time scale of 1.0, unlike Duration::mul_f64()
+fn scale_duration(d: Duration, time_scale: f64) -> Duration {
+    if time_scale == 1.0 {
+        return d;
     }
+    d.mul_f64(time_scale)
 }
 
 /// The main simulator function.
@@ -332,6 +459,9 @@ where
 /// are related to network activity (i.e., packets sent and received) to the
 /// output trace. This is recommended if you want to use the output trace for
 /// traffic analysis without further (recursive) simulation.
+///
+/// All simulated traffic is in blocking lane 0: the simulator panics on
+/// machines that block other lanes.
 pub fn sim(
     machines_client: &[Machine],
     machines_server: &[Machine],
@@ -380,8 +510,34 @@ pub struct SimulatorArgs<'a> {
     pub client_integration: Option<&'a Integration>,
     /// Optional server integration delays.
     pub server_integration: Option<&'a Integration>,
+    /// The size of padding packets in bytes, for both the client and server.
+    /// Defaults to [`DEFAULT_PADDING_SIZE`].
+    pub padding_size: u64,
+    /// The factor to scale time by (default 1.0), e.g., 2.0 to run the
+    /// simulation at half speed. The network delay, integration delays, and
+    /// the timeouts and durations of all actions of the machines are
+    /// multiplied by the factor. The queue is not: for a uniformly scaled
+    /// simulation, parse a trace with times scaled by the same factor, and
+    /// with the network (and any integration) used for parsing scaled as
+    /// well. The network bottleneck and limits of machines in absolute time
+    /// (e.g., [`Machine::allowed_blocked_microsec`]) are not scaled.
+    pub time_scale: f64,
+    /// If true (default false), the simulator checks the contracts between
+    /// the framework and its integration on every step, panicking with a
+    /// description of any violation. The events triggered, including those
+    /// in the queue, must refer to existing machines and lanes. The actions
+    /// returned must be at most one per existing and enabled machine, with
+    /// timeouts and durations within the caps of sampled values (e.g.,
+    /// [`MAX_SAMPLED_TIMEOUT`](maybenot::constants::MAX_SAMPLED_TIMEOUT)).
+    /// If the simulation runs until there is nothing left to simulate, all
+    /// blocking must have ended. For catching bugs in machines, the
+    /// framework, and the simulator during development.
+    pub check_contracts: bool,
 }
 
+/// The default size of padding packets in bytes, a full Ethernet MTU.
+pub const DEFAULT_PADDING_SIZE: u64 = 1500;
+
 impl<'a> SimulatorArgs<'a> {
     pub fn new(network: &'a Network, max_trace_length: usize, only_network_activity: bool) -> Self {
         Self {
@@ -397,6 +553,9 @@ impl<'a> SimulatorArgs<'a> {
             insecure_rng_seed: None,
             client_integration: None,
             server_integration: None,
+            padding_size: DEFAULT_PADDING_SIZE,
+            time_scale: 1.0,
+            check_contracts: false,
         }
     }
 }
@@ -425,26 +584,39 @@ pub fn sim_advanced(
     let mut client = SimState::new(
         machines_client,
         current_time,
-        args.max_padding_frac_client,
-        args.max_blocking_frac_client,
-        args.client_integration.cloned(),
-        args.insecure_rng_seed,
+        SimStateConfig {
+            max_padding_frac: args.max_padding_frac_client,
+            max_blocking_frac: args.max_blocking_frac_client,
+            integration: args.client_integration.cloned(),
+            insecure_rng_seed: args.insecure_rng_seed,
+            padding_size: args.padding_size,
+            time_scale: args.time_scale,
+            check_contracts: args.check_contracts,
+        },
     );
     let mut server = SimState::new(
         machines_server,
         current_time,
-        args.max_padding_frac_server,
-        args.max_blocking_frac_server,
-        args.server_integration.cloned(),
-        args.insecure_rng_seed,
+        SimStateConfig {
+            max_padding_frac: args.max_padding_frac_server,
+            max_blocking_frac: args.max_blocking_frac_server,
+            integration: args.server_integration.cloned(),
+            insecure_rng_seed: args.insecure_rng_seed,
+            padding_size: args.padding_size,
+            time_scale: args.time_scale,
+            check_contracts: args.check_contracts,
+        },
     );
     debug!("sim(): client machines {}", machines_client.len());
     debug!("sim(): server machines {}", machines_server.len());
 
-    let mut network =
-        NetworkBottleneck::new(args.network.clone(), Duration::from_secs(1), sq.max_pps);
+    let mut network = args.network.clone();
+    network.delay = scale_duration(network.delay, args.time_scale);
+    let mut network = NetworkBottleneck::new(network, Duration::from_secs(1), sq.max_pps);
 
     let mut sim_iterations = 0;
+    // if stopped by a limit, rather than running out of things to simulate
+    let mut stopped = false;
     let start_time = current_time;
     while let Some(next) = pick_next(sq, &mut client, &mut server, &mut network, current_time) {
         debug!("#########################################################");
@@ -475,13 +647,13 @@ pub fn sim_advanced(
         } else {
             debug!("sim(): @server next\n{:#?}", next);
         }
-        if let Some(blocking_until) = client.blocking_until {
+        if let Some(blocking_until) = client.blocking_until[0] {
             debug!(
                 "sim(): client is blocked until time {:#?}",
                 blocking_until.duration_since(start_time)
             );
         }
-        if let Some(blocking_until) = server.blocking_until {
+        if let Some(blocking_until) = server.blocking_until[0] {
             debug!(
                 "sim(): server is blocked until time {:#?}",
                 blocking_until.duration_since(start_time)
@@ -492,11 +664,30 @@ pub fn sim_advanced(
         // and the server. Returns true if there was network activity (i.e., a
         // packet was sent or received over the network), false otherwise.
         let network_activity = if next.client {
-            sim_network_stack(&next, sq, &client, &mut server, &mut network, &current_time)
+            sim_network_stack(
+                &next,
+                sq,
+                &mut client,
+                &mut server,
+                &mut network,
+                &current_time,
+            )
         } else {
-            sim_network_stack(&next, sq, &server, &mut client, &mut network, &current_time)
+            sim_network_stack(
+                &next,
+                sq,
+                &mut server,
+                &mut client,
+                &mut network,
+                &current_time,
+            )
         };
 
+        if args.check_contracts {
+            let state = if next.client { &client } else { &server };
+            contract::check_event(state, &next.event, next.client);
+        }
+
         // get actions, update scheduled actions
         if next.client {
             debug!("sim(): trigger @client framework {:?}", next.event);
@@ -548,6 +739,7 @@ pub fn sim_advanced(
                 "sim(): we done, reached max trace length {}",
                 args.max_trace_length
             );
+            stopped = true;
             break;
         }
 
@@ -558,6 +750,7 @@ pub fn sim_advanced(
                 "sim(): we done, reached max sim iterations {}",
                 args.max_sim_iterations
             );
+            stopped = true;
             break;
         }
 
@@ -565,6 +758,11 @@ pub fn sim_advanced(
         debug!("#########################################################");
     }
 
+    if args.check_contracts && !stopped {
+        contract::check_end(&client, true);
+        contract::check_end(&server, false);
+    }
+
     // sort the trace by time
     trace.sort_by(|a, b| a.time.cmp(&b.time));
 
@@ -594,9 +792,27 @@ fn pick_next<M: AsRef<[Machine]>>(
     );
     debug!("\tpick_next(): peek_scheduled_internal_timer = {:?}", i);
 
-    let (b, b_is_client) =
-        peek_blocked_exp(client.blocking_until, server.blocking_until, current_time);
-    debug!("\tpick_next(): peek_blocked_exp = {:?}", b);
+    // the earliest expiry of blocking of any lane, the lowest lane first
+    let (mut b_out, mut b_out_is_client, mut b_out_lane) = (Duration::MAX, true, 0);
+    for lane in 0..BLOCKING_LANES {
+        let (b, is_client) = peek_blocked_exp(
+            client.blocking_until[lane],
+            server.blocking_until[lane],
+            current_time,
+        );
+        if b < b_out {
+            (b_out, b_out_is_client, b_out_lane) = (b, is_client, lane);
+        }
+    }
+    debug!("\tpick_next(): peek_blocked_exp = {:?}", b_out);
+    let (b_in, b_in_is_client) = peek_blocked_exp(
+        client.incoming_blocking_until,
+        server.incoming_blocking_until,
+        current_time,
+    );
+    debug!("\tpick_next(): peek_blocked_exp incoming = {:?}", b_in);
+    // the earliest expiry of blocking, in either direction
+    let b = b_out.min(b_in);
 
     let n = network.peek_aggregate_delay(current_time);
     debug!("\tpick_next(): peek_aggregate_delay = {:?}", n);
@@ -668,24 +884,39 @@ fn pick_next<M: AsRef<[Machine]>>(
         // create SimEvent and move blocking into (what soon will be) the past
         // to indicate that it has been processed
         // ASSUMPTION: block outgoing is reported from integration
-        let delay: Duration;
-        if b_is_client {
-            delay = client.reporting_delay();
-            client.blocking_until = None;
+        let incoming = b_in < b_out;
+        let b_is_client = if incoming {
+            b_in_is_client
         } else {
-            delay = server.reporting_delay();
-            server.blocking_until = None;
-        }
+            b_out_is_client
+        };
+        let state = if b_is_client {
+            &mut *client
+        } else {
+            &mut *server
+        };
+        let delay = state.reporting_delay();
+        let event = if incoming {
+            state.incoming_blocking_until = None;
+            TriggerEvent::IncomingBlockingEnd
+        } else {
+            state.blocking_until[b_out_lane] = None;
+            TriggerEvent::BlockingEnd {
+                lane: b_out_lane as u8,
+            }
+        };
 
         return Some(SimEvent {
             client: b_is_client,
-            event: TriggerEvent::BlockingEnd,
+            event,
             time: current_time + b + delay,
             integration_delay: delay,
             bypass: false,
             replace: false,
             contains_padding: false,
+            size: 0,
             propagate_base_delay: None,
+            seq: 0,
         });
     }
 
@@ -757,7 +988,9 @@ fn do_internal_timer<M: AsRef<[Machine]>>(
         bypass: false,
         replace: false,
         contains_padding: false,
+        size: 0,
         propagate_base_delay: None,
+        seq: 0,
     })
 }
 
@@ -815,10 +1048,10 @@ fn do_scheduled_action<M: AsRef<[Machine]>>(
             replace,
             machine,
         } => {
-            let action_delay = if is_client {
-                client.action_delay()
+            let (action_delay, size) = if is_client {
+                (client.action_delay(), client.padding_size)
             } else {
-                server.action_delay()
+                (server.action_delay(), server.padding_size)
             };
 
             Some(SimEvent {
@@ -829,7 +1062,9 @@ fn do_scheduled_action<M: AsRef<[Machine]>>(
                 bypass,
                 replace,
                 contains_padding: true,
+                size,
                 propagate_base_delay: None,
+                seq: 0,
             })
         }
         TriggerAction::BlockOutgoing {
@@ -837,9 +1072,15 @@ fn do_scheduled_action<M: AsRef<[Machine]>>(
             duration,
             bypass,
             replace,
+            lane,
             machine,
         } => {
-            let block = a.time + duration;
+            let block = a.time
+                + if is_client {
+                    client.scale(duration)
+                } else {
+                    server.scale(duration)
+                };
             let event_bypass;
             // ASSUMPTION: block outgoing reported from integration
             let total_delay = if is_client {
@@ -849,33 +1090,78 @@ fn do_scheduled_action<M: AsRef<[Machine]>>(
             };
             let reported = a.time + total_delay;
 
-            // should we update client/server blocking?
-            if is_client {
-                if replace || block > client.blocking_until.unwrap_or(a.time) {
-                    client.blocking_until = Some(block);
-                    client.blocking_bypassable = bypass;
+            // should we update client/server blocking of the lane? Invalid
+            // lanes are ignored by the framework, so block nothing
+            let l = lane as usize;
+            if l >= BLOCKING_LANES {
+                event_bypass = false;
+            } else if is_client {
+                if replace || block > client.blocking_until[l].unwrap_or(a.time) {
+                    client.blocking_until[l] = Some(block);
+                    client.blocking_bypassable[l] = bypass;
                 }
-                event_bypass = client.blocking_bypassable;
+                event_bypass = client.blocking_bypassable[l];
             } else {
-                if replace || block > server.blocking_until.unwrap_or(a.time) {
-                    server.blocking_until = Some(block);
-                    server.blocking_bypassable = bypass;
+                if replace || block > server.blocking_until[l].unwrap_or(a.time) {
+                    server.blocking_until[l] = Some(block);
+                    server.blocking_bypassable[l] = bypass;
                 }
-                event_bypass = server.blocking_bypassable;
+                event_bypass = server.blocking_bypassable[l];
             }
 
             // event triggered regardless
             Some(SimEvent {
-                event: TriggerEvent::BlockingBegin { machine },
+                event: TriggerEvent::BlockingBegin { machine, lane },
                 time: reported,
                 integration_delay: total_delay,
                 client: is_client,
                 bypass: event_bypass,
                 replace: false,
                 contains_padding: false,
+                size: 0,
                 propagate_base_delay: None,
+                seq: 0,
             })
         }
+        TriggerAction::BlockIncoming {
+            timeout: _,
+            duration,
+            replace,
+            machine,
+        } => {
+            // ASSUMPTION: block incoming reported from integration, like
+            // block outgoing
+            let state = if is_client { client } else { server };
+            let block = a.time + state.scale(duration);
+            let total_delay = state.action_delay() + state.reporting_delay();
+            if replace || block > state.incoming_blocking_until.unwrap_or(a.time) {
+                state.incoming_blocking_until = Some(block);
+            }
+
+            Some(SimEvent {
+                event: TriggerEvent::IncomingBlockingBegin { machine },
+                time: a.time + total_delay,
+                integration_delay: total_delay,
+                client: is_client,
+                bypass: false,
+                replace: false,
+                contains_padding: false,
+                size: 0,
+                propagate_base_delay: None,
+                seq: 0,
+            })
+        }
+        TriggerAction::HoldOutgoing {
+            timeout: _,
+            duration,
+            probability,
+            machine: _,
+        } => {
+            // replaces any ongoing holding, and is not reported
+            let state = if is_client { client } else { server };
+            state.holding = Some((a.time + state.scale(duration), probability));
+            None
+        }
     }
 }
 
@@ -887,12 +1173,19 @@ fn trigger_update<M: AsRef<[Machine]>>(
     is_client: bool,
 ) {
     let trigger_delay = state.trigger_delay();
+    let time_scale = state.time_scale;
+
+    // the actions to check, if checking contracts
+    let mut checked = vec![];
 
     // parse actions and update
     for action in state
         .framework
         .trigger_events(&[next.event.clone()], *current_time)
     {
+        if state.check_contracts {
+            checked.push(action.clone());
+        }
         match action {
             TriggerAction::Cancel { machine, timer } => {
                 debug!(
@@ -912,6 +1205,27 @@ fn trigger_update<M: AsRef<[Machine]>>(
                         state.scheduled_action[machine.into_raw()] = None;
                         state.scheduled_internal_timer[machine.into_raw()] = None;
                     }
+                    Timer::ActionPadding => {
+                        if let Some(ScheduledAction {
+                            action: TriggerAction::SendPadding { .. },
+                            ..
+                        }) = state.scheduled_action[machine.into_raw()]
+                        {
+                            state.scheduled_action[machine.into_raw()] = None;
+                        }
+                    }
+                    Timer::ActionBlocking => {
+                        if let Some(ScheduledAction {
+                            action:
+                                TriggerAction::BlockOutgoing { .. }
+                                | TriggerAction::BlockIncoming { .. }
+                                | TriggerAction::HoldOutgoing { .. },
+                            ..
+                        }) = state.scheduled_action[machine.into_raw()]
+                        {
+                            state.scheduled_action[machine.into_raw()] = None;
+                        }
+                    }
                 }
             }
             TriggerAction::SendPadding {
@@ -926,15 +1240,11 @@ fn trigger_update<M: AsRef<[Machine]>>(
                 );
                 state.scheduled_action[machine.into_raw()] = Some(ScheduledAction {
                     action: action.clone(),
-                    time: *current_time + *timeout + trigger_delay,
+                    time: *current_time + scale_duration(*timeout, time_scale) + trigger_delay,
                 });
             }
             TriggerAction::BlockOutgoing {
-                timeout,
-                duration: _,
-                bypass: _,
-                replace: _,
-                machine,
+                timeout, machine, ..
             } => {
                 debug!(
                     "\ttrigger_update(): block outgoing action {:?} {:?}",
@@ -942,7 +1252,31 @@ fn trigger_update<M: AsRef<[Machine]>>(
                 );
                 state.scheduled_action[machine.into_raw()] = Some(ScheduledAction {
                     action: action.clone(),
-                    time: *current_time + *timeout + trigger_delay,
+                    time: *current_time + scale_duration(*timeout, time_scale) + trigger_delay,
+                });
+            }
+            TriggerAction::BlockIncoming {
+                timeout, machine, ..
+            } => {
+                debug!(
+                    "\ttrigger_update(): block incoming action {:?} {:?}",
+                    timeout, machine
+                );
+                state.scheduled_action[machine.into_raw()] = Some(ScheduledAction {
+                    action: action.clone(),
+                    time: *current_time + scale_duration(*timeout, time_scale) + trigger_delay,
+                });
+            }
+            TriggerAction::HoldOutgoing {
+                timeout, machine, ..
+            } => {
+                debug!(
+                    "\ttrigger_update(): hold outgoing action {:?} {:?}",
+                    timeout, machine
+                );
+                state.scheduled_action[machine.into_raw()] = Some(ScheduledAction {
+                    action: action.clone(),
+                    time: *current_time + scale_duration(*timeout, time_scale) + trigger_delay,
                 });
             }
             TriggerAction::UpdateTimer {
@@ -959,9 +1293,9 @@ fn trigger_update<M: AsRef<[Machine]>>(
                     state.scheduled_internal_timer[machine.into_raw()].unwrap_or(*current_time);
 
                 // update the timer
-                if *replace || current < *current_time + *duration {
-                    state.scheduled_internal_timer[machine.into_raw()] =
-                        Some(*current_time + *duration);
+                let end = *current_time + scale_duration(*duration, time_scale);
+                if *replace || current < end {
+                    state.scheduled_internal_timer[machine.into_raw()] = Some(end);
                     // TimerBegin event
                     sq.push_sim(SimEvent {
                         client: is_client,
@@ -971,12 +1305,18 @@ fn trigger_update<M: AsRef<[Machine]>>(
                         bypass: false,
                         replace: false,
                         contains_padding: false,
+                        size: 0,
                         propagate_base_delay: None,
+                        seq: 0,
                     });
                 }
             }
         };
     }
+
+    if state.check_contracts {
+        contract::check_actions(state, &checked, is_client);
+    }
 }
 
 /// Parse a trace into a [`SimQueue`] for use with [`sim`].
@@ -1013,7 +1353,11 @@ pub fn parse_trace_advanced(
         if parts.len() >= 2 {
             let timestamp =
                 starting_time + Duration::from_nanos(parts[0].trim().parse::<u64>().unwrap());
-            // let size = parts[2].trim().parse::<u64>().unwrap();
+            // the size is optional, for traces with only time and direction
+            let size = parts
+                .get(2)
+                .map(|s| s.trim().parse::<u64>().unwrap())
+                .unwrap_or(0);
 
             // NOTE: for supporting deterministic simulation with a seed, note
             // that once network is randomized and integration delays are used,
@@ -1032,6 +1376,7 @@ pub fn parse_trace_advanced(
                         false,
                         reported,
                         reporting_delay,
+                        size,
                     );
 
                     let m = sent_window.add(&timestamp);
@@ -1053,6 +1398,7 @@ pub fn parse_trace_advanced(
                         false,
                         reported,
                         reporting_delay,
+                        size,
                     );
 
                     let m = recv_window.add(&timestamp);
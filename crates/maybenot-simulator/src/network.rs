@@ -9,6 +9,7 @@ use std::{
 
 use log::debug;
 use maybenot::{Machine, TriggerEvent};
+use rand::Rng;
 
 use crate::{queue::SimQueue, RngSource, SimEvent, SimState};
 
@@ -229,14 +230,18 @@ impl WindowCount {
 // received. We current do not have a receiver-side queue. TODO?
 //
 // For sending padding, in principle we treat it like a normal packet, but we
-// need to consider the replace flag.
+// need to consider the replace flag: padding is replaced by a normal packet
+// that is either blocked or was sent at the same time as the padding. A blocked
+// normal packet that replaces padding with the bypass flag takes over the flag,
+// so it is sent right away if the active blocking is bypassable (see
+// test_bypass_replace_matrix for all combinations).
 //
 // Returns true if there was network activity (i.e., a packet was sent or
 // received), false otherwise.
 pub(crate) fn sim_network_stack<M: AsRef<[Machine]>>(
     next: &SimEvent,
     sq: &mut SimQueue,
-    state: &SimState<M, RngSource>,
+    state: &mut SimState<M, RngSource>,
     recipient: &mut SimState<M, RngSource>,
     network: &mut NetworkBottleneck,
     current_time: &Instant,
@@ -244,18 +249,29 @@ pub(crate) fn sim_network_stack<M: AsRef<[Machine]>>(
     let side = if next.client { "client" } else { "server" };
 
     match next.event {
-        // here we simulate sending the packet into the tunnel
+        // here we simulate sending the packet into the tunnel, unless held
+        // until any holding ends (LIMITATION: the delay of a held packet is
+        // not propagated to later packets, unlike for blocking)
         TriggerEvent::NormalSent => {
+            let sent = match state.holding {
+                Some((until, p)) if until > next.time && state.hold_rng.gen::<f64>() < p => {
+                    debug!("\toutgoing held, sending in {:?}", until - next.time);
+                    until
+                }
+                _ => next.time,
+            };
             debug!("\tqueue {:#?}", TriggerEvent::TunnelSent);
             sq.push_sim(SimEvent {
                 event: TriggerEvent::TunnelSent,
-                time: next.time,
+                time: sent,
                 integration_delay: next.integration_delay,
                 client: next.client,
                 contains_padding: false,
+                size: next.size,
                 bypass: false,
                 replace: false,
                 propagate_base_delay: None,
+                seq: 0,
             });
             false
         }
@@ -266,7 +282,7 @@ pub(crate) fn sim_network_stack<M: AsRef<[Machine]>>(
                 // blocked, we can replace the padding with that FIXME: here be
                 // bugs related to integration delays
                 if let (Some(queued), qid) =
-                    sq.peek_blocking(state.blocking_bypassable, next.client)
+                    sq.peek_blocking(state.blocking_bypassable[0], next.client)
                 {
                     if queued.client == next.client
                         && TriggerEvent::TunnelSent == queued.event
@@ -290,7 +306,7 @@ pub(crate) fn sim_network_stack<M: AsRef<[Machine]>>(
                         let mut entry = sq
                             .pop_blocking(
                                 qid,
-                                state.blocking_bypassable,
+                                state.blocking_bypassable[0],
                                 next.client,
                                 network.aggregate_base_delay,
                             )
@@ -323,6 +339,17 @@ pub(crate) fn sim_network_stack<M: AsRef<[Machine]>>(
                         return false;
                     }
                 }
+
+                // a normal packet sent at the same time takes the slot of the
+                // padding, but only of one padding packet
+                if state.last_normal_sent == Some(*current_time) {
+                    debug!(
+                        "\treplaced padding sent with normal sent at the same time @{}",
+                        side
+                    );
+                    state.last_normal_sent = None;
+                    return false;
+                }
             }
             // nothing to replace with (or we're not replacing), so queue up
             debug!("\tqueue {:#?}", TriggerEvent::TunnelSent);
@@ -332,13 +359,19 @@ pub(crate) fn sim_network_stack<M: AsRef<[Machine]>>(
                 integration_delay: next.integration_delay,
                 client: next.client,
                 contains_padding: true,
+                size: next.size,
                 bypass: next.bypass,
                 replace: next.replace,
                 propagate_base_delay: None,
+                seq: 0,
             });
             false
         }
         TriggerEvent::TunnelSent => {
+            // bypassable normal packets already replaced padding, see above
+            if !next.contains_padding && !next.bypass {
+                state.last_normal_sent = Some(*current_time);
+            }
             let reporting_delay = recipient.reporting_delay();
             let (network_delay, mut baseline_delay) = network.sample(current_time, next.client);
             if let Some(pps_delay) = baseline_delay {
@@ -381,9 +414,11 @@ pub(crate) fn sim_network_stack<M: AsRef<[Machine]>>(
                     integration_delay: reporting_delay,
                     client: !next.client,
                     contains_padding: false,
+                    size: next.size,
                     bypass: false,
                     replace: false,
                     propagate_base_delay: baseline_delay,
+                    seq: 0,
                 });
                 debug!(
                     "\tqueue {:#?}, arriving at recipient in {:?}",
@@ -402,11 +437,13 @@ pub(crate) fn sim_network_stack<M: AsRef<[Machine]>>(
                 integration_delay: reporting_delay,
                 client: !next.client,
                 contains_padding: true,
+                size: next.size,
                 bypass: false,
                 replace: false,
                 // NOTE: padding does not contribute to delaying the base trace
                 // (beyond filling the bottleneck window)
                 propagate_base_delay: baseline_delay,
+                seq: 0,
             });
             debug!(
                 "\tqueue {:#?}, arriving at recipient in {:?}",
@@ -420,15 +457,25 @@ pub(crate) fn sim_network_stack<M: AsRef<[Machine]>>(
                 network.push_aggregate_delay(bottleneck, current_time, next.client);
             }
 
-            // spawn NormalRecv or PaddingRecv
+            // spawn NormalRecv or PaddingRecv, delivered once any blocking of
+            // incoming traffic ends (LIMITATION: blocking extended after the
+            // packet was received does not delay it further)
+            let delivered = match state.incoming_blocking_until {
+                Some(until) if until > next.time => {
+                    debug!("\tincoming blocked, delivering in {:?}", until - next.time);
+                    until
+                }
+                _ => next.time,
+            };
             if next.contains_padding {
                 debug!("\tqueue {:#?}", TriggerEvent::PaddingRecv);
                 sq.push(
                     TriggerEvent::PaddingRecv,
                     next.client,
                     true,
-                    next.time,
+                    delivered,
                     next.integration_delay,
+                    next.size,
                 );
             } else {
                 debug!("\tqueue {:#?}", TriggerEvent::NormalRecv);
@@ -436,8 +483,9 @@ pub(crate) fn sim_network_stack<M: AsRef<[Machine]>>(
                     TriggerEvent::NormalRecv,
                     next.client,
                     false,
-                    next.time,
+                    delivered,
                     next.integration_delay,
+                    next.size,
                 );
             }
             true
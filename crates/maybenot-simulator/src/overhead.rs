@@ -0,0 +1,76 @@
+//! Functions for computing the overhead of defenses in simulated traces,
+//! optionally excluding warmup and cooldown windows.
+
+use std::time::Duration;
+
+use maybenot::TriggerEvent;
+
+use crate::SimEvent;
+
+/// The packets and bytes sent in a simulated trace, see [`compute_overhead`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Overhead {
+    /// The number of normal packets sent.
+    pub normal_packets: u64,
+    /// The number of padding packets sent.
+    pub padding_packets: u64,
+    /// The number of bytes of normal packets sent.
+    pub normal_bytes: u64,
+    /// The number of bytes of padding packets sent.
+    pub padding_bytes: u64,
+}
+
+impl Overhead {
+    /// The fraction of sent packets that are padding, or 0.0 if no packets
+    /// were sent.
+    pub fn padding_fraction(&self) -> f64 {
+        let total = self.normal_packets + self.padding_packets;
+        if total == 0 {
+            return 0.0;
+        }
+        self.padding_packets as f64 / total as f64
+    }
+
+    /// The fraction of sent bytes that are padding, or 0.0 if no bytes were
+    /// sent.
+    pub fn padding_bytes_fraction(&self) -> f64 {
+        let total = self.normal_bytes + self.padding_bytes;
+        if total == 0 {
+            return 0.0;
+        }
+        self.padding_bytes as f64 / total as f64
+    }
+}
+
+/// Count the packets sent in a simulated trace, by the client and server,
+/// i.e., [`TriggerEvent::TunnelSent`] events, such that each packet is
+/// counted once.
+///
+/// Packets sent within `warmup` of the first event of the trace, or within
+/// `cooldown` of the last event, are excluded, so that the steady-state
+/// overhead of a defense can be measured without, e.g., machines starting up.
+/// Use [`Duration::ZERO`] for both to count all packets.
+pub fn compute_overhead(trace: &[SimEvent], warmup: Duration, cooldown: Duration) -> Overhead {
+    let mut overhead = Overhead::default();
+    let (Some(first), Some(last)) = (trace.first(), trace.last()) else {
+        return overhead;
+    };
+    let start = first.time + warmup;
+    let Some(end) = last.time.checked_sub(cooldown) else {
+        return overhead;
+    };
+
+    for e in trace {
+        if e.event != TriggerEvent::TunnelSent || e.time < start || e.time > end {
+            continue;
+        }
+        if e.contains_padding {
+            overhead.padding_packets += 1;
+            overhead.padding_bytes += e.size;
+        } else {
+            overhead.normal_packets += 1;
+            overhead.normal_bytes += e.size;
+        }
+    }
+    overhead
+}
@@ -39,8 +39,8 @@ pub(crate) fn peek_queue<M: AsRef<[Machine]>>(
         return (duration_since, queue, peek.client);
     }
 
-    let client_blocking = client.blocking_until.is_some();
-    let server_blocking = server.blocking_until.is_some();
+    let client_blocking = client.blocking_until[0].is_some();
+    let server_blocking = server.blocking_until[0].is_some();
 
     // easy: no active blocking to consider
     if !client_blocking && !server_blocking {
@@ -56,13 +56,13 @@ pub(crate) fn peek_queue<M: AsRef<[Machine]>>(
     // is bypassable
     if (peek.client
         && client_blocking
-        && client.blocking_bypassable
+        && client.blocking_bypassable[0]
         // bypassable TunnelSent is the result of replaced padding
         && (peek.event.is_event(Event::TunnelSent))
         && peek.bypass)
         || (!peek.client
             && server_blocking
-            && server.blocking_bypassable
+            && server.blocking_bypassable[0]
             // bypassable TunnelSent is the result of replaced padding
             && (peek.event.is_event(Event::TunnelSent))
             && peek.bypass)
@@ -74,16 +74,16 @@ pub(crate) fn peek_queue<M: AsRef<[Machine]>>(
     // earliest client and server
     let (c_d, c_q, c_b) = peek_queue_earliest_side(
         sq,
-        client.blocking_until,
-        client.blocking_bypassable,
+        client.blocking_until[0],
+        client.blocking_bypassable[0],
         current_time,
         network_delay_sum,
         true,
     );
     let (s_d, s_q, s_b) = peek_queue_earliest_side(
         sq,
-        server.blocking_until,
-        server.blocking_bypassable,
+        server.blocking_until[0],
+        server.blocking_bypassable[0],
         current_time,
         network_delay_sum,
         false,
@@ -21,6 +21,9 @@ pub struct SimQueue {
     // The maximum number of packets/cells (depends on trace) per second before
     // adding delay due to a simulated bottleneck. None means no limit.
     pub(crate) max_pps: Option<usize>,
+    // The sequence number of the next pushed event, such that events that are
+    // otherwise equal are popped in the order they were pushed.
+    seq: u64,
 }
 
 impl Default for SimQueue {
@@ -35,6 +38,7 @@ impl SimQueue {
             client: EventQueue::new(),
             server: EventQueue::new(),
             max_pps: None,
+            seq: 0,
         }
     }
 
@@ -53,6 +57,7 @@ impl SimQueue {
         contains_padding: bool,
         time: Instant,
         delay: Duration,
+        size: u64,
     ) {
         self.push_sim(SimEvent {
             event,
@@ -60,13 +65,17 @@ impl SimQueue {
             integration_delay: delay,
             client: is_client,
             contains_padding,
+            size,
             bypass: false,
             replace: false,
             propagate_base_delay: None,
+            seq: 0,
         });
     }
 
-    pub fn push_sim(&mut self, item: SimEvent) {
+    pub fn push_sim(&mut self, mut item: SimEvent) {
+        item.seq = self.seq;
+        self.seq += 1;
         match item.client {
             true => self.client.push(item),
             false => self.server.push(item),
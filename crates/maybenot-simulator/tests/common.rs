@@ -52,11 +52,13 @@ pub fn make_sq(s: String, delay: Duration, starting_time: Instant) -> SimQueue {
     let mut sq = SimQueue::new();
     let integration_delay = Duration::from_micros(0);
 
-    // format we expect to parse: 0,s 18,s 25,r 25,r 30,s 35,r
+    // format we expect to parse: 0,s 18,s 25,r 25,r 30,s 35,r, optionally
+    // with the size in bytes of each packet: 0,s,1400 25,r,512
     for line in s.split(' ') {
         let parts: Vec<&str> = line.split(',').collect();
-        if parts.len() == 2 {
+        if parts.len() == 2 || parts.len() == 3 {
             let timestamp = starting_time + Duration::from_micros(parts[0].parse::<u64>().unwrap());
+            let size = parts.get(2).map_or(0, |s| s.parse::<u64>().unwrap());
 
             match parts[1] {
                 "s" | "sn" => {
@@ -67,6 +69,7 @@ pub fn make_sq(s: String, delay: Duration, starting_time: Instant) -> SimQueue {
                         false,
                         timestamp,
                         integration_delay,
+                        size,
                     );
                 }
                 "r" | "rn" => {
@@ -78,6 +81,7 @@ pub fn make_sq(s: String, delay: Duration, starting_time: Instant) -> SimQueue {
                         false,
                         sent,
                         integration_delay,
+                        size,
                     );
                 }
                 _ => {
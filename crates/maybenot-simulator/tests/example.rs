@@ -28,13 +28,21 @@ fn simple_machine_for_example() {
             },
             start: 20.0 * 1000.0,
             max: 0.0,
+            ..Default::default()
         },
         limit: None,
     });
     let m = Machine::new(0, 0.0, 0, 0.0, vec![s0, s1]).unwrap();
     assert_eq!(
-        m.serialize(),
-        "02eNp1ibEJAEAIA5Nf7B3N0v1cSESwEL0m5A6YvBqSgP7WeXfM5UoBW7ICYg=="
+        m.serialize().unwrap(),
+        "03eNrVUkFugzAQ/IvPKEp6zBeqVpGqnhBCi72AVWNT26ipEH/vGAipSD9QDti7O7PrGXsUZIz7YlX2pJS2DVb5wTGI8zETHV23fO1JInlA+kapjANWlZ2W3gWWG2cu7EghUmS0zUdBMmpnxdkOxmRCusFG9qjMcfoVgLfk0droTscbcmsrnVX6jx6PhYb6x2QNARVkbrG+7kd5rj2HdkmWd2r0ZMPcDFJG8ep8R+aNLYh5fspOh2NRTFN21zgKVNVlMTGF1XdPAWQcInAa1BuSvMVRd+yGmJBKh3l9t7rGnLSF76udrW7aeYtpyVsP6NMR32G5gxU22KRJvKw3BCeCAOGX1sT/x1cwTbuTpieGlLMlfIf0Z+b+gm2yPxM9eepWiOfPQXtennryvYxtmuiMWq35ARTiG4U="
+    );
+    // the same machine as serialized with version 2 of the format
+    assert_eq!(
+        Machine::from_str("02eNp1ibEJAEAIA5Nf7B3N0v1cSESwEL0m5A6YvBqSgP7WeXfM5UoBW7ICYg==")
+            .unwrap()
+            .name(),
+        m.name()
     );
 }
 
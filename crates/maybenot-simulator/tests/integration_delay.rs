@@ -35,6 +35,7 @@ fn get_test_machine() -> Machine {
             },
             start: 5.0 * 1000.0,
             max: 0.0,
+            ..Default::default()
         },
         limit: None,
     });
@@ -117,6 +117,7 @@ fn test_network_aggregate_base_delay_on_bypass_replace() {
     s1.action = Some(Action::BlockOutgoing {
         bypass: true,
         replace: false,
+        lane: 0,
         timeout: Dist {
             dist: DistType::Uniform {
                 low: 0.0,
@@ -124,6 +125,7 @@ fn test_network_aggregate_base_delay_on_bypass_replace() {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         },
         duration: Dist {
             dist: DistType::Uniform {
@@ -132,6 +134,7 @@ fn test_network_aggregate_base_delay_on_bypass_replace() {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         },
         limit: None,
     });
@@ -148,6 +151,7 @@ fn test_network_aggregate_base_delay_on_bypass_replace() {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         },
         limit: None,
     });
@@ -516,6 +520,7 @@ fn ratio_machine(n: usize) -> Machine {
     blocking_state.action = Some(Action::BlockOutgoing {
         bypass: true,
         replace: true,
+        lane: 0,
         timeout: Dist {
             dist: DistType::Uniform {
                 low: 0.0,
@@ -523,6 +528,7 @@ fn ratio_machine(n: usize) -> Machine {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         },
         duration: Dist {
             dist: DistType::Uniform {
@@ -531,6 +537,7 @@ fn ratio_machine(n: usize) -> Machine {
             },
             start: MAX_SAMPLED_BLOCK_DURATION,
             max: 0.0,
+            ..Default::default()
         },
         limit: None,
     });
@@ -562,6 +569,7 @@ fn ratio_machine(n: usize) -> Machine {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         },
         limit: None,
     });
@@ -2,7 +2,7 @@ pub mod common;
 
 use common::{run_test_sim, set_bypass, set_replace};
 
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use maybenot::{
     action::Action,
@@ -10,7 +10,10 @@ use maybenot::{
     dist::{Dist, DistType},
     event::Event,
     state::{State, Trans},
-    Machine, Timer,
+    Machine, MachineId, Timer, TriggerEvent,
+};
+use maybenot_simulator::{
+    network::Network, parse_trace, queue::SimQueue, sim_advanced, SimulatorArgs,
 };
 
 use enum_map::enum_map;
@@ -63,6 +66,7 @@ fn test_simple_pad_machine() {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         },
         limit: None,
     });
@@ -132,6 +136,7 @@ fn test_simple_block_machine() {
     s1.action = Some(Action::BlockOutgoing {
         bypass: false,
         replace: false,
+        lane: 0,
         timeout: Dist {
             dist: DistType::Uniform {
                 low: 5.0,
@@ -139,6 +144,7 @@ fn test_simple_block_machine() {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         },
         duration: Dist {
             dist: DistType::Uniform {
@@ -147,6 +153,7 @@ fn test_simple_block_machine() {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         },
         limit: None,
     });
@@ -193,6 +200,7 @@ fn test_both_block_machine() {
     s1.action = Some(Action::BlockOutgoing {
         bypass: false,
         replace: false,
+        lane: 0,
         timeout: Dist {
             dist: DistType::Uniform {
                 low: 5.0,
@@ -200,6 +208,7 @@ fn test_both_block_machine() {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         },
         duration: Dist {
             dist: DistType::Uniform {
@@ -208,6 +217,7 @@ fn test_both_block_machine() {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         },
         limit: None,
     });
@@ -248,6 +258,7 @@ fn test_block_and_padding() {
     s1.action = Some(Action::BlockOutgoing {
         bypass: false,
         replace: false,
+        lane: 0,
         timeout: Dist {
             dist: DistType::Uniform {
                 low: 5.0,
@@ -255,6 +266,7 @@ fn test_block_and_padding() {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         },
         duration: Dist {
             dist: DistType::Uniform {
@@ -263,6 +275,7 @@ fn test_block_and_padding() {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         },
         limit: None,
     });
@@ -280,6 +293,7 @@ fn test_block_and_padding() {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         },
         limit: Some(Dist {
             dist: DistType::Uniform {
@@ -288,6 +302,7 @@ fn test_block_and_padding() {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         }),
     });
     let m = Machine::new(0, 0.0, 0, 0.0, vec![s0, s1, s2]).unwrap();
@@ -332,6 +347,7 @@ fn test_bypass_machine() {
     s1.action = Some(Action::BlockOutgoing {
         bypass: true,
         replace: false,
+        lane: 0,
         timeout: Dist {
             dist: DistType::Uniform {
                 low: 5.0,
@@ -339,6 +355,7 @@ fn test_bypass_machine() {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         },
         duration: Dist {
             dist: DistType::Uniform {
@@ -347,6 +364,7 @@ fn test_bypass_machine() {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         },
         limit: None,
     });
@@ -364,6 +382,7 @@ fn test_bypass_machine() {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         },
         limit: Some(Dist {
             dist: DistType::Uniform {
@@ -372,6 +391,7 @@ fn test_bypass_machine() {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         }),
     });
     let mut m = Machine::new(0, 0.0, 0, 0.0, vec![s0, s1, s2]).unwrap();
@@ -501,6 +521,7 @@ fn test_bypass_replace_machine() {
     s1.action = Some(Action::BlockOutgoing {
         bypass: false,
         replace: false,
+        lane: 0,
         timeout: Dist {
             dist: DistType::Uniform {
                 low: 1.0,
@@ -508,6 +529,7 @@ fn test_bypass_replace_machine() {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         },
         duration: Dist {
             dist: DistType::Uniform {
@@ -516,6 +538,7 @@ fn test_bypass_replace_machine() {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         },
         limit: None,
     });
@@ -534,6 +557,7 @@ fn test_bypass_replace_machine() {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         },
         limit: Some(Dist {
             dist: DistType::Uniform {
@@ -542,6 +566,7 @@ fn test_bypass_replace_machine() {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         }),
     });
     let mut m = Machine::new(0, 0.0, 0, 0.0, vec![s0, s1, s2]).unwrap();
@@ -655,6 +680,7 @@ fn test_bypass_replace_machine() {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         });
     };
     run_test_sim(
@@ -712,6 +738,7 @@ fn test_timer_action_basic() {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         },
         limit: None,
     });
@@ -732,6 +759,7 @@ fn test_timer_action_basic() {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         },
         limit: None,
     });
@@ -770,6 +798,7 @@ fn test_timer_action_longest() {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         },
         limit: None,
     });
@@ -786,6 +815,7 @@ fn test_timer_action_longest() {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         },
         limit: None,
     });
@@ -802,6 +832,7 @@ fn test_timer_action_longest() {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         },
         limit: None,
     });
@@ -840,6 +871,7 @@ fn test_timer_action_replace() {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         },
         limit: None,
     });
@@ -856,6 +888,7 @@ fn test_timer_action_replace() {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         },
         limit: None,
     });
@@ -872,6 +905,7 @@ fn test_timer_action_replace() {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         },
         limit: None,
     });
@@ -911,6 +945,7 @@ fn test_action_cancel_timer_internal() {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         },
         limit: None,
     });
@@ -927,6 +962,7 @@ fn test_action_cancel_timer_internal() {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         },
         limit: None,
     });
@@ -973,6 +1009,7 @@ fn test_action_cancel_timer_action() {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         },
         limit: None,
     });
@@ -989,6 +1026,7 @@ fn test_action_cancel_timer_action() {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         },
         limit: None,
     });
@@ -1035,6 +1073,7 @@ fn test_action_cancel_timer_both() {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         },
         limit: None,
     });
@@ -1051,6 +1090,7 @@ fn test_action_cancel_timer_both() {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         },
         limit: None,
     });
@@ -1073,6 +1113,158 @@ fn test_action_cancel_timer_both() {
     );
 }
 
+#[test_log::test]
+fn test_action_cancel_timer_action_blocking() {
+    // one machine schedules padding and another blocking, then both cancel
+    // only pending blocking: observe the padding but no blocking
+    let s0 = State::new(enum_map! {
+        Event::NormalSent => vec![Trans(1, 1.0)],
+        _ => vec![],
+    });
+    let mut s1 = State::new(enum_map! {
+        Event::NormalSent => vec![Trans(2, 1.0)],
+        _ => vec![],
+    });
+    s1.action = Some(Action::SendPadding {
+        bypass: false,
+        replace: false,
+        timeout: Dist {
+            dist: DistType::Uniform {
+                low: 4.0,
+                high: 4.0,
+            },
+            start: 0.0,
+            max: 0.0,
+            ..Default::default()
+        },
+        limit: None,
+    });
+    let mut s2 = State::new(enum_map! {
+        _ => vec![],
+    });
+    s2.action = Some(Action::Cancel {
+        timer: Timer::ActionBlocking,
+    });
+    let padding = Machine::new(0, 0.0, 0, 0.0, vec![s0.clone(), s1, s2.clone()]).unwrap();
+
+    let mut s1 = State::new(enum_map! {
+        Event::NormalSent => vec![Trans(2, 1.0)],
+        _ => vec![],
+    });
+    s1.action = Some(Action::BlockOutgoing {
+        bypass: false,
+        replace: false,
+        lane: 0,
+        timeout: Dist {
+            dist: DistType::Uniform {
+                low: 4.0,
+                high: 4.0,
+            },
+            start: 0.0,
+            max: 0.0,
+            ..Default::default()
+        },
+        duration: Dist {
+            dist: DistType::Uniform {
+                low: 10.0,
+                high: 10.0,
+            },
+            start: 0.0,
+            max: 0.0,
+            ..Default::default()
+        },
+        limit: None,
+    });
+    let blocking = Machine::new(0, 0.0, 0, 0.0, vec![s0, s1, s2]).unwrap();
+
+    run_test_sim(
+        "0,sn 1,sn 6,rn 7,sn",
+        "0,sn 0,st 1,sn 1,st 4,sp 4,st 6,rt 6,rn 7,sn 7,st",
+        Duration::from_micros(5),
+        &[padding, blocking],
+        &[],
+        true,
+        100,
+        false,
+    );
+}
+
+#[test_log::test]
+fn test_action_cancel_timer_action_blocking_incoming() {
+    // server machine that schedules blocking of incoming traffic on a
+    // received packet, then cancels it as blocking: no blocking is observed
+    let s0 = State::new(enum_map! {
+        Event::TunnelRecv => vec![Trans(1, 1.0)],
+        _ => vec![],
+    });
+    let mut s1 = State::new(enum_map! {
+        Event::NormalRecv => vec![Trans(2, 1.0)],
+        _ => vec![],
+    });
+    s1.action = Some(Action::BlockIncoming {
+        replace: false,
+        timeout: Dist::constant(4.0),
+        duration: Dist::constant(30.0),
+        limit: None,
+    });
+    let mut s2 = State::new(enum_map! {
+        _ => vec![],
+    });
+    s2.action = Some(Action::Cancel {
+        timer: Timer::ActionBlocking,
+    });
+    let m = Machine::new(0, 0.0, 0, 0.0, vec![s0, s1, s2]).unwrap();
+
+    run_test_sim(
+        "0,sn 18,sn",
+        "5,rt 5,rn 23,rt 23,rn",
+        Duration::from_micros(5),
+        &[],
+        &[m],
+        false,
+        0,
+        false,
+    );
+}
+
+#[test_log::test]
+fn test_action_cancel_timer_action_blocking_holding() {
+    // client machine that schedules holding of outgoing traffic on a sent
+    // packet, then cancels it as blocking: no packet is held
+    let s0 = State::new(enum_map! {
+        Event::NormalSent => vec![Trans(1, 1.0)],
+        _ => vec![],
+    });
+    let mut s1 = State::new(enum_map! {
+        Event::TunnelSent => vec![Trans(2, 1.0)],
+        _ => vec![],
+    });
+    s1.action = Some(Action::HoldOutgoing {
+        probability: Dist::constant(1.0),
+        timeout: Dist::constant(4.0),
+        duration: Dist::constant(30.0),
+        limit: None,
+    });
+    let mut s2 = State::new(enum_map! {
+        _ => vec![],
+    });
+    s2.action = Some(Action::Cancel {
+        timer: Timer::ActionBlocking,
+    });
+    let m = Machine::new(0, 0.0, 0, 0.0, vec![s0, s1, s2]).unwrap();
+
+    run_test_sim(
+        "0,sn 18,sn",
+        "0,sn 0,st 18,sn 18,st",
+        Duration::from_micros(5),
+        &[m],
+        &[],
+        true,
+        0,
+        false,
+    );
+}
+
 #[test_log::test]
 fn test_counter_machine() {
     // Add 5 to the counter in the first state, then subtract 2 in the second,
@@ -1096,6 +1288,7 @@ fn test_counter_machine() {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             },
         )),
         None,
@@ -1114,6 +1307,7 @@ fn test_counter_machine() {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             },
         )),
         None,
@@ -1137,6 +1331,7 @@ fn test_counter_machine() {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         },
         limit: None,
     });
@@ -1179,6 +1374,7 @@ fn test_counter_machine() {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             },
         )),
     );
@@ -1193,6 +1389,7 @@ fn test_counter_machine() {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             },
         )),
     );
@@ -1219,6 +1416,7 @@ fn test_counter_machine() {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             },
         )),
     );
@@ -1233,3 +1431,855 @@ fn test_counter_machine() {
         false,
     );
 }
+
+#[test_log::test]
+fn test_packet_sizes() {
+    // pad once, 1 ms after each normal packet sent
+    let s0 = State::new(enum_map! {
+        Event::NormalSent => vec![Trans(1, 1.0)],
+        _ => vec![],
+    });
+    let mut s1 = State::new(enum_map! {
+        Event::PaddingSent => vec![Trans(0, 1.0)],
+        _ => vec![],
+    });
+    s1.action = Some(Action::SendPadding {
+        bypass: false,
+        replace: false,
+        timeout: Dist {
+            dist: DistType::Uniform {
+                low: 1000.0,
+                high: 1000.0,
+            },
+            start: 0.0,
+            max: 0.0,
+            ..Default::default()
+        },
+        limit: None,
+    });
+    let m = Machine::new(u64::MAX, 0.0, 0, 0.0, vec![s0, s1]).unwrap();
+
+    let raw_trace = "0,s,100
+    20000000,r,1000
+    40000000,s,200
+    60000000,r,1200
+    80000000,s,300";
+    let network = Network::new(Duration::from_millis(5), None);
+    let mut args = SimulatorArgs::new(&network, 0, true);
+    args.padding_size = 500;
+
+    // bytes (client sent, client received, server received) of normal and
+    // padding packets, respectively
+    let bytes = |machines: &[Machine]| {
+        let mut sq = parse_trace(raw_trace, &network);
+        let trace = sim_advanced(machines, &[], &mut sq, &args);
+        let mut bytes = [[0; 2]; 3];
+        for e in trace {
+            let i = match (e.client, &e.event) {
+                (true, TriggerEvent::TunnelSent) => 0,
+                (true, TriggerEvent::TunnelRecv) => 1,
+                (false, TriggerEvent::TunnelRecv) => 2,
+                _ => continue,
+            };
+            bytes[i][e.contains_padding as usize] += e.size;
+        }
+        bytes
+    };
+
+    // sizes from the base trace are preserved end-to-end
+    assert_eq!(bytes(&[]), [[600, 0], [2200, 0], [600, 0]]);
+    // with one padding packet per sent normal packet
+    assert_eq!(bytes(&[m]), [[600, 1500], [2200, 0], [600, 1500]]);
+}
+
+#[test_log::test]
+fn test_padding_recv_transition() {
+    // pads once on entering state 1, with no further transitions
+    let pad_once = || {
+        let mut s1 = State::new(enum_map! {
+            _ => vec![],
+        });
+        s1.action = Some(Action::SendPadding {
+            bypass: false,
+            replace: false,
+            timeout: Dist {
+                dist: DistType::Uniform {
+                    low: 0.0,
+                    high: 0.0,
+                },
+                start: 0.0,
+                max: 0.0,
+                ..Default::default()
+            },
+            limit: None,
+        });
+        s1
+    };
+    // the server pads once it receives a normal packet, and the client pads
+    // once it receives the padding of the server
+    let s0 = State::new(enum_map! {
+        Event::NormalRecv => vec![Trans(1, 1.0)],
+        _ => vec![],
+    });
+    let server = vec![Machine::new(u64::MAX, 0.0, 0, 0.0, vec![s0, pad_once()]).unwrap()];
+    let s0 = State::new(enum_map! {
+        Event::PaddingRecv => vec![Trans(1, 1.0)],
+        _ => vec![],
+    });
+    let client = vec![Machine::new(u64::MAX, 0.0, 0, 0.0, vec![s0, pad_once()]).unwrap()];
+
+    let delay = Duration::from_micros(5);
+    run_test_sim(
+        "0,sn",
+        "5,rt 5,rn 5,sp 5,st 15,rt 15,rp",
+        delay,
+        &client,
+        &server,
+        false,
+        0,
+        false,
+    );
+    run_test_sim(
+        "0,sn",
+        "0,sn 0,st 10,rt 10,rp 10,sp 10,st",
+        delay,
+        &client,
+        &server,
+        true,
+        0,
+        false,
+    );
+
+    // the padding arrives with the configured size, flagged as padding
+    let network = Network::new(delay, None);
+    let mut args = SimulatorArgs::new(&network, 0, true);
+    args.padding_size = 500;
+    let mut sq = common::make_sq("0,sn,100".to_string(), delay, Instant::now());
+    let trace = sim_advanced(&client, &server, &mut sq, &args);
+    let recv: Vec<(bool, bool, u64)> = trace
+        .iter()
+        .filter(|e| e.event == TriggerEvent::TunnelRecv)
+        .map(|e| (e.client, e.contains_padding, e.size))
+        .collect();
+    assert_eq!(
+        recv,
+        vec![(false, false, 100), (true, true, 500), (false, true, 500)]
+    );
+}
+
+#[test_log::test]
+fn test_block_incoming() {
+    // server machine that, on the first received packet, blocks incoming
+    // traffic for 30 us
+    let s0 = State::new(enum_map! {
+        Event::NormalRecv => vec![Trans(1, 1.0)],
+        _ => vec![],
+    });
+    let mut s1 = State::new(enum_map! {
+        _ => vec![],
+    });
+    s1.action = Some(Action::BlockIncoming {
+        replace: false,
+        timeout: Dist {
+            dist: DistType::Uniform {
+                low: 0.0,
+                high: 0.0,
+            },
+            start: 0.0,
+            max: 0.0,
+            ..Default::default()
+        },
+        duration: Dist {
+            dist: DistType::Uniform {
+                low: 30.0,
+                high: 30.0,
+            },
+            start: 0.0,
+            max: 0.0,
+            ..Default::default()
+        },
+        limit: None,
+    });
+    let m = Machine::new(0, 0.0, 0, 0.0, vec![s0, s1]).unwrap();
+
+    // the second packet is received at 23 us, but only delivered once the
+    // blocking ends at 35 us
+    run_test_sim(
+        "0,sn 18,sn",
+        "5,rt 5,rn 5,ib 23,rt 35,rn 35,ie",
+        Duration::from_micros(5),
+        &[],
+        &[m],
+        false,
+        0,
+        false,
+    );
+}
+
+#[test_log::test]
+fn test_replace_padding_bytes() {
+    // constant-rate padding every 1 ms, starting on the first normal packet
+    // sent, for a limited number of padding packets
+    let s0 = State::new(enum_map! {
+        Event::NormalSent => vec![Trans(1, 1.0)],
+        _ => vec![],
+    });
+    let mut s1 = State::new(enum_map! {
+        Event::PaddingSent => vec![Trans(1, 1.0)],
+        _ => vec![],
+    });
+    s1.action = Some(Action::SendPadding {
+        bypass: false,
+        replace: false,
+        timeout: Dist {
+            dist: DistType::Uniform {
+                low: 1000.0,
+                high: 1000.0,
+            },
+            start: 0.0,
+            max: 0.0,
+            ..Default::default()
+        },
+        limit: Some(Dist {
+            dist: DistType::Uniform {
+                low: 5.0,
+                high: 5.0,
+            },
+            start: 0.0,
+            max: 0.0,
+            ..Default::default()
+        }),
+    });
+    let m = Machine::new(u64::MAX, 0.0, 0, 0.0, vec![s0.clone(), s1.clone()]).unwrap();
+    set_replace(&mut s1, true);
+    let m_replace = Machine::new(u64::MAX, 0.0, 0, 0.0, vec![s0, s1]).unwrap();
+
+    // normal packets sent at the same time as the first three padding packets
+    let raw_trace = "0,s,100
+    1000000,s,100
+    2000000,s,100
+    3000000,s,100";
+    let network = Network::new(Duration::from_millis(5), None);
+    let mut args = SimulatorArgs::new(&network, 0, true);
+    args.padding_size = 500;
+
+    // bytes sent by the client of normal and padding packets, respectively
+    let bytes = |machines: &[Machine]| {
+        let mut sq = parse_trace(raw_trace, &network);
+        let trace = sim_advanced(machines, &[], &mut sq, &args);
+        let mut bytes = [0; 2];
+        for e in trace.iter().filter(|e| e.client) {
+            if e.event == TriggerEvent::TunnelSent {
+                bytes[e.contains_padding as usize] += e.size;
+            }
+        }
+        bytes
+    };
+
+    // five padding packets, at 1 to 5 ms
+    assert_eq!(bytes(&[m]), [400, 5 * 500]);
+    // the machine pads the same, but the normal packets at 1 to 3 ms take the
+    // slots of the replaceable padding, which is never put on the wire
+    assert_eq!(bytes(&[m_replace]), [400, 2 * 500]);
+}
+
+#[test_log::test]
+fn test_equal_time_events_fifo() {
+    // events at the same time, of the same type, and from the same side are
+    // popped in the order they were pushed, told apart by their sizes
+    let mut sq = SimQueue::new();
+    let time = Instant::now();
+    for size in [3, 1, 4, 1, 5, 9, 2, 6] {
+        sq.push(
+            TriggerEvent::NormalSent,
+            true,
+            false,
+            time,
+            Duration::ZERO,
+            size,
+        );
+    }
+    let mut sizes = vec![];
+    while let (Some(e), q, _) = sq.peek(Duration::ZERO, time) {
+        let client = e.client;
+        sizes.push(sq.pop(q, client, Duration::ZERO).unwrap().size);
+    }
+    assert_eq!(sizes, vec![3, 1, 4, 1, 5, 9, 2, 6]);
+
+    // and so are they in the resulting trace
+    let raw_trace = "0,s,300
+    0,s,100
+    0,s,400
+    0,s,200";
+    let network = Network::new(Duration::from_millis(5), None);
+    let args = SimulatorArgs::new(&network, 0, true);
+    let mut sq = parse_trace(raw_trace, &network);
+    let trace = sim_advanced(&[], &[], &mut sq, &args);
+    let sizes: Vec<u64> = trace
+        .iter()
+        .filter(|e| e.client && e.event == TriggerEvent::TunnelSent)
+        .map(|e| e.size)
+        .collect();
+    assert_eq!(sizes, vec![300, 100, 400, 200]);
+}
+
+#[test_log::test]
+fn test_constant_rate_machine() {
+    // client pads every 10 us once it sends its first packet, and the normal
+    // packet at 20 us replaces the padding due at the same time
+    assert!(Machine::constant_rate(0).is_err());
+    let m = Machine::constant_rate(10).unwrap();
+    run_test_sim(
+        "0,sn 20,sn",
+        "0,sn 0,st 10,sp 10,st 20,sn 20,st 20,sp 30,sp 30,st 40,sp 40,st 50,sp 50,st 60,sp 60,st 70,sp",
+        Duration::from_micros(5),
+        std::slice::from_ref(&m),
+        &[],
+        true,
+        30,
+        false,
+    );
+}
+
+#[test_log::test]
+fn test_fail_closed_constant_rate_machine() {
+    use maybenot_simulator::sim;
+
+    assert!(Machine::fail_closed_constant_rate(0).is_err());
+    let m = Machine::fail_closed_constant_rate(100).unwrap();
+    assert!(m.lint().is_empty());
+
+    // bursts of normal packets, with gaps, are only sent on the grid: each
+    // sent packet is either queued normal traffic or padding
+    let input = "0,sn 1,sn 2,sn 3,sn 4,sn 250,sn 251,sn 252,sn 1000,sn".to_string();
+    let delay = Duration::from_micros(5);
+    let mut sq = common::make_sq(input, delay, Instant::now());
+    let trace = sim(&[m], &[], &mut sq, delay, 100, true);
+
+    let sent: Vec<_> = trace
+        .iter()
+        .filter(|e| e.client && e.event == TriggerEvent::TunnelSent)
+        .collect();
+    let base = sent[0].time;
+    let times: Vec<u128> = sent
+        .iter()
+        .map(|e| e.time.duration_since(base).as_micros())
+        .collect();
+    // the first normal packet triggers the machine, then one packet per period
+    assert!(times.len() > 20);
+    for (i, time) in times.iter().enumerate() {
+        assert_eq!(*time, i as u128 * 100);
+    }
+
+    // all normal packets were sent, in place of padding
+    let normal = sent.iter().filter(|e| !e.contains_padding).count();
+    assert_eq!(normal, 9);
+}
+
+#[test_log::test]
+fn test_diff_traces() {
+    use maybenot_simulator::{diff::diff_traces, sim};
+
+    // pad once after the first sent packet
+    let pad = |timeout: f64| {
+        Some(Action::SendPadding {
+            bypass: false,
+            replace: false,
+            timeout: Dist {
+                dist: DistType::Uniform {
+                    low: timeout,
+                    high: timeout,
+                },
+                start: 0.0,
+                max: 0.0,
+                ..Default::default()
+            },
+            limit: None,
+        })
+    };
+    let s0 = State::new(enum_map! {
+        Event::NormalSent => vec![Trans(1, 1.0)],
+        _ => vec![],
+    });
+    let mut s1 = State::new(enum_map! {
+        _ => vec![],
+    });
+    s1.action = pad(2.0);
+    let old = Machine::new(100, 1.0, 0, 0.0, vec![s0.clone(), s1.clone()]).unwrap();
+
+    // and once more, in an added state
+    s1 = State::new(enum_map! {
+        Event::PaddingSent => vec![Trans(2, 1.0)],
+        _ => vec![],
+    });
+    s1.action = pad(2.0);
+    let mut s2 = State::new(enum_map! {
+        _ => vec![],
+    });
+    s2.action = pad(3.0);
+    let new = Machine::new(100, 1.0, 0, 0.0, vec![s0, s1, s2]).unwrap();
+
+    let input = "0,sn 10,sn 20,rn";
+    let delay = Duration::from_micros(5);
+    let start = Instant::now();
+    let run = |m: &Machine| {
+        let mut sq = common::make_sq(input.to_string(), delay, start);
+        sim(std::slice::from_ref(m), &[], &mut sq, delay, 0, true)
+            .into_iter()
+            .filter(|e| e.client)
+            .collect::<Vec<_>>()
+    };
+    let (old, new) = (run(&old), run(&new));
+
+    let diff = diff_traces(&old, &new);
+    assert_eq!(diff.added_padding.len(), 1);
+    assert_eq!(
+        diff.added_padding[0].time.duration_since(start),
+        Duration::from_micros(5)
+    );
+    assert_eq!(diff.added_padding[0].event, TriggerEvent::TunnelSent);
+    assert!(diff.removed_padding.is_empty());
+    assert!(diff.shifted.is_empty());
+    assert_eq!(diff.padding_delta(), 1);
+
+    // and the other way around
+    let diff = diff_traces(&new, &old);
+    assert_eq!(diff.removed_padding.len(), 1);
+    assert_eq!(diff.padding_delta(), -1);
+    assert!(diff_traces(&old, &old).is_empty());
+}
+
+#[test_log::test]
+fn test_bypass_replace_matrix() {
+    // the on-wire outcome of padding during blocking, for each combination of
+    // the bypass and replace flags of the padding and the bypass flag of the
+    // blocking, as specified for TriggerAction::SendPadding
+    let s0 = State::new(enum_map! {
+        Event::NormalSent => vec![Trans(1, 1.0)],
+        _ => vec![],
+    });
+    // 1: block for 1000us after 1us
+    let mut s1 = State::new(enum_map! {
+        Event::BlockingBegin => vec![Trans(2, 1.0)],
+        _ => vec![],
+    });
+    s1.action = Some(Action::BlockOutgoing {
+        bypass: false,
+        replace: false,
+        lane: 0,
+        timeout: Dist::constant(1.0),
+        duration: Dist::constant(1000.0),
+        limit: None,
+    });
+    // 2: pad once, 2us into the blocking
+    let mut s2 = State::new(enum_map! {
+        _ => vec![],
+    });
+    s2.action = Some(Action::SendPadding {
+        bypass: false,
+        replace: false,
+        timeout: Dist::constant(2.0),
+        limit: None,
+    });
+    let m = Machine::new(0, 0.0, 0, 0.0, vec![s0, s1, s2]).unwrap();
+
+    // a normal packet is blocked at 2us, queued when the padding is due
+    for (block_bypass, pad_bypass, pad_replace, output) in [
+        // neither: the padding waits for the blocking to end
+        (false, false, false, "0,st 1001,st 1001,st"),
+        (true, false, false, "0,st 1001,st 1001,st"),
+        // bypass only: the padding is sent if the blocking is bypassable
+        (false, true, false, "0,st 1001,st 1001,st"),
+        (true, true, false, "0,st 3,st 1001,st"),
+        // replace only: the queued normal packet replaces the padding, but it
+        // is still blocked
+        (false, false, true, "0,st 1001,st"),
+        (true, false, true, "0,st 1001,st"),
+        // both: the queued normal packet replaces the padding, and is sent if
+        // the blocking is bypassable
+        (false, true, true, "0,st 1001,st"),
+        (true, true, true, "0,st 3,st"),
+    ] {
+        let mut m = m.clone();
+        set_bypass(&mut m.states[1], block_bypass);
+        set_bypass(&mut m.states[2], pad_bypass);
+        set_replace(&mut m.states[2], pad_replace);
+        // the complete trace, not only a prefix as in run_test_sim()
+        let start = Instant::now();
+        let delay = Duration::from_micros(5);
+        let mut sq = common::make_sq("0,sn 2,sn".to_string(), delay, start);
+        let trace = maybenot_simulator::sim(&[m], &[], &mut sq, delay, 0, true);
+        let fmt = trace
+            .iter()
+            .filter(|e| e.client)
+            .map(|e| format!("{},{}", e.time.duration_since(start).as_micros(), e.event))
+            .collect::<Vec<_>>()
+            .join(" ");
+        assert_eq!(
+            fmt, output,
+            "block bypass {}, padding bypass {}, padding replace {}",
+            block_bypass, pad_bypass, pad_replace
+        );
+    }
+}
+
+#[test]
+fn test_compute_overhead_warmup() {
+    use maybenot_simulator::{overhead::compute_overhead, sim};
+
+    // on the first sent packet, pad a burst of 10 packets, then end
+    let s0 = State::new(enum_map! {
+        Event::NormalSent => vec![Trans(1, 1.0)],
+        _ => vec![],
+    });
+    let mut s1 = State::new(enum_map! {
+        Event::PaddingSent => vec![Trans(1, 1.0)],
+        Event::LimitReached => vec![Trans(maybenot::constants::STATE_END, 1.0)],
+        _ => vec![],
+    });
+    s1.action = Some(Action::SendPadding {
+        bypass: false,
+        replace: false,
+        timeout: Dist::constant(10.0),
+        limit: Some(Dist::constant(10.0)),
+    });
+    let m = Machine::new(100, 1.0, 0, 0.0, vec![s0, s1]).unwrap();
+
+    // the client sends a packet every millisecond for 100 ms
+    let input = (0..100)
+        .map(|i| format!("{},sn", i * 1000))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let delay = Duration::from_micros(5);
+    let mut sq = common::make_sq(input, delay, Instant::now());
+    let trace = sim(&[m], &[], &mut sq, delay, 0, true);
+
+    let all = compute_overhead(&trace, Duration::ZERO, Duration::ZERO);
+    assert_eq!(all.normal_packets, 100);
+    assert_eq!(all.padding_packets, 10);
+    assert!((all.padding_fraction() - 10.0 / 110.0).abs() < 1e-9);
+
+    // the padding is all within the first millisecond
+    let steady = compute_overhead(&trace, Duration::from_millis(1), Duration::ZERO);
+    assert_eq!(steady.normal_packets, 99);
+    assert_eq!(steady.padding_packets, 0);
+    assert_eq!(steady.padding_fraction(), 0.0);
+
+    // a cooldown excludes the last packets
+    let windowed = compute_overhead(&trace, Duration::ZERO, Duration::from_micros(9500));
+    assert_eq!(windowed.normal_packets, 90);
+    assert_eq!(windowed.padding_packets, 10);
+    assert!(windowed.padding_fraction() > all.padding_fraction());
+
+    // and nothing is left if the windows overlap
+    let none = compute_overhead(&trace, Duration::from_millis(60), Duration::from_millis(60));
+    assert_eq!(none, Default::default());
+}
+
+#[test]
+fn test_sized_trace_overhead() {
+    use maybenot_simulator::{overhead::compute_overhead, sim, DEFAULT_PADDING_SIZE};
+
+    // pad once on the first sent packet, then end
+    let s0 = State::new(enum_map! {
+        Event::NormalSent => vec![Trans(1, 1.0)],
+        _ => vec![],
+    });
+    let mut s1 = State::new(enum_map! {
+        Event::PaddingSent => vec![Trans(maybenot::constants::STATE_END, 1.0)],
+        _ => vec![],
+    });
+    s1.action = Some(Action::SendPadding {
+        bypass: false,
+        replace: false,
+        timeout: Dist {
+            dist: DistType::Uniform {
+                low: 10.0,
+                high: 10.0,
+            },
+            start: 0.0,
+            max: 0.0,
+            ..Default::default()
+        },
+        limit: None,
+    });
+    let m = Machine::new(100, 1.0, 0, 0.0, vec![s0, s1]).unwrap();
+
+    // sizes are optional, defaulting to 0
+    let input = "0,sn,1400 1000,rn,512 2000,sn 3000,sn,100".to_string();
+    let delay = Duration::from_micros(5);
+    let mut sq = common::make_sq(input, delay, Instant::now());
+    let trace = sim(&[m], &[], &mut sq, delay, 0, true);
+
+    let sizes: Vec<u64> = trace
+        .iter()
+        .filter(|e| e.event == TriggerEvent::TunnelSent && !e.contains_padding)
+        .map(|e| e.size)
+        .collect();
+    assert_eq!(sizes, vec![1400, 512, 0, 100]);
+
+    let overhead = compute_overhead(&trace, Duration::ZERO, Duration::ZERO);
+    assert_eq!(overhead.normal_packets, 4);
+    assert_eq!(overhead.normal_bytes, 2012);
+    assert_eq!(overhead.padding_packets, 1);
+    assert_eq!(overhead.padding_bytes, DEFAULT_PADDING_SIZE);
+    assert!(
+        (overhead.padding_bytes_fraction() - 1500.0 / 3512.0).abs() < 1e-9,
+        "{}",
+        overhead.padding_bytes_fraction()
+    );
+}
+
+#[test]
+fn test_time_scale() {
+    // pad, block outgoing, and set the internal timer in turn
+    let s0 = State::new(enum_map! {
+        Event::NormalSent => vec![Trans(1, 1.0)],
+        _ => vec![],
+    });
+    let mut s1 = State::new(enum_map! {
+        Event::PaddingSent => vec![Trans(2, 1.0)],
+        _ => vec![],
+    });
+    s1.action = Some(Action::SendPadding {
+        bypass: false,
+        replace: false,
+        timeout: Dist::constant(7.0),
+        limit: None,
+    });
+    let mut s2 = State::new(enum_map! {
+        Event::BlockingBegin => vec![Trans(3, 1.0)],
+        _ => vec![],
+    });
+    s2.action = Some(Action::BlockOutgoing {
+        bypass: false,
+        replace: false,
+        lane: 0,
+        timeout: Dist::constant(3.0),
+        duration: Dist::constant(11.0),
+        limit: None,
+    });
+    let mut s3 = State::new(enum_map! {
+        Event::TimerEnd => vec![Trans(0, 1.0)],
+        _ => vec![],
+    });
+    s3.action = Some(Action::UpdateTimer {
+        replace: false,
+        duration: Dist::constant(13.0),
+        limit: None,
+    });
+    let machines = [Machine::new(u64::MAX, 1.0, u64::MAX, 1.0, vec![s0, s1, s2, s3]).unwrap()];
+
+    let input = [(0, "sn"), (5, "sn"), (9, "rn"), (40, "sn"), (41, "rn")];
+    let run = |time_scale: f64| {
+        let trace = input
+            .iter()
+            .map(|(t, d)| format!("{},{}", (*t as f64 * time_scale) as u64, d))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let delay = Duration::from_micros(2);
+        let mut sq = common::make_sq(trace, delay.mul_f64(time_scale), Instant::now());
+        let network = Network::new(delay, None);
+        let mut args = SimulatorArgs::new(&network, 0, false);
+        args.max_padding_frac_client = 1.0;
+        args.max_blocking_frac_client = 1.0;
+        args.time_scale = time_scale;
+        let trace = sim_advanced(&machines, &[], &mut sq, &args);
+        let start = trace[0].time;
+        trace
+            .into_iter()
+            .map(|e| (e.time.duration_since(start), e.client, e.event))
+            .collect::<Vec<_>>()
+    };
+
+    // the same events, at twice the time
+    let base = run(1.0);
+    let scaled = run(2.0);
+    assert!(base
+        .iter()
+        .any(|(_, _, e)| matches!(e, TriggerEvent::TimerEnd { .. })));
+    assert_eq!(base.len(), scaled.len());
+    for (b, s) in base.iter().zip(scaled.iter()) {
+        assert_eq!((b.0 * 2, b.1, &b.2), (s.0, s.1, &s.2));
+    }
+}
+
+#[test_log::test]
+fn test_hold_outgoing() {
+    // client machine that, on the first normal packet sent, holds outgoing
+    // packets with probability 0.3 for a second
+    let s0 = State::new(enum_map! {
+        Event::NormalSent => vec![Trans(1, 1.0)],
+        _ => vec![],
+    });
+    let mut s1 = State::new(enum_map! {
+        _ => vec![],
+    });
+    s1.action = Some(Action::HoldOutgoing {
+        probability: Dist::constant(0.3),
+        timeout: Dist::constant(0.0),
+        duration: Dist::constant(1_000_000.0),
+        limit: None,
+    });
+    let m = Machine::new(0, 0.0, 0, 0.0, vec![s0, s1]).unwrap();
+
+    // 1000 normal packets, one every 100 us, all sent before the holding ends
+    let raw_trace: String = (0..1000)
+        .map(|i| format!("{},s,100\n", i * 100_000))
+        .collect();
+    let network = Network::new(Duration::from_millis(5), None);
+    let mut args = SimulatorArgs::new(&network, 0, true);
+    args.insecure_rng_seed = Some(0);
+    let mut sq = parse_trace(&raw_trace, &network);
+    let trace = sim_advanced(&[m], &[], &mut sq, &args);
+
+    let sent: Vec<_> = trace
+        .iter()
+        .filter(|e| e.client && e.event == TriggerEvent::TunnelSent)
+        .collect();
+    assert_eq!(sent.len(), 1000);
+    // held packets are all sent once the holding ends
+    let start = sent[0].time;
+    let held = sent
+        .iter()
+        .filter(|e| e.time.duration_since(start) >= Duration::from_millis(999))
+        .count();
+    // the first packet triggers the holding, so is never held
+    let frac = held as f64 / 999.0;
+    assert!((frac - 0.3).abs() < 0.05, "held fraction {}", frac);
+}
+
+#[test_log::test]
+fn test_check_contracts() {
+    // the client blocks on its first packet and pads at a constant rate, all
+    // within the contracts, so checking them does not change the trace
+    let s0 = State::new(enum_map! {
+        Event::NormalSent => vec![Trans(1, 1.0)],
+        _ => vec![],
+    });
+    let mut s1 = State::new(enum_map! {
+        _ => vec![],
+    });
+    s1.action = Some(Action::BlockOutgoing {
+        bypass: true,
+        replace: false,
+        lane: 0,
+        timeout: Dist {
+            dist: DistType::Uniform {
+                low: 0.0,
+                high: 0.0,
+            },
+            start: 0.0,
+            max: 0.0,
+            ..Default::default()
+        },
+        duration: Dist {
+            dist: DistType::Uniform {
+                low: 20.0,
+                high: 20.0,
+            },
+            start: 0.0,
+            max: 0.0,
+            ..Default::default()
+        },
+        limit: None,
+    });
+    let machines = vec![
+        Machine::new(u64::MAX, 0.0, u64::MAX, 0.0, vec![s0, s1]).unwrap(),
+        Machine::constant_rate(10).unwrap(),
+    ];
+
+    let network = Network::new(Duration::from_micros(5), None);
+    let mut args = SimulatorArgs::new(&network, 100, false);
+    args.insecure_rng_seed = Some(0);
+    let run = |args: &SimulatorArgs<'_>| {
+        let mut sq = parse_trace("0,s\n10000,s\n20000,r\n50000,s", &network);
+        let trace = sim_advanced(&machines, &machines, &mut sq, args);
+        let start = trace[0].time;
+        trace
+            .into_iter()
+            .map(|e| (e.event, e.client, e.time - start))
+            .collect::<Vec<_>>()
+    };
+    let unchecked = run(&args);
+    args.check_contracts = true;
+    assert_eq!(run(&args), unchecked);
+
+    // and without a trace length limit, checking that all blocking ended
+    args.max_trace_length = 0;
+    args.max_sim_iterations = 0;
+    let mut sq = parse_trace("0,s\n10000,s\n20000,r", &network);
+    sim_advanced(&machines[..1], &machines[..1], &mut sq, &args);
+}
+
+#[test_log::test]
+#[should_panic(expected = "contract violation @client: event PaddingSent")]
+fn test_check_contracts_unknown_machine() {
+    // a broken integration reports padding sent by a machine that does not
+    // exist
+    let network = Network::new(Duration::from_micros(5), None);
+    let mut args = SimulatorArgs::new(&network, 0, false);
+    args.check_contracts = true;
+    let mut sq = parse_trace("0,s", &network);
+    sq.push(
+        TriggerEvent::PaddingSent {
+            machine: MachineId::from_raw(1),
+        },
+        true,
+        true,
+        sq.get_first_time().unwrap(),
+        Duration::ZERO,
+        0,
+    );
+    sim_advanced(&[Machine::noop()], &[], &mut sq, &args);
+}
+
+#[test_log::test]
+#[should_panic(
+    expected = "contract violation @client: blocking of lane 0 by machine 0 \
+    without BlockingEnd"
+)]
+fn test_check_contracts_blocking_never_ends() {
+    // a broken integration reports blocking that the framework never asked
+    // for, and so that never ends
+    let network = Network::new(Duration::from_micros(5), None);
+    let mut args = SimulatorArgs::new(&network, 0, false);
+    args.check_contracts = true;
+    let mut sq = parse_trace("0,s", &network);
+    sq.push(
+        TriggerEvent::BlockingBegin {
+            machine: MachineId::from_raw(0),
+            lane: 0,
+        },
+        true,
+        false,
+        sq.get_first_time().unwrap(),
+        Duration::ZERO,
+        0,
+    );
+    sim_advanced(&[Machine::noop()], &[], &mut sq, &args);
+}
+
+#[test_log::test]
+#[should_panic(expected = "machine 1 blocks a lane other than 0")]
+fn test_block_other_lane() {
+    // the simulated traffic is all in lane 0, so machines blocking other lanes
+    // are rejected
+    let mut s0 = State::new(enum_map! {
+        _ => vec![],
+    });
+    s0.action = Some(Action::BlockOutgoing {
+        bypass: false,
+        replace: false,
+        lane: 1,
+        timeout: Dist::constant(0.0),
+        duration: Dist::constant(10.0),
+        limit: None,
+    });
+    let m = Machine::new(0, 0.0, 0, 0.0, vec![s0]).unwrap();
+
+    let network = Network::new(Duration::from_micros(5), None);
+    let args = SimulatorArgs::new(&network, 0, false);
+    let mut sq = parse_trace("0,s", &network);
+    sim_advanced(&[Machine::noop(), m], &[], &mut sq, &args);
+}
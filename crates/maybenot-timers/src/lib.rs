@@ -0,0 +1,63 @@
+//! Timers for integrating the Maybenot [`Framework`](maybenot::Framework) with
+//! async runtimes.
+//!
+//! An integration of Maybenot needs up to two timers per machine: an *action
+//! timer* for scheduled padding and blocking, and an *internal timer* updated
+//! by the machine itself. The rules for setting, overwriting, and cancelling
+//! these timers are easy to get subtly wrong. This crate encodes them once:
+//! hand every [`TriggerAction`](maybenot::TriggerAction) from the framework to
+//! [`Timers::apply()`], feed any returned
+//! [`TriggerEvent`](maybenot::TriggerEvent) back to the framework, and await
+//! [`Timers::expired()`] for the next action to take or event to trigger.
+//!
+//! The [`Timers`] are based on [tokio](https://tokio.rs) and available with
+//! the `tokio` feature (enabled by default).
+//!
+//! ```no_run
+//! use maybenot::{Framework, Machine, TriggerAction, TriggerEvent};
+//! use maybenot_timers::{Expired, Timers};
+//! use std::{str::FromStr, time::Instant};
+//!
+//! # async fn run() {
+//! let m = vec![Machine::from_str("02eNpjYEAHjOgCAAA0AAI=").unwrap()];
+//! let mut f = Framework::new(&m, 0.0, 0.0, Instant::now(), rand::thread_rng()).unwrap();
+//! let mut timers = Timers::new();
+//! let mut events = vec![TriggerEvent::NormalSent];
+//!
+//! loop {
+//!     let mut next = vec![];
+//!     for action in f.trigger_events(&events, Instant::now()) {
+//!         // events for any started internal timers
+//!         next.extend(timers.apply(action));
+//!     }
+//!     events = next;
+//!     if !events.is_empty() {
+//!         continue;
+//!     }
+//!
+//!     match timers.expired().await {
+//!         Expired::Action(TriggerAction::SendPadding { .. }) => {
+//!             // send padding, then trigger PaddingSent
+//!         }
+//!         Expired::Action(TriggerAction::BlockOutgoing { .. }) => {
+//!             // block outgoing traffic, then trigger BlockingBegin
+//!         }
+//!         Expired::Action(TriggerAction::BlockIncoming { .. }) => {
+//!             // delay delivery of received packets, then trigger
+//!             // IncomingBlockingBegin
+//!         }
+//!         Expired::Action(TriggerAction::HoldOutgoing { .. }) => {
+//!             // hold outgoing packets with the probability, nothing to
+//!             // trigger
+//!         }
+//!         Expired::Action(_) => unreachable!(),
+//!         Expired::Event(event) => events.push(event),
+//!     }
+//! }
+//! # }
+//! ```
+
+#[cfg(feature = "tokio")]
+mod tokio_timers;
+#[cfg(feature = "tokio")]
+pub use crate::tokio_timers::{Expired, Timers};
@@ -0,0 +1,190 @@
+//! Timers based on tokio.
+
+use std::collections::HashMap;
+use std::future::{poll_fn, Future};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use maybenot::{MachineId, Timer, TriggerAction, TriggerEvent};
+use tokio::time::{sleep_until, Instant, Sleep};
+
+/// Something that happened when a timer expired.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expired {
+    /// The timeout of a scheduled action expired, so the action
+    /// ([`TriggerAction::SendPadding`], [`TriggerAction::BlockOutgoing`],
+    /// [`TriggerAction::BlockIncoming`], or [`TriggerAction::HoldOutgoing`])
+    /// MUST now be taken. Once taken, the caller MUST trigger the resulting
+    /// [`TriggerEvent::PaddingSent`], [`TriggerEvent::BlockingBegin`], or
+    /// [`TriggerEvent::IncomingBlockingBegin`], if any.
+    Action(TriggerAction),
+    /// The internal timer of a machine expired: the event
+    /// ([`TriggerEvent::TimerEnd`]) MUST be triggered.
+    Event(TriggerEvent),
+}
+
+/// The action and internal timers of all machines running in a
+/// [`Framework`](maybenot::Framework).
+///
+/// Note that tokio timers have a resolution of one millisecond: timers expire
+/// at the earliest at their deadline, rounded up to the next millisecond.
+#[derive(Debug, Default)]
+pub struct Timers {
+    action: HashMap<MachineId, (Pin<Box<Sleep>>, TriggerAction)>,
+    internal: HashMap<MachineId, Pin<Box<Sleep>>>,
+}
+
+impl Timers {
+    /// Create a new set of timers, with no timers running.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns true if no timers are running.
+    pub fn is_empty(&self) -> bool {
+        self.action.is_empty() && self.internal.is_empty()
+    }
+
+    /// Apply a [`TriggerAction`] from the framework to the timers.
+    ///
+    /// Padding, blocking (in either direction), and holding actions overwrite
+    /// any action already scheduled for the machine. Cancel actions stop the specified
+    /// timer(s) for the machine, if running. Timer update actions set the
+    /// internal timer of the machine if the replace flag is set or the new
+    /// duration is longer than what remains of the current timer.
+    ///
+    /// Returns [`TriggerEvent::TimerBegin`] if the internal timer was set, which
+    /// MUST be triggered.
+    pub fn apply(&mut self, action: &TriggerAction) -> Option<TriggerEvent> {
+        let now = Instant::now();
+
+        match action {
+            TriggerAction::Cancel { machine, timer } => {
+                match timer {
+                    Timer::Action => {
+                        self.action.remove(machine);
+                    }
+                    Timer::Internal => {
+                        self.internal.remove(machine);
+                    }
+                    Timer::All => {
+                        self.action.remove(machine);
+                        self.internal.remove(machine);
+                    }
+                    Timer::ActionPadding => {
+                        if let Some((_, TriggerAction::SendPadding { .. })) =
+                            self.action.get(machine)
+                        {
+                            self.action.remove(machine);
+                        }
+                    }
+                    Timer::ActionBlocking => {
+                        if let Some((
+                            _,
+                            TriggerAction::BlockOutgoing { .. }
+                            | TriggerAction::BlockIncoming { .. }
+                            | TriggerAction::HoldOutgoing { .. },
+                        )) = self.action.get(machine)
+                        {
+                            self.action.remove(machine);
+                        }
+                    }
+                }
+                None
+            }
+            TriggerAction::SendPadding {
+                timeout, machine, ..
+            }
+            | TriggerAction::BlockOutgoing {
+                timeout, machine, ..
+            }
+            | TriggerAction::BlockIncoming {
+                timeout, machine, ..
+            }
+            | TriggerAction::HoldOutgoing {
+                timeout, machine, ..
+            } => {
+                let deadline = now + *timeout;
+                match self.action.get_mut(machine) {
+                    Some((sleep, scheduled)) => {
+                        sleep.as_mut().reset(deadline);
+                        *scheduled = action.clone();
+                    }
+                    None => {
+                        self.action
+                            .insert(*machine, (Box::pin(sleep_until(deadline)), action.clone()));
+                    }
+                }
+                None
+            }
+            TriggerAction::UpdateTimer {
+                duration,
+                replace,
+                machine,
+            } => {
+                let deadline = now + *duration;
+                match self.internal.get_mut(machine) {
+                    Some(sleep) => {
+                        if !*replace && sleep.deadline() >= deadline {
+                            return None;
+                        }
+                        sleep.as_mut().reset(deadline);
+                    }
+                    None => {
+                        self.internal
+                            .insert(*machine, Box::pin(sleep_until(deadline)));
+                    }
+                }
+                Some(TriggerEvent::TimerBegin { machine: *machine })
+            }
+        }
+    }
+
+    /// Wait for the next timer to expire. If no timers are running, this
+    /// waits forever, making it suitable for use in `tokio::select!`.
+    pub async fn expired(&mut self) -> Expired {
+        poll_fn(|cx| self.poll_expired(cx)).await
+    }
+
+    /// Poll for the next timer to expire. See [`Self::expired()`].
+    ///
+    /// If multiple timers have expired, the one with the earliest deadline is
+    /// returned first.
+    pub fn poll_expired(&mut self, cx: &mut Context<'_>) -> Poll<Expired> {
+        // poll all timers, registering the waker with those not yet expired
+        let mut action: Option<(Instant, MachineId)> = None;
+        for (machine, (sleep, _)) in self.action.iter_mut() {
+            if sleep.as_mut().poll(cx).is_pending() {
+                continue;
+            }
+            if !matches!(action, Some((deadline, _)) if deadline <= sleep.deadline()) {
+                action = Some((sleep.deadline(), *machine));
+            }
+        }
+        let mut internal: Option<(Instant, MachineId)> = None;
+        for (machine, sleep) in self.internal.iter_mut() {
+            if sleep.as_mut().poll(cx).is_pending() {
+                continue;
+            }
+            if !matches!(internal, Some((deadline, _)) if deadline <= sleep.deadline()) {
+                internal = Some((sleep.deadline(), *machine));
+            }
+        }
+
+        match (action, internal) {
+            (Some((a, machine)), Some((i, _))) if a <= i => {
+                let (_, action) = self.action.remove(&machine).unwrap();
+                Poll::Ready(Expired::Action(action))
+            }
+            (Some((_, machine)), None) => {
+                let (_, action) = self.action.remove(&machine).unwrap();
+                Poll::Ready(Expired::Action(action))
+            }
+            (_, Some((_, machine))) => {
+                self.internal.remove(&machine);
+                Poll::Ready(Expired::Event(TriggerEvent::TimerEnd { machine }))
+            }
+            (None, None) => Poll::Pending,
+        }
+    }
+}
@@ -0,0 +1,267 @@
+use std::time::Duration;
+
+use enum_map::enum_map;
+use maybenot::action::Action;
+use maybenot::dist::Dist;
+use maybenot::event::Event;
+use maybenot::state::{State, Trans};
+use maybenot::{Framework, Machine, MachineId, Timer, TriggerAction, TriggerEvent};
+use maybenot_timers::{Expired, Timers};
+use tokio::time::Instant;
+
+fn padding(machine: usize, timeout: u64) -> TriggerAction {
+    TriggerAction::SendPadding {
+        timeout: Duration::from_millis(timeout),
+        bypass: false,
+        replace: false,
+        machine: MachineId::from_raw(machine),
+    }
+}
+
+fn blocking(machine: usize, timeout: u64) -> TriggerAction {
+    TriggerAction::BlockOutgoing {
+        timeout: Duration::from_millis(timeout),
+        duration: Duration::from_millis(1),
+        bypass: false,
+        replace: false,
+        lane: 0,
+        machine: MachineId::from_raw(machine),
+    }
+}
+
+fn update_timer(machine: usize, duration: u64, replace: bool) -> TriggerAction {
+    TriggerAction::UpdateTimer {
+        duration: Duration::from_millis(duration),
+        replace,
+        machine: MachineId::from_raw(machine),
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_drive_machine() {
+    // a machine that pads, then sets its internal timer, then blocks once the
+    // timer expires, driven by real (but paused) tokio timers
+    let s0 = State::new(enum_map! {
+        Event::NormalSent => vec![Trans(1, 1.0)],
+        _ => vec![],
+    });
+    let mut s1 = State::new(enum_map! {
+        Event::PaddingSent => vec![Trans(2, 1.0)],
+        _ => vec![],
+    });
+    s1.action = Some(Action::SendPadding {
+        bypass: false,
+        replace: false,
+        timeout: Dist::constant(1000.0),
+        limit: None,
+    });
+    let mut s2 = State::new(enum_map! {
+        Event::TimerEnd => vec![Trans(3, 1.0)],
+        _ => vec![],
+    });
+    s2.action = Some(Action::UpdateTimer {
+        replace: false,
+        duration: Dist::constant(5000.0),
+        limit: None,
+    });
+    let mut s3 = State::new(enum_map! {
+        _ => vec![],
+    });
+    s3.action = Some(Action::BlockOutgoing {
+        bypass: false,
+        replace: false,
+        lane: 0,
+        timeout: Dist::constant(1000.0),
+        duration: Dist::constant(2000.0),
+        limit: None,
+    });
+    let m = vec![Machine::new(u64::MAX, 0.0, u64::MAX, 0.0, vec![s0, s1, s2, s3]).unwrap()];
+
+    let start = Instant::now();
+    let mut f = Framework::new(&m, 0.0, 0.0, start.into_std(), rand::thread_rng()).unwrap();
+    let mut timers = Timers::new();
+    let mut events = vec![TriggerEvent::NormalSent];
+    let mut log = vec![];
+
+    while log.len() < 3 {
+        let mut next = vec![];
+        for action in f.trigger_events(&events, Instant::now().into_std()) {
+            next.extend(timers.apply(action));
+        }
+        events = next;
+        if !events.is_empty() {
+            continue;
+        }
+
+        let expired = timers.expired().await;
+        log.push((expired.clone(), start.elapsed()));
+        match expired {
+            Expired::Action(TriggerAction::SendPadding { machine, .. }) => {
+                events.push(TriggerEvent::PaddingSent { machine });
+            }
+            Expired::Action(TriggerAction::BlockOutgoing { machine, lane, .. }) => {
+                events.push(TriggerEvent::BlockingBegin { machine, lane });
+            }
+            Expired::Action(action) => panic!("unexpected action {:?}", action),
+            Expired::Event(event) => events.push(event),
+        }
+    }
+
+    let machine = MachineId::from_raw(0);
+    assert!(matches!(
+        log[0],
+        (Expired::Action(TriggerAction::SendPadding { .. }), d) if d == Duration::from_millis(1)
+    ));
+    assert_eq!(
+        log[1],
+        (
+            Expired::Event(TriggerEvent::TimerEnd { machine }),
+            Duration::from_millis(6)
+        )
+    );
+    assert!(matches!(
+        log[2],
+        (Expired::Action(TriggerAction::BlockOutgoing { .. }), d) if d == Duration::from_millis(7)
+    ));
+    assert!(timers.is_empty());
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_action_overwrite() {
+    // a new action for the same machine overwrites the scheduled one, also
+    // when the new timeout is longer
+    let mut timers = Timers::new();
+    let start = Instant::now();
+
+    assert_eq!(timers.apply(&padding(0, 100)), None);
+    assert_eq!(timers.apply(&blocking(0, 300)), None);
+    assert_eq!(timers.apply(&padding(1, 200)), None);
+
+    assert_eq!(timers.expired().await, Expired::Action(padding(1, 200)));
+    assert_eq!(start.elapsed(), Duration::from_millis(200));
+    assert_eq!(timers.expired().await, Expired::Action(blocking(0, 300)));
+    assert_eq!(start.elapsed(), Duration::from_millis(300));
+    assert!(timers.is_empty());
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_action_cancel() {
+    let mut timers = Timers::new();
+    let start = Instant::now();
+    let cancel = |machine: usize, timer: Timer| TriggerAction::Cancel {
+        machine: MachineId::from_raw(machine),
+        timer,
+    };
+
+    // only cancel pending blocking: padding remains
+    timers.apply(&padding(0, 100));
+    timers.apply(&blocking(1, 200));
+    timers.apply(&cancel(0, Timer::ActionBlocking));
+    timers.apply(&cancel(1, Timer::ActionBlocking));
+    // cancelling the action timer leaves the internal timer
+    timers.apply(&update_timer(2, 300, false));
+    timers.apply(&blocking(2, 50));
+    timers.apply(&cancel(2, Timer::Action));
+
+    assert_eq!(timers.expired().await, Expired::Action(padding(0, 100)));
+    assert_eq!(start.elapsed(), Duration::from_millis(100));
+    assert_eq!(
+        timers.expired().await,
+        Expired::Event(TriggerEvent::TimerEnd {
+            machine: MachineId::from_raw(2)
+        })
+    );
+    assert_eq!(start.elapsed(), Duration::from_millis(300));
+    assert!(timers.is_empty());
+
+    // cancel all timers
+    timers.apply(&padding(0, 100));
+    timers.apply(&update_timer(0, 100, false));
+    timers.apply(&cancel(0, Timer::All));
+    assert!(timers.is_empty());
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_action_cancel_incoming_and_holding() {
+    let mut timers = Timers::new();
+    let cancel = |machine: usize, timer: Timer| TriggerAction::Cancel {
+        machine: MachineId::from_raw(machine),
+        timer,
+    };
+    let block_incoming = TriggerAction::BlockIncoming {
+        timeout: Duration::from_millis(100),
+        duration: Duration::from_millis(1),
+        replace: false,
+        machine: MachineId::from_raw(0),
+    };
+    let hold = TriggerAction::HoldOutgoing {
+        timeout: Duration::from_millis(100),
+        duration: Duration::from_millis(1),
+        probability: 0.5,
+        machine: MachineId::from_raw(1),
+    };
+
+    // cancelling padding keeps them
+    timers.apply(&block_incoming);
+    timers.apply(&hold);
+    timers.apply(&cancel(0, Timer::ActionPadding));
+    timers.apply(&cancel(1, Timer::ActionPadding));
+    assert!(!timers.is_empty());
+
+    // cancelling blocking does not
+    timers.apply(&cancel(0, Timer::ActionBlocking));
+    timers.apply(&cancel(1, Timer::ActionBlocking));
+    assert!(timers.is_empty());
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_independent_timers() {
+    // the action and internal timers of a machine coexist, and updating one
+    // does not affect the other
+    let mut timers = Timers::new();
+    let start = Instant::now();
+    let machine = MachineId::from_raw(0);
+
+    timers.apply(&padding(0, 100));
+    timers.apply(&update_timer(0, 50, false));
+    timers.apply(&blocking(0, 200));
+    timers.apply(&update_timer(0, 150, true));
+
+    assert_eq!(
+        timers.expired().await,
+        Expired::Event(TriggerEvent::TimerEnd { machine })
+    );
+    assert_eq!(start.elapsed(), Duration::from_millis(150));
+    assert_eq!(timers.expired().await, Expired::Action(blocking(0, 200)));
+    assert_eq!(start.elapsed(), Duration::from_millis(200));
+    assert!(timers.is_empty());
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_internal_timer_update() {
+    let mut timers = Timers::new();
+    let start = Instant::now();
+    let machine = MachineId::from_raw(0);
+    let begin = Some(TriggerEvent::TimerBegin { machine });
+
+    // a shorter duration without replace keeps the longer timer
+    assert_eq!(timers.apply(&update_timer(0, 500, false)), begin);
+    assert_eq!(timers.apply(&update_timer(0, 100, false)), None);
+    // a longer duration extends the timer
+    assert_eq!(timers.apply(&update_timer(0, 1000, false)), begin);
+    assert_eq!(
+        timers.expired().await,
+        Expired::Event(TriggerEvent::TimerEnd { machine })
+    );
+    assert_eq!(start.elapsed(), Duration::from_millis(1000));
+
+    // with replace, a shorter duration replaces the timer
+    assert_eq!(timers.apply(&update_timer(0, 500, false)), begin);
+    assert_eq!(timers.apply(&update_timer(0, 100, true)), begin);
+    assert_eq!(
+        timers.expired().await,
+        Expired::Event(TriggerEvent::TimerEnd { machine })
+    );
+    assert_eq!(start.elapsed(), Duration::from_millis(1100));
+    assert!(timers.is_empty());
+}
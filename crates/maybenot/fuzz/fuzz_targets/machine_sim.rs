@@ -0,0 +1,551 @@
+//! Runs random valid machines on random event sequences through a
+//! [`Framework`], asserting that it never panics and that padding and
+//! blocking are only scheduled when within the configured limits.
+//!
+//! Input starting with the current machine format version (e.g., "03") is
+//! treated as one or more serialized machines (separated by spaces) up until
+//! the first newline, followed by bytes driving the events. See the seed
+//! corpus in `seeds/machine_sim`. Otherwise, all input drives the generation
+//! of both machines and events.
+
+#![no_main]
+
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use arbitrary::{Result, Unstructured};
+use enum_map::EnumMap;
+use libfuzzer_sys::fuzz_target;
+use maybenot::action::Action;
+use maybenot::blocking::BlockingState;
+use maybenot::constants::{
+    BLOCKING_LANES, STATE_END, STATE_PREVIOUS, STATE_RANDOM, STATE_SIGNAL, VERSION,
+};
+use maybenot::counter::{Counter, Operation};
+use maybenot::dist::{Dist, DistType, TimeUnit};
+use maybenot::event::Event;
+use maybenot::state::{CounterCondition, State, Trans};
+use maybenot::{Framework, Machine, MachineId, OnEnd, Timer, TriggerAction, TriggerEvent};
+use rand_core::SeedableRng;
+use rand_xoshiro::Xoshiro256StarStar;
+
+const MAX_MACHINES: usize = 4;
+const MAX_STATES: usize = 5;
+const MAX_STEPS: usize = 1000;
+// machines may loop forever on events caused by their own actions (e.g.,
+// updating the timer on TimerBegin), so bound the events of each step
+const MAX_STEP_EVENTS: usize = 1000;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = run(data);
+});
+
+fn run(data: &[u8]) -> Result<()> {
+    let version = format!("{:02}", VERSION);
+    let (machines, mut u) = match data.strip_prefix(version.as_bytes()) {
+        Some(_) => {
+            let split = data.iter().position(|b| *b == b'\n').unwrap_or(data.len());
+            let Ok(head) = std::str::from_utf8(&data[..split]) else {
+                return Ok(());
+            };
+            let machines: Vec<Machine> = head
+                .split_whitespace()
+                .filter_map(|s| Machine::from_str(s).ok())
+                // parsing allows templates, which cannot run
+                .filter(|m| m.validate().is_ok())
+                .take(MAX_MACHINES)
+                .collect();
+            (
+                machines,
+                Unstructured::new(&data[(split + 1).min(data.len())..]),
+            )
+        }
+        None => {
+            let mut u = Unstructured::new(data);
+            let n = u.int_in_range(1..=MAX_MACHINES)?;
+            let mut machines = Vec::with_capacity(n);
+            for _ in 0..n {
+                if let Some(m) = machine(&mut u)? {
+                    machines.push(m);
+                }
+            }
+            (machines, u)
+        }
+    };
+
+    let max_padding_frac = frac(&mut u)?;
+    let max_blocking_frac = frac(&mut u)?;
+    let seed = u.arbitrary()?;
+    Harness::new(&machines, max_padding_frac, max_blocking_frac, seed).run(&mut u)
+}
+
+fn frac(u: &mut Unstructured<'_>) -> Result<f64> {
+    Ok(u.int_in_range(0..=100u8)? as f64 / 100.0)
+}
+
+fn dist(u: &mut Unstructured<'_>) -> Result<Dist> {
+    let dist = match u.int_in_range(0..=3u8)? {
+        0 => {
+            let low = u.int_in_range(0..=1000u16)? as f64;
+            let high = low + u.int_in_range(0..=1000u16)? as f64;
+            DistType::Uniform { low, high }
+        }
+        1 => DistType::Normal {
+            mean: u.int_in_range(0..=1000u16)? as f64,
+            stdev: u.int_in_range(0..=100u8)? as f64,
+        },
+        2 => DistType::Geometric {
+            probability: u.int_in_range(1..=100u8)? as f64 / 100.0,
+        },
+        _ => DistType::Poisson {
+            lambda: u.int_in_range(1..=100u8)? as f64,
+        },
+    };
+    Ok(Dist {
+        dist,
+        start: u.int_in_range(0..=100u8)? as f64,
+        max: u.int_in_range(0..=10000u16)? as f64,
+        unit: *u.choose(&[
+            TimeUnit::Microseconds,
+            TimeUnit::Milliseconds,
+            TimeUnit::Nanoseconds,
+            TimeUnit::Seconds,
+        ])?,
+    })
+}
+
+fn limit(u: &mut Unstructured<'_>) -> Result<Option<Dist>> {
+    Ok(if u.arbitrary()? { Some(dist(u)?) } else { None })
+}
+
+fn action(u: &mut Unstructured<'_>) -> Result<Option<Action>> {
+    Ok(match u.int_in_range(0..=8u8)? {
+        0 => None,
+        1 => Some(Action::Cancel {
+            timer: *u.choose(&[
+                Timer::Action,
+                Timer::Internal,
+                Timer::All,
+                Timer::ActionPadding,
+                Timer::ActionBlocking,
+            ])?,
+        }),
+        2 => Some(Action::SendPadding {
+            bypass: u.arbitrary()?,
+            replace: u.arbitrary()?,
+            timeout: dist(u)?,
+            limit: limit(u)?,
+        }),
+        3 => Some(Action::BlockOutgoing {
+            bypass: u.arbitrary()?,
+            replace: u.arbitrary()?,
+            lane: u.int_in_range(0..=BLOCKING_LANES as u8)?,
+            timeout: dist(u)?,
+            duration: dist(u)?,
+            limit: limit(u)?,
+        }),
+        4 => Some(Action::UpdateTimer {
+            replace: u.arbitrary()?,
+            duration: dist(u)?,
+            limit: limit(u)?,
+        }),
+        5 => Some(Action::UpdateTimerAligned {
+            replace: u.arbitrary()?,
+            period: dist(u)?,
+            limit: limit(u)?,
+        }),
+        6 => Some(Action::BlockIncoming {
+            replace: u.arbitrary()?,
+            timeout: dist(u)?,
+            duration: dist(u)?,
+            limit: limit(u)?,
+        }),
+        7 => Some(Action::HoldOutgoing {
+            probability: dist(u)?,
+            timeout: dist(u)?,
+            duration: dist(u)?,
+            limit: limit(u)?,
+        }),
+        _ => Some(Action::SendPaddingBurst {
+            bypass: u.arbitrary()?,
+            replace: u.arbitrary()?,
+            timeout: dist(u)?,
+            interval: dist(u)?,
+            count: u.int_in_range(0..=20)?,
+            limit: limit(u)?,
+        }),
+    })
+}
+
+fn counter(u: &mut Unstructured<'_>) -> Result<Option<Counter>> {
+    let operation = *u.choose(&[Operation::Increment, Operation::Decrement, Operation::Set])?;
+    Ok(match u.int_in_range(0..=3u8)? {
+        0 => None,
+        1 => Some(Counter::new(operation)),
+        2 => Some(Counter::new_copy(operation)),
+        _ => Some(Counter::new_dist(operation, dist(u)?)),
+    })
+}
+
+fn state(u: &mut Unstructured<'_>, num_states: usize) -> Result<State> {
+    let mut transitions: EnumMap<Event, Vec<Trans>> = EnumMap::default();
+    for event in Event::iter() {
+        if !u.arbitrary()? {
+            continue;
+        }
+        let mut left: f32 = 1.0;
+        for _ in 0..u.int_in_range(1..=3u8)? {
+            let to = match u.int_in_range(0..=num_states + 3)? {
+                s if s < num_states => s,
+                s if s == num_states => STATE_END,
+                s if s == num_states + 1 => STATE_SIGNAL,
+                s if s == num_states + 2 => STATE_PREVIOUS,
+                _ => STATE_RANDOM,
+            };
+            let p = left * u.int_in_range(0..=100u8)? as f32 / 100.0;
+            left -= p;
+            transitions[*event].push(Trans(to, p));
+        }
+    }
+
+    let mut s = State::new(transitions);
+    s.action = action(u)?;
+    s.counter = (counter(u)?, counter(u)?);
+    s.blocking_condition = u.arbitrary()?;
+    // invalid for other counters or below 0, rejected by validation
+    if u.ratio(1, 4)? {
+        s.counter_condition = Some(CounterCondition {
+            counter: u.int_in_range(0..=2)?,
+            threshold: u.int_in_range(0..=10)?,
+            below: u.arbitrary()?,
+        });
+    }
+    // invalid along with a limit distribution, rejected by validation
+    if u.ratio(1, 4)? {
+        s.fixed_limit = Some(u.int_in_range(0..=10)?);
+    }
+    // invalid without a limit, rejected by validation
+    if u.ratio(1, 4)? {
+        s.refresh_limit_on = Some(*u.choose(Event::iter().as_slice())?);
+    }
+    Ok(s)
+}
+
+fn machine(u: &mut Unstructured<'_>) -> Result<Option<Machine>> {
+    let num_states = u.int_in_range(1..=MAX_STATES)?;
+    let mut states = Vec::with_capacity(num_states);
+    for _ in 0..num_states {
+        states.push(state(u, num_states)?);
+    }
+    let mut m = Machine::noop();
+    m.allowed_padding_packets = u.int_in_range(0..=100u8)? as u64;
+    m.max_padding_frac = frac(u)?;
+    m.allowed_blocked_microsec = u.int_in_range(0..=10000u16)? as u64;
+    m.max_blocking_frac = frac(u)?;
+    m.states = states;
+    m.on_end = *u.choose(&[OnEnd::KeepPending, OnEnd::CancelPending])?;
+    // without a threshold, transitions on TimeThreshold are rejected by
+    // validation
+    if u.arbitrary()? {
+        m.time_threshold = Some(u.int_in_range(0..=10000u16)? as u64);
+    }
+    Ok(m.validate().is_ok().then_some(m))
+}
+
+/// Acts as an integration of the framework, executing actions and keeping
+/// track of what was sent and blocked to check against the limits.
+struct Harness<'a> {
+    framework: Framework<&'a [Machine], Xoshiro256StarStar>,
+    machines: &'a [Machine],
+    max_padding_frac: f64,
+    max_blocking_frac: f64,
+    start: Instant,
+    now: Instant,
+    // accounting, as described by the events triggered
+    normal_sent: u64,
+    padding_sent: u64,
+    machine_normal_sent: Vec<u64>,
+    machine_padding_sent: Vec<u64>,
+    blocked: Duration,
+    blocking_started: Option<Instant>,
+    lane_blocked: [Duration; BLOCKING_LANES],
+    lane_started: [Option<Instant>; BLOCKING_LANES],
+    // integration state, with one more lane than supported
+    blocking: Vec<BlockingState>,
+    internal_timers: Vec<Option<Instant>>,
+}
+
+impl<'a> Harness<'a> {
+    fn new(
+        machines: &'a [Machine],
+        max_padding_frac: f64,
+        max_blocking_frac: f64,
+        seed: u64,
+    ) -> Self {
+        let start = Instant::now();
+        Self {
+            framework: Framework::new(
+                machines,
+                max_padding_frac,
+                max_blocking_frac,
+                start,
+                Xoshiro256StarStar::seed_from_u64(seed),
+            )
+            .unwrap(),
+            machines,
+            max_padding_frac,
+            max_blocking_frac,
+            start,
+            now: start,
+            normal_sent: 0,
+            padding_sent: 0,
+            machine_normal_sent: vec![0; machines.len()],
+            machine_padding_sent: vec![0; machines.len()],
+            blocked: Duration::ZERO,
+            blocking_started: None,
+            lane_blocked: [Duration::ZERO; BLOCKING_LANES],
+            lane_started: [None; BLOCKING_LANES],
+            blocking: (0..=BLOCKING_LANES as u8)
+                .map(|lane| BlockingState::with_lane(lane, start))
+                .collect(),
+            internal_timers: vec![None; machines.len()],
+        }
+    }
+
+    fn run(&mut self, u: &mut Unstructured<'_>) -> Result<()> {
+        let n = self.machines.len() + 1;
+        // start the machines, with any resulting events in the first step
+        let mut started = vec![];
+        let actions: Vec<TriggerAction> = self.framework.start(self.now).cloned().collect();
+        for action in actions {
+            let execute: bool = u.arbitrary()?;
+            started.extend(self.act(&action, execute));
+        }
+        for _ in 0..MAX_STEPS {
+            if u.is_empty() {
+                break;
+            }
+            self.now += Duration::from_micros(u.int_in_range(0..=1000u16)? as u64);
+
+            let mut events = std::mem::take(&mut started);
+            // expired blocking and timers
+            for blocking in self.blocking.iter_mut() {
+                events.extend(blocking.expire(self.now));
+            }
+            for (mi, timer) in self.internal_timers.iter_mut().enumerate() {
+                if timer.is_some_and(|t| t <= self.now) {
+                    *timer = None;
+                    events.push(TriggerEvent::TimerEnd {
+                        machine: MachineId::from_raw(mi),
+                    });
+                }
+            }
+            // traffic, including bogus events from a misbehaving integration
+            let machine = MachineId::from_raw(u.int_in_range(0..=n)?);
+            let lane = u.int_in_range(0..=BLOCKING_LANES as u8)?;
+            events.push(
+                u.choose(&[
+                    TriggerEvent::NormalRecv,
+                    TriggerEvent::PaddingRecv,
+                    TriggerEvent::TunnelRecv,
+                    TriggerEvent::NormalSent,
+                    TriggerEvent::TunnelSent,
+                    TriggerEvent::PaddingSent { machine },
+                    TriggerEvent::BlockingBegin { machine, lane },
+                    TriggerEvent::BlockingEnd { lane },
+                    TriggerEvent::TimerBegin { machine },
+                    TriggerEvent::TimerEnd { machine },
+                    TriggerEvent::IncomingBlockingBegin { machine },
+                    TriggerEvent::IncomingBlockingEnd,
+                    TriggerEvent::Idle,
+                ])?
+                .clone(),
+            );
+
+            // one event at a time, such that the accounting at the time of
+            // each action is known
+            let mut budget = MAX_STEP_EVENTS;
+            while let Some(event) = events.pop() {
+                if budget == 0 {
+                    break;
+                }
+                budget -= 1;
+                self.account(&event);
+                let actions: Vec<TriggerAction> = self
+                    .framework
+                    .trigger_events(&[event], self.now)
+                    .cloned()
+                    .collect();
+                for action in actions {
+                    // any scheduled action either expires now, or is
+                    // considered replaced or cancelled
+                    let execute: bool = u.arbitrary()?;
+                    events.extend(self.act(&action, execute));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn account(&mut self, event: &TriggerEvent) {
+        match event {
+            TriggerEvent::NormalSent => {
+                self.normal_sent += 1;
+                self.machine_normal_sent.iter_mut().for_each(|n| *n += 1);
+            }
+            TriggerEvent::PaddingSent { machine } => {
+                self.padding_sent += 1;
+                if let Some(n) = self.machine_padding_sent.get_mut(machine.into_raw()) {
+                    *n += 1;
+                }
+            }
+            // blocked per lane, and in aggregate while any lane is blocked
+            TriggerEvent::BlockingBegin { lane, .. } => {
+                if let Some(started) = self.lane_started.get_mut(*lane as usize) {
+                    started.get_or_insert(self.now);
+                    self.blocking_started.get_or_insert(self.now);
+                }
+            }
+            TriggerEvent::BlockingEnd { lane } => {
+                if let Some(started) = self.lane_started.get_mut(*lane as usize) {
+                    if let Some(started) = started.take() {
+                        self.lane_blocked[*lane as usize] +=
+                            self.now.saturating_duration_since(started);
+                    }
+                }
+                if self.lane_started.iter().all(|s| s.is_none()) {
+                    if let Some(started) = self.blocking_started.take() {
+                        self.blocked += self.now.saturating_duration_since(started);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn act(&mut self, action: &TriggerAction, execute: bool) -> Option<TriggerEvent> {
+        match action {
+            TriggerAction::Cancel { machine, timer } => {
+                if matches!(timer, Timer::Internal | Timer::All) {
+                    self.internal_timers[machine.into_raw()] = None;
+                }
+                None
+            }
+            TriggerAction::SendPadding { machine, .. } => {
+                self.check_padding(machine.into_raw());
+                execute.then_some(TriggerEvent::PaddingSent { machine: *machine })
+            }
+            TriggerAction::BlockOutgoing { replace, lane, .. } => {
+                if !(*replace
+                    && self
+                        .lane_started
+                        .get(*lane as usize)
+                        .is_some_and(|s| s.is_some()))
+                {
+                    self.check_blocking(action);
+                }
+                if !execute {
+                    return None;
+                }
+                self.blocking[*lane as usize]
+                    .apply(action, self.now)
+                    .event()
+                    .cloned()
+            }
+            TriggerAction::UpdateTimer {
+                duration,
+                replace,
+                machine,
+            } => {
+                let timer = &mut self.internal_timers[machine.into_raw()];
+                let end = self.now + *duration;
+                if *replace || timer.map_or(true, |t| t < end) {
+                    *timer = Some(end);
+                    return Some(TriggerEvent::TimerBegin { machine: *machine });
+                }
+                None
+            }
+            // separate from blocking outgoing traffic, not checked here
+            TriggerAction::BlockIncoming { machine, .. } => {
+                execute.then_some(TriggerEvent::IncomingBlockingBegin { machine: *machine })
+            }
+            // checked like blocking, but never reported
+            TriggerAction::HoldOutgoing { probability, .. } => {
+                assert!((0.0..=1.0).contains(probability), "invalid probability");
+                self.check_blocking(action);
+                None
+            }
+        }
+    }
+
+    /// Padding MUST only be scheduled if the machine has allowed padding
+    /// packets left, or the fraction of padding is below the machine and
+    /// framework limits (at most one packet over, on the first packet).
+    fn check_padding(&self, mi: usize) {
+        let machine = &self.machines[mi];
+        let padding = self.machine_padding_sent[mi];
+        if padding < machine.allowed_padding_packets {
+            return;
+        }
+
+        let total = padding + self.machine_normal_sent[mi];
+        if machine.max_padding_frac > 0.0 {
+            if total == 0 {
+                return;
+            }
+            assert!(
+                (padding as f64 / total as f64) < machine.max_padding_frac,
+                "machine {mi} padding limit exceeded"
+            );
+        }
+
+        let total = self.padding_sent + self.normal_sent;
+        if self.max_padding_frac > 0.0 && total > 0 {
+            assert!(
+                (self.padding_sent as f64 / total as f64) < self.max_padding_frac,
+                "framework padding limit exceeded"
+            );
+        }
+    }
+
+    /// Blocking MUST only be scheduled if the machine has allowed blocking
+    /// duration left, or the fraction of time blocked is below the machine
+    /// and framework limits, of the lane blocked (or in aggregate, for
+    /// holding).
+    fn check_blocking(&self, action: &TriggerAction) {
+        // all machines are blocked by the same global blocking
+        let (mi, mut blocked, started) = match action {
+            TriggerAction::BlockOutgoing { machine, lane, .. } => (
+                machine.into_raw(),
+                self.lane_blocked[*lane as usize],
+                self.lane_started[*lane as usize],
+            ),
+            TriggerAction::HoldOutgoing { machine, .. } => {
+                (machine.into_raw(), self.blocked, self.blocking_started)
+            }
+            _ => return,
+        };
+        let machine = &self.machines[mi];
+        if let Some(started) = started {
+            blocked += self.now.saturating_duration_since(started);
+        }
+        if blocked < Duration::from_micros(machine.allowed_blocked_microsec) {
+            return;
+        }
+
+        // NaN if no time has passed, never over the limit
+        let frac = blocked.as_secs_f64() / (self.now - self.start).as_secs_f64();
+        if machine.max_blocking_frac > 0.0 {
+            assert!(
+                frac.is_nan() || frac < machine.max_blocking_frac,
+                "machine {mi} blocking limit exceeded"
+            );
+        }
+        if self.max_blocking_frac > 0.0 {
+            assert!(
+                frac.is_nan() || frac < self.max_blocking_frac,
+                "framework blocking limit exceeded"
+            );
+        }
+    }
+}
@@ -11,6 +11,17 @@ use std::hash::Hash;
 use self::dist::Dist;
 
 /// The different types of timers used by a [`Machine`].
+///
+/// Each machine has two timers, which are fully independent. The action timer
+/// holds the pending [`TriggerAction::SendPadding`],
+/// [`TriggerAction::BlockOutgoing`], [`TriggerAction::BlockIncoming`], or
+/// [`TriggerAction::HoldOutgoing`], if any, and a new such action replaces
+/// it. The internal timer is only updated
+/// by [`TriggerAction::UpdateTimer`]. Neither timer is affected by actions for
+/// the other, and only [`Timer::All`] cancels both. Since a machine takes at
+/// most one action per transition, and
+/// [`Framework::trigger_events()`](crate::Framework::trigger_events) returns at
+/// most one action per machine, the two timers are updated by separate calls.
 #[derive(Debug, Eq, Hash, PartialEq, Clone, Copy, Serialize, Deserialize)]
 pub enum Timer {
     /// The scheduled timer for actions with a timeout.
@@ -20,6 +31,13 @@ pub enum Timer {
     Internal,
     /// Apply to all timers.
     All,
+    /// The scheduled timer for actions with a timeout, but only if the
+    /// pending action is to send padding. Any pending blocking is kept.
+    ActionPadding,
+    /// The scheduled timer for actions with a timeout, but only if the
+    /// pending action is to block outgoing traffic, block incoming traffic, or
+    /// hold outgoing traffic. Any pending padding is kept.
+    ActionBlocking,
 }
 
 /// An Action happens upon transition to a [`State`](crate::state). All actions
@@ -56,9 +74,20 @@ pub enum Action {
     /// to prevent indefinite blocking (but comes at the cost of making it
     /// possible for a machine that indefinitely refresh blocking by using the
     /// replace flag).
+    ///
+    /// The lane determines the traffic blocked, in `[0,`
+    /// [`BLOCKING_LANES`]`)`: the integration maps each lane to a class of
+    /// outgoing traffic, e.g., bulk and interactive traffic, and blocking of
+    /// one lane does not affect the others. Traffic without a class of its
+    /// own SHOULD use lane 0. The bypass and replace flags apply within the
+    /// lane, and the blocking limits of the machine and framework are
+    /// accounted for per lane: a lane that reached its limits does not
+    /// prevent blocking of the others.
     BlockOutgoing {
         bypass: bool,
         replace: bool,
+        #[serde(default)]
+        lane: u8,
         timeout: Dist,
         duration: Dist,
         limit: Option<Dist>,
@@ -72,6 +101,83 @@ pub enum Action {
         duration: Dist,
         limit: Option<Dist>,
     },
+    /// Update the timer for a machine to expire at the next multiple of a
+    /// sampled period, counted from when the machine started.
+    ///
+    /// This allows for timers that fire on a fixed grid: since the duration is
+    /// computed from the current time at each update, any delay in reporting
+    /// [`TriggerEvent::TimerEnd`] does not accumulate across successive timer
+    /// fires. The framework emits a regular [`TriggerAction::UpdateTimer`]
+    /// with the remaining duration until the next multiple, so integrations
+    /// set the timer and report TimerBegin and TimerEnd exactly as for
+    /// [`Action::UpdateTimer`]. If the current time is exactly on a multiple,
+    /// the timer is set to expire one period later.
+    ///
+    /// The replace flag is as for [`Action::UpdateTimer`].
+    UpdateTimerAligned {
+        replace: bool,
+        period: Dist,
+        limit: Option<Dist>,
+    },
+    /// Schedule a burst of count padding packets: the first after a timeout,
+    /// and each following packet after an interval from when the previous
+    /// packet was sent.
+    ///
+    /// The framework schedules the packets of a burst one at a time as
+    /// regular [`TriggerAction::SendPadding`], so integrations handle bursts
+    /// like any other padding. The burst continues as long as the machine
+    /// does not transition on [`TriggerEvent::PaddingSent`]; any transition,
+    /// including to the same state (which starts a new burst), ends it. Each
+    /// packet in the burst counts toward the limit and the padding limits of
+    /// the machine. The count must be in [1, [`MAX_PADDING_BURST`]].
+    ///
+    /// The bypass and replace flags are as for [`Action::SendPadding`] and
+    /// apply to each packet in the burst.
+    SendPaddingBurst {
+        bypass: bool,
+        replace: bool,
+        timeout: Dist,
+        interval: Dist,
+        count: u32,
+        limit: Option<Dist>,
+    },
+    /// Schedule blocking of incoming traffic after a timeout.
+    ///
+    /// Blocking incoming traffic means delaying the delivery of received
+    /// packets to the application: packets are still received from the
+    /// network (and [`TriggerEvent::TunnelRecv`] reported), but
+    /// [`TriggerEvent::NormalRecv`] and [`TriggerEvent::PaddingRecv`] are only
+    /// reported once the packets are delivered, after the blocking ended.
+    ///
+    /// Blocking incoming traffic is accounted for separately from blocking
+    /// outgoing traffic, but against the same blocking limits of the machine
+    /// and framework. The replace flag is as for [`Action::BlockOutgoing`].
+    BlockIncoming {
+        replace: bool,
+        timeout: Dist,
+        duration: Dist,
+        limit: Option<Dist>,
+    },
+    /// Schedule holding of outgoing traffic after a timeout: for a duration,
+    /// each outgoing non-padding packet is held with a probability, until the
+    /// duration ends.
+    ///
+    /// Unlike [`Action::BlockOutgoing`], which blocks all outgoing traffic,
+    /// holding thins out and reshapes the stream of packets. The probability
+    /// is sampled once per action and clamped to [0.0, 1.0], and the
+    /// integration decides for each packet. A new holding replaces any ongoing
+    /// holding.
+    ///
+    /// Holding is not reported to the framework, so machines cannot transition
+    /// on it, and it is not accounted for as blocking. The action is only
+    /// taken while the blocking limits of the machine and framework allow
+    /// blocking outgoing traffic.
+    HoldOutgoing {
+        probability: Dist,
+        timeout: Dist,
+        duration: Dist,
+        limit: Option<Dist>,
+    },
 }
 
 impl fmt::Display for Action {
@@ -81,56 +187,217 @@ impl fmt::Display for Action {
 }
 
 impl Action {
-    /// Sample a timeout for a padding or blocking action.
+    /// Sample a timeout for a padding or blocking action. Other actions have
+    /// no timeout to sample, and return 0.
     pub(crate) fn sample_timeout<R: RngCore>(&self, rng: &mut R) -> u64 {
         match self {
-            Action::SendPadding { timeout, .. } | Action::BlockOutgoing { timeout, .. } => {
-                timeout.sample(rng).min(MAX_SAMPLED_TIMEOUT).round() as u64
+            Action::SendPadding { timeout, .. }
+            | Action::BlockOutgoing { timeout, .. }
+            | Action::BlockIncoming { timeout, .. }
+            | Action::HoldOutgoing { timeout, .. }
+            | Action::SendPaddingBurst { timeout, .. } => {
+                timeout.sample_micros(rng).min(MAX_SAMPLED_TIMEOUT).round() as u64
+            }
+            _ => 0,
+        }
+    }
+
+    /// Sample the interval between packets of a padding burst.
+    pub(crate) fn sample_interval<R: RngCore>(&self, rng: &mut R) -> u64 {
+        match self {
+            Action::SendPaddingBurst { interval, .. } => {
+                interval.sample_micros(rng).min(MAX_SAMPLED_TIMEOUT).round() as u64
             }
             _ => 0,
         }
     }
 
-    /// Sample a duration for a blocking or timer update action.
+    /// Sample a duration for a blocking or timer update action. For an aligned
+    /// timer update, this is the period (at least 1 microsecond).
     pub(crate) fn sample_duration<R: RngCore>(&self, rng: &mut R) -> u64 {
         match self {
-            Action::BlockOutgoing { duration, .. } => {
-                duration.sample(rng).min(MAX_SAMPLED_BLOCK_DURATION).round() as u64
+            Action::BlockOutgoing { duration, .. }
+            | Action::BlockIncoming { duration, .. }
+            | Action::HoldOutgoing { duration, .. } => duration
+                .sample_micros(rng)
+                .min(MAX_SAMPLED_BLOCK_DURATION)
+                .round() as u64,
+            Action::UpdateTimer { duration, .. } => duration
+                .sample_micros(rng)
+                .min(MAX_SAMPLED_TIMER_DURATION)
+                .round() as u64,
+            // a period of zero has no next multiple
+            Action::UpdateTimerAligned { period, .. } => period
+                .sample_micros(rng)
+                .min(MAX_SAMPLED_TIMER_DURATION)
+                .round()
+                .max(1.0) as u64,
+            _ => 0,
+        }
+    }
+
+    /// Sample the probability of holding each packet, clamped to [0.0, 1.0].
+    pub(crate) fn sample_probability<R: RngCore>(&self, rng: &mut R) -> f64 {
+        match self {
+            Action::HoldOutgoing { probability, .. } => probability.sample(rng).min(1.0),
+            _ => 0.0,
+        }
+    }
+
+    /// Sample a limit, clamped to [`STATE_LIMIT_MAX`]. Also returns true if
+    /// the limit was sampled from a distribution and saturated, i.e., the
+    /// sampled value was clamped and the limit is effectively unlimited.
+    /// Without a limit distribution, the limit is [`STATE_LIMIT_MAX`] and not
+    /// saturated, unlike a constant limit of 0 that never allows the action.
+    pub(crate) fn sample_limit<R: RngCore>(&self, rng: &mut R) -> (u64, bool) {
+        match self.limit() {
+            Some(limit) => {
+                let limit = limit.sample(rng).round();
+                // STATE_LIMIT_MAX as f64 rounds up, to 2^64
+                let saturated = limit >= STATE_LIMIT_MAX as f64;
+                (limit as u64, saturated)
+            }
+            None => (STATE_LIMIT_MAX, false),
+        }
+    }
+
+    /// Returns true if the limit distribution, if any, typically samples a
+    /// limit of at least [`STATE_LIMIT_MAX`], based on its mean (or lower
+    /// bound, if the mean is unknown). Such a limit is clamped when sampled,
+    /// making the action effectively unlimited, which is probably a mistake.
+    pub fn limit_saturates(&self) -> bool {
+        let Some(limit) = self.limit() else {
+            return false;
+        };
+        let (low, high) = limit.support();
+        let typical = match limit.mean() {
+            Some(mean) => (mean + limit.start).clamp(low, high),
+            None => low,
+        };
+        typical.round() >= STATE_LIMIT_MAX as f64
+    }
+
+    /// Returns true if a timeout, interval, duration, or period distribution
+    /// of the action often samples values above the cap it is clamped to
+    /// ([`MAX_SAMPLED_TIMEOUT`], [`MAX_SAMPLED_BLOCK_DURATION`], or
+    /// [`MAX_SAMPLED_TIMER_DURATION`]), based on its 99th percentile (or mean,
+    /// if the percentile is unknown, see [`Dist::quantile()`]). Such a
+    /// distribution is effectively a constant at the cap, which is probably a
+    /// mistake.
+    pub fn exceeds_caps(&self) -> bool {
+        let exceeds = |dist: &Dist, cap: f64| {
+            let typical = dist.quantile(0.99).or_else(|| {
+                let (low, high) = dist.support();
+                dist.mean().map(|mean| (mean + dist.start).clamp(low, high))
+            });
+            typical.is_some_and(|v| dist.unit.to_micros(v) > cap)
+        };
+        match self {
+            Action::SendPadding { timeout, .. } => exceeds(timeout, MAX_SAMPLED_TIMEOUT),
+            Action::BlockOutgoing {
+                timeout, duration, ..
             }
-            Action::UpdateTimer { duration, .. } => {
-                duration.sample(rng).min(MAX_SAMPLED_TIMER_DURATION).round() as u64
+            | Action::BlockIncoming {
+                timeout, duration, ..
             }
-            _ => 0,
+            | Action::HoldOutgoing {
+                timeout, duration, ..
+            } => {
+                exceeds(timeout, MAX_SAMPLED_TIMEOUT)
+                    || exceeds(duration, MAX_SAMPLED_BLOCK_DURATION)
+            }
+            Action::UpdateTimer { duration, .. } => exceeds(duration, MAX_SAMPLED_TIMER_DURATION),
+            Action::UpdateTimerAligned { period, .. } => {
+                exceeds(period, MAX_SAMPLED_TIMER_DURATION)
+            }
+            Action::SendPaddingBurst {
+                timeout, interval, ..
+            } => exceeds(timeout, MAX_SAMPLED_TIMEOUT) || exceeds(interval, MAX_SAMPLED_TIMEOUT),
+            Action::Cancel { .. } => false,
         }
     }
 
-    /// Sample a limit.
-    pub(crate) fn sample_limit<R: RngCore>(&self, rng: &mut R) -> u64 {
+    /// Returns the expected timeout of a padding or blocking action, without
+    /// sampling. The expected value, in microseconds (see [`Dist::unit`]), is
+    /// the mean of the distribution plus its start, clamped to the support of
+    /// the distribution and to
+    /// [`MAX_SAMPLED_TIMEOUT`] as when sampling. Note that this is an
+    /// estimate: clamping the mean differs from the mean of clamped samples.
+    /// Returns an error if the action has no timeout, or if the distribution
+    /// has no known mean (see [`Dist::mean()`]).
+    pub fn expected_timeout(&self) -> Result<f64, Error> {
         match self {
-            Action::SendPadding { limit, .. }
-            | Action::BlockOutgoing { limit, .. }
-            | Action::UpdateTimer { limit, .. } => {
-                if limit.is_none() {
-                    return STATE_LIMIT_MAX;
-                }
-                limit.unwrap().sample(rng).round() as u64
+            Action::SendPadding { timeout, .. }
+            | Action::BlockOutgoing { timeout, .. }
+            | Action::BlockIncoming { timeout, .. }
+            | Action::HoldOutgoing { timeout, .. }
+            | Action::SendPaddingBurst { timeout, .. } => expected(timeout, MAX_SAMPLED_TIMEOUT),
+            _ => Err(Error::Machine(format!("action has no timeout: {:?}", self))),
+        }
+    }
+
+    /// Returns the expected interval between packets of a padding burst, as
+    /// for [`Self::expected_timeout()`].
+    pub fn expected_interval(&self) -> Result<f64, Error> {
+        match self {
+            Action::SendPaddingBurst { interval, .. } => expected(interval, MAX_SAMPLED_TIMEOUT),
+            _ => Err(Error::Machine(format!(
+                "action has no interval: {:?}",
+                self
+            ))),
+        }
+    }
+
+    /// Returns the expected duration of a blocking or timer update action, or
+    /// the expected period of an aligned timer update, as for
+    /// [`Self::expected_timeout()`] but clamped to
+    /// [`MAX_SAMPLED_BLOCK_DURATION`] or [`MAX_SAMPLED_TIMER_DURATION`].
+    pub fn expected_duration(&self) -> Result<f64, Error> {
+        match self {
+            Action::BlockOutgoing { duration, .. }
+            | Action::BlockIncoming { duration, .. }
+            | Action::HoldOutgoing { duration, .. } => {
+                expected(duration, MAX_SAMPLED_BLOCK_DURATION)
             }
-            _ => STATE_LIMIT_MAX,
+            Action::UpdateTimer { duration, .. } => expected(duration, MAX_SAMPLED_TIMER_DURATION),
+            Action::UpdateTimerAligned { period, .. } => {
+                Ok(expected(period, MAX_SAMPLED_TIMER_DURATION)?.max(1.0))
+            }
+            _ => Err(Error::Machine(format!(
+                "action has no duration: {:?}",
+                self
+            ))),
         }
     }
 
-    /// Check if the action has a limit distribution.
-    pub(crate) fn has_limit(&self) -> bool {
+    fn limit(&self) -> Option<Dist> {
         match self {
             Action::SendPadding { limit, .. }
             | Action::BlockOutgoing { limit, .. }
-            | Action::UpdateTimer { limit, .. } => limit.is_some(),
-            _ => false,
+            | Action::UpdateTimer { limit, .. }
+            | Action::UpdateTimerAligned { limit, .. }
+            | Action::SendPaddingBurst { limit, .. }
+            | Action::BlockIncoming { limit, .. }
+            | Action::HoldOutgoing { limit, .. } => *limit,
+            _ => None,
         }
     }
 
+    /// Check if the action has a limit distribution.
+    pub(crate) fn has_limit(&self) -> bool {
+        self.limit().is_some()
+    }
+
     /// Validate all distributions contained in this action, if any.
     pub fn validate(&self) -> Result<(), Error> {
+        if let Action::BlockOutgoing { lane, .. } = self {
+            if *lane as usize >= BLOCKING_LANES {
+                Err(Error::Machine(format!(
+                    "blocking lane has to be [0, {}), got {}",
+                    BLOCKING_LANES, lane
+                )))?;
+            }
+        }
         match self {
             Action::SendPadding { timeout, limit, .. } => {
                 timeout.validate()?;
@@ -143,6 +410,12 @@ impl Action {
                 duration,
                 limit,
                 ..
+            }
+            | Action::BlockIncoming {
+                timeout,
+                duration,
+                limit,
+                ..
             } => {
                 timeout.validate()?;
                 duration.validate()?;
@@ -150,9 +423,46 @@ impl Action {
                     limit.validate()?;
                 }
             }
+            Action::SendPaddingBurst {
+                timeout,
+                interval,
+                count,
+                limit,
+                ..
+            } => {
+                if *count == 0 || *count > MAX_PADDING_BURST {
+                    Err(Error::Machine(format!(
+                        "padding burst count has to be [1, {}], got {}",
+                        MAX_PADDING_BURST, count
+                    )))?;
+                }
+                timeout.validate()?;
+                interval.validate()?;
+                if let Some(limit) = limit {
+                    limit.validate()?;
+                }
+            }
             Action::UpdateTimer {
                 duration, limit, ..
+            }
+            | Action::UpdateTimerAligned {
+                period: duration,
+                limit,
+                ..
+            } => {
+                duration.validate()?;
+                if let Some(limit) = limit {
+                    limit.validate()?;
+                }
+            }
+            Action::HoldOutgoing {
+                probability,
+                timeout,
+                duration,
+                limit,
             } => {
+                probability.validate()?;
+                timeout.validate()?;
                 duration.validate()?;
                 if let Some(limit) = limit {
                     limit.validate()?;
@@ -165,10 +475,31 @@ impl Action {
     }
 }
 
+/// The mean of a distribution plus its start, clamped to its support and cap.
+fn expected(dist: &Dist, cap: f64) -> Result<f64, Error> {
+    let Some(mean) = dist.mean() else {
+        Err(Error::Machine(format!("no known mean for {}", dist)))?
+    };
+    let (low, high) = dist.support();
+    Ok(dist
+        .unit
+        .to_micros((mean + dist.start).clamp(low, high))
+        .min(cap))
+}
+
 /// The action to be taken by the framework user.
-#[derive(PartialEq, Eq, Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "T::Duration: Serialize",
+    deserialize = "T::Duration: Deserialize<'de>"
+))]
 pub enum TriggerAction<T: crate::time::Instant = std::time::Instant> {
     /// Cancel the timer for a machine.
+    ///
+    /// For [`Timer::ActionPadding`] and [`Timer::ActionBlocking`], the action
+    /// timer MUST only be cancelled if the action pending on it is to send
+    /// padding or to block (outgoing or incoming) or hold traffic,
+    /// respectively. Otherwise, the pending action MUST be kept.
     Cancel { machine: MachineId, timer: Timer },
     /// Schedule padding to be injected after the given timeout for a machine.
     ///
@@ -198,11 +529,16 @@ pub enum TriggerAction<T: crate::time::Instant = std::time::Instant> {
     /// The replace flag indicates if the duration MUST replace any other
     /// currently ongoing blocking of outgoing traffic. If the flag is false,
     /// the longest of the two durations MUST be used.
+    ///
+    /// The lane is the class of outgoing traffic to block, as mapped by the
+    /// integration. Blocking of each lane is independent of the others: the
+    /// flags and durations above only apply to the blocking of the same lane.
     BlockOutgoing {
         timeout: T::Duration,
         duration: T::Duration,
         bypass: bool,
         replace: bool,
+        lane: u8,
         machine: MachineId,
     },
     /// Update the timer duration for a machine.
@@ -215,17 +551,362 @@ pub enum TriggerAction<T: crate::time::Instant = std::time::Instant> {
         replace: bool,
         machine: MachineId,
     },
+    /// Schedule blocking of incoming traffic after the given timeout for a
+    /// machine. The duration of the blocking is specified.
+    ///
+    /// While blocking incoming traffic, received packets MUST NOT be delivered
+    /// to the application: delivery is delayed until the blocking ends.
+    /// Blocking incoming traffic is independent of blocking outgoing traffic.
+    ///
+    /// The replace flag is as for [`TriggerAction::BlockOutgoing`].
+    BlockIncoming {
+        timeout: T::Duration,
+        duration: T::Duration,
+        replace: bool,
+        machine: MachineId,
+    },
+    /// Schedule holding of outgoing traffic after the given timeout for a
+    /// machine. The duration of the holding and the probability of holding
+    /// each packet, in [0.0, 1.0], are specified.
+    ///
+    /// While holding, each outgoing non-padding packet MUST be held with the
+    /// probability, independently of other packets, until the holding ends.
+    /// Held packets are then sent, subject to any blocking of outgoing
+    /// traffic. Padding packets are never held. A new holding MUST replace any
+    /// ongoing holding. Holding is not reported to the framework.
+    HoldOutgoing {
+        timeout: T::Duration,
+        duration: T::Duration,
+        probability: f64,
+        machine: MachineId,
+    },
 }
 
+impl<T: crate::time::Instant> TriggerAction<T> {
+    /// Returns the machine of the action.
+    pub fn machine(&self) -> MachineId {
+        match self {
+            TriggerAction::Cancel { machine, .. }
+            | TriggerAction::SendPadding { machine, .. }
+            | TriggerAction::BlockOutgoing { machine, .. }
+            | TriggerAction::UpdateTimer { machine, .. }
+            | TriggerAction::BlockIncoming { machine, .. }
+            | TriggerAction::HoldOutgoing { machine, .. } => *machine,
+        }
+    }
+
+    /// Returns the timer of the machine that the action is for: the timer to
+    /// cancel for [`TriggerAction::Cancel`], the internal timer for
+    /// [`TriggerAction::UpdateTimer`], and otherwise the action timer that
+    /// the action is scheduled on after its timeout.
+    pub fn timer(&self) -> Timer {
+        match self {
+            TriggerAction::Cancel { timer, .. } => *timer,
+            TriggerAction::UpdateTimer { .. } => Timer::Internal,
+            TriggerAction::SendPadding { .. }
+            | TriggerAction::BlockOutgoing { .. }
+            | TriggerAction::BlockIncoming { .. }
+            | TriggerAction::HoldOutgoing { .. } => Timer::Action,
+        }
+    }
+}
+
+// not derived, since that would require the instant (not only its duration)
+// to be comparable. Eq holds despite the probability of holding, since it is
+// never NaN
+impl<T: crate::time::Instant> PartialEq for TriggerAction<T> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                TriggerAction::Cancel { machine, timer },
+                TriggerAction::Cancel {
+                    machine: m,
+                    timer: t,
+                },
+            ) => machine == m && timer == t,
+            (
+                TriggerAction::SendPadding {
+                    timeout,
+                    bypass,
+                    replace,
+                    machine,
+                },
+                TriggerAction::SendPadding {
+                    timeout: to,
+                    bypass: b,
+                    replace: r,
+                    machine: m,
+                },
+            ) => timeout == to && bypass == b && replace == r && machine == m,
+            (
+                TriggerAction::BlockOutgoing {
+                    timeout,
+                    duration,
+                    bypass,
+                    replace,
+                    lane,
+                    machine,
+                },
+                TriggerAction::BlockOutgoing {
+                    timeout: to,
+                    duration: d,
+                    bypass: b,
+                    replace: r,
+                    lane: l,
+                    machine: m,
+                },
+            ) => {
+                timeout == to
+                    && duration == d
+                    && bypass == b
+                    && replace == r
+                    && lane == l
+                    && machine == m
+            }
+            (
+                TriggerAction::UpdateTimer {
+                    duration,
+                    replace,
+                    machine,
+                },
+                TriggerAction::UpdateTimer {
+                    duration: d,
+                    replace: r,
+                    machine: m,
+                },
+            ) => duration == d && replace == r && machine == m,
+            (
+                TriggerAction::BlockIncoming {
+                    timeout,
+                    duration,
+                    replace,
+                    machine,
+                },
+                TriggerAction::BlockIncoming {
+                    timeout: to,
+                    duration: d,
+                    replace: r,
+                    machine: m,
+                },
+            ) => timeout == to && duration == d && replace == r && machine == m,
+            (
+                TriggerAction::HoldOutgoing {
+                    timeout,
+                    duration,
+                    probability,
+                    machine,
+                },
+                TriggerAction::HoldOutgoing {
+                    timeout: to,
+                    duration: d,
+                    probability: p,
+                    machine: m,
+                },
+            ) => timeout == to && duration == d && probability == p && machine == m,
+            _ => false,
+        }
+    }
+}
+
+impl<T: crate::time::Instant> Eq for TriggerAction<T> where T::Duration: Eq {}
+
 impl fmt::Display for TriggerAction {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{:#?}", self)
     }
 }
 
+#[cfg(test)]
+impl proptest::arbitrary::Arbitrary for Action {
+    type Parameters = ();
+    type Strategy = proptest::strategy::BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        use proptest::prelude::*;
+
+        let timer = prop_oneof![
+            Just(Timer::Action),
+            Just(Timer::Internal),
+            Just(Timer::All),
+            Just(Timer::ActionPadding),
+            Just(Timer::ActionBlocking),
+        ];
+        prop_oneof![
+            timer.prop_map(|timer| Action::Cancel { timer }),
+            (
+                any::<bool>(),
+                any::<bool>(),
+                any::<Dist>(),
+                any::<Option<Dist>>()
+            )
+                .prop_map(|(bypass, replace, timeout, limit)| Action::SendPadding {
+                    bypass,
+                    replace,
+                    timeout,
+                    limit,
+                }),
+            (
+                any::<bool>(),
+                any::<bool>(),
+                0..BLOCKING_LANES as u8,
+                any::<Dist>(),
+                any::<Dist>(),
+                any::<Option<Dist>>()
+            )
+                .prop_map(|(bypass, replace, lane, timeout, duration, limit)| {
+                    Action::BlockOutgoing {
+                        bypass,
+                        replace,
+                        lane,
+                        timeout,
+                        duration,
+                        limit,
+                    }
+                }),
+            (any::<bool>(), any::<Dist>(), any::<Option<Dist>>()).prop_map(
+                |(replace, duration, limit)| Action::UpdateTimer {
+                    replace,
+                    duration,
+                    limit,
+                }
+            ),
+            (any::<bool>(), any::<Dist>(), any::<Option<Dist>>()).prop_map(
+                |(replace, period, limit)| Action::UpdateTimerAligned {
+                    replace,
+                    period,
+                    limit,
+                }
+            ),
+            (
+                any::<bool>(),
+                any::<bool>(),
+                any::<Dist>(),
+                any::<Dist>(),
+                1..=10u32,
+                any::<Option<Dist>>()
+            )
+                .prop_map(|(bypass, replace, timeout, interval, count, limit)| {
+                    Action::SendPaddingBurst {
+                        bypass,
+                        replace,
+                        timeout,
+                        interval,
+                        count,
+                        limit,
+                    }
+                }),
+            (
+                any::<bool>(),
+                any::<Dist>(),
+                any::<Dist>(),
+                any::<Option<Dist>>()
+            )
+                .prop_map(|(replace, timeout, duration, limit)| {
+                    Action::BlockIncoming {
+                        replace,
+                        timeout,
+                        duration,
+                        limit,
+                    }
+                }),
+            (
+                any::<Dist>(),
+                any::<Dist>(),
+                any::<Dist>(),
+                any::<Option<Dist>>()
+            )
+                .prop_map(|(probability, timeout, duration, limit)| {
+                    Action::HoldOutgoing {
+                        probability,
+                        timeout,
+                        duration,
+                        limit,
+                    }
+                }),
+        ]
+        .boxed()
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::{action::*, dist::DistType};
+    use crate::{
+        action::*,
+        dist::{DistType, TimeUnit},
+    };
+    use std::time::Duration;
+
+    #[test]
+    fn trigger_action_machine_and_timer() {
+        let machine = MachineId::from_raw(3);
+        let d = Duration::from_micros(1);
+        let actions: [(TriggerAction, Timer); 7] = [
+            (
+                TriggerAction::Cancel {
+                    machine,
+                    timer: Timer::ActionBlocking,
+                },
+                Timer::ActionBlocking,
+            ),
+            (
+                TriggerAction::Cancel {
+                    machine,
+                    timer: Timer::All,
+                },
+                Timer::All,
+            ),
+            (
+                TriggerAction::SendPadding {
+                    timeout: d,
+                    bypass: false,
+                    replace: false,
+                    machine,
+                },
+                Timer::Action,
+            ),
+            (
+                TriggerAction::BlockOutgoing {
+                    timeout: d,
+                    duration: d,
+                    bypass: false,
+                    replace: false,
+                    lane: 1,
+                    machine,
+                },
+                Timer::Action,
+            ),
+            (
+                TriggerAction::UpdateTimer {
+                    duration: d,
+                    replace: false,
+                    machine,
+                },
+                Timer::Internal,
+            ),
+            (
+                TriggerAction::BlockIncoming {
+                    timeout: d,
+                    duration: d,
+                    replace: false,
+                    machine,
+                },
+                Timer::Action,
+            ),
+            (
+                TriggerAction::HoldOutgoing {
+                    timeout: d,
+                    duration: d,
+                    probability: 0.5,
+                    machine,
+                },
+                Timer::Action,
+            ),
+        ];
+        for (action, timer) in actions {
+            assert_eq!(action.machine(), machine);
+            assert_eq!(action.timer(), timer);
+        }
+    }
 
     #[test]
     fn validate_cancel_action() {
@@ -252,6 +933,22 @@ mod tests {
 
         let r = a.validate();
         assert!(r.is_ok());
+
+        // action timer, only pending padding
+        let a = Action::Cancel {
+            timer: Timer::ActionPadding,
+        };
+
+        let r = a.validate();
+        assert!(r.is_ok());
+
+        // action timer, only pending blocking
+        let a = Action::Cancel {
+            timer: Timer::ActionBlocking,
+        };
+
+        let r = a.validate();
+        assert!(r.is_ok());
     }
 
     #[test]
@@ -267,6 +964,7 @@ mod tests {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             },
             limit: Some(Dist {
                 dist: DistType::Normal {
@@ -275,6 +973,7 @@ mod tests {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             }),
         };
 
@@ -290,6 +989,7 @@ mod tests {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             };
         }
 
@@ -305,6 +1005,7 @@ mod tests {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             };
         }
 
@@ -317,6 +1018,7 @@ mod tests {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             });
         }
 
@@ -330,6 +1032,7 @@ mod tests {
         let mut a = Action::BlockOutgoing {
             bypass: false,
             replace: false,
+            lane: 0,
             timeout: Dist {
                 dist: DistType::Uniform {
                     low: 10.0,
@@ -337,6 +1040,7 @@ mod tests {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             },
             duration: Dist {
                 dist: DistType::Uniform {
@@ -345,6 +1049,7 @@ mod tests {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             },
             limit: Some(Dist {
                 dist: DistType::Normal {
@@ -353,6 +1058,7 @@ mod tests {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             }),
         };
 
@@ -369,6 +1075,7 @@ mod tests {
 
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             };
         }
 
@@ -384,6 +1091,7 @@ mod tests {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             };
         }
 
@@ -396,6 +1104,7 @@ mod tests {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             };
         }
 
@@ -411,6 +1120,7 @@ mod tests {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             };
         }
 
@@ -423,11 +1133,75 @@ mod tests {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             });
         }
 
         let r = a.validate();
         assert!(r.is_err());
+
+        // repair limit dist
+        if let Action::BlockOutgoing { limit, .. } = &mut a {
+            *limit = None;
+        }
+
+        // the last blocking lane is valid, beyond it is not
+        if let Action::BlockOutgoing { lane, .. } = &mut a {
+            *lane = BLOCKING_LANES as u8 - 1;
+        }
+        assert!(a.validate().is_ok());
+        if let Action::BlockOutgoing { lane, .. } = &mut a {
+            *lane = BLOCKING_LANES as u8;
+        }
+        let r = a.validate();
+        assert!(r.is_err());
+    }
+
+    #[test]
+    fn validate_padding_burst_action() {
+        let dist = Dist {
+            dist: DistType::Uniform {
+                low: 10.0,
+                high: 10.0,
+            },
+            start: 0.0,
+            max: 0.0,
+            ..Default::default()
+        };
+        let mut a = Action::SendPaddingBurst {
+            bypass: false,
+            replace: false,
+            timeout: dist,
+            interval: dist,
+            count: 1,
+            limit: None,
+        };
+        assert!(a.validate().is_ok());
+
+        // count bounds
+        for (c, valid) in [
+            (0, false),
+            (MAX_PADDING_BURST, true),
+            (MAX_PADDING_BURST + 1, false),
+        ] {
+            if let Action::SendPaddingBurst { count, .. } = &mut a {
+                *count = c;
+            }
+            assert_eq!(a.validate().is_ok(), valid);
+        }
+
+        // invalid interval
+        if let Action::SendPaddingBurst {
+            count, interval, ..
+        } = &mut a
+        {
+            *count = 10;
+            interval.dist = DistType::Uniform {
+                low: 15.0,
+                high: 5.0,
+            };
+        }
+        assert!(a.validate().is_err());
     }
 
     #[test]
@@ -442,6 +1216,7 @@ mod tests {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             },
             limit: Some(Dist {
                 dist: DistType::Normal {
@@ -450,6 +1225,7 @@ mod tests {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             }),
         };
 
@@ -465,6 +1241,7 @@ mod tests {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             };
         }
 
@@ -480,6 +1257,7 @@ mod tests {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             };
         }
 
@@ -492,10 +1270,286 @@ mod tests {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             });
         }
 
         let r = a.validate();
         assert!(r.is_err());
     }
+
+    #[test]
+    fn limit_saturated() {
+        let mut rng = rand::thread_rng();
+        let limit = |low: f64, high: f64| {
+            Some(Dist {
+                dist: DistType::Uniform { low, high },
+                start: 0.0,
+                max: 0.0,
+                ..Default::default()
+            })
+        };
+        let padding = |limit: Option<Dist>| Action::SendPadding {
+            bypass: false,
+            replace: false,
+            timeout: Dist::default(),
+            limit,
+        };
+
+        // no limit is unlimited, but not saturated
+        let a = padding(None);
+        assert!(!a.limit_saturates());
+        assert_eq!(a.sample_limit(&mut rng), (STATE_LIMIT_MAX, false));
+
+        let a = padding(limit(10.0, 10.0));
+        assert!(!a.limit_saturates());
+        assert_eq!(a.sample_limit(&mut rng), (10, false));
+
+        // a limit with mean above the cap is valid, but saturates
+        let a = padding(limit(1e19, 1e20));
+        assert!(a.validate().is_ok());
+        assert!(a.limit_saturates());
+        let mut saturated = 0;
+        for _ in 0..1000 {
+            let (limit, s) = a.sample_limit(&mut rng);
+            assert!(limit >= 1e19 as u64);
+            assert_eq!(s, limit == STATE_LIMIT_MAX);
+            saturated += s as usize;
+        }
+        assert!(saturated > 800);
+
+        // a max clamps below the cap
+        let a = padding(Some(Dist {
+            dist: DistType::Uniform {
+                low: 1e20,
+                high: 1e21,
+            },
+            start: 0.0,
+            max: 1000.0,
+            ..Default::default()
+        }));
+        assert!(!a.limit_saturates());
+        assert_eq!(a.sample_limit(&mut rng), (1000, false));
+    }
+
+    #[test]
+    fn sample_none_and_constant() {
+        let mut rng = rand::thread_rng();
+        let padding = |limit: Option<Dist>| Action::SendPadding {
+            bypass: false,
+            replace: false,
+            timeout: Dist::constant(0.0),
+            limit,
+        };
+
+        // no limit is unlimited, a constant limit of 0 is not
+        let a = padding(None);
+        assert!(!a.has_limit());
+        assert_eq!(a.sample_limit(&mut rng), (STATE_LIMIT_MAX, false));
+        let a = padding(Some(Dist::constant(0.0)));
+        assert!(a.validate().is_ok());
+        assert!(a.has_limit());
+        assert_eq!(a.sample_limit(&mut rng), (0, false));
+        assert_eq!(a.sample_timeout(&mut rng), 0);
+
+        let a = padding(Some(Dist::constant(3.0)));
+        assert_eq!(a.sample_limit(&mut rng), (3, false));
+
+        // a constant timeout, converted from its unit
+        let a = Action::SendPadding {
+            bypass: false,
+            replace: false,
+            timeout: Dist::constant(5.0).with_unit(TimeUnit::Milliseconds),
+            limit: None,
+        };
+        assert_eq!(a.sample_timeout(&mut rng), 5000);
+
+        // actions without a timeout or limit distribution sample neither
+        let a = Action::Cancel { timer: Timer::All };
+        assert_eq!(a.sample_timeout(&mut rng), 0);
+        assert_eq!(a.sample_limit(&mut rng), (STATE_LIMIT_MAX, false));
+        let a = Action::UpdateTimer {
+            replace: false,
+            duration: Dist::constant(10.0),
+            limit: None,
+        };
+        assert_eq!(a.sample_timeout(&mut rng), 0);
+        assert_eq!(a.sample_duration(&mut rng), 10);
+    }
+
+    #[test]
+    fn exceeds_caps() {
+        let normal = |mean: f64, stdev: f64| Dist {
+            dist: DistType::Normal { mean, stdev },
+            start: 0.0,
+            max: 0.0,
+            ..Default::default()
+        };
+        let padding = |timeout: Dist| Action::SendPadding {
+            bypass: false,
+            replace: false,
+            timeout,
+            limit: None,
+        };
+
+        // a day in microseconds is the cap
+        assert!(!padding(normal(1000.0, 100.0)).exceeds_caps());
+        assert!(!padding(normal(1e9, 1e9)).exceeds_caps());
+
+        // over-dispersed: the mean is well below the cap, but the 99th
+        // percentile is not
+        let a = padding(normal(1e9, 1e11));
+        assert!(a.validate().is_ok());
+        assert!(a.exceeds_caps());
+        let mut rng = rand::thread_rng();
+        let capped = (0..1000)
+            .filter(|_| a.sample_timeout(&mut rng) == MAX_SAMPLED_TIMEOUT as u64)
+            .count();
+        assert!(capped > 100);
+
+        // a max below the cap clamps first
+        let mut timeout = normal(1e9, 1e11);
+        timeout.max = 1e9;
+        assert!(!padding(timeout).exceeds_caps());
+
+        // per cap, also for the duration
+        let block = Action::BlockOutgoing {
+            bypass: false,
+            replace: false,
+            lane: 0,
+            timeout: normal(0.0, 0.0),
+            duration: normal(1e9, 1e11),
+            limit: None,
+        };
+        assert!(block.exceeds_caps());
+
+        // without a closed form quantile, by mean
+        let timer = |lambda: f64| Action::UpdateTimer {
+            replace: false,
+            duration: Dist {
+                dist: DistType::Poisson { lambda },
+                start: 0.0,
+                max: 0.0,
+                ..Default::default()
+            },
+            limit: None,
+        };
+        assert!(!timer(1e6).exceeds_caps());
+        assert!(timer(1e11).exceeds_caps());
+        assert!(!Action::Cancel { timer: Timer::All }.exceeds_caps());
+    }
+
+    #[test]
+    fn expected_values() {
+        let dist = |dist: DistType, start: f64| Dist {
+            dist,
+            start,
+            max: 0.0,
+            ..Default::default()
+        };
+        let mut rng = rand::thread_rng();
+        let n = 100_000;
+        let empirical = |sample: &mut dyn FnMut() -> u64| {
+            (0..n).map(|_| sample() as f64).sum::<f64>() / n as f64
+        };
+
+        // padding rate as expected timeout, compared to sampled timeouts
+        let a = Action::SendPadding {
+            bypass: false,
+            replace: false,
+            timeout: dist(DistType::Exponential { rate: 0.001 }, 100.0),
+            limit: None,
+        };
+        let e = a.expected_timeout().unwrap();
+        assert_eq!(e, 1100.0);
+        let mean = empirical(&mut || a.sample_timeout(&mut rng));
+        assert!((mean - e).abs() / e < 0.02, "{} vs {}", mean, e);
+        assert!(a.expected_duration().is_err());
+        assert!(a.expected_interval().is_err());
+
+        // blocking, with the duration clamped to its cap
+        let a = Action::BlockOutgoing {
+            bypass: false,
+            replace: false,
+            lane: 0,
+            timeout: dist(
+                DistType::Normal {
+                    mean: 1000.0,
+                    stdev: 100.0,
+                },
+                0.0,
+            ),
+            duration: dist(
+                DistType::Uniform {
+                    low: 2.0 * MAX_SAMPLED_BLOCK_DURATION,
+                    high: 3.0 * MAX_SAMPLED_BLOCK_DURATION,
+                },
+                0.0,
+            ),
+            limit: None,
+        };
+        let e = a.expected_timeout().unwrap();
+        let mean = empirical(&mut || a.sample_timeout(&mut rng));
+        assert!((mean - e).abs() / e < 0.02, "{} vs {}", mean, e);
+        assert_eq!(a.expected_duration().unwrap(), MAX_SAMPLED_BLOCK_DURATION);
+        assert_eq!(
+            empirical(&mut || a.sample_duration(&mut rng)),
+            MAX_SAMPLED_BLOCK_DURATION
+        );
+
+        // bursts and timers
+        let a = Action::SendPaddingBurst {
+            bypass: false,
+            replace: false,
+            timeout: dist(DistType::Poisson { lambda: 10.0 }, 0.0),
+            interval: dist(
+                DistType::Uniform {
+                    low: 10.0,
+                    high: 30.0,
+                },
+                0.0,
+            ),
+            count: 5,
+            limit: None,
+        };
+        assert_eq!(a.expected_timeout().unwrap(), 10.0);
+        let e = a.expected_interval().unwrap();
+        assert_eq!(e, 20.0);
+        let mean = empirical(&mut || a.sample_interval(&mut rng));
+        assert!((mean - e).abs() / e < 0.02, "{} vs {}", mean, e);
+        let a = Action::UpdateTimer {
+            replace: false,
+            duration: dist(
+                DistType::Gamma {
+                    scale: 100.0,
+                    shape: 2.0,
+                },
+                0.0,
+            ),
+            limit: None,
+        };
+        let e = a.expected_duration().unwrap();
+        assert_eq!(e, 200.0);
+        let mean = empirical(&mut || a.sample_duration(&mut rng));
+        assert!((mean - e).abs() / e < 0.02, "{} vs {}", mean, e);
+        assert!(a.expected_timeout().is_err());
+
+        // no field or no known mean
+        assert!(Action::Cancel { timer: Timer::All }
+            .expected_timeout()
+            .is_err());
+        let a = Action::SendPadding {
+            bypass: false,
+            replace: false,
+            timeout: dist(
+                DistType::Pareto {
+                    scale: 1.0,
+                    shape: 1.0,
+                },
+                0.0,
+            ),
+            limit: None,
+        };
+        assert!(a.expected_timeout().is_err());
+    }
 }
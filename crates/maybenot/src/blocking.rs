@@ -0,0 +1,312 @@
+//! Reference implementation of global blocking of outgoing traffic, for use
+//! by integrators of the [`Framework`](crate::Framework).
+
+use crate::time::{Duration as _, Instant};
+use crate::{TriggerAction, TriggerEvent};
+
+/// The effect of applying a [`TriggerAction`] to a [`BlockingState`]. For all
+/// blocking effects, the contained [`TriggerEvent::BlockingBegin`] MUST be
+/// triggered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Effect {
+    /// The action was not to block outgoing traffic, nothing changed.
+    None,
+    /// No blocking was taking place, blocking started.
+    Started(TriggerEvent),
+    /// Blocking was taking place, and the action's duration was longer than
+    /// the remaining duration, so blocking was extended.
+    Extended(TriggerEvent),
+    /// Blocking was taking place, and the action had the replace flag set, so
+    /// the blocking duration was replaced (possibly shortening the blocking).
+    Replaced(TriggerEvent),
+    /// Blocking was taking place with a longer remaining duration than the
+    /// action's duration, so nothing changed.
+    Unchanged(TriggerEvent),
+}
+
+impl Effect {
+    /// Returns the event that MUST be triggered, if any.
+    pub fn event(&self) -> Option<&TriggerEvent> {
+        match self {
+            Effect::None => None,
+            Effect::Started(e)
+            | Effect::Extended(e)
+            | Effect::Replaced(e)
+            | Effect::Unchanged(e) => Some(e),
+        }
+    }
+}
+
+/// The state of global blocking of a lane of outgoing traffic, shared by all
+/// machines in a [`Framework`](crate::Framework). Use one state per lane in
+/// use, see [`BLOCKING_LANES`](crate::constants::BLOCKING_LANES).
+///
+/// Apply each [`TriggerAction::BlockOutgoing`] once its timeout has expired
+/// with [`Self::apply()`], and call [`Self::expire()`] when blocking may have
+/// ended (before applying any new action). Trigger all returned events. This
+/// encodes the rules for starting, extending, and replacing blocking as
+/// expected by the framework.
+#[derive(Debug, Clone)]
+pub struct BlockingState<T = std::time::Instant>
+where
+    T: Instant,
+{
+    lane: u8,
+    started: T,
+    duration: T::Duration,
+    active: bool,
+    bypassable: bool,
+}
+
+impl<T> BlockingState<T>
+where
+    T: Instant,
+{
+    /// Create a new state for lane 0, with no blocking taking place.
+    pub fn new(now: T) -> Self {
+        Self::with_lane(0, now)
+    }
+
+    /// Create a new state for the given lane, with no blocking taking place.
+    pub fn with_lane(lane: u8, now: T) -> Self {
+        Self {
+            lane,
+            started: now,
+            duration: T::Duration::zero(),
+            active: false,
+            bypassable: false,
+        }
+    }
+
+    /// Returns the lane of the state.
+    pub fn lane(&self) -> u8 {
+        self.lane
+    }
+
+    /// Returns true if blocking is taking place.
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Returns true if blocking is taking place and MAY be bypassed by padding
+    /// with the bypass flag set.
+    pub fn is_bypassable(&self) -> bool {
+        self.active && self.bypassable
+    }
+
+    /// Apply an action, whose timeout has expired, at the given time.
+    ///
+    /// If no blocking is taking place, blocking starts for the action's
+    /// duration. Otherwise, if the replace flag is set, the blocking duration
+    /// is replaced. If not, the longest of the action's duration and the
+    /// remaining duration is used. The bypass flag of the action applies if
+    /// the blocking was started, extended, or replaced. Actions blocking other
+    /// lanes have no effect.
+    ///
+    /// Returns the [`Effect`] of the action, with the event to trigger.
+    pub fn apply(&mut self, action: &TriggerAction<T>, now: T) -> Effect {
+        let TriggerAction::BlockOutgoing {
+            duration,
+            bypass,
+            replace,
+            lane,
+            machine,
+            ..
+        } = action
+        else {
+            return Effect::None;
+        };
+        if *lane != self.lane {
+            return Effect::None;
+        }
+        let event = TriggerEvent::BlockingBegin {
+            machine: *machine,
+            lane: self.lane,
+        };
+
+        // blocking that should have ended but has not yet expired is ongoing
+        // until expire() is called
+        let effect = if !self.active {
+            Effect::Started(event)
+        } else if *replace {
+            Effect::Replaced(event)
+        } else {
+            // duration since blocking started, if the action's duration is
+            // used instead of what remains
+            let mut end = now.saturating_duration_since(self.started);
+            end += *duration;
+            if end > self.duration {
+                Effect::Extended(event)
+            } else {
+                return Effect::Unchanged(event);
+            }
+        };
+
+        self.active = true;
+        self.started = now;
+        self.duration = *duration;
+        self.bypassable = *bypass;
+        effect
+    }
+
+    /// Expire any blocking that has ended at the given time.
+    ///
+    /// Returns [`TriggerEvent::BlockingEnd`] if blocking ended, which MUST be
+    /// triggered.
+    pub fn expire(&mut self, now: T) -> Option<TriggerEvent> {
+        if self.active && now.saturating_duration_since(self.started) >= self.duration {
+            self.active = false;
+            self.bypassable = false;
+            return Some(TriggerEvent::BlockingEnd { lane: self.lane });
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::blocking::*;
+    use crate::MachineId;
+    use std::ops::Add;
+    use std::time::Duration;
+
+    fn block(machine: usize, duration: u64, bypass: bool, replace: bool) -> TriggerAction {
+        TriggerAction::BlockOutgoing {
+            timeout: Duration::from_micros(0),
+            duration: Duration::from_micros(duration),
+            bypass,
+            replace,
+            lane: 0,
+            machine: MachineId::from_raw(machine),
+        }
+    }
+
+    #[test]
+    fn blocking_longest_duration() {
+        let start = std::time::Instant::now();
+        let at = |micros| start.add(Duration::from_micros(micros));
+        let mut b = BlockingState::new(start);
+        let begin = |machine| TriggerEvent::BlockingBegin {
+            machine: MachineId::from_raw(machine),
+            lane: 0,
+        };
+
+        // start blocking for 10 us, bypassable
+        assert_eq!(
+            b.apply(&block(0, 10, true, false), at(0)),
+            Effect::Started(begin(0))
+        );
+        assert!(b.is_active());
+        assert!(b.is_bypassable());
+
+        // at 5 us, 5 us remain: shorter duration without replace is ignored,
+        // including the bypass flag, but the event is still triggered
+        assert_eq!(
+            b.apply(&block(1, 3, false, false), at(5)),
+            Effect::Unchanged(begin(1))
+        );
+        assert!(b.is_bypassable());
+        assert_eq!(b.expire(at(9)), None);
+
+        // at 9 us, 1 us remains: extend to 10 us from now
+        assert_eq!(
+            b.apply(&block(1, 10, false, false), at(9)),
+            Effect::Extended(begin(1))
+        );
+        assert!(!b.is_bypassable());
+        assert_eq!(b.expire(at(10)), None);
+        assert_eq!(b.expire(at(18)), None);
+        assert_eq!(
+            b.expire(at(19)),
+            Some(TriggerEvent::BlockingEnd { lane: 0 })
+        );
+        assert!(!b.is_active());
+        assert_eq!(b.expire(at(20)), None);
+    }
+
+    #[test]
+    fn blocking_replace() {
+        let start = std::time::Instant::now();
+        let at = |micros| start.add(Duration::from_micros(micros));
+        let mut b = BlockingState::new(start);
+        let begin = TriggerEvent::BlockingBegin {
+            machine: MachineId::from_raw(0),
+            lane: 0,
+        };
+
+        // replace when not blocking starts blocking
+        assert_eq!(
+            b.apply(&block(0, 100, false, true), at(0)),
+            Effect::Started(begin.clone())
+        );
+        // replace shortens the blocking
+        assert_eq!(
+            b.apply(&block(0, 10, true, true), at(1)),
+            Effect::Replaced(begin.clone())
+        );
+        assert!(b.is_bypassable());
+        assert_eq!(b.expire(at(10)), None);
+        assert_eq!(
+            b.expire(at(11)),
+            Some(TriggerEvent::BlockingEnd { lane: 0 })
+        );
+
+        // other actions have no effect
+        let padding = TriggerAction::SendPadding {
+            timeout: Duration::from_micros(0),
+            bypass: false,
+            replace: false,
+            machine: MachineId::from_raw(0),
+        };
+        assert_eq!(b.apply(&padding, at(12)), Effect::None);
+        assert_eq!(Effect::None.event(), None);
+        assert!(!b.is_active());
+    }
+
+    #[test]
+    fn blocking_lanes() {
+        let start = std::time::Instant::now();
+        let at = |micros| start.add(Duration::from_micros(micros));
+        let mut b0 = BlockingState::new(start);
+        let mut b1 = BlockingState::with_lane(1, start);
+        assert_eq!(b0.lane(), 0);
+        assert_eq!(b1.lane(), 1);
+        let block1 = TriggerAction::BlockOutgoing {
+            timeout: Duration::from_micros(0),
+            duration: Duration::from_micros(10),
+            bypass: false,
+            replace: false,
+            lane: 1,
+            machine: MachineId::from_raw(0),
+        };
+
+        // each state only applies actions of its own lane
+        assert_eq!(b0.apply(&block1, at(0)), Effect::None);
+        assert!(!b0.is_active());
+        assert_eq!(
+            b1.apply(&block1, at(0)),
+            Effect::Started(TriggerEvent::BlockingBegin {
+                machine: MachineId::from_raw(0),
+                lane: 1,
+            })
+        );
+        assert_eq!(
+            b0.apply(&block(0, 5, false, false), at(0)),
+            Effect::Started(TriggerEvent::BlockingBegin {
+                machine: MachineId::from_raw(0),
+                lane: 0,
+            })
+        );
+
+        // and the lanes expire independently
+        assert_eq!(
+            b0.expire(at(5)),
+            Some(TriggerEvent::BlockingEnd { lane: 0 })
+        );
+        assert!(b1.is_active());
+        assert_eq!(
+            b1.expire(at(10)),
+            Some(TriggerEvent::BlockingEnd { lane: 1 })
+        );
+    }
+}
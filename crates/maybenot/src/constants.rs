@@ -1,15 +1,66 @@
 //! Global constants for the framework.
+//!
+//! These are the limits that [`Machine::validate()`](crate::Machine::validate)
+//! and the framework enforce, for integrations to validate their own
+//! configuration against. Sampled durations are in microseconds, after
+//! conversion from the [`TimeUnit`](crate::dist::TimeUnit) of their
+//! distribution.
+//!
+//! ```
+//! use maybenot::constants::{MAX_SAMPLED_TIMEOUT, STATE_MAX};
+//! use maybenot::dist::{Dist, DistType};
+//!
+//! // a timeout of up to an hour is well within the limit
+//! let hour = 60.0 * 60.0 * 1000.0 * 1000.0;
+//! assert!(hour <= MAX_SAMPLED_TIMEOUT);
+//! let timeout = Dist {
+//!     dist: DistType::Uniform {
+//!         low: 0.0,
+//!         high: hour,
+//!     },
+//!     start: 0.0,
+//!     max: MAX_SAMPLED_TIMEOUT,
+//!     ..Default::default()
+//! };
+//! assert!(timeout.validate().is_ok());
+//!
+//! // machine states are indexed by usize, up to STATE_MAX states
+//! let states: usize = 100;
+//! assert!(states <= STATE_MAX);
+//! ```
 
 /// The highest possible version of a [`Machine`](crate::Machine) supported by
 /// this framework.
-pub const VERSION: u8 = 2;
+///
+/// The version is the prefix of serialized machines. Since version 3, machines
+/// are encoded with the names of their fields, and fields added later have
+/// defaults, so machines serialized before a field was added still decode.
+/// The version MUST be incremented with any change to the serialization format
+/// that causes previously serialized machines to no longer deserialize as
+/// before, such as removing or renaming a field. Machines of version 2 are
+/// still decoded, while older versions are rejected with an error. See the
+/// changelog for the changes of each version.
+pub const VERSION: u8 = 3;
+
+/// A feature bit of [`Machine::requires`](crate::Machine::requires): the
+/// machine requires the `portable-sample` feature, e.g., because it was tuned
+/// with samples that must be the same on all platforms.
+pub const FEATURE_PORTABLE_SAMPLE: u32 = 1 << 0;
+
+/// The feature bits of [`Machine::requires`](crate::Machine::requires) that
+/// this framework supports, depending on the features it was built with.
+pub const SUPPORTED_FEATURES: u32 = if cfg!(feature = "portable-sample") {
+    FEATURE_PORTABLE_SAMPLE
+} else {
+    0
+};
 
 /// The maximum size of a decompressed encoded [`Machine`](crate::Machine) in
 /// bytes. Set to 1MB. This is a soft limit and can be increased if necessary.
 pub const MAX_DECOMPRESSED_SIZE: usize = 1 << 20;
 
 /// The number of [`Event`](crate::event)s in the framework.
-pub const EVENT_NUM: usize = 13;
+pub const EVENT_NUM: usize = 16;
 
 /// The maximum sampled timeout in a [`State`](crate::state), set to a day in
 /// microseconds.
@@ -23,9 +74,18 @@ pub const MAX_SAMPLED_TIMER_DURATION: f64 = 24.0 * 60.0 * 60.0 * 1000.0 * 1000.0
 /// day in microseconds.
 pub const MAX_SAMPLED_BLOCK_DURATION: f64 = 24.0 * 60.0 * 60.0 * 1000.0 * 1000.0;
 
+/// The number of blocking lanes of outgoing traffic: each
+/// [`Action::BlockOutgoing`](crate::action::Action::BlockOutgoing) blocks the
+/// traffic of one lane, in `[0, BLOCKING_LANES)`, independently of the others.
+pub const BLOCKING_LANES: usize = 4;
+
+/// The maximum number of padding packets in a burst scheduled by a single
+/// [`Action::SendPaddingBurst`](crate::action::Action::SendPaddingBurst).
+pub const MAX_PADDING_BURST: u32 = 1000;
+
 /// The maximum possible sampled limit of a [`State`](crate::state). This is the
 /// default if no limit dist is specified (in practice, the same as no limit).
-pub(crate) const STATE_LIMIT_MAX: u64 = u64::MAX;
+pub const STATE_LIMIT_MAX: u64 = u64::MAX;
 
 /// A pseudo-state that means the [`Machine`](crate::Machine) should completely
 /// stop.
@@ -33,6 +93,13 @@ pub const STATE_END: usize = u32::MAX as usize;
 /// A pseudo-state that triggers a Signal [`Event`](crate::event) in all other
 /// running machines.
 pub const STATE_SIGNAL: usize = STATE_END - 1;
+/// A pseudo-state that means the [`Machine`](crate::Machine) should return to
+/// the state it was in before its current state. Before the first change of
+/// state, the previous state is the first state.
+pub const STATE_PREVIOUS: usize = STATE_SIGNAL - 1;
+/// A pseudo-state that means the [`Machine`](crate::Machine) should transition
+/// to one of its states, chosen uniformly at random.
+pub const STATE_RANDOM: usize = STATE_PREVIOUS - 1;
 /// The maximum number of [`State`](crate::state)s a [`Machine`](crate::Machine)
 /// can have.
-pub const STATE_MAX: usize = STATE_SIGNAL - 1;
+pub const STATE_MAX: usize = STATE_RANDOM - 1;
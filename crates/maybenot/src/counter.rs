@@ -112,6 +112,7 @@ mod tests {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             },
         );
 
@@ -126,6 +127,7 @@ mod tests {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         });
 
         let r = cu.validate();
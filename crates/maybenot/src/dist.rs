@@ -2,8 +2,8 @@
 
 use rand_core::RngCore;
 use rand_distr::{
-    Beta, Binomial, Distribution, Gamma, Geometric, LogNormal, Normal, Pareto, Poisson, SkewNormal,
-    Uniform, Weibull,
+    Beta, Binomial, Distribution, Exp, Gamma, Geometric, LogNormal, Normal, Pareto, Poisson,
+    SkewNormal, Uniform, Weibull,
 };
 use serde::{Deserialize, Serialize};
 use std::fmt;
@@ -13,6 +13,20 @@ use crate::Error;
 /// DistType represents the type of a [`Dist`]. Supports a wide range of
 /// different distributions. Some are probably useless and some are probably
 /// missing. Uses the [`rand_distr`] crate for sampling.
+///
+/// With the `portable-sample` feature, the Uniform, Normal, LogNormal,
+/// Pareto, Weibull, and Exponential distributions are instead sampled using only
+/// platform-independent floating-point operations (software implementations
+/// of transcendental functions), such that the same seed results in the same
+/// samples on all platforms. Note that this changes the sampled values
+/// compared to without the feature.
+///
+/// There is no type for the absence of a distribution. Where a distribution
+/// is optional, such as the limit of an [`Action`](crate::action::Action),
+/// it is an `Option<Dist>`, and `None` is never sampled: see the field for
+/// its meaning. A constant distribution (see [`Dist::constant()`]) always
+/// samples a value, so a constant 0 is not the same as none, e.g., a limit of
+/// 0 never allows the action, while no limit never restricts it.
 #[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
 pub enum DistType {
     /// Uniformly random [low, high). If low == high, constant.
@@ -56,7 +70,12 @@ pub enum DistType {
         /// The probability of success.
         probability: f64,
     },
-    /// Geometric distribution with set probability. Useful for yes/no events.
+    /// Geometric distribution with set probability. Useful for yes/no events,
+    /// e.g., the number of events before a transition in a discrete backoff.
+    /// Samples are the number of failures before the first success, i.e.,
+    /// integer counts in `[0, inf)` with mean `(1 - probability) /
+    /// probability`, so no rounding is needed when used as a limit. The
+    /// probability has to be in `(0, 1]`.
     Geometric {
         /// The probability of success.
         probability: f64,
@@ -70,13 +89,17 @@ pub enum DistType {
         shape: f64,
     },
     /// Poisson distribution with set lambda. Useful for occurrence of
-    /// independent events at a given rate.
+    /// independent events at a given rate, e.g., the number of packets in a
+    /// burst. Samples are non-negative integers, but a non-integer start
+    /// results in non-integer values: round before use as a count.
     Poisson {
         /// The lambda of the distribution.
         lambda: f64,
     },
     /// Weibull distribution with set scale and shape. Useful for occurrence of
-    /// independent events at a given rate.
+    /// independent events at a given rate, and for lifetimes (e.g., of
+    /// connections) with a tunable tail: heavier for a shape below one, and
+    /// lighter above. Samples are non-negative.
     Weibull {
         /// The scale of the distribution.
         scale: f64,
@@ -97,6 +120,12 @@ pub enum DistType {
         /// The beta of the distribution.
         beta: f64,
     },
+    /// Exponential distribution with set rate. Useful for the time between
+    /// independent events at a given rate, i.e., a Poisson process.
+    Exponential {
+        /// The rate of the distribution, with mean 1/rate.
+        rate: f64,
+    },
 }
 
 impl fmt::Display for DistType {
@@ -105,31 +134,103 @@ impl fmt::Display for DistType {
     }
 }
 
+/// A parameter of a [`Dist`] that a parameter of a machine template can be
+/// bound to, see [`Machine::instantiate()`](crate::Machine::instantiate).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DistParam {
+    /// The starting value of the distribution.
+    Start,
+    /// The maximum value of the distribution.
+    Max,
+    /// The first parameter of the [`DistType`], e.g., low for Uniform, mean
+    /// for Normal, and rate for Exponential.
+    First,
+    /// The second parameter of the [`DistType`], e.g., high for Uniform and
+    /// stdev for Normal.
+    Second,
+    /// The third parameter of the [`DistType`], only shape for SkewNormal.
+    Third,
+}
+
+/// The unit of time of the values sampled from a [`Dist`] that is a timeout,
+/// interval, duration, or period of an [`Action`](crate::action::Action).
+/// Sampled values are converted to microseconds before they are clamped to
+/// the caps in [`constants`](crate::constants) and scheduled. Since actions
+/// are scheduled with microsecond resolution, values in nanoseconds are
+/// rounded to the nearest microsecond. Distributions of limits, counters, and
+/// probabilities are unitless: their unit is ignored.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimeUnit {
+    /// Microseconds, the default and the unit of all caps of the framework.
+    #[default]
+    Microseconds,
+    /// Milliseconds, i.e., 1000 microseconds.
+    Milliseconds,
+    /// Nanoseconds, i.e., 1/1000 microseconds.
+    Nanoseconds,
+    /// Seconds, i.e., 1000000 microseconds.
+    Seconds,
+}
+
+impl fmt::Display for TimeUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let unit = match self {
+            TimeUnit::Microseconds => "us",
+            TimeUnit::Milliseconds => "ms",
+            TimeUnit::Nanoseconds => "ns",
+            TimeUnit::Seconds => "s",
+        };
+        write!(f, "{}", unit)
+    }
+}
+
+impl TimeUnit {
+    /// Convert a value in this unit to microseconds.
+    pub fn to_micros(self, value: f64) -> f64 {
+        match self {
+            TimeUnit::Microseconds => value,
+            TimeUnit::Milliseconds => value * 1000.0,
+            TimeUnit::Nanoseconds => value / 1000.0,
+            TimeUnit::Seconds => value * 1_000_000.0,
+        }
+    }
+}
+
 /// A distribution used in a [`State`](crate::state). Can be sampled to get a
 /// value. The value is clamped to the range [start, max] if both are set.
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct Dist {
     /// The type of distribution.
     pub dist: DistType,
-    /// The starting value that the sampled value is added to.
+    /// The starting value that the sampled value is added to. May be negative
+    /// to subtract a baseline from the sampled value, which is then clamped
+    /// to 0.0.
     pub start: f64,
     /// The maximum value that can be sampled (including starting value).
     pub max: f64,
+    /// The unit of time of sampled values, start, and max, if the
+    /// distribution is a time (see [`TimeUnit`]). Defaults to microseconds.
+    #[serde(default)]
+    pub unit: TimeUnit,
 }
 
 impl fmt::Display for Dist {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let clamp;
-        if self.start > 0.0 && self.max > 0.0 {
+        if self.start != 0.0 && self.max > 0.0 {
             clamp = format!(", start {}, clamped to [0.0, {}]", self.start, self.max);
-        } else if self.start > 0.0 {
+        } else if self.start != 0.0 {
             clamp = format!(", start {}, clamped to [0.0, f64::MAX]", self.start);
         } else if self.max > 0.0 {
             clamp = format!(", clamped to [0.0, {}]", self.max);
         } else {
             clamp = ", clamped to [0.0, f64::MAX]".to_string();
         }
-        write!(f, "{}{}", self.dist, clamp)
+        write!(f, "{}{}", self.dist, clamp)?;
+        if self.unit != TimeUnit::Microseconds {
+            write!(f, ", in {}", self.unit)?;
+        }
+        Ok(())
     }
 }
 
@@ -147,13 +248,75 @@ impl Default for Dist {
 }
 
 impl Dist {
-    /// Create a new [`Dist`].
+    /// Create a new [`Dist`], in microseconds if a time.
     pub fn new(dist: DistType, start: f64, max: f64) -> Self {
-        Dist { dist, start, max }
+        Dist {
+            dist,
+            start,
+            max,
+            unit: TimeUnit::Microseconds,
+        }
+    }
+
+    /// Create a constant [`Dist`] that always samples the given value, in
+    /// microseconds if a time: a [`DistType::Uniform`] with equal bounds,
+    /// and neither start nor max. Valid for any finite value, but a negative
+    /// value samples 0.0 (see [`Self::sample()`]).
+    pub fn constant(value: f64) -> Self {
+        Self::new(
+            DistType::Uniform {
+                low: value,
+                high: value,
+            },
+            0.0,
+            0.0,
+        )
+    }
+
+    /// Returns the [`Dist`] with the given [`TimeUnit`].
+    pub fn with_unit(self, unit: TimeUnit) -> Self {
+        Dist { unit, ..self }
     }
 
-    /// Validate that the parameters are valid for the set [`DistType`].
+    /// Validate that the parameters are valid for the set [`DistType`]. All
+    /// parameters, including start and max, have to be finite.
     pub fn validate(&self) -> Result<(), Error> {
+        // non-finite parameters poison sampling and comparisons, and are not
+        // consistently rejected by rand_distr
+        let params = match self.dist {
+            DistType::Uniform { low, high } => [low, high, 0.0],
+            DistType::Normal { mean, stdev } => [mean, stdev, 0.0],
+            DistType::SkewNormal {
+                location,
+                scale,
+                shape,
+            } => [location, scale, shape],
+            DistType::LogNormal { mu, sigma } => [mu, sigma, 0.0],
+            DistType::Binomial { probability, .. } => [probability, 0.0, 0.0],
+            DistType::Geometric { probability } => [probability, 0.0, 0.0],
+            DistType::Pareto { scale, shape }
+            | DistType::Weibull { scale, shape }
+            | DistType::Gamma { scale, shape } => [scale, shape, 0.0],
+            DistType::Poisson { lambda } => [lambda, 0.0, 0.0],
+            DistType::Beta { alpha, beta } => [alpha, beta, 0.0],
+            DistType::Exponential { rate } => [rate, 0.0, 0.0],
+        };
+        if params.iter().any(|p| !p.is_finite()) {
+            Err(Error::Machine(format!(
+                "got non-finite parameter in {}",
+                self.dist
+            )))?;
+        }
+        if !self.start.is_finite() {
+            Err(Error::Machine(format!(
+                "got non-finite start {}",
+                self.start
+            )))?;
+        }
+        if !self.max.is_finite() {
+            Err(Error::Machine(format!("got non-finite max {}", self.max)))?;
+        }
+
         match self.dist {
             DistType::Uniform { low, high } => {
                 if low > high {
@@ -161,6 +324,11 @@ impl Dist {
                         "for Uniform dist, got low > high".to_string(),
                     ))?;
                 }
+                if !(high - low).is_finite() {
+                    Err(Error::Machine(
+                        "for Uniform dist, got non-finite range".to_string(),
+                    ))?;
+                }
             }
             DistType::Normal { mean, stdev } => {
                 Normal::new(mean, stdev).map_err(|e| Error::Machine(e.to_string()))?;
@@ -183,6 +351,12 @@ impl Dist {
                 Binomial::new(trials, probability).map_err(|e| Error::Machine(e.to_string()))?;
             }
             DistType::Geometric { probability } => {
+                // rand_distr accepts 0.0, which would always sample u64::MAX
+                if probability <= 0.0 {
+                    Err(Error::Machine(
+                        "for Geometric dist, got probability <= 0.0".to_string(),
+                    ))?;
+                }
                 Geometric::new(probability).map_err(|e| Error::Machine(e.to_string()))?;
             }
             DistType::Pareto { scale, shape } => {
@@ -202,12 +376,216 @@ impl Dist {
             DistType::Beta { alpha, beta } => {
                 Beta::new(alpha, beta).map_err(|e| Error::Machine(e.to_string()))?;
             }
+            DistType::Exponential { rate } => {
+                if !(rate > 0.0 && rate.is_finite()) {
+                    Err(Error::Machine(format!(
+                        "for Exponential dist, got rate {}, expected positive and finite",
+                        rate
+                    )))?;
+                }
+            }
         };
 
+        // a negative start subtracts from the sampled value, which is clamped
+        // to 0.0: make sure that not every value is clamped
+        if self.start < 0.0 && Dist::new(self.dist, 0.0, 0.0).support().1 + self.start <= 0.0 {
+            Err(Error::Machine(format!(
+                "start {} is so negative that every sampled value is 0.0",
+                self.start
+            )))?;
+        }
+
+        Ok(())
+    }
+
+    /// Set a parameter of the distribution to a value. Returns an error if the
+    /// [`DistType`] has no such parameter, or for a number of Binomial trials
+    /// that is not a non-negative integer.
+    pub(crate) fn set_param(&mut self, param: DistParam, value: f64) -> Result<(), Error> {
+        let dist = self.dist;
+        let field = match (param, &mut self.dist) {
+            (DistParam::Start, _) => &mut self.start,
+            (DistParam::Max, _) => &mut self.max,
+            (DistParam::First, DistType::Binomial { trials, .. }) => {
+                if value < 0.0 || value.fract() != 0.0 {
+                    Err(Error::Machine(format!(
+                        "for Binomial dist, got {} trials",
+                        value
+                    )))?;
+                }
+                *trials = value as u64;
+                return Ok(());
+            }
+            (DistParam::First, DistType::Uniform { low: v, .. })
+            | (DistParam::First, DistType::Normal { mean: v, .. })
+            | (DistParam::First, DistType::SkewNormal { location: v, .. })
+            | (DistParam::First, DistType::LogNormal { mu: v, .. })
+            | (DistParam::First, DistType::Geometric { probability: v })
+            | (DistParam::First, DistType::Pareto { scale: v, .. })
+            | (DistParam::First, DistType::Poisson { lambda: v })
+            | (DistParam::First, DistType::Weibull { scale: v, .. })
+            | (DistParam::First, DistType::Gamma { scale: v, .. })
+            | (DistParam::First, DistType::Beta { alpha: v, .. })
+            | (DistParam::First, DistType::Exponential { rate: v })
+            | (DistParam::Second, DistType::Uniform { high: v, .. })
+            | (DistParam::Second, DistType::Normal { stdev: v, .. })
+            | (DistParam::Second, DistType::SkewNormal { scale: v, .. })
+            | (DistParam::Second, DistType::LogNormal { sigma: v, .. })
+            | (DistParam::Second, DistType::Binomial { probability: v, .. })
+            | (DistParam::Second, DistType::Pareto { shape: v, .. })
+            | (DistParam::Second, DistType::Weibull { shape: v, .. })
+            | (DistParam::Second, DistType::Gamma { shape: v, .. })
+            | (DistParam::Second, DistType::Beta { beta: v, .. })
+            | (DistParam::Third, DistType::SkewNormal { shape: v, .. }) => v,
+            _ => Err(Error::Machine(format!(
+                "{} has no parameter {:?}",
+                dist, param
+            )))?,
+        };
+        *field = value;
         Ok(())
     }
 
-    /// Sample the distribution. May panic if not valid (see [`Self::validate()`]).
+    /// Returns the effective lower and upper bounds of values that can be
+    /// sampled from the distribution, after start and max have been applied.
+    /// The upper bound is [`f64::INFINITY`] if the distribution is unbounded
+    /// and no max is set.
+    ///
+    /// For distributions with bounded support (e.g., Uniform, Binomial, and
+    /// Beta) the bounds are exact. For distributions with unbounded support,
+    /// the bounds are those of the clamping: e.g., a Normal distribution is
+    /// bounded by [0.0, max], which shows that any sampled value below zero is
+    /// clamped to 0.0 and above max to max. The bounds are only meaningful
+    /// for valid distributions (see [`Self::validate()`]).
+    pub fn support(&self) -> (f64, f64) {
+        let (low, high) = match self.dist {
+            // high is exclusive, unless low == high
+            DistType::Uniform { low, high } => (low, high),
+            DistType::Normal { mean, stdev } => {
+                if stdev == 0.0 {
+                    (mean, mean)
+                } else {
+                    (f64::NEG_INFINITY, f64::INFINITY)
+                }
+            }
+            DistType::SkewNormal {
+                location, scale, ..
+            } => {
+                if scale == 0.0 {
+                    (location, location)
+                } else {
+                    (f64::NEG_INFINITY, f64::INFINITY)
+                }
+            }
+            DistType::LogNormal { .. }
+            | DistType::Geometric { .. }
+            | DistType::Poisson { .. }
+            | DistType::Weibull { .. }
+            | DistType::Gamma { .. }
+            | DistType::Exponential { .. } => (0.0, f64::INFINITY),
+            DistType::Binomial { trials, .. } => (0.0, trials as f64),
+            DistType::Pareto { scale, .. } => (scale, f64::INFINITY),
+            DistType::Beta { .. } => (0.0, 1.0),
+        };
+
+        // same as in sample(): add start, then clamp to [0.0, max]
+        let mut low = (low + self.start).max(0.0);
+        let mut high = (high + self.start).max(0.0);
+        if self.max > 0.0 {
+            low = low.min(self.max);
+            high = high.min(self.max);
+        }
+        (low, high)
+    }
+
+    /// Returns the analytic mean of the [`DistType`], before start and max are
+    /// applied, or None if the mean is undefined (e.g., for Pareto with shape
+    /// <= 1.0) or too large to represent (Weibull with a tiny shape). The mean
+    /// is only meaningful for valid distributions (see [`Self::validate()`]).
+    pub fn mean(&self) -> Option<f64> {
+        let mean = match self.dist {
+            DistType::Uniform { low, high } => (low + high) / 2.0,
+            DistType::Normal { mean, .. } => mean,
+            DistType::SkewNormal {
+                location,
+                scale,
+                shape,
+            } => {
+                let delta = shape / (1.0 + shape * shape).sqrt();
+                location + scale * delta * (2.0 / std::f64::consts::PI).sqrt()
+            }
+            DistType::LogNormal { mu, sigma } => (mu + sigma * sigma / 2.0).exp(),
+            DistType::Binomial {
+                trials,
+                probability,
+            } => trials as f64 * probability,
+            // the number of failures before the first success
+            DistType::Geometric { probability } => (1.0 - probability) / probability,
+            DistType::Pareto { scale, shape } => {
+                if shape <= 1.0 {
+                    return None;
+                }
+                shape * scale / (shape - 1.0)
+            }
+            DistType::Poisson { lambda } => lambda,
+            DistType::Weibull { scale, shape } => {
+                let mean = scale * gamma(1.0 + 1.0 / shape);
+                if !mean.is_finite() {
+                    return None;
+                }
+                mean
+            }
+            DistType::Gamma { scale, shape } => scale * shape,
+            DistType::Beta { alpha, beta } => alpha / (alpha + beta),
+            DistType::Exponential { rate } => 1.0 / rate,
+        };
+        Some(mean)
+    }
+
+    /// Returns the p-quantile of sampled values, for p in (0, 1), i.e., the
+    /// value that a fraction p of samples are at most. Start and max are
+    /// applied as in [`Self::sample()`]. Returns None if p is not in (0, 1),
+    /// or if the quantile function of the [`DistType`] has no simple closed
+    /// form (SkewNormal, Binomial, Poisson, Gamma, and Beta). The quantile is
+    /// only meaningful for valid distributions (see [`Self::validate()`]).
+    pub fn quantile(&self, p: f64) -> Option<f64> {
+        if !(p > 0.0 && p < 1.0) {
+            return None;
+        }
+        let q = match self.dist {
+            DistType::Uniform { low, high } => low + (high - low) * p,
+            DistType::Normal { mean, stdev } => mean + stdev * standard_normal_quantile(p),
+            DistType::LogNormal { mu, sigma } => (mu + sigma * standard_normal_quantile(p)).exp(),
+            // the number of failures before the first success
+            DistType::Geometric { probability } => {
+                if probability >= 1.0 {
+                    0.0
+                } else {
+                    ((1.0 - p).ln() / (1.0 - probability).ln() - 1.0)
+                        .ceil()
+                        .max(0.0)
+                }
+            }
+            DistType::Pareto { scale, shape } => scale * (1.0 - p).powf(-1.0 / shape),
+            DistType::Weibull { scale, shape } => scale * (-(1.0 - p).ln()).powf(1.0 / shape),
+            DistType::Exponential { rate } => -(1.0 - p).ln() / rate,
+            DistType::SkewNormal { .. }
+            | DistType::Binomial { .. }
+            | DistType::Poisson { .. }
+            | DistType::Gamma { .. }
+            | DistType::Beta { .. } => return None,
+        };
+
+        // same as in sample(): add start, then clamp to [0.0, max]
+        let q = (q + self.start).max(0.0);
+        if self.max > 0.0 {
+            return Some(q.min(self.max));
+        }
+        Some(q)
+    }
+
+    /// Sample the distribution. Never panics: an invalid distribution (see
+    /// [`Self::validate()`]) samples 0.0 before adding the start.
     pub fn sample<R: RngCore>(self, rng: &mut R) -> f64 {
         let mut r: f64 = 0.0;
         r = r.max(self.dist_sample(rng) + self.start);
@@ -217,7 +595,59 @@ impl Dist {
         r
     }
 
+    /// Sample the distribution as a time in microseconds, i.e., a sample
+    /// (see [`Self::sample()`]) converted from the [`TimeUnit`] of the
+    /// distribution.
+    pub fn sample_micros<R: RngCore>(self, rng: &mut R) -> f64 {
+        self.unit.to_micros(self.sample(rng))
+    }
+
+    #[cfg(feature = "portable-sample")]
     fn dist_sample<R: RngCore>(self, rng: &mut R) -> f64 {
+        match self.dist {
+            DistType::Uniform { low, high } => {
+                if low == high {
+                    return low;
+                }
+                low + (high - low) * portable::open01(rng)
+            }
+            DistType::Normal { mean, stdev } => mean + stdev * portable::standard_normal(rng),
+            DistType::LogNormal { mu, sigma } => {
+                libm::exp(mu + sigma * portable::standard_normal(rng))
+            }
+            DistType::Pareto { scale, shape } => {
+                scale * libm::pow(portable::open01(rng), -1.0 / shape)
+            }
+            DistType::Weibull { scale, shape } => {
+                scale * libm::pow(-libm::log(portable::open01(rng)), 1.0 / shape)
+            }
+            DistType::Exponential { rate } => {
+                if !(rate > 0.0 && rate.is_finite()) {
+                    return 0.0;
+                }
+                -libm::log(portable::open01(rng)) / rate
+            }
+            DistType::Geometric { probability } => {
+                // always succeeds with probability 1.0, 0.0 if invalid
+                if !(probability > 0.0 && probability < 1.0) {
+                    return 0.0;
+                }
+                // inverse transform of the number of failures
+                libm::floor(libm::log(portable::open01(rng)) / libm::log1p(-probability))
+            }
+            _ => self.rand_distr_sample(rng),
+        }
+    }
+
+    #[cfg(not(feature = "portable-sample"))]
+    fn dist_sample<R: RngCore>(self, rng: &mut R) -> f64 {
+        self.rand_distr_sample(rng)
+    }
+
+    /// Samples with [`rand_distr`]. Parameters rejected by
+    /// [`Self::validate()`] never panic, but sample 0.0, so that a machine that
+    /// was mutated into an invalid state cannot bring down the caller.
+    fn rand_distr_sample<R: RngCore>(self, rng: &mut R) -> f64 {
         match self.dist {
             DistType::Uniform { low, high } => {
                 // special common case for handcrafted machines, also not
@@ -225,39 +655,293 @@ impl Dist {
                 if low == high {
                     return low;
                 }
+                if low > high || !(high - low).is_finite() {
+                    return 0.0;
+                }
                 Uniform::new(low, high).sample(rng)
             }
-            DistType::Normal { mean, stdev } => Normal::new(mean, stdev).unwrap().sample(rng),
+            DistType::Normal { mean, stdev } => {
+                Normal::new(mean, stdev).map_or(0.0, |d| d.sample(rng))
+            }
             DistType::SkewNormal {
                 location,
                 scale,
                 shape,
-            } => SkewNormal::new(location, scale, shape).unwrap().sample(rng),
-            DistType::LogNormal { mu, sigma } => LogNormal::new(mu, sigma).unwrap().sample(rng),
+            } => SkewNormal::new(location, scale, shape).map_or(0.0, |d| d.sample(rng)),
+            DistType::LogNormal { mu, sigma } => {
+                LogNormal::new(mu, sigma).map_or(0.0, |d| d.sample(rng))
+            }
             DistType::Binomial {
                 trials,
                 probability,
-            } => Binomial::new(trials, probability).unwrap().sample(rng) as f64,
+            } => Binomial::new(trials, probability).map_or(0.0, |d| d.sample(rng) as f64),
             DistType::Geometric { probability } => {
-                Geometric::new(probability).unwrap().sample(rng) as f64
+                if probability <= 0.0 {
+                    return 0.0;
+                }
+                Geometric::new(probability).map_or(0.0, |d| d.sample(rng) as f64)
+            }
+            DistType::Pareto { scale, shape } => {
+                Pareto::new(scale, shape).map_or(0.0, |d| d.sample(rng))
+            }
+            DistType::Poisson { lambda } => Poisson::new(lambda).map_or(0.0, |d| d.sample(rng)),
+            DistType::Weibull { scale, shape } => {
+                Weibull::new(scale, shape).map_or(0.0, |d| d.sample(rng))
             }
-            DistType::Pareto { scale, shape } => Pareto::new(scale, shape).unwrap().sample(rng),
-            DistType::Poisson { lambda } => Poisson::new(lambda).unwrap().sample(rng),
-            DistType::Weibull { scale, shape } => Weibull::new(scale, shape).unwrap().sample(rng),
             DistType::Gamma { scale, shape } => {
                 // note order below inverted from others for some reason in
                 // rand_distr
-                Gamma::new(shape, scale).unwrap().sample(rng)
+                Gamma::new(shape, scale).map_or(0.0, |d| d.sample(rng))
+            }
+            DistType::Beta { alpha, beta } => Beta::new(alpha, beta).map_or(0.0, |d| d.sample(rng)),
+            DistType::Exponential { rate } => {
+                if !(rate > 0.0 && rate.is_finite()) {
+                    return 0.0;
+                }
+                Exp::new(rate).map_or(0.0, |d| d.sample(rng))
             }
-            DistType::Beta { alpha, beta } => Beta::new(alpha, beta).unwrap().sample(rng),
         }
     }
 }
 
+/// The quantile function of the standard normal distribution, using the
+/// rational approximation by Peter J. Acklam (relative error below 1.15e-9).
+fn standard_normal_quantile(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969683028665376e1,
+        2.209460984245205e2,
+        -2.759285104469687e2,
+        1.383_577_518_672_69e2,
+        -3.066479806614716e1,
+        2.506628277459239,
+    ];
+    const B: [f64; 5] = [
+        -5.447609879822406e1,
+        1.615858368580409e2,
+        -1.556989798598866e2,
+        6.680131188771972e1,
+        -1.328068155288572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784894002430293e-3,
+        -3.223964580411365e-1,
+        -2.400758277161838,
+        -2.549732539343734,
+        4.374664141464968,
+        2.938163982698783,
+    ];
+    const D: [f64; 4] = [
+        7.784695709041462e-3,
+        3.224671290700398e-1,
+        2.445134137142996,
+        3.754408661907416,
+    ];
+    const P_LOW: f64 = 0.02425;
+
+    // the tails, by symmetry
+    let tail = |p: f64| {
+        let q = (-2.0 * p.ln()).sqrt();
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    };
+    if p < P_LOW {
+        return tail(p);
+    }
+    if p > 1.0 - P_LOW {
+        return -tail(1.0 - p);
+    }
+    let q = p - 0.5;
+    let r = q * q;
+    (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+        / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+}
+
+/// The gamma function for x >= 0.5, using the Lanczos approximation with g = 7
+/// (relative error below 1e-13). Infinite for x above about 171.6.
+fn gamma(x: f64) -> f64 {
+    const G: f64 = 7.0;
+    const P: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+
+    let x = x - 1.0;
+    let t = x + G + 0.5;
+    let sum = P[1..]
+        .iter()
+        .enumerate()
+        .fold(P[0], |sum, (i, p)| sum + p / (x + i as f64 + 1.0));
+    // split the power to not overflow before the exponential underflows
+    let pow = t.powf((x + 0.5) / 2.0);
+    (2.0 * std::f64::consts::PI).sqrt() * pow * (-t).exp() * pow * sum
+}
+
+/// Sampling using only platform-independent floating-point operations: basic
+/// arithmetic and square roots are correctly rounded per IEEE 754, and
+/// transcendental functions are from [`libm`].
+#[cfg(feature = "portable-sample")]
+mod portable {
+    use rand_core::RngCore;
+
+    /// A uniform sample in the open interval (0, 1), using 52 random bits.
+    pub(super) fn open01<R: RngCore>(rng: &mut R) -> f64 {
+        ((rng.next_u64() >> 12) as f64 + 0.5) / (1u64 << 52) as f64
+    }
+
+    /// A standard normal sample, using the Box-Muller transform.
+    pub(super) fn standard_normal<R: RngCore>(rng: &mut R) -> f64 {
+        let u1 = open01(rng);
+        let u2 = open01(rng);
+        (-2.0 * libm::log(u1)).sqrt() * libm::cos(2.0 * std::f64::consts::PI * u2)
+    }
+}
+
+#[cfg(test)]
+impl proptest::arbitrary::Arbitrary for Dist {
+    type Parameters = ();
+    type Strategy = proptest::strategy::BoxedStrategy<Self>;
+
+    /// Arbitrary distributions, with parameters in ranges that are
+    /// reasonable for machines (but not necessarily valid).
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        use proptest::prelude::*;
+
+        let dist = prop_oneof![
+            (0.0..1000.0, 0.0..1000.0).prop_map(|(low, width)| DistType::Uniform {
+                low,
+                high: low + width
+            }),
+            (0.0..1000.0, 0.0..100.0).prop_map(|(mean, stdev)| DistType::Normal { mean, stdev }),
+            (0.0..5.0, 0.0..2.0).prop_map(|(mu, sigma)| DistType::LogNormal { mu, sigma }),
+            (0.01..1.0).prop_map(|probability| DistType::Geometric { probability }),
+            (0.1..100.0).prop_map(|lambda| DistType::Poisson { lambda }),
+            (0.001..10.0).prop_map(|rate| DistType::Exponential { rate }),
+        ];
+        let unit = prop_oneof![
+            4 => Just(TimeUnit::Microseconds),
+            1 => Just(TimeUnit::Milliseconds),
+            1 => Just(TimeUnit::Nanoseconds),
+        ];
+        (
+            dist,
+            prop_oneof![Just(0.0), 0.0..100.0],
+            prop_oneof![Just(0.0), 0.0..10000.0],
+            unit,
+        )
+            .prop_map(|(dist, start, max, unit)| Dist {
+                dist,
+                start,
+                max,
+                unit,
+            })
+            .boxed()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn sample_invalid_no_panic() {
+        // parameters rejected by validate() must not panic when sampled
+        let mut rng = rand::thread_rng();
+        let dists = [
+            DistType::Uniform {
+                low: 10.0,
+                high: 1.0,
+            },
+            DistType::Uniform {
+                low: f64::NEG_INFINITY,
+                high: f64::INFINITY,
+            },
+            DistType::Uniform {
+                low: f64::NAN,
+                high: 1.0,
+            },
+            DistType::Normal {
+                mean: 1.0,
+                stdev: f64::INFINITY,
+            },
+            DistType::SkewNormal {
+                location: 1.0,
+                scale: -1.0,
+                shape: 1.0,
+            },
+            DistType::LogNormal {
+                mu: 1.0,
+                sigma: f64::NAN,
+            },
+            DistType::Binomial {
+                trials: 10,
+                probability: 1.5,
+            },
+            DistType::Geometric { probability: -0.5 },
+            DistType::Pareto {
+                scale: 0.0,
+                shape: 1.0,
+            },
+            DistType::Poisson { lambda: -1.0 },
+            DistType::Weibull {
+                scale: -1.0,
+                shape: 1.0,
+            },
+            DistType::Gamma {
+                scale: 1.0,
+                shape: -1.0,
+            },
+            DistType::Beta {
+                alpha: 0.0,
+                beta: 1.0,
+            },
+            DistType::Exponential { rate: 0.0 },
+            DistType::Exponential { rate: f64::NAN },
+        ];
+        for dist in dists {
+            let d = Dist {
+                dist,
+                start: 0.0,
+                max: 0.0,
+                ..Default::default()
+            };
+            assert!(d.validate().is_err(), "{:?}", d);
+            let s = d.sample(&mut rng);
+            assert!(s >= 0.0, "{:?}", d);
+        }
+    }
+
+    #[test]
+    fn constant() {
+        let mut rng = rand::thread_rng();
+        for value in [0.0, 1.0, 123.5, 1e9] {
+            let d = Dist::constant(value);
+            d.validate().unwrap();
+            assert_eq!(d.support(), (value, value));
+            assert_eq!(d.mean(), Some(value));
+            for _ in 0..10 {
+                assert_eq!(d.sample(&mut rng), value);
+            }
+        }
+        assert_eq!(
+            Dist::constant(2.0)
+                .with_unit(TimeUnit::Milliseconds)
+                .sample_micros(&mut rng),
+            2000.0
+        );
+
+        // negative values are clamped, non-finite ones invalid
+        assert_eq!(Dist::constant(-5.0).sample(&mut rng), 0.0);
+        assert!(Dist::constant(f64::NAN).validate().is_err());
+        assert!(Dist::constant(f64::INFINITY).validate().is_err());
+    }
+
     #[test]
     fn validate_uniform_dist() {
         // valid dist
@@ -268,6 +952,7 @@ mod tests {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         };
 
         let r = d.validate();
@@ -281,12 +966,56 @@ mod tests {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         };
 
         let r = d.validate();
         assert!(r.is_err());
     }
 
+    #[test]
+    fn validate_non_finite_dist() {
+        let valid = Dist::new(
+            DistType::SkewNormal {
+                location: 10.0,
+                scale: 1.0,
+                shape: 0.0,
+            },
+            0.0,
+            0.0,
+        );
+        assert!(valid.validate().is_ok());
+
+        for v in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            for param in [
+                DistParam::First,
+                DistParam::Second,
+                DistParam::Third,
+                DistParam::Start,
+                DistParam::Max,
+            ] {
+                let mut d = valid;
+                d.set_param(param, v).unwrap();
+                let r = d.validate();
+                assert!(r.is_err(), "{:?} set to {} is valid", param, v);
+                assert!(r.unwrap_err().to_string().contains("non-finite"));
+            }
+
+            // also for dists that rand_distr would otherwise accept
+            let d = Dist::new(DistType::Uniform { low: 0.0, high: v }, 0.0, 0.0);
+            assert!(d.validate().is_err());
+            let d = Dist::new(
+                DistType::Pareto {
+                    scale: 1.0,
+                    shape: v,
+                },
+                0.0,
+                0.0,
+            );
+            assert!(d.validate().is_err());
+        }
+    }
+
     #[test]
     fn validate_normal_dist() {
         // valid dist
@@ -297,6 +1026,7 @@ mod tests {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         };
 
         let r = d.validate();
@@ -310,6 +1040,7 @@ mod tests {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         };
 
         let r = d.validate();
@@ -327,6 +1058,7 @@ mod tests {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         };
 
         let r = d.validate();
@@ -341,6 +1073,7 @@ mod tests {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         };
 
         let r = d.validate();
@@ -357,6 +1090,7 @@ mod tests {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         };
 
         let r = d.validate();
@@ -370,6 +1104,7 @@ mod tests {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         };
 
         let r = d.validate();
@@ -386,6 +1121,7 @@ mod tests {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         };
 
         let r = d.validate();
@@ -399,6 +1135,7 @@ mod tests {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         };
 
         let r = d.validate();
@@ -412,6 +1149,7 @@ mod tests {
             dist: DistType::Geometric { probability: 0.5 },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         };
 
         let r = d.validate();
@@ -422,10 +1160,141 @@ mod tests {
             dist: DistType::Geometric { probability: 1.1 },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         };
 
         let r = d.validate();
         assert!(r.is_err());
+
+        // probability has to be in (0, 1]
+        for probability in [0.0, -0.0, f64::NAN] {
+            let d = Dist::new(DistType::Geometric { probability }, 0.0, 0.0);
+            assert!(d.validate().is_err());
+        }
+        let d = Dist::new(DistType::Geometric { probability: 1.0 }, 0.0, 0.0);
+        assert!(d.validate().is_ok());
+    }
+
+    #[test]
+    fn quantile() {
+        let close = |a: f64, b: f64| (a - b).abs() <= 1e-6 * b.abs().max(1.0);
+
+        let d = Dist::new(
+            DistType::Uniform {
+                low: 10.0,
+                high: 20.0,
+            },
+            0.0,
+            0.0,
+        );
+        assert!(close(d.quantile(0.25).unwrap(), 12.5));
+        assert_eq!(d.quantile(0.0), None);
+        assert_eq!(d.quantile(1.0), None);
+        assert_eq!(d.quantile(f64::NAN), None);
+
+        let d = Dist::new(
+            DistType::Normal {
+                mean: 100.0,
+                stdev: 10.0,
+            },
+            0.0,
+            0.0,
+        );
+        assert!(close(d.quantile(0.5).unwrap(), 100.0));
+        assert!(close(d.quantile(0.975).unwrap(), 119.599640));
+        assert!(close(d.quantile(0.01).unwrap(), 76.736521));
+        assert!(close(d.quantile(0.999).unwrap(), 130.902323));
+
+        // start and max as when sampling
+        let d = Dist::new(DistType::Exponential { rate: 0.5 }, 5.0, 0.0);
+        assert!(close(d.quantile(0.5).unwrap(), 5.0 + 2.0 * 2f64.ln()));
+        let d = Dist::new(DistType::Exponential { rate: 0.5 }, 5.0, 6.0);
+        assert_eq!(d.quantile(0.99), Some(6.0));
+        let d = Dist::new(
+            DistType::Normal {
+                mean: -100.0,
+                stdev: 1.0,
+            },
+            0.0,
+            0.0,
+        );
+        assert_eq!(d.quantile(0.5), Some(0.0));
+
+        let d = Dist::new(DistType::Geometric { probability: 0.5 }, 0.0, 0.0);
+        assert_eq!(d.quantile(0.5), Some(0.0));
+        assert_eq!(d.quantile(0.75), Some(1.0));
+        assert_eq!(d.quantile(0.76), Some(2.0));
+        let d = Dist::new(
+            DistType::Pareto {
+                scale: 2.0,
+                shape: 1.0,
+            },
+            0.0,
+            0.0,
+        );
+        assert!(close(d.quantile(0.5).unwrap(), 4.0));
+        let d = Dist::new(
+            DistType::Weibull {
+                scale: 3.0,
+                shape: 1.0,
+            },
+            0.0,
+            0.0,
+        );
+        assert!(close(d.quantile(0.5).unwrap(), 3.0 * 2f64.ln()));
+        let d = Dist::new(
+            DistType::LogNormal {
+                mu: 1.0,
+                sigma: 0.5,
+            },
+            0.0,
+            0.0,
+        );
+        assert!(close(d.quantile(0.5).unwrap(), 1f64.exp()));
+
+        // no closed form
+        let d = Dist::new(DistType::Poisson { lambda: 1.0 }, 0.0, 0.0);
+        assert_eq!(d.quantile(0.5), None);
+
+        // matches sampling
+        let mut rng = rand::thread_rng();
+        let d = Dist::new(
+            DistType::Normal {
+                mean: 10.0,
+                stdev: 3.0,
+            },
+            0.0,
+            0.0,
+        );
+        let q = d.quantile(0.9).unwrap();
+        let below = (0..10_000).filter(|_| d.sample(&mut rng) <= q).count();
+        assert!((8800..9200).contains(&below), "{}", below);
+    }
+
+    #[test]
+    fn sample_geometric_success_rate() {
+        use rand_core::SeedableRng;
+        use rand_xoshiro::Xoshiro256StarStar;
+
+        let mut rng = Xoshiro256StarStar::seed_from_u64(0);
+        let n = 100_000;
+        for p in [0.05, 0.3, 0.9] {
+            let d = Dist::new(DistType::Geometric { probability: p }, 0.0, 0.0);
+            let samples: Vec<f64> = (0..n).map(|_| d.sample(&mut rng)).collect();
+            assert!(samples.iter().all(|s| *s >= 0.0 && s.fract() == 0.0));
+            // one success per sample, after that many failures
+            let failures = samples.iter().sum::<f64>();
+            let rate = n as f64 / (n as f64 + failures);
+            assert!((rate - p).abs() < 0.02 * p, "rate {}", rate);
+            let mean = failures / n as f64;
+            let expected = d.mean().unwrap();
+            assert_eq!(expected, (1.0 - p) / p);
+            assert!((mean - expected).abs() < 0.03 * expected, "mean {}", mean);
+        }
+
+        // always succeeds
+        let d = Dist::new(DistType::Geometric { probability: 1.0 }, 0.0, 0.0);
+        assert!((0..100).all(|_| d.sample(&mut rng) == 0.0));
     }
 
     #[test]
@@ -438,6 +1307,7 @@ mod tests {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         };
 
         let r = d.validate();
@@ -451,6 +1321,7 @@ mod tests {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         };
 
         let r = d.validate();
@@ -464,6 +1335,7 @@ mod tests {
             dist: DistType::Poisson { lambda: 1.0 },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         };
 
         let r = d.validate();
@@ -474,12 +1346,36 @@ mod tests {
             dist: DistType::Poisson { lambda: -1.0 },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         };
 
         let r = d.validate();
         assert!(r.is_err());
     }
 
+    #[test]
+    fn sample_poisson_mean_variance() {
+        use rand_core::SeedableRng;
+        use rand_xoshiro::Xoshiro256StarStar;
+
+        // small and large lambda are sampled with different algorithms
+        let mut rng = Xoshiro256StarStar::seed_from_u64(0);
+        let n = 100_000;
+        for lambda in [0.5, 4.0, 100.0] {
+            let d = Dist::new(DistType::Poisson { lambda }, 0.0, 0.0);
+            let samples: Vec<f64> = (0..n).map(|_| d.sample(&mut rng)).collect();
+            assert!(samples.iter().all(|s| *s >= 0.0 && s.fract() == 0.0));
+            let mean = samples.iter().sum::<f64>() / n as f64;
+            let variance = samples.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / n as f64;
+            assert!((mean - lambda).abs() < 0.02 * lambda, "mean {}", mean);
+            assert!(
+                (variance - lambda).abs() < 0.05 * lambda,
+                "variance {}",
+                variance
+            );
+        }
+    }
+
     #[test]
     fn validate_weibull_dist() {
         // valid dist
@@ -490,6 +1386,7 @@ mod tests {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         };
 
         let r = d.validate();
@@ -503,10 +1400,119 @@ mod tests {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         };
 
         let r = d.validate();
         assert!(r.is_err());
+
+        // dist with zero shape or scale
+        for (scale, shape) in [(1.0, 0.0), (0.0, 1.0), (-1.0, 1.0)] {
+            let d = Dist::new(DistType::Weibull { scale, shape }, 0.0, 0.0);
+            assert!(d.validate().is_err(), "{} {}", scale, shape);
+        }
+    }
+
+    #[test]
+    fn time_unit_conversion() {
+        assert_eq!(TimeUnit::default(), TimeUnit::Microseconds);
+        assert_eq!(TimeUnit::Microseconds.to_micros(1.5), 1.5);
+        assert_eq!(TimeUnit::Milliseconds.to_micros(1.5), 1500.0);
+        assert_eq!(TimeUnit::Nanoseconds.to_micros(1500.0), 1.5);
+        assert_eq!(TimeUnit::Seconds.to_micros(1.5), 1_500_000.0);
+
+        // sampled in the unit, including start and max
+        let mut rng = rand::thread_rng();
+        let d = Dist::new(
+            DistType::Uniform {
+                low: 10.0,
+                high: 10.0,
+            },
+            5.0,
+            12.0,
+        )
+        .with_unit(TimeUnit::Milliseconds);
+        assert_eq!(d.sample(&mut rng), 12.0);
+        assert_eq!(d.sample_micros(&mut rng), 12_000.0);
+        assert_eq!(
+            d.to_string(),
+            "Uniform { low: 10.0, high: 10.0 }, start 5, clamped to [0.0, 12], in ms"
+        );
+        assert!(!Dist::new(DistType::Exponential { rate: 1.0 }, 0.0, 0.0)
+            .to_string()
+            .contains(" in "));
+    }
+
+    #[test]
+    fn gamma_function() {
+        // factorials, and gamma(1/2) = sqrt(pi)
+        let mut factorial = 1.0;
+        for n in 1..20 {
+            let g = gamma(n as f64);
+            assert!((g - factorial).abs() / factorial < 1e-12, "{} {}", n, g);
+            factorial *= n as f64;
+        }
+        let g = gamma(0.5);
+        assert!((g - std::f64::consts::PI.sqrt()).abs() < 1e-12, "{}", g);
+        assert!(gamma(171.0).is_finite());
+        assert_eq!(gamma(172.0), f64::INFINITY);
+    }
+
+    #[test]
+    fn sample_weibull_mean() {
+        use rand_core::SeedableRng;
+        use rand_xoshiro::Xoshiro256StarStar;
+
+        let mut rng = Xoshiro256StarStar::seed_from_u64(0);
+        let n = 100_000;
+        for (scale, shape, expected) in [
+            // exponential with rate 1/scale
+            (10.0, 1.0, 10.0),
+            // Rayleigh, scale * sqrt(pi) / 2
+            (2.0, 2.0, std::f64::consts::PI.sqrt()),
+            // heavier tail, scale * gamma(3) = scale * 2
+            (5.0, 0.5, 10.0),
+            (1000.0, 3.0, 1000.0 * gamma(1.0 + 1.0 / 3.0)),
+        ] {
+            let d = Dist::new(DistType::Weibull { scale, shape }, 0.0, 0.0);
+            let mean = d.mean().unwrap();
+            assert!((mean - expected).abs() / expected < 1e-12, "{}", mean);
+            let samples: Vec<f64> = (0..n).map(|_| d.sample(&mut rng)).collect();
+            assert!(samples.iter().all(|s| *s >= 0.0));
+            let empirical = samples.iter().sum::<f64>() / n as f64;
+            assert!(
+                (empirical - mean).abs() / mean < 0.03,
+                "shape {}, mean {} vs {}",
+                shape,
+                empirical,
+                mean
+            );
+        }
+
+        // too large to represent
+        let d = Dist::new(
+            DistType::Weibull {
+                scale: 1.0,
+                shape: 0.001,
+            },
+            0.0,
+            0.0,
+        );
+        assert_eq!(d.mean(), None);
+
+        // start is added and max clamps
+        let d = Dist::new(
+            DistType::Weibull {
+                scale: 1.0,
+                shape: 2.0,
+            },
+            5.0,
+            6.0,
+        );
+        for _ in 0..1000 {
+            let s = d.sample(&mut rng);
+            assert!((5.0..=6.0).contains(&s));
+        }
     }
 
     #[test]
@@ -519,6 +1525,7 @@ mod tests {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         };
 
         let r = d.validate();
@@ -532,6 +1539,7 @@ mod tests {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         };
 
         let r = d.validate();
@@ -548,6 +1556,7 @@ mod tests {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         };
 
         let r = d.validate();
@@ -561,12 +1570,40 @@ mod tests {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         };
 
         let r = d.validate();
         assert!(r.is_err());
     }
 
+    #[test]
+    fn validate_exponential_dist() {
+        // valid dist
+        let d = Dist {
+            dist: DistType::Exponential { rate: 0.5 },
+            start: 0.0,
+            max: 0.0,
+            ..Default::default()
+        };
+
+        let r = d.validate();
+        assert!(r.is_ok());
+
+        // dist with zero, negative, and non-finite rate
+        for rate in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            let d = Dist {
+                dist: DistType::Exponential { rate },
+                start: 0.0,
+                max: 0.0,
+                ..Default::default()
+            };
+
+            let r = d.validate();
+            assert!(r.is_err());
+        }
+    }
+
     #[test]
     fn sample_clamp() {
         // make sure start and max are applied
@@ -579,6 +1616,7 @@ mod tests {
             },
             start: 5.0,
             max: 0.0,
+            ..Default::default()
         };
         assert_eq!(d.sample(&mut rand::thread_rng()), 5.0);
 
@@ -590,6 +1628,7 @@ mod tests {
             },
             start: 0.0,
             max: 5.0,
+            ..Default::default()
         };
         assert_eq!(d.sample(&mut rand::thread_rng()), 5.0);
 
@@ -601,7 +1640,423 @@ mod tests {
             },
             start: 0.0,
             max: 0.0,
+            ..Default::default()
         };
         assert_eq!(d.sample(&mut rand::thread_rng()), 0.0);
     }
+
+    #[test]
+    fn sample_negative_start() {
+        // negative start clamps to zero
+        let d = Dist {
+            dist: DistType::Uniform {
+                low: 10.0,
+                high: 20.0,
+            },
+            start: -15.0,
+            max: 0.0,
+            ..Default::default()
+        };
+        assert!(d.validate().is_ok());
+        assert_eq!(d.support(), (0.0, 5.0));
+        let mut zero = false;
+        for _ in 0..1000 {
+            let v = d.sample(&mut rand::thread_rng());
+            assert!((0.0..5.0).contains(&v));
+            zero |= v == 0.0;
+        }
+        assert!(zero);
+
+        // negative start yields a reduced positive timeout
+        let d = Dist {
+            dist: DistType::Uniform {
+                low: 100.0,
+                high: 100.0,
+            },
+            start: -30.0,
+            max: 0.0,
+            ..Default::default()
+        };
+        assert!(d.validate().is_ok());
+        assert_eq!(d.sample(&mut rand::thread_rng()), 70.0);
+        assert_eq!(
+            format!("{}", d),
+            format!("{}, start -30, clamped to [0.0, f64::MAX]", d.dist)
+        );
+
+        // a start so negative that every value is clamped is invalid
+        let d = Dist {
+            dist: DistType::Uniform {
+                low: 10.0,
+                high: 20.0,
+            },
+            start: -20.0,
+            max: 0.0,
+            ..Default::default()
+        };
+        assert!(d.validate().is_err());
+        let d = Dist {
+            dist: DistType::Beta {
+                alpha: 2.0,
+                beta: 2.0,
+            },
+            start: -1.0,
+            max: 0.0,
+            ..Default::default()
+        };
+        assert!(d.validate().is_err());
+        // but not for unbounded distributions
+        let d = Dist {
+            dist: DistType::Normal {
+                mean: 0.0,
+                stdev: 1.0,
+            },
+            start: -1000.0,
+            max: 0.0,
+            ..Default::default()
+        };
+        assert!(d.validate().is_ok());
+    }
+
+    #[test]
+    fn support_uniform() {
+        let d = Dist::new(
+            DistType::Uniform {
+                low: 10.0,
+                high: 20.0,
+            },
+            0.0,
+            0.0,
+        );
+        assert_eq!(d.support(), (10.0, 20.0));
+        let d = Dist::new(
+            DistType::Uniform {
+                low: 10.0,
+                high: 10.0,
+            },
+            0.0,
+            0.0,
+        );
+        assert_eq!(d.support(), (10.0, 10.0));
+        // start shifts, max clamps
+        let d = Dist::new(
+            DistType::Uniform {
+                low: 10.0,
+                high: 20.0,
+            },
+            5.0,
+            18.0,
+        );
+        assert_eq!(d.support(), (15.0, 18.0));
+        // negative values are clamped to 0.0
+        let d = Dist::new(
+            DistType::Uniform {
+                low: -20.0,
+                high: 10.0,
+            },
+            0.0,
+            0.0,
+        );
+        assert_eq!(d.support(), (0.0, 10.0));
+        // max below low: always max
+        let d = Dist::new(
+            DistType::Uniform {
+                low: 10.0,
+                high: 20.0,
+            },
+            0.0,
+            5.0,
+        );
+        assert_eq!(d.support(), (5.0, 5.0));
+    }
+
+    #[test]
+    fn support_normal() {
+        let d = Dist::new(
+            DistType::Normal {
+                mean: 100.0,
+                stdev: 10.0,
+            },
+            0.0,
+            0.0,
+        );
+        assert_eq!(d.support(), (0.0, f64::INFINITY));
+        // the truncation is all that bounds the distribution
+        let d = Dist::new(
+            DistType::Normal {
+                mean: 100.0,
+                stdev: 10.0,
+            },
+            0.0,
+            50.0,
+        );
+        assert_eq!(d.support(), (0.0, 50.0));
+        let d = Dist::new(
+            DistType::Normal {
+                mean: 100.0,
+                stdev: 0.0,
+            },
+            1.0,
+            0.0,
+        );
+        assert_eq!(d.support(), (101.0, 101.0));
+    }
+
+    #[test]
+    fn support_skewnormal() {
+        let d = Dist::new(
+            DistType::SkewNormal {
+                location: 100.0,
+                scale: 10.0,
+                shape: -2.0,
+            },
+            0.0,
+            200.0,
+        );
+        assert_eq!(d.support(), (0.0, 200.0));
+        let d = Dist::new(
+            DistType::SkewNormal {
+                location: 100.0,
+                scale: 0.0,
+                shape: -2.0,
+            },
+            0.0,
+            0.0,
+        );
+        assert_eq!(d.support(), (100.0, 100.0));
+    }
+
+    #[test]
+    fn support_lognormal() {
+        let d = Dist::new(
+            DistType::LogNormal {
+                mu: 2.0,
+                sigma: 1.0,
+            },
+            5.0,
+            0.0,
+        );
+        assert_eq!(d.support(), (5.0, f64::INFINITY));
+    }
+
+    #[test]
+    fn support_binomial() {
+        let d = Dist::new(
+            DistType::Binomial {
+                trials: 10,
+                probability: 0.5,
+            },
+            1.0,
+            0.0,
+        );
+        assert_eq!(d.support(), (1.0, 11.0));
+    }
+
+    #[test]
+    fn support_geometric() {
+        let d = Dist::new(DistType::Geometric { probability: 0.5 }, 0.0, 0.0);
+        assert_eq!(d.support(), (0.0, f64::INFINITY));
+    }
+
+    #[test]
+    fn support_pareto() {
+        let d = Dist::new(
+            DistType::Pareto {
+                scale: 3.0,
+                shape: 2.0,
+            },
+            0.0,
+            100.0,
+        );
+        assert_eq!(d.support(), (3.0, 100.0));
+    }
+
+    #[test]
+    fn support_poisson() {
+        let d = Dist::new(DistType::Poisson { lambda: 4.0 }, 0.0, 10.0);
+        assert_eq!(d.support(), (0.0, 10.0));
+    }
+
+    #[test]
+    fn support_weibull() {
+        let d = Dist::new(
+            DistType::Weibull {
+                scale: 1.0,
+                shape: 2.0,
+            },
+            0.0,
+            0.0,
+        );
+        assert_eq!(d.support(), (0.0, f64::INFINITY));
+    }
+
+    #[test]
+    fn support_gamma() {
+        let d = Dist::new(
+            DistType::Gamma {
+                scale: 1.0,
+                shape: 2.0,
+            },
+            2.0,
+            0.0,
+        );
+        assert_eq!(d.support(), (2.0, f64::INFINITY));
+    }
+
+    #[test]
+    fn support_beta() {
+        let d = Dist::new(
+            DistType::Beta {
+                alpha: 2.0,
+                beta: 2.0,
+            },
+            0.0,
+            0.0,
+        );
+        assert_eq!(d.support(), (0.0, 1.0));
+        // start shifts both bounds
+        let d = Dist::new(
+            DistType::Beta {
+                alpha: 2.0,
+                beta: 2.0,
+            },
+            10.0,
+            0.0,
+        );
+        assert_eq!(d.support(), (10.0, 11.0));
+    }
+
+    #[test]
+    fn support_exponential() {
+        let d = Dist::new(DistType::Exponential { rate: 2.0 }, 1.0, 0.0);
+        assert_eq!(d.support(), (1.0, f64::INFINITY));
+    }
+
+    #[test]
+    fn sample_exponential_mean() {
+        use rand_core::SeedableRng;
+        use rand_xoshiro::Xoshiro256StarStar;
+
+        let mut rng = Xoshiro256StarStar::seed_from_u64(0);
+        let n = 100_000;
+        for rate in [0.01, 0.5, 2.0] {
+            let d = Dist::new(DistType::Exponential { rate }, 0.0, 0.0);
+            assert_eq!(d.mean(), Some(1.0 / rate));
+            let mean = (0..n).map(|_| d.sample(&mut rng)).sum::<f64>() / n as f64;
+            assert!(
+                (mean - 1.0 / rate).abs() < 0.02 / rate,
+                "rate {}, mean {}",
+                rate,
+                mean
+            );
+        }
+
+        // start is added and max clamps
+        let d = Dist::new(DistType::Exponential { rate: 1.0 }, 5.0, 6.0);
+        for _ in 0..1000 {
+            let s = d.sample(&mut rng);
+            assert!((5.0..=6.0).contains(&s));
+        }
+    }
+
+    #[test]
+    fn exponential_serialize() {
+        let d = Dist::new(DistType::Exponential { rate: 0.25 }, 1.0, 100.0);
+        let bin = bincode::serialize(&d).unwrap();
+        assert_eq!(bincode::deserialize::<Dist>(&bin).unwrap(), d);
+    }
+
+    #[cfg(not(feature = "portable-sample"))]
+    #[test]
+    fn uniform_sample_fixture() {
+        use rand_core::SeedableRng;
+        use rand_xoshiro::Xoshiro256StarStar;
+
+        // uniform sampling takes no transcendental functions, so the sample
+        // stream under a fixed seed is the same on all platforms, and MUST
+        // NOT change: seeded simulations and fixtures depend on it
+        let sample = |low: f64, high: f64| -> Vec<u64> {
+            let d = Dist::new(DistType::Uniform { low, high }, 0.0, 0.0);
+            let mut rng = Xoshiro256StarStar::seed_from_u64(1234);
+            (0..4).map(|_| d.sample(&mut rng).to_bits()).collect()
+        };
+        assert_eq!(
+            sample(0.0, 1000.0),
+            [
+                0x4046ca846d0e3c94,
+                0x408a56f35c74fa15,
+                0x40859734302176d8,
+                0x408b32270c569adf
+            ]
+        );
+        // including narrow ranges, where the sampler may lose precision
+        assert_eq!(
+            sample(1.0, 1.0 + 1e-9),
+            [
+                0x3ff00000000321e4,
+                0x3ff000000039ebe8,
+                0x3ff00000002f7a73,
+                0x3ff00000003bcdef
+            ]
+        );
+    }
+
+    #[cfg(feature = "portable-sample")]
+    #[test]
+    fn portable_sample_fixture() {
+        use rand_core::SeedableRng;
+        use rand_xoshiro::Xoshiro256StarStar;
+
+        // sample streams under a fixed seed MUST be identical on all platforms
+        let sample = |dist: DistType| -> Vec<u64> {
+            let d = Dist {
+                dist,
+                start: 0.0,
+                max: 0.0,
+                ..Default::default()
+            };
+            let mut rng = Xoshiro256StarStar::seed_from_u64(1234);
+            (0..4).map(|_| d.dist_sample(&mut rng).to_bits()).collect()
+        };
+
+        let uniform = sample(DistType::Uniform {
+            low: 0.0,
+            high: 1000.0,
+        });
+        assert_eq!(
+            uniform,
+            [
+                0x4046ca846d0e3ca4,
+                0x408a56f35c74fa16,
+                0x40859734302176d9,
+                0x408b32270c569ae0
+            ]
+        );
+        let normal = sample(DistType::Normal {
+            mean: 100.0,
+            stdev: 15.0,
+        });
+        assert_eq!(
+            normal,
+            [
+                0x405e227fd2504fee,
+                0x405b3625d18712cf,
+                0x405f4bcf579fbc96,
+                0x405a63abcd3b11fb
+            ]
+        );
+        let lognormal = sample(DistType::LogNormal {
+            mu: 2.0,
+            sigma: 0.5,
+        });
+        assert_eq!(
+            lognormal,
+            [
+                0x402d4e42bc2dfb7e,
+                0x4023d8a4c5abd564,
+                0x40311b608affbf0d,
+                0x4021c9210cad0798
+            ]
+        );
+    }
 }
@@ -9,8 +9,18 @@ pub enum Error {
     /// Invalid blocking limit.
     BlockingLimit,
 
+    /// Invalid limit of received padding.
+    PaddingRecvLimit,
+
     /// Invalid machine. The string describes why in detail.
     Machine(String),
+
+    /// The machine requires a feature that the framework was not built with.
+    /// The string names the feature.
+    UnsupportedFeature(String),
+
+    /// Invalid event log. The string describes why in detail.
+    EventLog(String),
 }
 
 impl fmt::Display for Error {
@@ -18,7 +28,14 @@ impl fmt::Display for Error {
         match *self {
             Error::PaddingLimit => write!(f, "max_padding_frac has to be between [0.0, 1.0]"),
             Error::BlockingLimit => write!(f, "max_blocking_frac has to be between [0.0, 1.0]"),
+            Error::PaddingRecvLimit => {
+                write!(f, "max_padding_recv_frac has to be between [0.0, 1.0]")
+            }
             Error::Machine(ref msg) => write!(f, "invalid machine: {}", msg),
+            Error::UnsupportedFeature(ref feature) => {
+                write!(f, "unsupported feature required by machine: {}", feature)
+            }
+            Error::EventLog(ref msg) => write!(f, "invalid event log: {}", msg),
         }
     }
 }
@@ -10,6 +10,10 @@ use std::hash::Hash;
 use std::slice::Iter;
 
 /// An Event may trigger a [`State`](crate::state) transition.
+///
+/// The order of the variants is fixed: it is the order of [`Event::iter()`]
+/// and of the transitions of a [`State`](crate::state), which are stored by
+/// event index. New events MUST be appended, never reordered.
 #[derive(Debug, Enum, Eq, Hash, PartialEq, Clone, Copy, Serialize, Deserialize)]
 pub enum Event {
     /// NormalRecv is when we received a normal, non-padding packet.
@@ -40,6 +44,19 @@ pub enum Event {
     TimerEnd,
     /// Signal is when a machine transitioned to [`STATE_SIGNAL`](crate::constants).
     Signal,
+    /// Idle is when the application has had no normal traffic for a while,
+    /// as reported by the integration ([`TriggerEvent::Idle`]).
+    Idle,
+    /// MachineStart is when the machine is started (internal), see
+    /// [`Framework::start()`](crate::Framework::start). It is delivered at
+    /// most once per machine, letting state 0 take an initial action without
+    /// any other event.
+    MachineStart,
+    /// TimeThreshold is when the time the machine has been running reached
+    /// its [`Machine::time_threshold`](crate::Machine::time_threshold)
+    /// (internal). It is delivered at most once per machine, see
+    /// [`Framework::machine_elapsed()`](crate::Framework::machine_elapsed).
+    TimeThreshold,
 }
 
 impl fmt::Display for Event {
@@ -49,6 +66,8 @@ impl fmt::Display for Event {
 }
 
 impl Event {
+    /// Iterate over all events in a fixed order, ascending by
+    /// [`Event::to_usize()`].
     pub fn iter() -> Iter<'static, Event> {
         static EVENTS: [Event; EVENT_NUM] = [
             NormalRecv,
@@ -64,6 +83,9 @@ impl Event {
             TimerBegin,
             TimerEnd,
             Signal,
+            Idle,
+            MachineStart,
+            TimeThreshold,
         ];
         EVENTS.iter()
     }
@@ -86,17 +108,39 @@ pub enum TriggerEvent {
     /// Sent non-padding packet.
     NormalSent,
     /// Sent padding packet.
+    ///
+    /// Padding scheduled by a machine that has since reached
+    /// [`STATE_END`](crate::constants::STATE_END) MUST still be reported:
+    /// it counts toward the padding fraction of the framework, since it was
+    /// sent, but does not affect the limits of the terminated machine.
     PaddingSent { machine: MachineId },
     /// Sent packet in the tunnel.
     TunnelSent,
-    /// Blocking of outgoing traffic started by the action from a machine.
-    BlockingBegin { machine: MachineId },
-    /// Blocking of outgoing traffic stopped.
-    BlockingEnd,
+    /// Blocking of outgoing traffic of a lane started by the action from a
+    /// machine, see
+    /// [`TriggerAction::BlockOutgoing`](crate::TriggerAction::BlockOutgoing).
+    /// Machines transition on blocking of any lane.
+    BlockingBegin { machine: MachineId, lane: u8 },
+    /// Blocking of outgoing traffic of a lane stopped.
+    BlockingEnd { lane: u8 },
     /// A machine's timer started.
     TimerBegin { machine: MachineId },
     /// A machine's timer expired.
     TimerEnd { machine: MachineId },
+    /// Blocking of incoming traffic started by the action from a machine.
+    /// Used for accounting only: machines cannot transition on it.
+    IncomingBlockingBegin { machine: MachineId },
+    /// Blocking of incoming traffic stopped. Used for accounting only:
+    /// machines cannot transition on it.
+    IncomingBlockingEnd,
+    /// The application has been idle, without normal traffic, for a while.
+    ///
+    /// Detecting idleness is up to the integration: it SHOULD trigger this
+    /// event once no normal packets have been sent or received for some
+    /// threshold of its choosing, e.g., to let machines start cover traffic.
+    /// The event is not repeated by the framework, and nothing ends the idle
+    /// period other than the traffic that machines transition on.
+    Idle,
 }
 
 impl TriggerEvent {
@@ -108,11 +152,13 @@ impl TriggerEvent {
             TriggerEvent::NormalSent => e == Event::NormalSent,
             TriggerEvent::PaddingSent { .. } => e == Event::PaddingSent,
             TriggerEvent::BlockingBegin { .. } => e == Event::BlockingBegin,
-            TriggerEvent::BlockingEnd => e == Event::BlockingEnd,
+            TriggerEvent::BlockingEnd { .. } => e == Event::BlockingEnd,
             TriggerEvent::TimerBegin { .. } => e == Event::TimerBegin,
             TriggerEvent::TimerEnd { .. } => e == Event::TimerEnd,
             TriggerEvent::TunnelSent => e == Event::TunnelSent,
             TriggerEvent::TunnelRecv => e == Event::TunnelRecv,
+            TriggerEvent::Idle => e == Event::Idle,
+            TriggerEvent::IncomingBlockingBegin { .. } | TriggerEvent::IncomingBlockingEnd => false,
         }
     }
 }
@@ -128,9 +174,12 @@ impl fmt::Display for TriggerEvent {
             TriggerEvent::PaddingSent { .. } => write!(f, "sp"),
             TriggerEvent::TunnelSent => write!(f, "st"),
             TriggerEvent::BlockingBegin { .. } => write!(f, "bb"),
-            TriggerEvent::BlockingEnd => write!(f, "be"),
+            TriggerEvent::BlockingEnd { .. } => write!(f, "be"),
             TriggerEvent::TimerBegin { .. } => write!(f, "tb"),
             TriggerEvent::TimerEnd { .. } => write!(f, "te"),
+            TriggerEvent::IncomingBlockingBegin { .. } => write!(f, "ib"),
+            TriggerEvent::IncomingBlockingEnd => write!(f, "ie"),
+            TriggerEvent::Idle => write!(f, "id"),
         }
     }
 }
@@ -158,4 +207,37 @@ mod tests {
         assert_eq!(Event::TunnelSent.to_string(), "TunnelSent");
         assert_eq!(Event::Signal.to_string(), "Signal");
     }
+
+    #[test]
+    fn event_order() {
+        // the ordinal of each event is part of the serialization format of
+        // machines: if this test fails, an event was reordered or removed
+        assert_eq!(Event::NormalRecv.to_usize(), 0);
+        assert_eq!(Event::PaddingRecv.to_usize(), 1);
+        assert_eq!(Event::TunnelRecv.to_usize(), 2);
+        assert_eq!(Event::NormalSent.to_usize(), 3);
+        assert_eq!(Event::PaddingSent.to_usize(), 4);
+        assert_eq!(Event::TunnelSent.to_usize(), 5);
+        assert_eq!(Event::BlockingBegin.to_usize(), 6);
+        assert_eq!(Event::BlockingEnd.to_usize(), 7);
+        assert_eq!(Event::LimitReached.to_usize(), 8);
+        assert_eq!(Event::CounterZero.to_usize(), 9);
+        assert_eq!(Event::TimerBegin.to_usize(), 10);
+        assert_eq!(Event::TimerEnd.to_usize(), 11);
+        assert_eq!(Event::Signal.to_usize(), 12);
+        assert_eq!(Event::Idle.to_usize(), 13);
+        assert_eq!(Event::MachineStart.to_usize(), 14);
+        assert_eq!(Event::TimeThreshold.to_usize(), 15);
+
+        // events added since version 2 of the format display by name as well
+        assert_eq!(Event::Idle.to_string(), "Idle");
+        assert_eq!(Event::MachineStart.to_string(), "MachineStart");
+
+        // iter() yields every event exactly once, in ordinal order
+        assert_eq!(Event::iter().len(), EVENT_NUM);
+        for (i, e) in Event::iter().enumerate() {
+            assert_eq!(e.to_usize(), i);
+            assert_eq!(Event::from_usize(i), *e);
+        }
+    }
 }
@@ -2,17 +2,21 @@
 //! encrypted communication.
 
 use rand_core::RngCore;
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
 
 use crate::*;
 
 use self::action::Action;
-use self::constants::{STATE_END, STATE_LIMIT_MAX, STATE_SIGNAL};
+use self::constants::{
+    BLOCKING_LANES, EVENT_NUM, STATE_END, STATE_PREVIOUS, STATE_RANDOM, STATE_SIGNAL,
+};
 use self::counter::Operation;
 use self::event::Event;
 use crate::time::Duration as _;
 
 /// An opaque token representing one machine running inside the framework.
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct MachineId(usize);
 
 impl MachineId {
@@ -34,17 +38,196 @@ impl MachineId {
     }
 }
 
+/// The reason an action of a machine was suppressed, see
+/// [`Framework::last_suppression()`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum SuppressReason {
+    /// The limit of the state ([`Action`] `limit`) was reached.
+    StateLimit,
+    /// The padding limit of the machine was reached
+    /// ([`Machine::max_padding_frac`]).
+    MachinePaddingLimit,
+    /// The padding limit of the framework was reached
+    /// ([`Framework::max_padding_frac()`]).
+    FrameworkPaddingLimit,
+    /// The limit on the fraction of received padding of the framework was
+    /// reached ([`Framework::max_padding_recv_frac()`]).
+    FrameworkPaddingRecvLimit,
+    /// The blocking limit of the machine was reached
+    /// ([`Machine::max_blocking_frac`]).
+    MachineBlockingLimit,
+    /// The blocking limit of the framework was reached
+    /// ([`Framework::max_blocking_frac()`]).
+    FrameworkBlockingLimit,
+    /// The limit shared with other states was reached
+    /// ([`State::shared_limit`](crate::state::State::shared_limit)).
+    SharedLimit,
+    /// The blocking status did not match the condition of the state
+    /// ([`State::blocking_condition`](crate::state::State::blocking_condition)).
+    BlockingCondition,
+    /// The counter did not satisfy the condition of the state
+    /// ([`State::counter_condition`](crate::state::State::counter_condition)).
+    CounterCondition,
+}
+
+/// Counts of the work done by a [`Framework`] since it was created or the
+/// counts were reset, for profiling machines without relying on timing. See
+/// [`Framework::metrics()`].
+#[cfg(feature = "metrics")]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Metrics {
+    /// The number of state transitions taken, including to the same state,
+    /// [`STATE_END`], and [`STATE_SIGNAL`].
+    pub transitions: u64,
+    /// The number of values drawn from the random number generators, for
+    /// sampling transitions and distributions.
+    pub rng_samples: u64,
+    /// The number of actions returned by [`Framework::trigger_events()`].
+    pub actions: u64,
+}
+
+// wraps the random number generators of a framework, to count values drawn
+// from them with the metrics feature: either one shared by all machines, or
+// one per machine (see Framework::with_machine_rngs())
 #[derive(Debug, Clone)]
-struct MachineRuntime<T: crate::time::Instant> {
+struct FrameworkRng<R> {
+    rngs: Vec<R>,
+    // the generator to draw from, always 0 when shared
+    current: usize,
+    #[cfg(feature = "metrics")]
+    samples: u64,
+}
+
+impl<R: RngCore> FrameworkRng<R> {
+    // selects the generator of the machine to draw from
+    fn select(&mut self, mi: usize) -> &mut Self {
+        if self.rngs.len() > 1 {
+            self.current = mi;
+        }
+        self
+    }
+}
+
+impl<R: RngCore> RngCore for FrameworkRng<R> {
+    fn next_u32(&mut self) -> u32 {
+        #[cfg(feature = "metrics")]
+        {
+            self.samples += 1;
+        }
+        self.rngs[self.current].next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        #[cfg(feature = "metrics")]
+        {
+            self.samples += 1;
+        }
+        self.rngs[self.current].next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        #[cfg(feature = "metrics")]
+        {
+            self.samples += 1;
+        }
+        self.rngs[self.current].fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        #[cfg(feature = "metrics")]
+        {
+            self.samples += 1;
+        }
+        self.rngs[self.current].try_fill_bytes(dest)
+    }
+}
+
+// The times of the runtime are of type I, the instants of the framework while
+// running, and the time before the snapshot in a FrameworkSnapshot.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "T::Duration: Serialize, I: Serialize",
+    deserialize = "T::Duration: Deserialize<'de>, I: Deserialize<'de>"
+))]
+struct MachineRuntime<T: crate::time::Instant, I = T> {
     current_state: usize,
+    // the state before the last change of state, see STATE_PREVIOUS
+    previous_state: usize,
     state_limit: u64,
+    state_limit_saturated: bool,
     padding_sent: u64,
     normal_sent: u64,
     blocking_duration: T::Duration,
-    machine_start: T,
+    // the blocking duration of each lane, see below_limit_blocking()
+    lane_blocking_duration: [T::Duration; BLOCKING_LANES],
+    incoming_blocking_duration: T::Duration,
+    machine_start: I,
     allowed_blocked_microsec: T::Duration,
     counter_a: u64,
     counter_b: u64,
+    shared_limits: Vec<u64>,
+    // padding packets of the current burst not yet sent, including any
+    // scheduled packet
+    burst_remaining: u32,
+    // the last action returned for the machine, and when, for coalescing
+    // redundant actions with a minimum spacing (see set_min_spacing())
+    last_action: Option<(I, TriggerAction<T>)>,
+    // the reason the last action of the machine was suppressed, if any, see
+    // last_suppression()
+    last_suppression: Option<SuppressReason>,
+    // the action last scheduled on the action timer of the machine, until
+    // taken or cancelled, see has_pending_action()
+    pending_action: Option<TriggerAction<T>>,
+    // if set, the machine processes no events, see set_enabled()
+    disabled: bool,
+    // if set, the machine has transitioned on Event::MachineStart, see start()
+    started: bool,
+    // if set, the machine has transitioned on Event::TimeThreshold
+    threshold_reached: bool,
+    // the time of the last occurrence of each event for the machine, only
+    // kept (EVENT_NUM long) for machines with gap conditions, see
+    // State::gap_condition
+    last_event: Vec<Option<I>>,
+}
+
+impl<T: crate::time::Instant, I> MachineRuntime<T, I> {
+    // maps the times of the runtime, see FrameworkSnapshot
+    fn map_times<J, E>(
+        self,
+        mut f: impl FnMut(I) -> Result<J, E>,
+    ) -> Result<MachineRuntime<T, J>, E> {
+        Ok(MachineRuntime {
+            current_state: self.current_state,
+            previous_state: self.previous_state,
+            state_limit: self.state_limit,
+            state_limit_saturated: self.state_limit_saturated,
+            padding_sent: self.padding_sent,
+            normal_sent: self.normal_sent,
+            blocking_duration: self.blocking_duration,
+            lane_blocking_duration: self.lane_blocking_duration,
+            incoming_blocking_duration: self.incoming_blocking_duration,
+            machine_start: f(self.machine_start)?,
+            allowed_blocked_microsec: self.allowed_blocked_microsec,
+            counter_a: self.counter_a,
+            counter_b: self.counter_b,
+            shared_limits: self.shared_limits,
+            burst_remaining: self.burst_remaining,
+            last_action: match self.last_action {
+                Some((t, action)) => Some((f(t)?, action)),
+                None => None,
+            },
+            last_suppression: self.last_suppression,
+            pending_action: self.pending_action,
+            disabled: self.disabled,
+            started: self.started,
+            threshold_reached: self.threshold_reached,
+            last_event: self
+                .last_event
+                .into_iter()
+                .map(|t| t.map(&mut f).transpose())
+                .collect::<Result<_, E>>()?,
+        })
+    }
 }
 
 #[derive(PartialEq)]
@@ -53,15 +236,101 @@ enum StateChange {
     Unchanged,
 }
 
+/// The blocking of a lane of outgoing traffic, see
+/// [`BLOCKING_LANES`](crate::constants::BLOCKING_LANES).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "T::Duration: Serialize, I: Serialize",
+    deserialize = "T::Duration: Deserialize<'de>, I: Deserialize<'de>"
+))]
+struct LaneBlocking<T: crate::time::Instant, I = T> {
+    duration: T::Duration,
+    started: I,
+    active: bool,
+    owner: Option<MachineId>,
+    bypassable: bool,
+    // when the blocking ends, as time since started
+    ends: T::Duration,
+}
+
+impl<T: crate::time::Instant> LaneBlocking<T> {
+    fn new(current_time: T) -> Self {
+        Self {
+            duration: T::Duration::zero(),
+            started: current_time,
+            active: false,
+            owner: None,
+            bypassable: false,
+            ends: T::Duration::zero(),
+        }
+    }
+}
+
+impl<T: crate::time::Instant, I> LaneBlocking<T, I> {
+    // maps the start of the blocking, see FrameworkSnapshot
+    fn map_times<J, E>(
+        self,
+        mut f: impl FnMut(I) -> Result<J, E>,
+    ) -> Result<LaneBlocking<T, J>, E> {
+        Ok(LaneBlocking {
+            duration: self.duration,
+            started: f(self.started)?,
+            active: self.active,
+            owner: self.owner,
+            bypassable: self.bypassable,
+            ends: self.ends,
+        })
+    }
+}
+
 /// An internal signal target for signaling other machines. A machine will not
 /// signal itself, but, if multiple machines send signals at the same time, then
 /// a signal will be sent to all machines.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 enum SignalTarget {
     All,
     AllExcept(usize),
 }
 
+/// A snapshot of the runtime state of a [`Framework`], including the state of
+/// its random number generator, created by [`Framework::snapshot()`].
+///
+/// Restoring a snapshot with [`Framework::restore()`] makes the framework
+/// continue with exactly the same random stream as when the snapshot was
+/// taken. The times of the snapshot are kept relative to when it was taken,
+/// and are restored relative to the time of restoring, so a snapshot can be
+/// restored in another process, e.g., after a restart. The snapshot is
+/// serializable if the random number generator and the durations of the
+/// framework are (e.g., `ChaCha8Rng` and `Xoshiro256StarStar` with their
+/// `serde1` features, and [`std::time::Duration`]).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "R: Serialize, T::Duration: Serialize",
+    deserialize = "R: Deserialize<'de>, T::Duration: Deserialize<'de>"
+))]
+pub struct FrameworkSnapshot<R, T>
+where
+    T: crate::time::Instant,
+{
+    rngs: Vec<R>,
+    // the times of the snapshot are the durations before it was taken
+    runtime: Vec<MachineRuntime<T, T::Duration>>,
+    normal_sent_packets: u64,
+    padding_sent_packets: u64,
+    normal_recv_packets: u64,
+    padding_recv_packets: u64,
+    blocking_duration: T::Duration,
+    blocking_started: T::Duration,
+    blocking_active: bool,
+    lanes: [LaneBlocking<T, T::Duration>; BLOCKING_LANES],
+    incoming_blocking_duration: T::Duration,
+    incoming_blocking_started: T::Duration,
+    incoming_blocking_active: bool,
+    signal_pending: Option<SignalTarget>,
+    counter_zeroed_once: (bool, bool),
+    framework_start: T::Duration,
+}
+
 /// An instance of the Maybenot framework.
 ///
 /// An instance of the [`Framework`] repeatedly takes as *input* one or more
@@ -76,8 +345,9 @@ where
 {
     // updated each time the framework is triggered
     current_time: T,
-    // random number generator, used for sampling distributions and transitions
-    rng: R,
+    // random number generators, shared or per machine, used for sampling
+    // distributions and transitions
+    rng: FrameworkRng<R>,
     // we allocate the actions vector once and reuse it, handing out references
     // as part of the iterator in [`Framework::trigger_events`].
     actions: Vec<Option<TriggerAction<T>>>,
@@ -89,16 +359,45 @@ where
     max_padding_frac: f64,
     normal_sent_packets: u64,
     padding_sent_packets: u64,
-    // blocking accounting
+    // received padding accounting, 0.0 for no limit
+    max_padding_recv_frac: f64,
+    normal_recv_packets: u64,
+    padding_recv_packets: u64,
+    // blocking accounting, in aggregate (while any lane is blocked) and per
+    // lane
     max_blocking_frac: f64,
     blocking_duration: T::Duration,
     blocking_started: T,
     blocking_active: bool,
+    lanes: [LaneBlocking<T>; BLOCKING_LANES],
+    incoming_blocking_duration: T::Duration,
+    incoming_blocking_started: T,
+    incoming_blocking_active: bool,
     // for internal signaling: if set, specifies the target machines to signal
     signal_pending: Option<SignalTarget>,
     // only allow each counter to be zeroed once per trigger_events call
     counter_zeroed_once: (bool, bool),
     framework_start: T,
+    // if set, identical actions for a machine within this duration are
+    // coalesced
+    min_spacing: Option<T::Duration>,
+    // if set, blocking actions of machines starting at the same time are
+    // merged into one
+    merge_blocking: bool,
+    // timeouts and durations of actions are rounded up to a multiple of this
+    // many microseconds, 1 for no rounding
+    timer_granularity: u64,
+    // the number of calls with a current_time earlier than a previous call
+    clock_regressions: u64,
+    // the packets and uptime after which the framework is warm, see
+    // is_warm()
+    warm_up_packets: u64,
+    warm_up_duration: T::Duration,
+    #[cfg(feature = "metrics")]
+    metrics: Metrics,
+    // the timeouts and durations of returned actions, per machine, if enabled
+    #[cfg(feature = "metrics")]
+    action_histograms: Vec<ActionHistograms>,
 }
 
 impl<M, R, T> Framework<M, R, T>
@@ -112,13 +411,20 @@ where
     /// The max padding/blocking fractions are enforced as a total across all machines.
     /// The only way those limits can be violated are through
     /// [`Machine::allowed_padding_packets`] and
-    /// [`Machine::allowed_blocked_microsec`], respectively.
+    /// [`Machine::allowed_blocked_microsec`], respectively. The blocking
+    /// limits of outgoing traffic apply to each lane separately (see
+    /// [`Action::BlockOutgoing`]).
     ///
     /// The current time is handed to the framework here (and later in [`Self::trigger_events()`])
     /// to make some types of use cases of the framework easier (weird machines and
     /// for simulation). The generic time type also allows for using custom time sources.
     /// This can for example improve performance.
     ///
+    /// The framework never mutates its machines: all runtime state is kept
+    /// per framework instance. To share one set of machines between many
+    /// framework instances (e.g., one per tunnel) without cloning, pass a
+    /// shared reference such as `&[Machine]` or `Arc<[Machine]>`.
+    ///
     /// Returns an error on any invalid [`Machine`] or limits not being fractions [0.0, 1.0].
     pub fn new(
         machines: M,
@@ -126,6 +432,50 @@ where
         max_blocking_frac: f64,
         current_time: T,
         rng: R,
+    ) -> Result<Self, Error> {
+        Self::with_rngs(
+            machines,
+            max_padding_frac,
+            max_blocking_frac,
+            current_time,
+            vec![rng],
+        )
+    }
+
+    /// Create a new framework instance like [`Self::new()`], but with a
+    /// random number generator per machine, created by `machine_rng`.
+    ///
+    /// With a single generator, all machines draw from the same random
+    /// stream, so adding or removing a machine shifts the samples of every
+    /// other machine. With a generator per machine, e.g., a counter-based
+    /// generator seeded from the same seed with the index of the machine as
+    /// stream (`ChaCha8Rng::set_stream()`), a machine behaves the same
+    /// regardless of which other machines run alongside it.
+    pub fn with_machine_rngs(
+        machines: M,
+        max_padding_frac: f64,
+        max_blocking_frac: f64,
+        current_time: T,
+        mut machine_rng: impl FnMut(MachineId) -> R,
+    ) -> Result<Self, Error> {
+        let rngs = (0..machines.as_ref().len())
+            .map(|i| machine_rng(MachineId(i)))
+            .collect();
+        Self::with_rngs(
+            machines,
+            max_padding_frac,
+            max_blocking_frac,
+            current_time,
+            rngs,
+        )
+    }
+
+    fn with_rngs(
+        machines: M,
+        max_padding_frac: f64,
+        max_blocking_frac: f64,
+        current_time: T,
+        rngs: Vec<R>,
     ) -> Result<Self, Error> {
         if !(0.0..=1.0).contains(&max_padding_frac) {
             Err(Error::PaddingLimit)?;
@@ -139,14 +489,31 @@ where
             m.validate()?;
             runtime.push(MachineRuntime {
                 current_state: 0,
+                previous_state: 0,
                 state_limit: 0,
+                state_limit_saturated: false,
                 padding_sent: 0,
                 normal_sent: 0,
                 blocking_duration: T::Duration::zero(),
+                lane_blocking_duration: [T::Duration::zero(); BLOCKING_LANES],
+                incoming_blocking_duration: T::Duration::zero(),
                 machine_start: current_time,
                 allowed_blocked_microsec: T::Duration::from_micros(m.allowed_blocked_microsec),
                 counter_a: 0,
                 counter_b: 0,
+                shared_limits: vec![0; m.shared_limits.len()],
+                burst_remaining: 0,
+                last_action: None,
+                last_suppression: None,
+                pending_action: None,
+                disabled: false,
+                started: false,
+                threshold_reached: false,
+                last_event: if m.states.iter().any(|s| s.gap_condition.is_some()) {
+                    vec![None; EVENT_NUM]
+                } else {
+                    vec![]
+                },
             });
         }
 
@@ -158,22 +525,57 @@ where
             machines,
             runtime,
             current_time,
-            rng,
+            rng: FrameworkRng {
+                rngs,
+                current: 0,
+                #[cfg(feature = "metrics")]
+                samples: 0,
+            },
             max_blocking_frac,
             max_padding_frac,
             framework_start: current_time,
             blocking_active: false,
+            lanes: [LaneBlocking::new(current_time); BLOCKING_LANES],
             blocking_started: current_time,
             blocking_duration: T::Duration::zero(),
+            incoming_blocking_duration: T::Duration::zero(),
+            incoming_blocking_started: current_time,
+            incoming_blocking_active: false,
             padding_sent_packets: 0,
             normal_sent_packets: 0,
+            max_padding_recv_frac: 0.0,
+            padding_recv_packets: 0,
+            normal_recv_packets: 0,
             signal_pending: None,
             counter_zeroed_once: (false, false),
+            min_spacing: None,
+            merge_blocking: false,
+            timer_granularity: 1,
+            clock_regressions: 0,
+            warm_up_packets: 100,
+            warm_up_duration: T::Duration::from_micros(1_000_000),
+            #[cfg(feature = "metrics")]
+            metrics: Metrics::default(),
+            #[cfg(feature = "metrics")]
+            action_histograms: vec![],
         };
 
-        for (runtime, machine) in s.runtime.iter_mut().zip(s.machines.as_ref().iter()) {
-            if let Some(action) = machine.states[0].action {
-                runtime.state_limit = action.sample_limit(&mut s.rng);
+        for (mi, (runtime, machine)) in s
+            .runtime
+            .iter_mut()
+            .zip(s.machines.as_ref().iter())
+            .enumerate()
+        {
+            if machine.states[0].action.is_some() {
+                (runtime.state_limit, runtime.state_limit_saturated) =
+                    machine.states[0].sample_limit(s.rng.select(mi));
+            }
+            for (limit, dist) in runtime
+                .shared_limits
+                .iter_mut()
+                .zip(machine.shared_limits.iter())
+            {
+                *limit = dist.sample(s.rng.select(mi)).round() as u64;
             }
         }
 
@@ -185,6 +587,384 @@ where
         self.machines.as_ref().len()
     }
 
+    /// Returns the maximum fraction of padding packets, enforced as a total
+    /// across all machines (see [`Self::new()`]).
+    pub fn max_padding_frac(&self) -> f64 {
+        self.max_padding_frac
+    }
+
+    /// Returns the maximum fraction of time to block outgoing traffic,
+    /// enforced as a total across all machines (see [`Self::new()`]).
+    pub fn max_blocking_frac(&self) -> f64 {
+        self.max_blocking_frac
+    }
+
+    /// Returns the maximum fraction of received packets that may be padding
+    /// for machines to send padding, 0.0 for no limit (see
+    /// [`Self::set_max_padding_recv_frac()`]).
+    pub fn max_padding_recv_frac(&self) -> f64 {
+        self.max_padding_recv_frac
+    }
+
+    /// Returns the number of normal and padding packets received, in that
+    /// order, as reported by [`TriggerEvent::NormalRecv`] and
+    /// [`TriggerEvent::PaddingRecv`].
+    pub fn recv_packets(&self) -> (u64, u64) {
+        (self.normal_recv_packets, self.padding_recv_packets)
+    }
+
+    /// Returns the machine responsible for the currently active blocking of
+    /// the lane, if any. This is the machine of the
+    /// [`TriggerEvent::BlockingBegin`] that started the blocking of the lane,
+    /// until the next [`TriggerEvent::BlockingEnd`] of the lane.
+    pub fn blocking_owner(&self, lane: u8) -> Option<MachineId> {
+        self.lanes.get(lane as usize).and_then(|l| l.owner)
+    }
+
+    /// Returns true if the currently active blocking of the lane is
+    /// bypassable, i.e., if padding with the bypass flag set may be sent
+    /// despite the blocking. This is the bypass flag of the
+    /// [`TriggerAction::BlockOutgoing`] that started the blocking, or of the
+    /// last one that overwrote it: with the replace flag set, or with a
+    /// duration that extended the blocking. Returns false if no blocking of
+    /// the lane is active, or if the blocking was not started by an action of
+    /// the framework.
+    pub fn blocking_bypassable(&self, lane: u8) -> bool {
+        self.lanes
+            .get(lane as usize)
+            .is_some_and(|l| l.active && l.bypassable)
+    }
+
+    /// Returns the total duration that the lane has been blocked, including
+    /// any ongoing blocking, as of the last call to
+    /// [`Self::trigger_events()`]. The framework limits the blocking of each
+    /// lane separately by `max_blocking_frac` (see [`Self::new()`]), while
+    /// holding outgoing traffic is limited by the blocking of all lanes in
+    /// aggregate, i.e., the time any lane was blocked.
+    pub fn lane_blocking_duration(&self, lane: u8) -> T::Duration {
+        let Some(l) = self.lanes.get(lane as usize) else {
+            return T::Duration::zero();
+        };
+        let mut duration = l.duration;
+        if l.active {
+            duration += self.current_time.saturating_duration_since(l.started);
+        }
+        duration
+    }
+
+    /// Returns the current time of the framework: the latest `current_time`
+    /// given to [`Self::new()`] or [`Self::trigger_events()`].
+    pub fn now(&self) -> T {
+        self.current_time
+    }
+
+    /// Returns the time the framework has been running, as of the
+    /// `current_time` of the last call to [`Self::trigger_events()`] (or
+    /// creation of the framework). This is the time that the fraction of
+    /// blocking `max_blocking_frac` (see [`Self::new()`]) is of.
+    pub fn uptime(&self) -> T::Duration {
+        self.current_time
+            .saturating_duration_since(self.framework_start)
+    }
+
+    /// Returns true if the framework is warm: it has been running for long
+    /// enough, and seen enough packets sent and received, for the fractions
+    /// of padding and blocking it limits to be meaningful (see
+    /// [`Self::set_warm_up()`]). Before, the fractions are of small totals,
+    /// so a few actions may cause large spikes that are not indicative of
+    /// the overhead over time.
+    pub fn is_warm(&self) -> bool {
+        let packets = self.normal_sent_packets
+            + self.padding_sent_packets
+            + self.normal_recv_packets
+            + self.padding_recv_packets;
+        packets >= self.warm_up_packets && self.uptime() >= self.warm_up_duration
+    }
+
+    /// Returns the number of calls to [`Self::trigger_events()`] with a
+    /// `current_time` earlier than the current time of the framework (see
+    /// [`Self::now()`]), i.e., how many times the clock of the caller went
+    /// backwards.
+    pub fn clock_regressions(&self) -> u64 {
+        self.clock_regressions
+    }
+
+    /// Returns the time the machine has been running, as of the
+    /// `current_time` of the last call to [`Self::trigger_events()`] (or
+    /// creation of the framework). Returns None if there is no such machine.
+    ///
+    /// Machines start when the framework is created, and are never reset:
+    /// neither state transitions nor reaching [`STATE_END`] affect the
+    /// elapsed time. Restoring a [`FrameworkSnapshot`] restores the start
+    /// time of each machine, and whether it has reached its time threshold.
+    ///
+    /// To have a machine transition once some time has elapsed, set its
+    /// [`Machine::time_threshold`]: the machine transitions on
+    /// [`Event::TimeThreshold`] once, on the first call to
+    /// [`Self::trigger_events()`] (or [`Self::start()`]) with a
+    /// `current_time` at or after the threshold, before any of the events of
+    /// the call. Since the framework only runs when triggered, the event may
+    /// be late: to transition at a precise time, use the internal timer of
+    /// the machine ([`Action::UpdateTimer`]). A disabled machine transitions
+    /// once enabled again.
+    pub fn machine_elapsed(&self, machine: MachineId) -> Option<T::Duration> {
+        let runtime = self.runtime.get(machine.into_raw())?;
+        Some(
+            self.current_time
+                .saturating_duration_since(runtime.machine_start),
+        )
+    }
+
+    /// Returns the reason the most recent action of the machine was
+    /// suppressed, or None if no action has been suppressed or there is no
+    /// such machine.
+    ///
+    /// An action is suppressed, and not returned by
+    /// [`Self::trigger_events()`], when scheduling it would exceed one of the
+    /// limits of the machine or framework. The reason is kept until the next
+    /// suppressed action, also when later actions are scheduled: it is a
+    /// diagnostic for machines that appear inert.
+    pub fn last_suppression(&self, machine: MachineId) -> Option<SuppressReason> {
+        self.runtime.get(machine.into_raw())?.last_suppression
+    }
+
+    /// Returns true if the limit sampled for the current state of the machine
+    /// saturated, i.e., was clamped to
+    /// [`STATE_LIMIT_MAX`](crate::constants::STATE_LIMIT_MAX), making the state
+    /// effectively unlimited. Returns false if there is no such machine. See
+    /// [`Action::limit_saturates()`](crate::action::Action::limit_saturates)
+    /// to check machines before running them.
+    pub fn state_limit_saturated(&self, machine: MachineId) -> bool {
+        self.runtime
+            .get(machine.into_raw())
+            .is_some_and(|runtime| runtime.state_limit_saturated)
+    }
+
+    /// Returns the limit of the current state of the machine: the number of
+    /// actions it may still take before [`Event::LimitReached`], sampled on
+    /// entering the state (see
+    /// [`State::fixed_limit`](crate::state::State::fixed_limit)).
+    /// Returns None if there is no such machine.
+    pub fn state_limit(&self, machine: MachineId) -> Option<u64> {
+        Some(self.runtime.get(machine.into_raw())?.state_limit)
+    }
+
+    /// Override the limit of the current state of the machine (see
+    /// [`Self::state_limit()`]) until it enters another state, e.g., to test
+    /// limit accounting without crafting limit distributions. Does nothing if
+    /// there is no such machine. Only available with the `testing` feature.
+    #[cfg(any(test, feature = "testing"))]
+    pub fn set_state_limit(&mut self, machine: MachineId, limit: u64) {
+        if let Some(runtime) = self.runtime.get_mut(machine.into_raw()) {
+            runtime.state_limit = limit;
+            runtime.state_limit_saturated = false;
+        }
+    }
+
+    /// Enable or disable a machine, e.g., to experiment with defenses without
+    /// recreating the framework. All machines are enabled by default.
+    ///
+    /// A disabled machine processes no events, so it neither transitions nor
+    /// returns actions, and its runtime state (current state, limits,
+    /// counters, and per-machine accounting) is kept as is until it is
+    /// enabled again. It then continues in the state it was disabled in, with
+    /// no action scheduled until its next transition. Global accounting for
+    /// the padding and blocking limits of the framework continues while a
+    /// machine is disabled, but the machine no longer contributes to it.
+    ///
+    /// When disabling an enabled machine, returns a [`TriggerAction::Cancel`]
+    /// of all its timers that MUST be taken by the caller, so that no pending
+    /// action of the machine is taken while it is disabled. Returns None
+    /// otherwise, including if there is no such machine.
+    pub fn set_enabled(&mut self, machine: MachineId, enabled: bool) -> Option<TriggerAction<T>> {
+        let runtime = self.runtime.get_mut(machine.into_raw())?;
+        let cancel = !enabled && !runtime.disabled;
+        runtime.disabled = !enabled;
+        if !cancel {
+            return None;
+        }
+        runtime.burst_remaining = 0;
+        runtime.last_action = None;
+        runtime.pending_action = None;
+        Some(TriggerAction::Cancel {
+            machine,
+            timer: Timer::All,
+        })
+    }
+
+    /// Returns true if the machine is enabled (see [`Self::set_enabled()`]).
+    /// Returns false if there is no such machine.
+    pub fn is_enabled(&self, machine: MachineId) -> bool {
+        self.runtime
+            .get(machine.into_raw())
+            .is_some_and(|runtime| !runtime.disabled)
+    }
+
+    /// Returns true if the machine has an action pending on its action timer,
+    /// i.e., a [`TriggerAction::SendPadding`], [`TriggerAction::BlockOutgoing`],
+    /// or [`TriggerAction::BlockIncoming`] has been returned by
+    /// [`Self::trigger_events()`] for the machine, and has neither been taken
+    /// nor cancelled since. Returns false if there is no such machine.
+    ///
+    /// An action is taken once the caller triggers the resulting
+    /// [`TriggerEvent::PaddingSent`], [`TriggerEvent::BlockingBegin`], or
+    /// [`TriggerEvent::IncomingBlockingBegin`] for the machine, and cancelled
+    /// by a returned [`TriggerAction::Cancel`] applying to it. The internal
+    /// timer of the machine is not considered, and neither is a pending
+    /// [`TriggerAction::HoldOutgoing`], since holding is not reported to the
+    /// framework.
+    pub fn has_pending_action(&self, machine: MachineId) -> bool {
+        self.runtime
+            .get(machine.into_raw())
+            .is_some_and(|runtime| runtime.pending_action.is_some())
+    }
+
+    /// Returns the machines with an action returned by the most recent call
+    /// to [`Self::trigger_events()`] (or [`Self::trigger_events_iter()`] and
+    /// [`Self::start()`]), in increasing order, e.g., to only update the
+    /// timers of those machines. This is the machine of each returned
+    /// [`TriggerAction`], without iterating over the actions again. Empty
+    /// before the first call and after [`Self::restore()`].
+    pub fn last_active_machines(&self) -> impl Iterator<Item = MachineId> + '_ {
+        self.actions
+            .iter()
+            .enumerate()
+            .filter(|(_, action)| action.is_some())
+            .map(|(mi, _)| MachineId(mi))
+    }
+
+    /// Set the minimum spacing between identical actions for the same
+    /// machine, or None (the default) to return all actions.
+    ///
+    /// Machines that reschedule on every event cause the caller to replace
+    /// its timers just as often, also when nothing changed. With a minimum
+    /// spacing, an action is not returned by [`Self::trigger_events()`] if it
+    /// is identical to the last action returned for the machine less than
+    /// `min_spacing` ago. The action previously returned then stays in effect,
+    /// so a timer is not pushed back by an identical timeout. Any different
+    /// action is always returned, and so is the first action after the
+    /// previous one was taken (i.e., after [`TriggerEvent::PaddingSent`],
+    /// [`TriggerEvent::BlockingBegin`], or [`TriggerEvent::TimerEnd`] for the
+    /// machine).
+    ///
+    /// Note that for a coalesced [`TriggerAction::UpdateTimer`], there is no
+    /// [`TriggerEvent::TimerBegin`] to trigger.
+    pub fn set_min_spacing(&mut self, min_spacing: Option<T::Duration>) {
+        self.min_spacing = min_spacing;
+    }
+
+    /// Set the maximum fraction of received packets that may be padding for
+    /// machines to send padding, 0.0 (the default) for no limit.
+    ///
+    /// The fraction is of [`TriggerEvent::PaddingRecv`] out of all
+    /// [`TriggerEvent::PaddingRecv`] and [`TriggerEvent::NormalRecv`] since
+    /// the framework was created. When it is reached, padding actions of all
+    /// machines are suppressed with
+    /// [`SuppressReason::FrameworkPaddingRecvLimit`]. This bounds the total
+    /// overhead of defenses where the peer pads as well, e.g., on request by
+    /// the local machines. Like the other padding limits, the limit does not
+    /// apply to [`Machine::allowed_padding_packets`].
+    ///
+    /// Returns an error if the limit is not a fraction [0.0, 1.0].
+    pub fn set_max_padding_recv_frac(&mut self, max_padding_recv_frac: f64) -> Result<(), Error> {
+        if !(0.0..=1.0).contains(&max_padding_recv_frac) {
+            Err(Error::PaddingRecvLimit)?;
+        }
+        self.max_padding_recv_frac = max_padding_recv_frac;
+        Ok(())
+    }
+
+    /// Set the number of packets, sent and received, and the uptime (see
+    /// [`Self::uptime()`]) after which the framework is warm (see
+    /// [`Self::is_warm()`]). Both are required, and default to 100 packets
+    /// and one second. Does not affect the limits, which always apply.
+    pub fn set_warm_up(&mut self, packets: u64, duration: T::Duration) {
+        self.warm_up_packets = packets;
+        self.warm_up_duration = duration;
+    }
+
+    /// Set if [`TriggerAction::BlockOutgoing`] of different machines, starting
+    /// at the same time, should be merged into one action (default false).
+    ///
+    /// Since blocking is global, the integration otherwise has to combine
+    /// concurrent blocking itself. With merging, [`Self::trigger_events()`]
+    /// returns one action for all blocking of a lane with the same timeout,
+    /// with the same effect as applying them in the order of the machines:
+    /// the duration is the longest, unless the replace flag of a later action
+    /// overrides it. The merged action has the replace flag if any action
+    /// has, and the bypass flag only if all actions have. The machine of the
+    /// merged action is the machine whose duration is used, and so the only
+    /// machine with a [`TriggerEvent::BlockingBegin`] to trigger. Note that
+    /// the action timers of the other machines are left as they were.
+    pub fn set_merge_blocking(&mut self, merge: bool) {
+        self.merge_blocking = merge;
+    }
+
+    /// Set the granularity of timers, or None (the default) for microsecond
+    /// precision. Timeouts and durations of actions returned by
+    /// [`Self::trigger_events()`] are rounded up to a multiple of the
+    /// granularity, e.g., to whole milliseconds for platforms with a 1 ms timer
+    /// tick. Rounding up ensures that actions never happen earlier than
+    /// sampled.
+    pub fn set_timer_granularity(&mut self, granularity: Option<T::Duration>) {
+        self.timer_granularity = granularity.map_or(1, |g| {
+            (g.div_duration_f64(T::Duration::from_micros(1)) as u64).max(1)
+        });
+    }
+
+    /// Returns counts of the work done by the framework since it was created
+    /// or [`Self::reset_metrics()`] was called, including sampling limits when
+    /// creating the framework. Requires the `metrics` feature.
+    #[cfg(feature = "metrics")]
+    pub fn metrics(&self) -> Metrics {
+        Metrics {
+            rng_samples: self.rng.samples,
+            ..self.metrics
+        }
+    }
+
+    /// Reset the counts returned by [`Self::metrics()`] to zero. Requires the
+    /// `metrics` feature.
+    #[cfg(feature = "metrics")]
+    pub fn reset_metrics(&mut self) {
+        self.metrics = Metrics::default();
+        self.rng.samples = 0;
+    }
+
+    /// Enable or disable collecting histograms of the timeouts and durations
+    /// of the actions returned by [`Self::trigger_events()`] (default
+    /// disabled). Since the histograms take about a kilobyte per machine,
+    /// they are only allocated once enabled, and dropped when disabled.
+    /// Requires the `metrics` feature.
+    #[cfg(feature = "metrics")]
+    pub fn set_action_histograms(&mut self, enabled: bool) {
+        if !enabled {
+            self.action_histograms = vec![];
+        } else if self.action_histograms.is_empty() {
+            self.action_histograms = vec![ActionHistograms::default(); self.runtime.len()];
+        }
+    }
+
+    /// Returns a snapshot of the histograms of the timeouts and durations of
+    /// the actions returned by [`Self::trigger_events()`] for the machine,
+    /// since the histograms were enabled with [`Self::set_action_histograms()`]
+    /// or [`Self::reset_action_histograms()`] was called. This shows what the
+    /// machine actually does, after limits and rounding, compared to its
+    /// distributions. Returns None if the histograms are disabled or there is
+    /// no such machine. Requires the `metrics` feature.
+    #[cfg(feature = "metrics")]
+    pub fn action_histograms(&self, machine: MachineId) -> Option<ActionHistograms> {
+        self.action_histograms.get(machine.into_raw()).copied()
+    }
+
+    /// Remove all values recorded in the histograms returned by
+    /// [`Self::action_histograms()`], e.g., to collect them over a window.
+    /// Requires the `metrics` feature.
+    #[cfg(feature = "metrics")]
+    pub fn reset_action_histograms(&mut self) {
+        self.action_histograms.iter_mut().for_each(|h| h.reset());
+    }
+
     /// Trigger zero or more [`TriggerEvent`] for all machines running in the
     /// framework.
     ///
@@ -195,19 +975,68 @@ where
     /// nondecreasing clock. This means that the time passed SHOULD never be
     /// earlier than what was given to [`Framework::new()`] or a previous call
     /// to `trigger_events` for the same framework instance. If this requirement
-    /// is not followed, blocking durations MAY be inaccurately accounted for,
-    /// leading to less or more [`TriggerAction::BlockOutgoing`] than intended
-    /// by set framework and machine limits. The consequences of this depend on
-    /// the running machines (e.g., a machine may also pad as a consequence of
-    /// blocking) and the use-case for the user of the framework.
+    /// is not followed, the earlier time is ignored: the framework keeps its
+    /// current time (see [`Self::now()`]) and counts the call in
+    /// [`Self::clock_regressions()`]. Durations are then accounted for as if
+    /// no time had passed, which MAY lead to less or more
+    /// [`TriggerAction::BlockOutgoing`] than intended by set framework and
+    /// machine limits. The consequences of this depend on the running machines
+    /// (e.g., a machine may also pad as a consequence of blocking) and the
+    /// use-case for the user of the framework.
     ///
     /// Returns an iterator of zero or more [`TriggerAction`] that MUST be taken
     /// by the caller.
+    ///
+    /// This method does not allocate on the heap: all memory needed to run the
+    /// machines is allocated in [`Framework::new()`], making the time spent per
+    /// call predictable.
     pub fn trigger_events(
         &mut self,
         events: &[TriggerEvent],
         current_time: T,
     ) -> impl Iterator<Item = &TriggerAction<T>> {
+        self.process_events(events.iter().cloned(), current_time, false);
+        self.actions.iter().filter_map(|action| action.as_ref())
+    }
+
+    /// Like [`Self::trigger_events()`], but consumes the events from an
+    /// iterator, e.g., when draining a channel of events, so that the caller
+    /// does not need to collect them first.
+    pub fn trigger_events_iter<I>(
+        &mut self,
+        events: I,
+        current_time: T,
+    ) -> impl Iterator<Item = &TriggerAction<T>>
+    where
+        I: IntoIterator<Item = TriggerEvent>,
+    {
+        self.process_events(events, current_time, false);
+        self.actions.iter().filter_map(|action| action.as_ref())
+    }
+
+    /// Start the machines in the framework: each enabled machine that has not
+    /// been started yet transitions on [`Event::MachineStart`]. This lets
+    /// state 0 of a machine take an initial action without waiting for any
+    /// other event, e.g., to begin padding as soon as a connection is opened.
+    ///
+    /// The event is delivered exactly once per machine: calling this method
+    /// again only starts machines that were disabled at the previous call
+    /// (see [`Self::set_enabled()`]). Restoring a snapshot taken before a
+    /// machine was started (see [`Self::restore()`]) makes it start again.
+    /// Machines are never started implicitly.
+    ///
+    /// Like [`Self::trigger_events()`], but without any [`TriggerEvent`]:
+    /// returns an iterator of zero or more [`TriggerAction`] that MUST be
+    /// taken by the caller.
+    pub fn start(&mut self, current_time: T) -> impl Iterator<Item = &TriggerAction<T>> {
+        self.process_events(std::iter::empty(), current_time, true);
+        self.actions.iter().filter_map(|action| action.as_ref())
+    }
+
+    fn process_events<I>(&mut self, events: I, current_time: T, start: bool)
+    where
+        I: IntoIterator<Item = TriggerEvent>,
+    {
         // reset all actions
         self.actions.fill(None);
 
@@ -218,9 +1047,45 @@ where
         // per machine, but that future events may replace those actions. Under
         // load, this is preferable (because something already happened before
         // we could cause an action, so better to catch up).
-        self.current_time = current_time;
+        // never let the time go backwards, since all accounting is relative to
+        // the latest time
+        if self
+            .current_time
+            .saturating_duration_since(current_time)
+            .is_zero()
+        {
+            self.current_time = current_time;
+        } else {
+            self.clock_regressions += 1;
+        }
+        if start {
+            for mi in 0..self.runtime.len() {
+                if self.runtime[mi].started || self.runtime[mi].disabled {
+                    continue;
+                }
+                self.runtime[mi].started = true;
+                self.transition(mi, Event::MachineStart);
+            }
+        }
+        for mi in 0..self.runtime.len() {
+            let runtime = &self.runtime[mi];
+            let Some(threshold) = self.machines.as_ref()[mi].time_threshold else {
+                continue;
+            };
+            if runtime.threshold_reached
+                || runtime.disabled
+                || self
+                    .current_time
+                    .saturating_duration_since(runtime.machine_start)
+                    < T::Duration::from_micros(threshold)
+            {
+                continue;
+            }
+            self.runtime[mi].threshold_reached = true;
+            self.transition(mi, Event::TimeThreshold);
+        }
         for e in events {
-            self.process_event(e);
+            self.process_event(&e);
         }
 
         // handle internal signaling: at most one signal per call to
@@ -255,20 +1120,162 @@ where
             }
         }
 
-        // only return actions, no None
-        self.actions.iter().filter_map(|action| action.as_ref())
+        if self.merge_blocking {
+            self.merge_blocking_actions();
+        }
+
+        if let Some(min_spacing) = self.min_spacing {
+            self.coalesce_actions(min_spacing);
+        }
+
+        self.track_pending_actions();
+
+        #[cfg(feature = "metrics")]
+        {
+            self.metrics.actions += self.actions.iter().filter(|a| a.is_some()).count() as u64;
+            self.record_action_histograms();
+        }
+    }
+
+    #[cfg(feature = "metrics")]
+    fn record_action_histograms(&mut self) {
+        let micros = |d: T::Duration| d.div_duration_f64(T::Duration::from_micros(1)) as u64;
+        for (action, h) in self.actions.iter().zip(self.action_histograms.iter_mut()) {
+            match action {
+                Some(TriggerAction::SendPadding { timeout, .. }) => {
+                    h.timeouts.record(micros(*timeout));
+                }
+                Some(TriggerAction::BlockOutgoing {
+                    timeout, duration, ..
+                })
+                | Some(TriggerAction::BlockIncoming {
+                    timeout, duration, ..
+                })
+                | Some(TriggerAction::HoldOutgoing {
+                    timeout, duration, ..
+                }) => {
+                    h.timeouts.record(micros(*timeout));
+                    h.block_durations.record(micros(*duration));
+                }
+                Some(TriggerAction::UpdateTimer { duration, .. }) => {
+                    h.timer_durations.record(micros(*duration));
+                }
+                Some(TriggerAction::Cancel { .. }) | None => {}
+            }
+        }
+    }
+
+    fn merge_blocking_actions(&mut self) {
+        for i in 0..self.actions.len() {
+            let Some(TriggerAction::BlockOutgoing { timeout, lane, .. }) = self.actions[i] else {
+                continue;
+            };
+            // merge later blocking of the same lane starting at the same time
+            // into the first, as if applied in machine order
+            for j in i + 1..self.actions.len() {
+                let Some(TriggerAction::BlockOutgoing {
+                    timeout: t,
+                    duration: d,
+                    bypass: b,
+                    replace: r,
+                    lane: l,
+                    machine: m,
+                }) = self.actions[j]
+                else {
+                    continue;
+                };
+                if t != timeout || l != lane {
+                    continue;
+                }
+                if let Some(TriggerAction::BlockOutgoing {
+                    duration,
+                    bypass,
+                    replace,
+                    machine,
+                    ..
+                }) = &mut self.actions[i]
+                {
+                    if r || d > *duration {
+                        *duration = d;
+                        *machine = m;
+                    }
+                    *replace |= r;
+                    // fail closed: bypassable only if all blocking is
+                    *bypass &= b;
+                }
+                self.actions[j] = None;
+            }
+        }
+    }
+
+    fn coalesce_actions(&mut self, min_spacing: T::Duration) {
+        for (action, runtime) in self.actions.iter_mut().zip(self.runtime.iter_mut()) {
+            let Some(a) = action else {
+                continue;
+            };
+            if let Some((at, last)) = &runtime.last_action {
+                if last == a && self.current_time.saturating_duration_since(*at) < min_spacing {
+                    *action = None;
+                    continue;
+                }
+            }
+            runtime.last_action = Some((self.current_time, a.clone()));
+        }
+    }
+
+    fn track_pending_actions(&mut self) {
+        for (action, runtime) in self.actions.iter().zip(self.runtime.iter_mut()) {
+            let Some(a) = action else {
+                continue;
+            };
+            match a {
+                TriggerAction::SendPadding { .. }
+                | TriggerAction::BlockOutgoing { .. }
+                | TriggerAction::BlockIncoming { .. } => {
+                    runtime.pending_action = Some(a.clone());
+                }
+                // replaces any pending action, but is never reported taken
+                TriggerAction::HoldOutgoing { .. } => {
+                    runtime.pending_action = None;
+                }
+                TriggerAction::Cancel { timer, .. } => {
+                    let cancelled = matches!(
+                        (timer, &runtime.pending_action),
+                        (Timer::Action | Timer::All, _)
+                            | (
+                                Timer::ActionPadding,
+                                Some(TriggerAction::SendPadding { .. })
+                            )
+                            | (
+                                Timer::ActionBlocking,
+                                Some(
+                                    TriggerAction::BlockOutgoing { .. }
+                                        | TriggerAction::BlockIncoming { .. }
+                                        | TriggerAction::HoldOutgoing { .. }
+                                )
+                            )
+                    );
+                    if cancelled {
+                        runtime.pending_action = None;
+                    }
+                }
+                TriggerAction::UpdateTimer { .. } => {}
+            }
+        }
     }
 
     fn process_event(&mut self, e: &TriggerEvent) {
         match e {
             TriggerEvent::NormalRecv => {
-                // no special accounting needed
+                self.normal_recv_packets += 1;
+
                 for mi in 0..self.runtime.len() {
                     self.transition(mi, Event::NormalRecv);
                 }
             }
             TriggerEvent::PaddingRecv => {
-                // no special accounting needed
+                self.padding_recv_packets += 1;
+
                 for mi in 0..self.runtime.len() {
                     self.transition(mi, Event::PaddingRecv);
                 }
@@ -283,25 +1290,38 @@ where
                 self.normal_sent_packets += 1;
 
                 for mi in 0..self.runtime.len() {
+                    if self.runtime[mi].disabled {
+                        continue;
+                    }
                     self.runtime[mi].normal_sent += 1;
 
                     self.transition(mi, Event::NormalSent);
                 }
             }
             TriggerEvent::PaddingSent { machine } => {
+                // counted also for a machine that has ended, e.g., with padding
+                // still pending, since the padding was sent
                 self.padding_sent_packets += 1;
 
                 let mi = machine.into_raw();
-                if mi >= self.runtime.len() {
+                if mi >= self.runtime.len() || self.runtime[mi].disabled {
                     return;
                 }
                 self.runtime[mi].padding_sent += 1;
+                self.runtime[mi].last_action = None;
+                self.runtime[mi].pending_action = None;
+                self.decrement_shared_limit(mi);
+                // the sent packet may be part of a burst
+                let state = self.runtime[mi].current_state;
+                let remaining = self.runtime[mi].burst_remaining.saturating_sub(1);
+                self.runtime[mi].burst_remaining = remaining;
                 if self.transition(mi, Event::PaddingSent) == StateChange::Unchanged
                     && self.runtime[mi].current_state != STATE_END
                 {
                     // decrement only makes sense if we didn't change state
                     self.decrement_limit(mi);
                 }
+                self.continue_burst(mi, state, remaining);
             }
             TriggerEvent::TunnelSent => {
                 // accounting is based on normal/padding sent, not tunnel
@@ -309,15 +1329,66 @@ where
                     self.transition(mi, Event::TunnelSent);
                 }
             }
-            TriggerEvent::BlockingBegin { machine } => {
-                // keep track of when we start blocking (for accounting in BlockingEnd)
-                if !self.blocking_active {
-                    self.blocking_active = true;
-                    self.blocking_started = self.current_time;
+            TriggerEvent::Idle => {
+                // detected by the integration, no accounting needed
+                for mi in 0..self.runtime.len() {
+                    self.transition(mi, Event::Idle);
+                }
+            }
+            TriggerEvent::BlockingBegin { machine, lane } => {
+                let pending = self
+                    .runtime
+                    .get(machine.into_raw())
+                    .filter(|runtime| !runtime.disabled)
+                    .and_then(|runtime| match runtime.pending_action {
+                        Some(TriggerAction::BlockOutgoing {
+                            duration,
+                            bypass,
+                            replace,
+                            lane: l,
+                            ..
+                        }) if l == *lane => Some((duration, bypass, replace)),
+                        _ => None,
+                    });
+                if let Some(l) = self.lanes.get_mut(*lane as usize) {
+                    // keep track of when we start blocking, in aggregate and
+                    // of the lane (for accounting in BlockingEnd)
+                    if !self.blocking_active {
+                        self.blocking_active = true;
+                        self.blocking_started = self.current_time;
+                    }
+                    let started = !l.active;
+                    if started {
+                        l.active = true;
+                        l.started = self.current_time;
+                        l.owner = Some(*machine);
+                        l.bypassable = false;
+                        l.ends = T::Duration::zero();
+                    }
+                    // as the integration, the blocking is overwritten if
+                    // replaced or extended, see TriggerAction::BlockOutgoing
+                    if let Some((duration, bypass, replace)) = pending {
+                        let mut ends = self.current_time.saturating_duration_since(l.started);
+                        ends += duration;
+                        if started || replace || ends > l.ends {
+                            l.bypassable = bypass;
+                            l.ends = ends;
+                        }
+                    }
+                }
+                if machine.into_raw() < self.runtime.len()
+                    && !self.runtime[machine.into_raw()].disabled
+                {
+                    self.runtime[machine.into_raw()].last_action = None;
+                    self.runtime[machine.into_raw()].pending_action = None;
+                    self.decrement_shared_limit(machine.into_raw());
                 }
 
                 // blocking is a global event
                 for mi in 0..self.runtime.len() {
+                    if self.runtime[mi].disabled {
+                        continue;
+                    }
                     if self.transition(mi, Event::BlockingBegin) == StateChange::Unchanged
                         && self.runtime[mi].current_state != STATE_END
                         && mi == machine.into_raw()
@@ -328,9 +1399,19 @@ where
                     }
                 }
             }
-            TriggerEvent::BlockingEnd => {
+            TriggerEvent::BlockingEnd { lane } => {
+                let mut lane_blocked = T::Duration::zero();
+                if let Some(l) = self.lanes.get_mut(*lane as usize) {
+                    if l.active {
+                        lane_blocked = self.current_time.saturating_duration_since(l.started);
+                        l.duration += lane_blocked;
+                        l.active = false;
+                    }
+                    l.owner = None;
+                }
+                // in aggregate, blocking ends once no lane is blocked
                 let mut blocked = T::Duration::zero();
-                if self.blocking_active {
+                if self.blocking_active && self.lanes.iter().all(|l| !l.active) {
                     blocked = self
                         .current_time
                         .saturating_duration_since(self.blocking_started);
@@ -339,19 +1420,26 @@ where
                 }
 
                 for mi in 0..self.runtime.len() {
+                    if self.runtime[mi].disabled {
+                        continue;
+                    }
                     // since block is global, every machine was blocked the
-                    // same duration
+                    // same duration, in aggregate and of the lane
                     if !blocked.is_zero() {
                         self.runtime[mi].blocking_duration += blocked;
                     }
+                    if !lane_blocked.is_zero() {
+                        self.runtime[mi].lane_blocking_duration[*lane as usize] += lane_blocked;
+                    }
                     self.transition(mi, Event::BlockingEnd);
                 }
             }
             TriggerEvent::TimerBegin { machine } => {
                 let mi = machine.into_raw();
-                if mi >= self.runtime.len() {
+                if mi >= self.runtime.len() || self.runtime[mi].disabled {
                     return;
                 }
+                self.decrement_shared_limit(mi);
                 if self.transition(mi, Event::TimerBegin) == StateChange::Unchanged
                     && self.runtime[mi].current_state != STATE_END
                 {
@@ -361,40 +1449,108 @@ where
             }
             TriggerEvent::TimerEnd { machine } => {
                 let mi = machine.into_raw();
-                if mi >= self.runtime.len() {
+                if mi >= self.runtime.len() || self.runtime[mi].disabled {
                     return;
                 }
+                self.runtime[mi].last_action = None;
                 self.transition(mi, Event::TimerEnd);
             }
-        };
-    }
-
-    fn transition(&mut self, mi: usize, event: Event) -> StateChange {
-        // a machine in end state cannot transition
-        if self.runtime[mi].current_state == STATE_END {
-            return StateChange::Unchanged;
-        }
-
+            TriggerEvent::IncomingBlockingBegin { machine } => {
+                // separate from blocking outgoing traffic, and not an event
+                // machines transition on
+                if !self.incoming_blocking_active {
+                    self.incoming_blocking_active = true;
+                    self.incoming_blocking_started = self.current_time;
+                }
+                let mi = machine.into_raw();
+                if mi >= self.runtime.len() || self.runtime[mi].disabled {
+                    return;
+                }
+                self.runtime[mi].last_action = None;
+                self.runtime[mi].pending_action = None;
+                self.decrement_shared_limit(mi);
+                if self.runtime[mi].current_state != STATE_END {
+                    self.decrement_limit(mi);
+                }
+            }
+            TriggerEvent::IncomingBlockingEnd => {
+                if !self.incoming_blocking_active {
+                    return;
+                }
+                let blocked = self
+                    .current_time
+                    .saturating_duration_since(self.incoming_blocking_started);
+                self.incoming_blocking_duration += blocked;
+                self.incoming_blocking_active = false;
+                for runtime in self.runtime.iter_mut().filter(|r| !r.disabled) {
+                    runtime.incoming_blocking_duration += blocked;
+                }
+            }
+        };
+    }
+
+    fn transition(&mut self, mi: usize, event: Event) -> StateChange {
+        // a machine in end state, or disabled, cannot transition
+        if self.runtime[mi].current_state == STATE_END || self.runtime[mi].disabled {
+            return StateChange::Unchanged;
+        }
+
+        // the time since the previous occurrence of the event, if tracked
+        let gap = self.runtime[mi]
+            .last_event
+            .get_mut(event.to_usize())
+            .and_then(|last| last.replace(self.current_time))
+            .map(|last| {
+                self.current_time
+                    .saturating_duration_since(last)
+                    .div_duration_f64(T::Duration::from_micros(1)) as u64
+            });
+
         // sample next state
         // new block for immutable ref, makes things less ugly
         let next_state = {
             let machine = &self.machines.as_ref()[mi];
             let state = &machine.states[self.runtime[mi].current_state];
-            state.sample_state(event, &mut self.rng)
+            match state.gap_condition {
+                Some(condition) if condition.event == event && !condition.holds(gap) => None,
+                _ => state.sample_state(event, self.rng.select(mi)),
+            }
         };
 
         // if no next state on event, done
         let Some(next_state) = next_state else {
             return StateChange::Unchanged;
         };
+        // resolve the pseudo-states that are states of the machine
+        let next_state = match next_state {
+            STATE_PREVIOUS => self.runtime[mi].previous_state,
+            STATE_RANDOM => {
+                use rand::Rng;
+                // sampled as u32 to be the same on all platforms
+                let n = self.machines.as_ref()[mi].states.len() as u32;
+                self.rng.select(mi).gen_range(0..n) as usize
+            }
+            s => s,
+        };
+        #[cfg(feature = "metrics")]
+        {
+            self.metrics.transitions += 1;
+        }
 
         // we got a next state, act on it
         match next_state {
             STATE_END => {
                 // this is a state change (because we can never reach here if already in
-                // STATE_END, see first check above), but we don't cancel any pending
-                // action, nor schedule any new action
+                // STATE_END, see first check above), but we don't schedule any new
+                // action, and only cancel pending actions if the machine says so
                 self.runtime[mi].current_state = STATE_END;
+                self.runtime[mi].burst_remaining = 0;
+                if self.machines.as_ref()[mi].on_end == OnEnd::CancelPending {
+                    self.actions[mi] = Some(TriggerAction::Cancel {
+                        machine: MachineId(mi),
+                        timer: Timer::All,
+                    });
+                }
                 StateChange::Changed
             }
             STATE_SIGNAL => {
@@ -410,17 +1566,27 @@ where
             }
             _ => {
                 let curr_state = self.runtime[mi].current_state;
+                // any transition ends a burst, see continue_burst()
+                self.runtime[mi].burst_remaining = 0;
 
                 // transition to same or different state?
                 if curr_state != next_state {
+                    self.runtime[mi].previous_state = curr_state;
                     self.runtime[mi].current_state = next_state;
-                    self.runtime[mi].state_limit = if let Some(action) =
-                        self.machines.as_ref()[mi].states[next_state].action
-                    {
-                        action.sample_limit(&mut self.rng)
-                    } else {
-                        STATE_LIMIT_MAX
-                    };
+                    (
+                        self.runtime[mi].state_limit,
+                        self.runtime[mi].state_limit_saturated,
+                    ) = self.machines.as_ref()[mi].states[next_state]
+                        .sample_limit(self.rng.select(mi));
+                } else if self.machines.as_ref()[mi].states[curr_state].refresh_limit_on
+                    == Some(event)
+                {
+                    // refreshed before scheduling, see State::refresh_limit_on
+                    (
+                        self.runtime[mi].state_limit,
+                        self.runtime[mi].state_limit_saturated,
+                    ) = self.machines.as_ref()[mi].states[curr_state]
+                        .sample_limit(self.rng.select(mi));
                 }
 
                 // update the counter, possible recursion: we need to update the
@@ -430,13 +1596,19 @@ where
                 // finally, two chained transitions in and out of a state should
                 // count as a changed state, so we need to keep track of it to
                 // not prematurely decrement any limit.
+                let has_action = self.machines.as_ref()[mi].states[next_state]
+                    .action
+                    .is_some();
                 let below_limits =
                     self.below_action_limits(&self.runtime[mi], &self.machines.as_ref()[mi]);
                 let (allow_schedule, state_changed) = self.update_counter(mi);
 
                 // schedule an action if allowed by counter update and below all limits
-                if allow_schedule && below_limits {
-                    self.schedule_action(mi, next_state);
+                if allow_schedule && has_action {
+                    match below_limits {
+                        Ok(()) => self.schedule_action(mi, next_state),
+                        Err(reason) => self.runtime[mi].last_suppression = Some(reason),
+                    }
                 }
 
                 if curr_state == self.runtime[mi].current_state && !state_changed {
@@ -460,7 +1632,7 @@ where
             let change = if counter_a.copy {
                 old_value_b
             } else {
-                counter_a.sample_value(&mut self.rng)
+                counter_a.sample_value(self.rng.select(mi))
             };
 
             let updated_value_a = &mut self.runtime[mi].counter_a;
@@ -486,7 +1658,7 @@ where
             let change = if counter_b.copy {
                 old_value_a
             } else {
-                counter_b.sample_value(&mut self.rng)
+                counter_b.sample_value(self.rng.select(mi))
             };
 
             let updated_value_b = &mut self.runtime[mi].counter_b;
@@ -523,6 +1695,8 @@ where
     fn schedule_action(&mut self, mi: usize, state: usize) {
         let index = MachineId(mi);
         let action = self.machines.as_ref()[mi].states[state].action;
+        let granularity = self.timer_granularity;
+        let to_duration = |micros| T::Duration::from_micros(round_up(micros, granularity));
 
         self.actions[mi] = match action {
             Some(action) => match action {
@@ -533,95 +1707,262 @@ where
                 Action::SendPadding {
                     bypass, replace, ..
                 } => Some(TriggerAction::SendPadding {
-                    timeout: T::Duration::from_micros(action.sample_timeout(&mut self.rng)),
+                    timeout: to_duration(action.sample_timeout(self.rng.select(mi))),
                     bypass,
                     replace,
                     machine: index,
                 }),
                 Action::BlockOutgoing {
-                    bypass, replace, ..
+                    bypass,
+                    replace,
+                    lane,
+                    ..
                 } => Some(TriggerAction::BlockOutgoing {
-                    timeout: T::Duration::from_micros(action.sample_timeout(&mut self.rng)),
-                    duration: T::Duration::from_micros(action.sample_duration(&mut self.rng)),
+                    timeout: to_duration(action.sample_timeout(self.rng.select(mi))),
+                    duration: to_duration(action.sample_duration(self.rng.select(mi))),
                     bypass,
                     replace,
+                    lane,
                     machine: index,
                 }),
                 Action::UpdateTimer { replace, .. } => Some(TriggerAction::UpdateTimer {
-                    duration: T::Duration::from_micros(action.sample_duration(&mut self.rng)),
+                    duration: to_duration(action.sample_duration(self.rng.select(mi))),
+                    replace,
+                    machine: index,
+                }),
+                Action::BlockIncoming { replace, .. } => Some(TriggerAction::BlockIncoming {
+                    timeout: to_duration(action.sample_timeout(self.rng.select(mi))),
+                    duration: to_duration(action.sample_duration(self.rng.select(mi))),
                     replace,
                     machine: index,
                 }),
+                Action::HoldOutgoing { .. } => Some(TriggerAction::HoldOutgoing {
+                    timeout: to_duration(action.sample_timeout(self.rng.select(mi))),
+                    duration: to_duration(action.sample_duration(self.rng.select(mi))),
+                    probability: action.sample_probability(self.rng.select(mi)),
+                    machine: index,
+                }),
+                Action::SendPaddingBurst {
+                    bypass,
+                    replace,
+                    count,
+                    ..
+                } => {
+                    self.runtime[mi].burst_remaining = count;
+                    Some(TriggerAction::SendPadding {
+                        timeout: to_duration(action.sample_timeout(self.rng.select(mi))),
+                        bypass,
+                        replace,
+                        machine: index,
+                    })
+                }
+                Action::UpdateTimerAligned { replace, .. } => {
+                    let period = action.sample_duration(self.rng.select(mi));
+                    let elapsed = self
+                        .current_time
+                        .saturating_duration_since(self.runtime[mi].machine_start)
+                        .div_duration_f64(T::Duration::from_micros(1))
+                        as u64;
+                    Some(TriggerAction::UpdateTimer {
+                        duration: to_duration(period - elapsed % period),
+                        replace,
+                        machine: index,
+                    })
+                }
             },
             None => None,
         };
     }
 
+    fn continue_burst(&mut self, mi: usize, state: usize, remaining: u32) {
+        // Any transition on the sent packet resets the burst: to zero, or to
+        // the count of a new burst in the same state, which is larger than
+        // what remained. Another state may have a new burst with a count
+        // equal to what remained, so the state has to be the same too. A
+        // machine may also end (e.g., on a zeroed counter) after scheduling a
+        // burst, while still in the same transition.
+        let runtime = &self.runtime[mi];
+        if remaining == 0
+            || state == STATE_END
+            || runtime.current_state != state
+            || runtime.burst_remaining != remaining
+        {
+            return;
+        }
+        if let Err(reason) = self.below_action_limits(runtime, &self.machines.as_ref()[mi]) {
+            self.runtime[mi].burst_remaining = 0;
+            self.runtime[mi].last_suppression = Some(reason);
+            return;
+        }
+
+        let action = self.machines.as_ref()[mi].states[state].action;
+        if let Some(
+            action @ Action::SendPaddingBurst {
+                bypass, replace, ..
+            },
+        ) = action
+        {
+            let interval = action.sample_interval(self.rng.select(mi));
+            self.actions[mi] = Some(TriggerAction::SendPadding {
+                timeout: T::Duration::from_micros(round_up(interval, self.timer_granularity)),
+                bypass,
+                replace,
+                machine: MachineId(mi),
+            });
+        }
+    }
+
     fn decrement_limit(&mut self, mi: usize) {
         if self.runtime[mi].state_limit > 0 {
             self.runtime[mi].state_limit -= 1;
         }
         let cs = self.runtime[mi].current_state;
 
-        if let Some(action) = self.machines.as_ref()[mi].states[cs].action {
-            if self.runtime[mi].state_limit == 0 && action.has_limit() {
-                // take no action and trigger limit reached
-                self.actions[mi] = None;
-                // next, we trigger internally event LimitReached
-                self.transition(mi, Event::LimitReached);
-            }
+        if self.runtime[mi].state_limit == 0 && self.machines.as_ref()[mi].states[cs].has_limit() {
+            // take no action and trigger limit reached
+            self.actions[mi] = None;
+            // next, we trigger internally event LimitReached
+            self.transition(mi, Event::LimitReached);
         }
     }
 
-    fn below_action_limits(&self, runtime: &MachineRuntime<T>, machine: &Machine) -> bool {
+    fn decrement_shared_limit(&mut self, mi: usize) {
+        // the action was taken by the state the machine is in as the event
+        // is triggered, before transitioning
+        let cs = self.runtime[mi].current_state;
+        if cs == STATE_END {
+            return;
+        }
+
+        if let Some(index) = self.machines.as_ref()[mi].states[cs].shared_limit {
+            let limit = &mut self.runtime[mi].shared_limits[index];
+            *limit = limit.saturating_sub(1);
+        }
+    }
+
+    // checks the limits of the action of the current state, if any, returning
+    // the reason it should be suppressed
+    fn below_action_limits(
+        &self,
+        runtime: &MachineRuntime<T>,
+        machine: &Machine,
+    ) -> Result<(), SuppressReason> {
         let current = &machine.states[runtime.current_state];
 
         let Some(action) = current.action else {
-            return false;
+            return Ok(());
         };
 
+        // a shared limit applies regardless of action type
+        if let Some(index) = current.shared_limit {
+            if runtime.shared_limits[index] == 0 {
+                return Err(SuppressReason::SharedLimit);
+            }
+        }
+
+        // as does the condition on blocking status
+        if let Some(blocked) = current.blocking_condition {
+            let active = match action {
+                Action::BlockOutgoing { lane, .. } => {
+                    self.lanes.get(lane as usize).is_some_and(|l| l.active)
+                }
+                _ => self.blocking_active,
+            };
+            if blocked != active {
+                return Err(SuppressReason::BlockingCondition);
+            }
+        }
+
+        // and the condition on a counter, before it is updated
+        if let Some(condition) = current.counter_condition {
+            let value = match condition.counter {
+                0 => runtime.counter_a,
+                _ => runtime.counter_b,
+            };
+            if !condition.holds(value) {
+                return Err(SuppressReason::CounterCondition);
+            }
+        }
+
         match action {
-            Action::BlockOutgoing { .. } => self.below_limit_blocking(runtime, machine),
-            Action::SendPadding { .. } => self.below_limit_padding(runtime, machine),
-            Action::UpdateTimer { .. } => runtime.state_limit > 0,
-            _ => true,
+            Action::BlockOutgoing { .. } | Action::HoldOutgoing { .. } => {
+                self.below_limit_blocking(runtime, machine, false)
+            }
+            Action::BlockIncoming { .. } => self.below_limit_blocking(runtime, machine, true),
+            Action::SendPadding { .. } | Action::SendPaddingBurst { .. } => {
+                self.below_limit_padding(runtime, machine)
+            }
+            Action::UpdateTimer { .. } | Action::UpdateTimerAligned { .. } => {
+                below_state_limit(runtime)
+            }
+            _ => Ok(()),
         }
     }
 
-    fn below_limit_blocking(&self, runtime: &MachineRuntime<T>, machine: &Machine) -> bool {
+    fn below_limit_blocking(
+        &self,
+        runtime: &MachineRuntime<T>,
+        machine: &Machine,
+        incoming: bool,
+    ) -> Result<(), SuppressReason> {
         let current = &machine.states[runtime.current_state];
-        // blocking action
-
-        // special case: we always allow overwriting existing blocking
-        let replace = if let Some(Action::BlockOutgoing { replace, .. }) = current.action {
-            replace
+        // blocking action, of outgoing or incoming traffic: each direction is
+        // accounted for separately, and blocking of outgoing traffic per lane
+        // (holding outgoing traffic has no lane, so is checked in aggregate)
+        let lane = match current.action {
+            Some(Action::BlockOutgoing { lane, .. }) => {
+                self.lanes.get(lane as usize).map(|l| (lane as usize, l))
+            }
+            _ => None,
+        };
+        let (mut m_block_dur, mut g_block_dur, active, started) = if incoming {
+            (
+                runtime.incoming_blocking_duration,
+                self.incoming_blocking_duration,
+                self.incoming_blocking_active,
+                self.incoming_blocking_started,
+            )
+        } else if let Some((i, l)) = lane {
+            (
+                runtime.lane_blocking_duration[i],
+                l.duration,
+                l.active,
+                l.started,
+            )
         } else {
-            false
+            (
+                runtime.blocking_duration,
+                self.blocking_duration,
+                self.blocking_active,
+                self.blocking_started,
+            )
+        };
+
+        // special case: we always allow overwriting existing blocking, of the
+        // same lane for outgoing traffic
+        let replace = match current.action {
+            Some(Action::BlockOutgoing { replace, .. }) => replace && lane.is_some(),
+            Some(Action::BlockIncoming { replace, .. }) => replace,
+            _ => false,
         };
 
-        if replace && self.blocking_active {
+        if replace && active {
             // we still check against state limit, because it's machine internal
-            return runtime.state_limit > 0;
+            return below_state_limit(runtime);
         }
 
         // compute durations we've been blocking
-        let mut m_block_dur = runtime.blocking_duration;
-        let mut g_block_dur = self.blocking_duration;
-        if self.blocking_active {
+        if active {
             // account for ongoing blocking as well, add duration
-            m_block_dur += self
-                .current_time
-                .saturating_duration_since(self.blocking_started);
-            g_block_dur += self
-                .current_time
-                .saturating_duration_since(self.blocking_started);
+            m_block_dur += self.current_time.saturating_duration_since(started);
+            g_block_dur += self.current_time.saturating_duration_since(started);
         }
 
         // machine allowed blocking duration first, since it bypasses the
         // other two types of limits
         if m_block_dur < runtime.allowed_blocked_microsec {
             // we still check against state limit, because it's machine internal
-            return runtime.state_limit > 0;
+            return below_state_limit(runtime);
         }
 
         // does the machine limit say no, if set?
@@ -631,39 +1972,50 @@ where
                     .saturating_duration_since(runtime.machine_start),
             );
             if f >= machine.max_blocking_frac {
-                return false;
+                return Err(SuppressReason::MachineBlockingLimit);
             }
         }
 
         // does the framework say no?
         if self.max_blocking_frac > 0.0 {
-            let f: f64 = g_block_dur.div_duration_f64(
-                self.current_time
-                    .saturating_duration_since(self.framework_start),
-            );
+            let f: f64 = g_block_dur.div_duration_f64(self.uptime());
             if f >= self.max_blocking_frac {
-                return false;
+                return Err(SuppressReason::FrameworkBlockingLimit);
             }
         }
 
         // only state-limit left to consider
-        runtime.state_limit > 0
+        below_state_limit(runtime)
     }
 
-    fn below_limit_padding(&self, runtime: &MachineRuntime<T>, machine: &Machine) -> bool {
+    fn below_limit_padding(
+        &self,
+        runtime: &MachineRuntime<T>,
+        machine: &Machine,
+    ) -> Result<(), SuppressReason> {
         // no limits apply if not made up padding count
         if runtime.padding_sent < machine.allowed_padding_packets {
-            return runtime.state_limit > 0;
+            return below_state_limit(runtime);
+        }
+
+        // hit global limit of received padding?
+        if self.max_padding_recv_frac > 0.0 {
+            let total = self.padding_recv_packets + self.normal_recv_packets;
+            if total > 0
+                && self.padding_recv_packets as f64 / total as f64 >= self.max_padding_recv_frac
+            {
+                return Err(SuppressReason::FrameworkPaddingRecvLimit);
+            }
         }
 
         // hit machine limits?
         if machine.max_padding_frac > 0.0 {
             let total = runtime.normal_sent + runtime.padding_sent;
             if total == 0 {
-                return true;
+                return Ok(());
             }
             if runtime.padding_sent as f64 / total as f64 >= machine.max_padding_frac {
-                return false;
+                return Err(SuppressReason::MachinePaddingLimit);
             }
         }
 
@@ -671,20 +2023,155 @@ where
         if self.max_padding_frac > 0.0 {
             let total = self.padding_sent_packets + self.normal_sent_packets;
             if total == 0 {
-                return true;
+                return Ok(());
             }
             if self.padding_sent_packets as f64 / total as f64 >= self.max_padding_frac {
-                return false;
+                return Err(SuppressReason::FrameworkPaddingLimit);
             }
         }
 
         // only state-limit left to consider
-        runtime.state_limit > 0
+        below_state_limit(runtime)
+    }
+}
+
+// rounds up to the nearest multiple of granularity, saturating
+fn round_up(micros: u64, granularity: u64) -> u64 {
+    micros.div_ceil(granularity).saturating_mul(granularity)
+}
+
+fn below_state_limit<T: crate::time::Instant>(
+    runtime: &MachineRuntime<T>,
+) -> Result<(), SuppressReason> {
+    if runtime.state_limit > 0 {
+        Ok(())
+    } else {
+        Err(SuppressReason::StateLimit)
+    }
+}
+
+impl<M, R, T> Framework<M, R, T>
+where
+    M: AsRef<[Machine]>,
+    R: RngCore + Clone,
+    T: crate::time::Instant,
+{
+    /// Take a snapshot of the runtime state of the framework, including the
+    /// state of its random number generator, as of the `current_time` of the
+    /// last call to [`Self::trigger_events()`] (or creation of the framework).
+    pub fn snapshot(&self) -> FrameworkSnapshot<R, T> {
+        let before = |t: T| Ok::<_, Infallible>(self.current_time.saturating_duration_since(t));
+        FrameworkSnapshot {
+            rngs: self.rng.rngs.clone(),
+            runtime: self
+                .runtime
+                .iter()
+                .map(|r| r.clone().map_times(before).unwrap_or_else(|e| match e {}))
+                .collect(),
+            normal_sent_packets: self.normal_sent_packets,
+            padding_sent_packets: self.padding_sent_packets,
+            normal_recv_packets: self.normal_recv_packets,
+            padding_recv_packets: self.padding_recv_packets,
+            blocking_duration: self.blocking_duration,
+            blocking_started: self
+                .current_time
+                .saturating_duration_since(self.blocking_started),
+            blocking_active: self.blocking_active,
+            lanes: self
+                .lanes
+                .map(|l| l.map_times(before).unwrap_or_else(|e| match e {})),
+            incoming_blocking_duration: self.incoming_blocking_duration,
+            incoming_blocking_started: self
+                .current_time
+                .saturating_duration_since(self.incoming_blocking_started),
+            incoming_blocking_active: self.incoming_blocking_active,
+            signal_pending: self.signal_pending.clone(),
+            counter_zeroed_once: self.counter_zeroed_once,
+            framework_start: self
+                .current_time
+                .saturating_duration_since(self.framework_start),
+        }
+    }
+
+    /// Restore the runtime state of the framework from a snapshot taken with
+    /// [`Self::snapshot()`], as if the snapshot was taken at `current_time`.
+    /// The framework will continue as it would have after the snapshot was
+    /// taken, including the random stream.
+    ///
+    /// Returns an error if the snapshot was taken from a framework with a
+    /// different number of machines, shared limits, or random number
+    /// generators, or if a time of the snapshot is before the earliest
+    /// instant that can be represented relative to `current_time`.
+    pub fn restore(
+        &mut self,
+        snapshot: FrameworkSnapshot<R, T>,
+        current_time: T,
+    ) -> Result<(), Error> {
+        if snapshot.runtime.len() != self.runtime.len() {
+            Err(Error::Machine(
+                "snapshot has a different number of machines".to_string(),
+            ))?;
+        }
+        if snapshot.rngs.len() != self.rng.rngs.len() {
+            Err(Error::Machine(
+                "snapshot has a different number of random number generators".to_string(),
+            ))?;
+        }
+        for (runtime, machine) in snapshot.runtime.iter().zip(self.machines.as_ref()) {
+            if runtime.shared_limits.len() != machine.shared_limits.len()
+                || runtime.current_state >= machine.states.len()
+                    && runtime.current_state != STATE_END
+                || runtime.previous_state >= machine.states.len()
+            {
+                Err(Error::Machine(
+                    "snapshot does not match the running machines".to_string(),
+                ))?;
+            }
+        }
+
+        let at = |before: T::Duration| {
+            current_time
+                .checked_sub(before)
+                .ok_or_else(|| Error::Machine("snapshot time out of range".to_string()))
+        };
+        let runtime = snapshot
+            .runtime
+            .into_iter()
+            .map(|r| r.map_times(at))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut lanes = [LaneBlocking::new(current_time); BLOCKING_LANES];
+        for (lane, l) in lanes.iter_mut().zip(snapshot.lanes) {
+            *lane = l.map_times(at)?;
+        }
+        let blocking_started = at(snapshot.blocking_started)?;
+        let incoming_blocking_started = at(snapshot.incoming_blocking_started)?;
+        let framework_start = at(snapshot.framework_start)?;
+
+        self.current_time = current_time;
+        self.rng.rngs = snapshot.rngs;
+        self.runtime = runtime;
+        self.normal_sent_packets = snapshot.normal_sent_packets;
+        self.padding_sent_packets = snapshot.padding_sent_packets;
+        self.normal_recv_packets = snapshot.normal_recv_packets;
+        self.padding_recv_packets = snapshot.padding_recv_packets;
+        self.blocking_duration = snapshot.blocking_duration;
+        self.blocking_started = blocking_started;
+        self.blocking_active = snapshot.blocking_active;
+        self.lanes = lanes;
+        self.incoming_blocking_duration = snapshot.incoming_blocking_duration;
+        self.incoming_blocking_started = incoming_blocking_started;
+        self.incoming_blocking_active = snapshot.incoming_blocking_active;
+        self.signal_pending = snapshot.signal_pending;
+        self.counter_zeroed_once = snapshot.counter_zeroed_once;
+        self.framework_start = framework_start;
+        self.actions.fill(None);
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::constants::STATE_LIMIT_MAX;
     use crate::counter::Counter;
     use crate::dist::*;
     use crate::framework::*;
@@ -701,6 +2188,14 @@ mod tests {
         assert!(f.is_ok());
     }
 
+    #[test]
+    fn framework_limits() {
+        let machines = vec![];
+        let f = Framework::new(&machines, 0.5, 0.25, Instant::now(), rand::thread_rng()).unwrap();
+        assert_eq!(f.max_padding_frac(), 0.5);
+        assert_eq!(f.max_blocking_frac(), 0.25);
+    }
+
     #[test]
     fn reuse_machines() {
         let machines = vec![];
@@ -716,7 +2211,8 @@ mod tests {
         _ => vec![],
         });
         let m = Machine::new(0, 0.0, 0, 0.0, vec![s0]).unwrap();
-        assert_eq!(m.serialize(), "02eNpjYEAHjOgCAAA0AAI=");
+        assert_eq!(m.serialize().unwrap(), "03eNptULtuwzAM/BfORtHZv9Cle1EIjETHRPVwKRoJYPjfQ9uJAzhZJPF4d+RpAoyxXCi4AUPgfLbb/5FWaD8bSHjd8U7QG/hh8ENyisW4wSX2Uir5XbM2DqKqqGS2PxOgVy4Z2jzG2IAvY1YS66z1cvwavUcx68iJ9cHcbX3Jgd94vDbOOLyCnQU4Wcy95utxlFAnVPsNdE+pCua6mlmUaZ4Pmy75DCrZUQ7QwhfR8G1PWxoaGFAw3SlC/yMLbf+snMhpv0wsMWyz5huVhpSI");
+        assert_eq!(Machine::noop().name(), m.name());
     }
 
     #[test]
@@ -739,6 +2235,7 @@ mod tests {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             },
             limit: None,
         });
@@ -759,6 +2256,7 @@ mod tests {
 
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             },
             limit: None,
         });
@@ -776,6 +2274,7 @@ mod tests {
         _ = f.trigger_events(
             &[TriggerEvent::BlockingBegin {
                 machine: MachineId(0),
+                lane: 0,
             }],
             current_time,
         );
@@ -786,6 +2285,7 @@ mod tests {
         _ = f.trigger_events(
             &[TriggerEvent::BlockingBegin {
                 machine: MachineId(0),
+                lane: 0,
             }],
             current_time,
         );
@@ -912,6 +2412,7 @@ mod tests {
         s0.action = Some(Action::BlockOutgoing {
             bypass: false,
             replace: false,
+            lane: 0,
             timeout: Dist {
                 dist: DistType::Uniform {
                     low: 1.0,
@@ -919,6 +2420,7 @@ mod tests {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             },
             duration: Dist {
                 dist: DistType::Uniform {
@@ -927,6 +2429,7 @@ mod tests {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             },
             limit: None,
         });
@@ -946,6 +2449,7 @@ mod tests {
                 duration: Duration::from_micros(10),
                 bypass: false,
                 replace: false,
+                lane: 0,
                 machine: MachineId(0),
             })
         );
@@ -954,6 +2458,7 @@ mod tests {
         _ = f.trigger_events(
             &[TriggerEvent::BlockingBegin {
                 machine: MachineId(0),
+                lane: 0,
             }],
             current_time,
         );
@@ -969,6 +2474,7 @@ mod tests {
                     duration: Duration::from_micros(10),
                     bypass: false,
                     replace: false,
+                    lane: 0,
                     machine: MachineId(0),
                 })
             );
@@ -995,6 +2501,7 @@ mod tests {
 
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             },
             limit: None,
         });
@@ -1013,6 +2520,7 @@ mod tests {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             },
             limit: None,
         });
@@ -1093,6 +2601,7 @@ mod tests {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             },
             limit: None,
         });
@@ -1107,6 +2616,7 @@ mod tests {
                     },
                     start: 0.0,
                     max: 0.0,
+                    ..Default::default()
                 },
             )),
         );
@@ -1166,6 +2676,7 @@ mod tests {
                     },
                     start: 0.0,
                     max: 0.0,
+                    ..Default::default()
                 },
             )),
         );
@@ -1188,6 +2699,7 @@ mod tests {
                     },
                     start: 0.0,
                     max: 0.0,
+                    ..Default::default()
                 },
             )),
         );
@@ -1208,6 +2720,7 @@ mod tests {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             },
             limit: None,
         });
@@ -1252,6 +2765,7 @@ mod tests {
                     },
                     start: 0.0,
                     max: 0.0,
+                    ..Default::default()
                 },
             )),
             None,
@@ -1273,6 +2787,7 @@ mod tests {
                     },
                     start: 0.0,
                     max: 0.0,
+                    ..Default::default()
                 },
             )),
             None,
@@ -1315,6 +2830,7 @@ mod tests {
                     },
                     start: 0.0,
                     max: 0.0,
+                    ..Default::default()
                 },
             )),
             Some(Counter::new_dist(
@@ -1326,6 +2842,7 @@ mod tests {
                     },
                     start: 0.0,
                     max: 0.0,
+                    ..Default::default()
                 },
             )),
         );
@@ -1346,6 +2863,7 @@ mod tests {
                     },
                     start: 0.0,
                     max: 0.0,
+                    ..Default::default()
                 },
             )),
             Some(Counter::new_dist(
@@ -1357,6 +2875,7 @@ mod tests {
                     },
                     start: 0.0,
                     max: 0.0,
+                    ..Default::default()
                 },
             )),
         );
@@ -1418,6 +2937,7 @@ mod tests {
                     },
                     start: 0.0,
                     max: 0.0,
+                    ..Default::default()
                 },
             )),
             Some(Counter::new_dist(
@@ -1429,6 +2949,7 @@ mod tests {
                     },
                     start: 0.0,
                     max: 0.0,
+                    ..Default::default()
                 },
             )),
         );
@@ -1449,6 +2970,7 @@ mod tests {
                     },
                     start: 0.0,
                     max: 0.0,
+                    ..Default::default()
                 },
             )),
         );
@@ -1467,6 +2989,7 @@ mod tests {
                     },
                     start: 0.0,
                     max: 0.0,
+                    ..Default::default()
                 },
             )),
             None,
@@ -1528,6 +3051,7 @@ mod tests {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             },
             limit: None,
         });
@@ -1551,6 +3075,7 @@ mod tests {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             },
             limit: None,
         });
@@ -1573,6 +3098,7 @@ mod tests {
                     },
                     start: 0.0,
                     max: 0.0,
+                    ..Default::default()
                 },
             )),
             None,
@@ -1630,6 +3156,7 @@ mod tests {
                     },
                     start: 0.0,
                     max: 0.0,
+                    ..Default::default()
                 },
             )),
             Some(Counter::new_dist(
@@ -1641,6 +3168,7 @@ mod tests {
                     },
                     start: 0.0,
                     max: 0.0,
+                    ..Default::default()
                 },
             )),
         );
@@ -1724,6 +3252,7 @@ mod tests {
                     },
                     start: 0.0,
                     max: 0.0,
+                    ..Default::default()
                 },
             )),
             Some(Counter::new(Operation::Set)),
@@ -1746,6 +3275,7 @@ mod tests {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             },
             limit: Some(Dist {
                 dist: DistType::Uniform {
@@ -1754,6 +3284,7 @@ mod tests {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             }),
         });
 
@@ -1852,6 +3383,7 @@ mod tests {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             },
             limit: None,
         });
@@ -1913,6 +3445,7 @@ mod tests {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             },
             limit: None,
         });
@@ -1963,6 +3496,7 @@ mod tests {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             },
             limit: None,
         });
@@ -2025,6 +3559,7 @@ mod tests {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             },
             limit: None,
         });
@@ -2075,6 +3610,7 @@ mod tests {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             },
             limit: None,
         });
@@ -2159,6 +3695,7 @@ mod tests {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             },
             limit: None,
         });
@@ -2274,6 +3811,7 @@ mod tests {
         s0.action = Some(Action::BlockOutgoing {
             bypass: false,
             replace: false,
+            lane: 0,
             timeout: Dist {
                 dist: DistType::Uniform {
                     low: 2.0,
@@ -2281,6 +3819,7 @@ mod tests {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             },
             duration: Dist {
                 dist: DistType::Uniform {
@@ -2289,6 +3828,7 @@ mod tests {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             },
             limit: None,
         });
@@ -2312,6 +3852,7 @@ mod tests {
                     duration: Duration::from_micros(2),
                     bypass: false,
                     replace: false,
+                    lane: 0,
                     machine: MachineId(0),
                 })
             );
@@ -2319,6 +3860,7 @@ mod tests {
             _ = f.trigger_events(
                 &[TriggerEvent::BlockingBegin {
                     machine: MachineId(0),
+                    lane: 0,
                 }],
                 current_time,
             );
@@ -2329,11 +3871,12 @@ mod tests {
                     duration: Duration::from_micros(2),
                     bypass: false,
                     replace: false,
+                    lane: 0,
                     machine: MachineId(0),
                 })
             );
             current_time = current_time.add(Duration::from_micros(2));
-            _ = f.trigger_events(&[TriggerEvent::BlockingEnd], current_time);
+            _ = f.trigger_events(&[TriggerEvent::BlockingEnd { lane: 0 }], current_time);
         }
         assert_eq!(f.actions[0], None);
         assert_eq!(f.runtime[0].blocking_duration, Duration::from_micros(10));
@@ -2360,6 +3903,7 @@ mod tests {
                 duration: Duration::from_micros(2),
                 bypass: false,
                 replace: false,
+                lane: 0,
                 machine: MachineId(0),
             })
         );
@@ -2381,6 +3925,7 @@ mod tests {
         s0.action = Some(Action::BlockOutgoing {
             bypass: false,
             replace: false,
+            lane: 0,
             timeout: Dist {
                 dist: DistType::Uniform {
                     low: 2.0,
@@ -2389,6 +3934,7 @@ mod tests {
 
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             },
             duration: Dist {
                 dist: DistType::Uniform {
@@ -2398,6 +3944,7 @@ mod tests {
 
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             },
             limit: None,
         });
@@ -2421,6 +3968,7 @@ mod tests {
                     duration: Duration::from_micros(2),
                     bypass: false,
                     replace: false,
+                    lane: 0,
                     machine: MachineId(0),
                 })
             );
@@ -2428,6 +3976,7 @@ mod tests {
             _ = f.trigger_events(
                 &[TriggerEvent::BlockingBegin {
                     machine: MachineId(0),
+                    lane: 0,
                 }],
                 current_time,
             );
@@ -2438,11 +3987,12 @@ mod tests {
                     duration: Duration::from_micros(2),
                     bypass: false,
                     replace: false,
+                    lane: 0,
                     machine: MachineId(0),
                 })
             );
             current_time = current_time.add(Duration::from_micros(2));
-            _ = f.trigger_events(&[TriggerEvent::BlockingEnd], current_time);
+            _ = f.trigger_events(&[TriggerEvent::BlockingEnd { lane: 0 }], current_time);
         }
         assert_eq!(f.actions[0], None);
         assert_eq!(f.runtime[0].blocking_duration, Duration::from_micros(10));
@@ -2469,6 +4019,7 @@ mod tests {
                 duration: Duration::from_micros(2),
                 bypass: false,
                 replace: false,
+                lane: 0,
                 machine: MachineId(0),
             })
         );
@@ -2490,6 +4041,7 @@ mod tests {
         s0.action = Some(Action::BlockOutgoing {
             bypass: false,
             replace: true, // NOTE
+            lane: 0,
             timeout: Dist {
                 dist: DistType::Uniform {
                     low: 2.0,
@@ -2497,6 +4049,7 @@ mod tests {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             },
             duration: Dist {
                 dist: DistType::Uniform {
@@ -2505,6 +4058,7 @@ mod tests {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             },
             limit: None,
         });
@@ -2521,6 +4075,7 @@ mod tests {
         s0.action = Some(Action::BlockOutgoing {
             bypass: false,
             replace: false,
+            lane: 0,
             timeout: Dist {
                 dist: DistType::Uniform {
                     low: 0.0,
@@ -2529,6 +4084,7 @@ mod tests {
 
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             },
             duration: Dist {
                 dist: DistType::Uniform {
@@ -2537,6 +4093,7 @@ mod tests {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             },
             limit: None,
         });
@@ -2559,6 +4116,7 @@ mod tests {
                 duration: Duration::from_micros(2),
                 bypass: false,
                 replace: true,
+                lane: 0,
                 machine: MachineId(0),
             })
         );
@@ -2566,12 +4124,13 @@ mod tests {
         _ = f.trigger_events(
             &[TriggerEvent::BlockingBegin {
                 machine: MachineId(0),
+                lane: 0,
             }],
             current_time,
         );
 
         current_time = current_time.add(Duration::from_micros(2));
-        _ = f.trigger_events(&[TriggerEvent::BlockingEnd], current_time);
+        _ = f.trigger_events(&[TriggerEvent::BlockingEnd { lane: 0 }], current_time);
 
         // ensure machine 0 can no longer block
         _ = f.trigger_events(&[TriggerEvent::NormalRecv], current_time);
@@ -2590,6 +4149,7 @@ mod tests {
                 duration: Duration::from_micros(1000),
                 bypass: false,
                 replace: false,
+                lane: 0,
                 machine: MachineId(1),
             })
         );
@@ -2597,6 +4157,7 @@ mod tests {
         _ = f.trigger_events(
             &[TriggerEvent::BlockingBegin {
                 machine: MachineId(1),
+                lane: 0,
             }],
             current_time,
         );
@@ -2611,6 +4172,7 @@ mod tests {
                 duration: Duration::from_micros(2),
                 bypass: false,
                 replace: true,
+                lane: 0,
                 machine: MachineId(0),
             })
         );
@@ -2643,6 +4205,7 @@ mod tests {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             },
             limit: Some(Dist {
                 dist: DistType::Uniform {
@@ -2651,6 +4214,7 @@ mod tests {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             }),
         });
 
@@ -2694,4 +4258,3664 @@ mod tests {
         assert_eq!(f.actions[0], None);
         assert_eq!(f.runtime[0].state_limit, 0);
     }
+
+    #[test]
+    fn framework_machine_shared_limit() {
+        // two states that take turns padding, sharing a single limit of 3
+        // padding packets
+
+        // state 0
+        let s0 = State::new(enum_map! {
+            Event::NormalSent => vec![Trans(1, 1.0)],
+        _ => vec![],
+        });
+
+        // state 1 and 2, identical padding actions
+        let action = Some(Action::SendPadding {
+            bypass: false,
+            replace: false,
+            timeout: Dist {
+                dist: DistType::Uniform {
+                    low: 1.0,
+                    high: 1.0,
+                },
+                start: 0.0,
+                max: 0.0,
+                ..Default::default()
+            },
+            limit: None,
+        });
+        let mut s1 = State::new(enum_map! {
+            Event::PaddingSent => vec![Trans(2, 1.0)],
+        _ => vec![],
+        });
+        s1.action = action;
+        let mut s2 = State::new(enum_map! {
+            Event::PaddingSent => vec![Trans(1, 1.0)],
+        _ => vec![],
+        });
+        s2.action = action;
+
+        // machine, with state 1 and 2 sharing a limit
+        let mut m = Machine::new(100000, 0.0, 0, 0.0, vec![s0, s1, s2]).unwrap();
+        m.states[1].shared_limit = Some(0);
+        m.states[2].shared_limit = Some(0);
+        m.shared_limits = vec![Dist {
+            dist: DistType::Uniform {
+                low: 3.0,
+                high: 3.0,
+            },
+            start: 0.0,
+            max: 0.0,
+            ..Default::default()
+        }];
+        m.validate().unwrap();
+
+        let mut current_time = Instant::now();
+        let machines = vec![m];
+        let mut f = Framework::new(&machines, 0.0, 0.0, current_time, rand::thread_rng()).unwrap();
+        assert_eq!(f.runtime[0].shared_limits, vec![3]);
+
+        // start padding in state 1
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+
+        // the limit is shared between state 1 and 2, regardless of the state
+        // changes in between
+        for remaining in (0..3).rev() {
+            assert_eq!(
+                f.actions[0],
+                Some(TriggerAction::SendPadding {
+                    timeout: Duration::from_micros(1),
+                    bypass: false,
+                    replace: false,
+                    machine: MachineId(0),
+                })
+            );
+            current_time = current_time.add(Duration::from_micros(1));
+            _ = f.trigger_events(
+                &[TriggerEvent::PaddingSent {
+                    machine: MachineId(0),
+                }],
+                current_time,
+            );
+            assert_eq!(f.runtime[0].shared_limits[0], remaining);
+        }
+
+        // shared limit exhausted, no more padding in either state
+        assert_eq!(f.actions[0], None);
+        assert_eq!(f.runtime[0].padding_sent, 3);
+    }
+
+    #[test]
+    fn framework_snapshot_restore() {
+        use rand_core::SeedableRng;
+        use rand_xoshiro::Xoshiro256StarStar;
+
+        // a machine that pads with random timeouts and random limits, going
+        // back and forth between two states to consume randomness
+        let mut s0 = State::new(enum_map! {
+            Event::NormalSent => vec![Trans(1, 1.0)],
+            _ => vec![],
+        });
+        s0.action = None;
+        let mut s1 = State::new(enum_map! {
+            Event::PaddingSent => vec![Trans(1, 0.5), Trans(0, 0.5)],
+            _ => vec![],
+        });
+        s1.action = Some(Action::SendPadding {
+            bypass: false,
+            replace: false,
+            timeout: Dist {
+                dist: DistType::Uniform {
+                    low: 0.0,
+                    high: 1000.0,
+                },
+                start: 0.0,
+                max: 0.0,
+                ..Default::default()
+            },
+            limit: None,
+        });
+        let m = Machine::new(u64::MAX, 0.0, 0, 0.0, vec![s0, s1]).unwrap();
+        let machines = vec![m];
+
+        let start = Instant::now();
+        let mut f = Framework::new(
+            &machines,
+            0.0,
+            0.0,
+            start,
+            Xoshiro256StarStar::seed_from_u64(0),
+        )
+        .unwrap();
+
+        let run = |f: &mut Framework<_, Xoshiro256StarStar, Instant>, from: u64, n: u64| {
+            let mut out = vec![];
+            for i in from..from + n {
+                let now = start.add(Duration::from_micros(i * 10));
+                let events = [
+                    TriggerEvent::NormalSent,
+                    TriggerEvent::PaddingSent {
+                        machine: MachineId(0),
+                    },
+                ];
+                out.extend(f.trigger_events(&events, now).cloned());
+            }
+            out
+        };
+
+        run(&mut f, 0, 10);
+        let snapshot = f.snapshot();
+        let first = run(&mut f, 10, 20);
+        f.restore(snapshot, start.add(Duration::from_micros(90)))
+            .unwrap();
+        let second = run(&mut f, 10, 20);
+        assert!(!first.is_empty());
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn framework_restore_mismatch() {
+        let s0 = State::new(enum_map! {
+            _ => vec![],
+        });
+        let m = Machine::new(0, 0.0, 0, 0.0, vec![s0]).unwrap();
+        let one = vec![m.clone()];
+        let two = vec![m.clone(), m];
+
+        let f1 = Framework::new(&one, 0.0, 0.0, Instant::now(), rand::thread_rng()).unwrap();
+        let mut f2 = Framework::new(&two, 0.0, 0.0, Instant::now(), rand::thread_rng()).unwrap();
+        assert!(f2.restore(f1.snapshot(), Instant::now()).is_err());
+
+        // the same machines, but with a generator per machine
+        let f3 =
+            Framework::with_machine_rngs(&two, 0.0, 0.0, Instant::now(), |_| rand::thread_rng())
+                .unwrap();
+        assert!(f2.restore(f3.snapshot(), Instant::now()).is_err());
+        assert!(f2.restore(f2.snapshot(), Instant::now()).is_ok());
+    }
+
+    #[test]
+    fn framework_snapshot_serialize() {
+        use rand_core::SeedableRng;
+        use rand_xoshiro::Xoshiro256StarStar;
+
+        // block lane 1 for a random duration, then pad with random timeouts
+        // and update the internal timer with random durations
+        let s0 = State::new(enum_map! {
+            Event::NormalSent => vec![Trans(1, 1.0)],
+            _ => vec![],
+        });
+        let mut s1 = State::new(enum_map! {
+            Event::BlockingBegin => vec![Trans(2, 1.0)],
+            _ => vec![],
+        });
+        s1.action = Some(Action::BlockOutgoing {
+            bypass: true,
+            replace: false,
+            lane: 1,
+            timeout: Dist::constant(0.0),
+            duration: Dist::new(
+                DistType::Uniform {
+                    low: 1000.0,
+                    high: 5000.0,
+                },
+                0.0,
+                0.0,
+            ),
+            limit: None,
+        });
+        let mut s2 = State::new(enum_map! {
+            Event::PaddingSent => vec![Trans(2, 0.5), Trans(3, 0.5)],
+            _ => vec![],
+        });
+        s2.action = Some(Action::SendPadding {
+            bypass: true,
+            replace: false,
+            timeout: Dist::new(
+                DistType::Uniform {
+                    low: 0.0,
+                    high: 1000.0,
+                },
+                0.0,
+                0.0,
+            ),
+            limit: None,
+        });
+        let mut s3 = State::new(enum_map! {
+            Event::TimerEnd => vec![Trans(0, 1.0)],
+            _ => vec![],
+        });
+        s3.action = Some(Action::UpdateTimer {
+            replace: false,
+            duration: Dist::new(
+                DistType::Uniform {
+                    low: 0.0,
+                    high: 1000.0,
+                },
+                0.0,
+                0.0,
+            ),
+            limit: None,
+        });
+        let m = Machine::new(u64::MAX, 0.0, u64::MAX, 0.0, vec![s0, s1, s2, s3]).unwrap();
+        let machines = vec![m];
+
+        let run = |f: &mut Framework<_, Xoshiro256StarStar, Instant>, at: Instant, n: u64| {
+            let mut out = vec![];
+            for i in 0..n {
+                let now = at.add(Duration::from_micros(i * 100));
+                let events = [
+                    TriggerEvent::NormalSent,
+                    TriggerEvent::BlockingBegin {
+                        machine: MachineId(0),
+                        lane: 1,
+                    },
+                    TriggerEvent::PaddingSent {
+                        machine: MachineId(0),
+                    },
+                    TriggerEvent::TimerEnd {
+                        machine: MachineId(0),
+                    },
+                ];
+                out.extend(f.trigger_events(&events, now).cloned());
+            }
+            out
+        };
+
+        let start = Instant::now();
+        let mut f = Framework::new(
+            &machines,
+            0.0,
+            0.0,
+            start,
+            Xoshiro256StarStar::seed_from_u64(0),
+        )
+        .unwrap();
+        run(&mut f, start, 5);
+        let taken = f.now();
+        assert_eq!(f.blocking_owner(1), Some(MachineId(0)));
+        assert!(f.has_pending_action(MachineId(0)));
+
+        // snapshot to bytes and back, and restore in a new framework a second
+        // later, as after a restart
+        let bytes = serde_json::to_vec(&f.snapshot()).unwrap();
+        let snapshot: FrameworkSnapshot<Xoshiro256StarStar, Instant> =
+            serde_json::from_slice(&bytes).unwrap();
+        let later = taken.add(Duration::from_secs(1));
+        let mut restored = Framework::new(
+            &machines,
+            0.0,
+            0.0,
+            later,
+            Xoshiro256StarStar::seed_from_u64(1),
+        )
+        .unwrap();
+        restored.restore(snapshot, later).unwrap();
+
+        // the lanes, timers and random stream continue as in the original
+        assert_eq!(restored.blocking_owner(1), Some(MachineId(0)));
+        assert_eq!(restored.blocking_bypassable(1), f.blocking_bypassable(1));
+        assert_eq!(
+            restored.lane_blocking_duration(1),
+            f.lane_blocking_duration(1)
+        );
+        assert!(restored.has_pending_action(MachineId(0)));
+        assert_eq!(restored.uptime(), f.uptime());
+        assert_eq!(
+            restored.machine_elapsed(MachineId(0)),
+            f.machine_elapsed(MachineId(0))
+        );
+        let first = run(&mut f, taken.add(Duration::from_micros(100)), 20);
+        let second = run(&mut restored, later.add(Duration::from_micros(100)), 20);
+        assert!(!first.is_empty());
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn framework_machine_rngs() {
+        use rand_chacha::ChaCha8Rng;
+        use rand_core::SeedableRng;
+
+        // pads with random timeouts, moving between two states at random
+        let mut s0 = State::new(enum_map! {
+            Event::NormalSent => vec![Trans(1, 0.5), Trans(0, 0.5)],
+            _ => vec![],
+        });
+        s0.action = Some(Action::SendPadding {
+            bypass: false,
+            replace: false,
+            timeout: Dist {
+                dist: DistType::Uniform {
+                    low: 0.0,
+                    high: 1000.0,
+                },
+                start: 0.0,
+                max: 0.0,
+                ..Default::default()
+            },
+            limit: None,
+        });
+        let mut s1 = State::new(enum_map! {
+            Event::NormalSent => vec![Trans(0, 0.5), Trans(STATE_RANDOM, 0.5)],
+            _ => vec![],
+        });
+        s1.action = s0.action;
+        let m = Machine::new(u64::MAX, 0.0, 0, 0.0, vec![s0, s1]).unwrap();
+
+        let start = Instant::now();
+        // the actions of the first machine, running alongside n-1 siblings,
+        // with a generator per machine or one shared
+        let run = |n: usize, isolated: bool| {
+            let machines = vec![m.clone(); n];
+            let mut f = if isolated {
+                Framework::with_machine_rngs(&machines, 0.0, 0.0, start, |machine| {
+                    let mut rng = ChaCha8Rng::seed_from_u64(0);
+                    rng.set_stream(machine.into_raw() as u64);
+                    rng
+                })
+            } else {
+                Framework::new(&machines, 0.0, 0.0, start, ChaCha8Rng::seed_from_u64(0))
+            }
+            .unwrap();
+            let mut out = vec![];
+            for i in 0..100 {
+                let now = start.add(Duration::from_micros(i * 10));
+                out.extend(
+                    f.trigger_events(&[TriggerEvent::NormalSent], now)
+                        .filter(|a| match a {
+                            TriggerAction::SendPadding { machine, .. } => *machine == MachineId(0),
+                            _ => false,
+                        })
+                        .cloned(),
+                );
+            }
+            out
+        };
+
+        let alone = run(1, true);
+        assert_eq!(alone.len(), 100);
+        assert_eq!(run(2, true), alone);
+        assert_eq!(run(5, true), alone);
+        // a single generator is also drawn from by the siblings
+        assert_eq!(run(1, false), run(1, false));
+        assert_ne!(run(2, false), run(1, false));
+    }
+
+    mod alloc_counter {
+        use std::alloc::{GlobalAlloc, Layout, System};
+        use std::cell::Cell;
+
+        // counts allocations (and bytes currently allocated) made by the
+        // current thread, so that tests running in parallel do not interfere
+        // with each other
+        thread_local! {
+            static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+            static BYTES: Cell<usize> = const { Cell::new(0) };
+        }
+
+        struct CountingAllocator;
+
+        unsafe impl GlobalAlloc for CountingAllocator {
+            unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+                let _ = ALLOCATIONS.try_with(|a| a.set(a.get() + 1));
+                let _ = BYTES.try_with(|b| b.set(b.get().wrapping_add(layout.size())));
+                System.alloc(layout)
+            }
+
+            unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+                let _ = BYTES.try_with(|b| b.set(b.get().wrapping_sub(layout.size())));
+                System.dealloc(ptr, layout)
+            }
+
+            unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+                let _ = ALLOCATIONS.try_with(|a| a.set(a.get() + 1));
+                let _ = BYTES.try_with(|b| {
+                    b.set(b.get().wrapping_add(new_size).wrapping_sub(layout.size()))
+                });
+                System.realloc(ptr, layout, new_size)
+            }
+        }
+
+        #[global_allocator]
+        static GLOBAL: CountingAllocator = CountingAllocator;
+
+        pub fn allocations() -> usize {
+            ALLOCATIONS.with(|a| a.get())
+        }
+
+        pub fn allocated_bytes() -> usize {
+            BYTES.with(|b| b.get())
+        }
+    }
+
+    #[test]
+    fn framework_shared_machines_memory() {
+        // many frameworks sharing one immutable set of machines only allocate
+        // their own runtime state, compared to each having a clone
+        const FRAMEWORKS: usize = 10_000;
+        let mut states = vec![];
+        for i in 0..5 {
+            let mut s = State::new(enum_map! {
+                Event::NormalSent => vec![Trans((i + 1) % 5, 0.5), Trans(i, 0.5)],
+                Event::PaddingSent => vec![Trans(0, 1.0)],
+                _ => vec![],
+            });
+            s.action = Some(Action::SendPadding {
+                bypass: false,
+                replace: false,
+                timeout: Dist::default(),
+                limit: None,
+            });
+            states.push(s);
+        }
+        let m = Machine::new(1000, 1.0, 0, 0.0, states).unwrap();
+        let machines = vec![m; 2];
+        let current_time = Instant::now();
+
+        let before = alloc_counter::allocated_bytes();
+        let shared: std::sync::Arc<[Machine]> = machines.clone().into();
+        let frameworks: Vec<_> = (0..FRAMEWORKS)
+            .map(|_| {
+                Framework::new(shared.clone(), 0.0, 0.0, current_time, rand::thread_rng()).unwrap()
+            })
+            .collect();
+        let shared_bytes = alloc_counter::allocated_bytes().wrapping_sub(before);
+        drop(frameworks);
+
+        let before = alloc_counter::allocated_bytes();
+        let frameworks: Vec<_> = (0..FRAMEWORKS)
+            .map(|_| {
+                Framework::new(machines.clone(), 0.0, 0.0, current_time, rand::thread_rng())
+                    .unwrap()
+            })
+            .collect();
+        let cloned_bytes = alloc_counter::allocated_bytes().wrapping_sub(before);
+        drop(frameworks);
+
+        assert!(shared_bytes * 5 < cloned_bytes);
+    }
+
+    #[test]
+    fn trigger_events_no_allocation() {
+        // plan: run machines that exercise padding, blocking, timers, counters,
+        // signals, and limits, and count heap allocations while triggering
+        let mut s0 = State::new(enum_map! {
+            Event::NormalSent => vec![Trans(1, 1.0)],
+            Event::NormalRecv => vec![Trans(2, 1.0)],
+            Event::Signal => vec![Trans(3, 1.0)],
+            _ => vec![],
+        });
+        s0.counter = (
+            Some(Counter::new_dist(
+                Operation::Set,
+                Dist {
+                    dist: DistType::Uniform {
+                        low: 1.0,
+                        high: 3.0,
+                    },
+                    start: 0.0,
+                    max: 0.0,
+                    ..Default::default()
+                },
+            )),
+            None,
+        );
+        let mut s1 = State::new(enum_map! {
+            Event::PaddingSent => vec![Trans(1, 0.8), Trans(0, 0.1), Trans(STATE_SIGNAL, 0.1)],
+            Event::LimitReached => vec![Trans(0, 1.0)],
+            _ => vec![],
+        });
+        s1.action = Some(Action::SendPadding {
+            bypass: false,
+            replace: false,
+            timeout: Dist {
+                dist: DistType::Uniform {
+                    low: 0.0,
+                    high: 10.0,
+                },
+                start: 0.0,
+                max: 0.0,
+                ..Default::default()
+            },
+            limit: Some(Dist {
+                dist: DistType::Uniform {
+                    low: 1.0,
+                    high: 5.0,
+                },
+                start: 0.0,
+                max: 0.0,
+                ..Default::default()
+            }),
+        });
+        s1.counter = (Some(Counter::new(Operation::Decrement)), None);
+        let mut s2 = State::new(enum_map! {
+            Event::BlockingBegin => vec![Trans(0, 1.0)],
+            Event::CounterZero => vec![Trans(0, 1.0)],
+            _ => vec![],
+        });
+        s2.action = Some(Action::BlockOutgoing {
+            bypass: false,
+            replace: false,
+            lane: 0,
+            timeout: Dist {
+                dist: DistType::Uniform {
+                    low: 0.0,
+                    high: 10.0,
+                },
+                start: 0.0,
+                max: 0.0,
+                ..Default::default()
+            },
+            duration: Dist {
+                dist: DistType::Uniform {
+                    low: 10.0,
+                    high: 100.0,
+                },
+                start: 0.0,
+                max: 0.0,
+                ..Default::default()
+            },
+            limit: None,
+        });
+        let mut s3 = State::new(enum_map! {
+            Event::TimerEnd => vec![Trans(0, 1.0)],
+            Event::TimerBegin => vec![Trans(3, 1.0)],
+            _ => vec![],
+        });
+        s3.action = Some(Action::UpdateTimer {
+            replace: false,
+            duration: Dist {
+                dist: DistType::Uniform {
+                    low: 10.0,
+                    high: 100.0,
+                },
+                start: 0.0,
+                max: 0.0,
+                ..Default::default()
+            },
+            limit: None,
+        });
+        let m = Machine::new(u64::MAX, 0.0, u64::MAX, 0.0, vec![s0, s1, s2, s3]).unwrap();
+        let machines = vec![m.clone(), m.clone(), m];
+
+        let mut current_time = Instant::now();
+        let mut f = Framework::new(&machines, 0.5, 0.5, current_time, rand::thread_rng()).unwrap();
+
+        let events = [
+            TriggerEvent::NormalSent,
+            TriggerEvent::NormalRecv,
+            TriggerEvent::PaddingSent {
+                machine: MachineId(0),
+            },
+            TriggerEvent::PaddingSent {
+                machine: MachineId(1),
+            },
+            TriggerEvent::PaddingRecv,
+            TriggerEvent::TunnelSent,
+            TriggerEvent::TunnelRecv,
+            TriggerEvent::BlockingBegin {
+                machine: MachineId(2),
+                lane: 0,
+            },
+            TriggerEvent::BlockingEnd { lane: 0 },
+            TriggerEvent::TimerBegin {
+                machine: MachineId(0),
+            },
+            TriggerEvent::TimerEnd {
+                machine: MachineId(1),
+            },
+        ];
+
+        // warm up, e.g., any lazy initialization of the thread-local rng
+        let _ = f.trigger_events(&events, current_time).count();
+
+        let before = alloc_counter::allocations();
+        let mut actions = 0;
+        for i in 0..10_000 {
+            current_time = current_time.add(Duration::from_micros(7));
+            // vary the batches of events
+            let n = 1 + i % events.len();
+            actions += f.trigger_events(&events[..n], current_time).count();
+            actions += f
+                .trigger_events(&events[events.len() - n..], current_time)
+                .count();
+        }
+        let after = alloc_counter::allocations();
+
+        assert!(actions > 0);
+        assert_eq!(after - before, 0);
+
+        // events drained from a channel are consumed without collecting them,
+        // which would allocate
+        let (tx, rx) = std::sync::mpsc::channel();
+        let (mut allocations, mut collected) = (0, 0);
+        for i in 0..1000 {
+            current_time = current_time.add(Duration::from_micros(7));
+            let n = 1 + i % events.len();
+            for e in &events[..n] {
+                tx.send(e.clone()).unwrap();
+            }
+            let before = alloc_counter::allocations();
+            if i % 2 == 0 {
+                f.trigger_events_iter(rx.try_iter(), current_time).count();
+                allocations += alloc_counter::allocations() - before;
+            } else {
+                let events: Vec<_> = rx.try_iter().collect();
+                f.trigger_events(&events, current_time).count();
+                collected += alloc_counter::allocations() - before;
+            }
+        }
+        assert_eq!(allocations, 0);
+        assert!(collected >= 500);
+    }
+
+    #[test]
+    fn framework_blocking_owner() {
+        // two machines contending for blocking: the first to begin blocking
+        // owns it until blocking ends
+        let s0 = State::new(enum_map! {
+            _ => vec![],
+        });
+        let m = Machine::new(0, 0.0, 0, 0.0, vec![s0]).unwrap();
+        let machines = vec![m.clone(), m];
+
+        let mut current_time = Instant::now();
+        let mut f = Framework::new(&machines, 0.0, 0.0, current_time, rand::thread_rng()).unwrap();
+        assert_eq!(f.blocking_owner(0), None);
+
+        _ = f.trigger_events(
+            &[TriggerEvent::BlockingBegin {
+                machine: MachineId(1),
+                lane: 0,
+            }],
+            current_time,
+        );
+        assert_eq!(f.blocking_owner(0), Some(MachineId(1)));
+
+        // the other machine extending the blocking does not take ownership
+        current_time = current_time.add(Duration::from_micros(5));
+        _ = f.trigger_events(
+            &[TriggerEvent::BlockingBegin {
+                machine: MachineId(0),
+                lane: 0,
+            }],
+            current_time,
+        );
+        assert_eq!(f.blocking_owner(0), Some(MachineId(1)));
+
+        current_time = current_time.add(Duration::from_micros(5));
+        _ = f.trigger_events(&[TriggerEvent::BlockingEnd { lane: 0 }], current_time);
+        assert_eq!(f.blocking_owner(0), None);
+
+        // new blocking, in the same batch as the other machine
+        current_time = current_time.add(Duration::from_micros(5));
+        _ = f.trigger_events(
+            &[
+                TriggerEvent::BlockingBegin {
+                    machine: MachineId(0),
+                    lane: 0,
+                },
+                TriggerEvent::BlockingBegin {
+                    machine: MachineId(1),
+                    lane: 0,
+                },
+            ],
+            current_time,
+        );
+        assert_eq!(f.blocking_owner(0), Some(MachineId(0)));
+    }
+
+    #[test]
+    fn framework_update_timer_aligned() {
+        // state 0, wait for first normal packet
+        let s0 = State::new(enum_map! {
+            Event::NormalSent => vec![Trans(1, 1.0)],
+            _ => vec![],
+        });
+
+        // state 1, set the timer to the next multiple of 1000 us on every
+        // timer expiry
+        let mut s1 = State::new(enum_map! {
+            Event::TimerEnd => vec![Trans(1, 1.0)],
+            _ => vec![],
+        });
+        s1.action = Some(Action::UpdateTimerAligned {
+            replace: true,
+            period: Dist {
+                dist: DistType::Uniform {
+                    low: 1000.0,
+                    high: 1000.0,
+                },
+                start: 0.0,
+                max: 0.0,
+                ..Default::default()
+            },
+            limit: None,
+        });
+
+        let m = Machine::new(0, 0.0, 0, 0.0, vec![s0, s1]).unwrap();
+        let start = Instant::now();
+        let machines = vec![m];
+        let mut f = Framework::new(&machines, 0.0, 0.0, start, rand::thread_rng()).unwrap();
+
+        // between grid points: the timer expires at the next one
+        let mut current_time = start.add(Duration::from_micros(300));
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+        assert_eq!(
+            f.actions[0],
+            Some(TriggerAction::UpdateTimer {
+                duration: Duration::from_micros(700),
+                replace: true,
+                machine: MachineId(0),
+            })
+        );
+
+        // TimerEnd is reported with jitter, but the timer keeps to the grid
+        let mut deadline = Duration::from_micros(1000);
+        for jitter in [0, 37, 250, 1, 999, 0, 500] {
+            current_time = start.add(deadline).add(Duration::from_micros(jitter));
+            _ = f.trigger_events(
+                &[TriggerEvent::TimerEnd {
+                    machine: MachineId(0),
+                }],
+                current_time,
+            );
+            let Some(TriggerAction::UpdateTimer { duration, .. }) = f.actions[0] else {
+                panic!("expected timer update, got {:?}", f.actions[0]);
+            };
+            let fires_at = current_time.duration_since(start) + duration;
+            assert_eq!(fires_at, deadline + Duration::from_micros(1000));
+            deadline = fires_at;
+        }
+    }
+
+    #[test]
+    fn framework_blocking_condition() {
+        // state 0, pad only while blocking
+        let mut s0 = State::new(enum_map! {
+            Event::NormalSent => vec![Trans(0, 1.0)],
+            Event::BlockingBegin => vec![Trans(0, 1.0)],
+            Event::BlockingEnd => vec![Trans(0, 1.0)],
+            Event::PaddingSent => vec![Trans(0, 1.0)],
+            _ => vec![],
+        });
+        s0.action = Some(Action::SendPadding {
+            bypass: true,
+            replace: false,
+            timeout: Dist {
+                dist: DistType::Uniform {
+                    low: 2.0,
+                    high: 2.0,
+                },
+                start: 0.0,
+                max: 0.0,
+                ..Default::default()
+            },
+            limit: None,
+        });
+        s0.blocking_condition = Some(true);
+
+        let m = Machine::new(0, 0.0, 0, 0.0, vec![s0]).unwrap();
+        let mut current_time = Instant::now();
+        let machines = vec![m];
+        let mut f = Framework::new(&machines, 0.0, 0.0, current_time, rand::thread_rng()).unwrap();
+        let padding = TriggerAction::SendPadding {
+            timeout: Duration::from_micros(2),
+            bypass: true,
+            replace: false,
+            machine: MachineId(0),
+        };
+
+        // not blocking: no padding
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+        assert_eq!(f.actions[0], None);
+
+        // blocking (by another party): the event is evaluated with the new
+        // status, so padding is scheduled
+        current_time = current_time.add(Duration::from_micros(10));
+        _ = f.trigger_events(
+            &[TriggerEvent::BlockingBegin {
+                machine: MachineId(1),
+                lane: 0,
+            }],
+            current_time,
+        );
+        assert_eq!(f.actions[0], Some(padding.clone()));
+
+        // continue padding while blocking
+        for _ in 0..3 {
+            current_time = current_time.add(Duration::from_micros(2));
+            _ = f.trigger_events(
+                &[TriggerEvent::PaddingSent {
+                    machine: MachineId(0),
+                }],
+                current_time,
+            );
+            assert_eq!(f.actions[0], Some(padding.clone()));
+        }
+
+        // blocking ended: no more padding
+        current_time = current_time.add(Duration::from_micros(1));
+        _ = f.trigger_events(&[TriggerEvent::BlockingEnd { lane: 0 }], current_time);
+        assert_eq!(f.actions[0], None);
+        current_time = current_time.add(Duration::from_micros(1));
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+        assert_eq!(f.actions[0], None);
+
+        // the inverse condition only pads when not blocking
+        let mut m = machines[0].clone();
+        m.states[0].blocking_condition = Some(false);
+        let machines = vec![m];
+        let mut f = Framework::new(&machines, 0.0, 0.0, current_time, rand::thread_rng()).unwrap();
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+        assert_eq!(f.actions[0], Some(padding.clone()));
+        _ = f.trigger_events(
+            &[TriggerEvent::BlockingBegin {
+                machine: MachineId(1),
+                lane: 0,
+            }],
+            current_time,
+        );
+        assert_eq!(f.actions[0], None);
+
+        // padding considers any lane, while blocking considers the lane the
+        // action targets
+        let mut m = machines[0].clone();
+        m.states[0].blocking_condition = Some(true);
+        let mut b = m.clone();
+        b.states[0].action = Some(Action::BlockOutgoing {
+            bypass: false,
+            replace: false,
+            lane: 0,
+            timeout: Dist::constant(2.0),
+            duration: Dist::constant(2.0),
+            limit: None,
+        });
+        let machines = vec![m, b];
+        let mut f = Framework::new(&machines, 0.0, 0.0, current_time, rand::thread_rng()).unwrap();
+        _ = f.trigger_events(
+            &[TriggerEvent::BlockingBegin {
+                machine: MachineId(2),
+                lane: 1,
+            }],
+            current_time,
+        );
+        assert_eq!(f.actions[0], Some(padding.clone()));
+        assert_eq!(f.actions[1], None);
+        _ = f.trigger_events(
+            &[TriggerEvent::BlockingBegin {
+                machine: MachineId(2),
+                lane: 0,
+            }],
+            current_time,
+        );
+        assert!(matches!(
+            f.actions[1],
+            Some(TriggerAction::BlockOutgoing { lane: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn framework_counter_condition() {
+        // state 0 sets a budget of 3 padding packets in counter A, and state 1
+        // pads while the budget remains, decrementing it
+        let mut s0 = State::new(enum_map! {
+            Event::NormalRecv => vec![Trans(0, 1.0)],
+            Event::NormalSent => vec![Trans(1, 1.0)],
+            _ => vec![],
+        });
+        s0.counter = (
+            Some(Counter::new_dist(Operation::Set, Dist::constant(3.0))),
+            None,
+        );
+        let mut s1 = State::padding(
+            enum_map! {
+                Event::NormalRecv => vec![Trans(0, 1.0)],
+                Event::NormalSent => vec![Trans(1, 1.0)],
+                Event::PaddingSent => vec![Trans(1, 1.0)],
+                _ => vec![],
+            },
+            Dist::constant(2.0),
+            None,
+        );
+        s1.counter = (Some(Counter::new(Operation::Decrement)), None);
+        s1.counter_condition = Some(CounterCondition {
+            counter: 0,
+            threshold: 1,
+            below: false,
+        });
+        let machines = vec![Machine::new(u64::MAX, 0.0, 0, 0.0, vec![s0, s1]).unwrap()];
+        let current_time = Instant::now();
+        let mut f = Framework::new(&machines, 0.0, 0.0, current_time, rand::thread_rng()).unwrap();
+        let machine = MachineId(0);
+        let padding_sent = [TriggerEvent::PaddingSent { machine }];
+
+        _ = f.trigger_events(&[TriggerEvent::NormalRecv], current_time);
+        assert_eq!(f.runtime[0].counter_a, 3);
+        assert_eq!(
+            f.trigger_events(&[TriggerEvent::NormalSent], current_time)
+                .count(),
+            1
+        );
+        assert_eq!(f.trigger_events(&padding_sent, current_time).count(), 1);
+        assert_eq!(f.trigger_events(&padding_sent, current_time).count(), 1);
+        assert_eq!(f.runtime[0].counter_a, 0);
+
+        // the budget is spent: the machine still transitions, but no longer
+        // pads, on any event
+        for event in [padding_sent[0].clone(), TriggerEvent::NormalSent] {
+            assert_eq!(f.trigger_events(&[event], current_time).count(), 0);
+            assert_eq!(f.runtime[0].current_state, 1);
+            assert_eq!(
+                f.last_suppression(machine),
+                Some(SuppressReason::CounterCondition)
+            );
+        }
+
+        // until the budget is set again
+        _ = f.trigger_events(&[TriggerEvent::NormalRecv], current_time);
+        assert_eq!(
+            f.trigger_events(&[TriggerEvent::NormalSent], current_time)
+                .count(),
+            1
+        );
+        assert_eq!(f.runtime[0].counter_a, 2);
+
+        // the inverse condition pads only once the budget is spent
+        let mut m = machines[0].clone();
+        m.states[1].counter_condition = Some(CounterCondition {
+            counter: 0,
+            threshold: 1,
+            below: true,
+        });
+        let machines = vec![m];
+        let mut f = Framework::new(&machines, 0.0, 0.0, current_time, rand::thread_rng()).unwrap();
+        _ = f.trigger_events(&[TriggerEvent::NormalRecv], current_time);
+        for _ in 0..3 {
+            assert_eq!(
+                f.trigger_events(&[TriggerEvent::NormalSent], current_time)
+                    .count(),
+                0
+            );
+        }
+        assert_eq!(
+            f.trigger_events(&[TriggerEvent::NormalSent], current_time)
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn framework_on_end() {
+        // state 0, wait for a normal packet
+        let s0 = State::new(enum_map! {
+            Event::NormalSent => vec![Trans(1, 1.0)],
+            _ => vec![],
+        });
+        // state 1, set the internal timer
+        let mut s1 = State::new(enum_map! {
+            Event::TimerBegin => vec![Trans(2, 1.0)],
+            _ => vec![],
+        });
+        s1.action = Some(Action::UpdateTimer {
+            replace: false,
+            duration: Dist {
+                dist: DistType::Uniform {
+                    low: 1000.0,
+                    high: 1000.0,
+                },
+                start: 0.0,
+                max: 0.0,
+                ..Default::default()
+            },
+            limit: None,
+        });
+        // state 2, schedule padding, end on received padding
+        let mut s2 = State::new(enum_map! {
+            Event::PaddingRecv => vec![Trans(STATE_END, 1.0)],
+            _ => vec![],
+        });
+        s2.action = Some(Action::SendPadding {
+            bypass: false,
+            replace: false,
+            timeout: Dist {
+                dist: DistType::Uniform {
+                    low: 100.0,
+                    high: 100.0,
+                },
+                start: 0.0,
+                max: 0.0,
+                ..Default::default()
+            },
+            limit: None,
+        });
+        let keep = Machine::new(1000, 1.0, 0, 0.0, vec![s0, s1, s2]).unwrap();
+        assert_eq!(keep.on_end, OnEnd::KeepPending);
+        let mut cancel = keep.clone();
+        cancel.on_end = OnEnd::CancelPending;
+
+        let padding = TriggerAction::SendPadding {
+            timeout: Duration::from_micros(100),
+            bypass: false,
+            replace: false,
+            machine: MachineId(0),
+        };
+        let cancel_all = TriggerAction::Cancel {
+            machine: MachineId(0),
+            timer: Timer::All,
+        };
+        let timer_begin = TriggerEvent::TimerBegin {
+            machine: MachineId(0),
+        };
+
+        for (m, expected) in [
+            (keep.clone(), None),
+            (cancel.clone(), Some(cancel_all.clone())),
+        ] {
+            let current_time = Instant::now();
+            let machines = vec![m];
+            let mut f =
+                Framework::new(&machines, 0.0, 0.0, current_time, rand::thread_rng()).unwrap();
+
+            // timer and padding in flight when the machine ends
+            _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+            _ = f.trigger_events(
+                &[TriggerEvent::TimerBegin {
+                    machine: MachineId(0),
+                }],
+                current_time,
+            );
+            assert_eq!(f.actions[0], Some(padding.clone()));
+            _ = f.trigger_events(&[TriggerEvent::PaddingRecv], current_time);
+            assert_eq!(f.runtime[0].current_state, STATE_END);
+            assert_eq!(f.actions[0], expected);
+
+            // the machine stays quiet once ended
+            _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+            assert_eq!(f.actions[0], None);
+        }
+
+        // padding scheduled in the same call as ending is replaced by the
+        // cancel action, if configured
+        for (m, expected) in [(keep, padding), (cancel, cancel_all)] {
+            let current_time = Instant::now();
+            let machines = vec![m];
+            let mut f =
+                Framework::new(&machines, 0.0, 0.0, current_time, rand::thread_rng()).unwrap();
+            _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+            _ = f.trigger_events(
+                &[timer_begin.clone(), TriggerEvent::PaddingRecv],
+                current_time,
+            );
+            assert_eq!(f.runtime[0].current_state, STATE_END);
+            assert_eq!(f.actions[0], Some(expected));
+        }
+    }
+
+    #[test]
+    fn framework_machine_elapsed() {
+        let s0 = State::new(enum_map! {
+            _ => vec![],
+        });
+        let m = Machine::new(0, 0.0, 0, 0.0, vec![s0]).unwrap();
+        let start = Instant::now();
+        let machines = vec![m.clone(), m];
+        let mut f = Framework::new(&machines, 0.0, 0.0, start, rand::thread_rng()).unwrap();
+
+        assert_eq!(f.machine_elapsed(MachineId(0)), Some(Duration::ZERO));
+        _ = f.trigger_events(
+            &[TriggerEvent::NormalSent],
+            start.add(Duration::from_micros(42)),
+        );
+        assert_eq!(
+            f.machine_elapsed(MachineId(0)),
+            Some(Duration::from_micros(42))
+        );
+        assert_eq!(
+            f.machine_elapsed(MachineId(1)),
+            Some(Duration::from_micros(42))
+        );
+        assert_eq!(f.machine_elapsed(MachineId(2)), None);
+
+        // follows the time of the last call, but never goes backwards
+        _ = f.trigger_events(
+            &[TriggerEvent::NormalSent],
+            start.add(Duration::from_micros(50)),
+        );
+        assert_eq!(
+            f.machine_elapsed(MachineId(0)),
+            Some(Duration::from_micros(50))
+        );
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], start);
+        assert_eq!(
+            f.machine_elapsed(MachineId(0)),
+            Some(Duration::from_micros(50))
+        );
+    }
+
+    #[test]
+    fn framework_time_threshold() {
+        // state 0, wait for the threshold
+        let s0 = State::new(enum_map! {
+            Event::TimeThreshold => vec![Trans(1, 1.0)],
+            _ => vec![],
+        });
+        // state 1, pad on entering, and again on any further threshold
+        let mut s1 = State::new(enum_map! {
+            Event::TimeThreshold => vec![Trans(1, 1.0)],
+            _ => vec![],
+        });
+        s1.action = Some(Action::SendPadding {
+            bypass: false,
+            replace: false,
+            timeout: Dist::constant(0.0),
+            limit: None,
+        });
+        let mut m = Machine::noop();
+        m.allowed_padding_packets = 1000;
+        m.max_padding_frac = 1.0;
+        m.states = vec![s0, s1];
+        m.time_threshold = Some(100);
+        let machines = vec![m.clone(), m];
+        let start = Instant::now();
+        let mut f = Framework::new(&machines, 0.0, 0.0, start, rand::thread_rng()).unwrap();
+        let padding = |mi| TriggerAction::SendPadding {
+            timeout: Duration::ZERO,
+            bypass: false,
+            replace: false,
+            machine: MachineId(mi),
+        };
+        let at = |micros| start.add(Duration::from_micros(micros));
+
+        // nothing before the threshold
+        _ = f.set_enabled(MachineId(1), false);
+        assert_eq!(f.trigger_events(&[], at(50)).count(), 0);
+        assert_eq!(
+            f.trigger_events(&[TriggerEvent::NormalSent], at(99))
+                .count(),
+            0
+        );
+        assert_eq!(f.runtime[0].current_state, 0);
+
+        // the transition fires once the threshold is reached, without events
+        let actions: Vec<_> = f.trigger_events(&[], at(100)).cloned().collect();
+        assert_eq!(actions, vec![padding(0)]);
+        assert_eq!(f.runtime[0].current_state, 1);
+
+        // at most once per machine, and deferred for disabled machines
+        assert_eq!(f.trigger_events(&[], at(200)).count(), 0);
+        _ = f.set_enabled(MachineId(1), true);
+        let actions: Vec<_> = f.trigger_events(&[], at(300)).cloned().collect();
+        assert_eq!(actions, vec![padding(1)]);
+        assert_eq!(f.trigger_events(&[], at(400)).count(), 0);
+    }
+
+    #[test]
+    fn framework_uptime_warm() {
+        let m = Machine::noop();
+        let start = Instant::now();
+        let machines = vec![m];
+        let mut f = Framework::new(&machines, 0.0, 0.0, start, rand::thread_rng()).unwrap();
+        assert_eq!(f.uptime(), Duration::ZERO);
+        assert!(!f.is_warm());
+
+        f.set_warm_up(4, Duration::from_millis(10));
+        let mut now = start;
+        for i in 1..=4 {
+            now = start.add(Duration::from_millis(i));
+            let event = match i % 2 {
+                0 => TriggerEvent::NormalRecv,
+                _ => TriggerEvent::NormalSent,
+            };
+            _ = f.trigger_events(&[event], now);
+            assert_eq!(f.uptime(), Duration::from_millis(i));
+            // enough packets, but not enough time
+            assert!(!f.is_warm());
+        }
+
+        // the uptime advances without packets
+        now = now.add(Duration::from_millis(6));
+        _ = f.trigger_events(&[], now);
+        assert_eq!(f.uptime(), Duration::from_millis(10));
+        assert!(f.is_warm());
+        // and never goes backwards
+        _ = f.trigger_events(&[], start);
+        assert_eq!(f.uptime(), Duration::from_millis(10));
+
+        // not warm without enough packets, regardless of uptime
+        f.set_warm_up(5, Duration::ZERO);
+        assert!(!f.is_warm());
+        _ = f.trigger_events(&[TriggerEvent::PaddingRecv], now);
+        assert!(f.is_warm());
+
+        // always warm without a warm-up
+        let mut f = Framework::new(&machines, 0.0, 0.0, start, rand::thread_rng()).unwrap();
+        f.set_warm_up(0, Duration::ZERO);
+        assert!(f.is_warm());
+    }
+
+    #[test]
+    fn framework_padding_burst() {
+        // state 0, wait for a normal packet
+        let s0 = State::new(enum_map! {
+            Event::NormalSent => vec![Trans(1, 1.0)],
+            _ => vec![],
+        });
+        // state 1, a burst of three packets, stopped by received packets
+        let mut s1 = State::new(enum_map! {
+            Event::NormalRecv => vec![Trans(0, 1.0)],
+            Event::LimitReached => vec![Trans(0, 1.0)],
+            _ => vec![],
+        });
+        s1.action = Some(Action::SendPaddingBurst {
+            bypass: false,
+            replace: true,
+            timeout: Dist::constant(5.0),
+            interval: Dist::constant(2.0),
+            count: 3,
+            limit: None,
+        });
+        let m = Machine::new(1000, 1.0, 0, 0.0, vec![s0, s1]).unwrap();
+
+        let mut current_time = Instant::now();
+        let machines = vec![m.clone()];
+        let mut f = Framework::new(&machines, 0.0, 0.0, current_time, rand::thread_rng()).unwrap();
+        let padding = |timeout| {
+            Some(TriggerAction::SendPadding {
+                timeout: Duration::from_micros(timeout),
+                bypass: false,
+                replace: true,
+                machine: MachineId(0),
+            })
+        };
+        let sent = [TriggerEvent::PaddingSent {
+            machine: MachineId(0),
+        }];
+
+        // one transition, three packets
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+        assert_eq!(f.actions[0], padding(5));
+        for expected in [padding(2), padding(2), None] {
+            current_time = current_time.add(Duration::from_micros(2));
+            _ = f.trigger_events(&sent, current_time);
+            assert_eq!(f.actions[0], expected);
+        }
+        assert_eq!(f.runtime[0].padding_sent, 3);
+
+        // a transition ends the burst
+        _ = f.trigger_events(&[TriggerEvent::NormalRecv], current_time);
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+        assert_eq!(f.actions[0], padding(5));
+        _ = f.trigger_events(&sent, current_time);
+        assert_eq!(f.actions[0], padding(2));
+        _ = f.trigger_events(&[TriggerEvent::NormalRecv], current_time);
+        _ = f.trigger_events(&sent, current_time);
+        assert_eq!(f.actions[0], None);
+        assert_eq!(f.runtime[0].current_state, 0);
+
+        // each packet counts toward the limit
+        let mut m = m;
+        if let Some(Action::SendPaddingBurst { limit, .. }) = &mut m.states[1].action {
+            *limit = Some(Dist::constant(2.0));
+        }
+        let machines = vec![m];
+        let mut f = Framework::new(&machines, 0.0, 0.0, current_time, rand::thread_rng()).unwrap();
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+        assert_eq!(f.actions[0], padding(5));
+        _ = f.trigger_events(&sent, current_time);
+        assert_eq!(f.actions[0], padding(2));
+        _ = f.trigger_events(&sent, current_time);
+        assert_eq!(f.actions[0], None);
+        assert_eq!(f.runtime[0].current_state, 0);
+    }
+
+    #[test]
+    fn framework_padding_burst_end() {
+        // state 0, wait for a normal packet
+        let s0 = State::new(enum_map! {
+            Event::NormalSent => vec![Trans(1, 1.0)],
+            _ => vec![],
+        });
+        // state 1, set the counter to one and wait for a received packet
+        let mut s1 = State::new(enum_map! {
+            Event::NormalRecv => vec![Trans(2, 1.0)],
+            _ => vec![],
+        });
+        s1.counter = (
+            Some(Counter::new_dist(Operation::Set, Dist::constant(1.0))),
+            None,
+        );
+        // state 2, schedule a burst and zero the counter, ending the machine
+        // in the same transition
+        let mut s2 = State::new(enum_map! {
+            Event::CounterZero => vec![Trans(STATE_END, 1.0)],
+            _ => vec![],
+        });
+        s2.action = Some(Action::SendPaddingBurst {
+            bypass: false,
+            replace: false,
+            timeout: Dist::constant(5.0),
+            interval: Dist::constant(2.0),
+            count: 3,
+            limit: None,
+        });
+        s2.counter = (Some(Counter::new(Operation::Decrement)), None);
+        let m = Machine::new(1000, 1.0, 0, 0.0, vec![s0, s1, s2]).unwrap();
+
+        let current_time = Instant::now();
+        let machines = vec![m];
+        let mut f = Framework::new(&machines, 0.0, 0.0, current_time, rand::thread_rng()).unwrap();
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+        _ = f.trigger_events(&[TriggerEvent::NormalRecv], current_time);
+        assert_eq!(f.runtime[0].current_state, STATE_END);
+        assert!(f.has_pending_action(MachineId(0)));
+
+        // the pending packet is sent, but the burst is not continued
+        _ = f.trigger_events(
+            &[TriggerEvent::PaddingSent {
+                machine: MachineId(0),
+            }],
+            current_time,
+        );
+        assert_eq!(f.actions[0], None);
+        assert_eq!(f.runtime[0].padding_sent, 1);
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn framework_metrics() {
+        // state 0, pad after each sent packet, with a sampled limit
+        let mut s0 = State::new(enum_map! {
+            Event::NormalSent => vec![Trans(0, 1.0)],
+            _ => vec![],
+        });
+        s0.action = Some(Action::SendPadding {
+            bypass: false,
+            replace: false,
+            timeout: Dist::new(
+                DistType::Uniform {
+                    low: 0.0,
+                    high: 10.0,
+                },
+                0.0,
+                0.0,
+            ),
+            limit: Some(Dist::new(
+                DistType::Uniform {
+                    low: 5.0,
+                    high: 15.0,
+                },
+                0.0,
+                0.0,
+            )),
+        });
+        let machines = vec![Machine::new(1000, 1.0, 0, 0.0, vec![s0]).unwrap()];
+        let current_time = Instant::now();
+        let mut f = Framework::new(&machines, 0.0, 0.0, current_time, rand::thread_rng()).unwrap();
+
+        // sampling the limit of the first state
+        assert_eq!(
+            f.metrics(),
+            Metrics {
+                transitions: 0,
+                rng_samples: 1,
+                actions: 0,
+            }
+        );
+        f.reset_metrics();
+        assert_eq!(f.metrics(), Metrics::default());
+
+        // three transitions, each sampling the transition and the timeout,
+        // resulting in one action
+        _ = f.trigger_events(
+            &[
+                TriggerEvent::NormalSent,
+                TriggerEvent::NormalSent,
+                TriggerEvent::NormalSent,
+            ],
+            current_time,
+        );
+        assert_eq!(
+            f.metrics(),
+            Metrics {
+                transitions: 3,
+                rng_samples: 6,
+                actions: 1,
+            }
+        );
+
+        // no transitions, nothing sampled
+        _ = f.trigger_events(
+            &[
+                TriggerEvent::NormalRecv,
+                TriggerEvent::PaddingSent {
+                    machine: MachineId(0),
+                },
+            ],
+            current_time,
+        );
+        assert_eq!(
+            f.metrics(),
+            Metrics {
+                transitions: 3,
+                rng_samples: 6,
+                actions: 1,
+            }
+        );
+
+        // one more of each
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+        assert_eq!(
+            f.metrics(),
+            Metrics {
+                transitions: 4,
+                rng_samples: 8,
+                actions: 2,
+            }
+        );
+        f.reset_metrics();
+        assert_eq!(f.metrics(), Metrics::default());
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn framework_action_histograms() {
+        // pad, then block, then set the timer, in turn
+        let mut s0 = State::new(enum_map! {
+            Event::PaddingSent => vec![Trans(1, 1.0)],
+            _ => vec![],
+        });
+        s0.action = Some(Action::SendPadding {
+            bypass: false,
+            replace: false,
+            timeout: Dist::constant(100.0),
+            limit: None,
+        });
+        let mut s1 = State::new(enum_map! {
+            Event::BlockingBegin => vec![Trans(2, 1.0)],
+            _ => vec![],
+        });
+        s1.action = Some(Action::BlockOutgoing {
+            bypass: false,
+            replace: false,
+            lane: 0,
+            timeout: Dist::constant(0.0),
+            duration: Dist::constant(1000.0),
+            limit: None,
+        });
+        let mut s2 = State::new(enum_map! {
+            Event::TimerEnd => vec![Trans(0, 1.0)],
+            _ => vec![],
+        });
+        s2.action = Some(Action::UpdateTimer {
+            replace: false,
+            duration: Dist::constant(5000.0),
+            limit: None,
+        });
+        let m = Machine::new(u64::MAX, 1.0, u64::MAX, 1.0, vec![s0, s1, s2]).unwrap();
+        let machines = vec![m, Machine::noop()];
+        let current_time = Instant::now();
+        let mut f = Framework::new(&machines, 0.0, 0.0, current_time, rand::thread_rng()).unwrap();
+        assert_eq!(f.action_histograms(MachineId(0)), None);
+        f.set_action_histograms(true);
+
+        // two rounds, with the first padding scheduled on creation
+        let machine = MachineId(0);
+        for _ in 0..2 {
+            for e in [
+                TriggerEvent::PaddingSent { machine },
+                TriggerEvent::BlockingBegin { machine, lane: 0 },
+                TriggerEvent::TimerEnd { machine },
+            ] {
+                _ = f.trigger_events(&[e], current_time);
+            }
+        }
+
+        let h = f.action_histograms(machine).unwrap();
+        // the padding scheduled on creation is not returned
+        assert_eq!(h.timeouts.count(), 4);
+        assert_eq!(h.timeouts.buckets()[Histogram::bucket(100)], 2);
+        assert_eq!(h.timeouts.buckets()[Histogram::bucket(0)], 2);
+        assert_eq!(h.block_durations.count(), 2);
+        assert_eq!(h.block_durations.mean(), Some(1000.0));
+        assert_eq!(h.block_durations.buckets()[Histogram::bucket(1000)], 2);
+        assert_eq!(h.timer_durations.count(), 2);
+        assert_eq!(h.timer_durations.buckets()[Histogram::bucket(5000)], 2);
+        assert_eq!(
+            f.action_histograms(MachineId(1)),
+            Some(ActionHistograms::default())
+        );
+        assert_eq!(f.action_histograms(MachineId(2)), None);
+
+        // a snapshot is unaffected by later actions, and reset clears
+        _ = f.trigger_events(&[TriggerEvent::PaddingSent { machine }], current_time);
+        assert_eq!(h.timeouts.count(), 4);
+        assert_eq!(f.action_histograms(machine).unwrap().timeouts.count(), 5);
+        f.reset_action_histograms();
+        assert_eq!(
+            f.action_histograms(machine),
+            Some(ActionHistograms::default())
+        );
+
+        // disabled, nothing is collected
+        f.set_action_histograms(false);
+        _ = f.trigger_events(&[TriggerEvent::PaddingSent { machine }], current_time);
+        assert_eq!(f.action_histograms(machine), None);
+        f.set_action_histograms(true);
+        assert_eq!(
+            f.action_histograms(machine),
+            Some(ActionHistograms::default())
+        );
+    }
+
+    #[test]
+    fn framework_min_spacing() {
+        // state 0, reschedule the same padding on every received packet
+        let mut s0 = State::new(enum_map! {
+            Event::NormalRecv => vec![Trans(0, 1.0)],
+            Event::NormalSent => vec![Trans(1, 1.0)],
+            _ => vec![],
+        });
+        s0.action = Some(Action::SendPadding {
+            bypass: false,
+            replace: false,
+            timeout: Dist::constant(100.0),
+            limit: None,
+        });
+        // state 1, the same but with a different timeout
+        let mut s1 = State::new(enum_map! {
+            Event::NormalRecv => vec![Trans(1, 1.0)],
+            _ => vec![],
+        });
+        s1.action = Some(Action::SendPadding {
+            bypass: false,
+            replace: false,
+            timeout: Dist::constant(200.0),
+            limit: None,
+        });
+        let m = Machine::new(1000, 1.0, 0, 0.0, vec![s0, s1]).unwrap();
+
+        let start = Instant::now();
+        let at = |micros| start.add(Duration::from_micros(micros));
+        let machines = vec![m];
+        let mut f = Framework::new(&machines, 0.0, 0.0, start, rand::thread_rng()).unwrap();
+        f.set_min_spacing(Some(Duration::from_micros(50)));
+        let padding = |timeout| {
+            Some(TriggerAction::SendPadding {
+                timeout: Duration::from_micros(timeout),
+                bypass: false,
+                replace: false,
+                machine: MachineId(0),
+            })
+        };
+
+        // redundant reschedules within the spacing are coalesced
+        _ = f.trigger_events(&[TriggerEvent::NormalRecv], at(0));
+        assert_eq!(f.actions[0], padding(100));
+        _ = f.trigger_events(&[TriggerEvent::NormalRecv], at(10));
+        assert_eq!(f.actions[0], None);
+        _ = f.trigger_events(&[TriggerEvent::NormalRecv], at(49));
+        assert_eq!(f.actions[0], None);
+        // ... but not once the spacing has passed
+        _ = f.trigger_events(&[TriggerEvent::NormalRecv], at(50));
+        assert_eq!(f.actions[0], padding(100));
+
+        // genuine changes are always returned
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], at(51));
+        assert_eq!(f.actions[0], padding(200));
+        _ = f.trigger_events(&[TriggerEvent::NormalRecv], at(52));
+        assert_eq!(f.actions[0], None);
+
+        // once the padding is sent, the same action is scheduled again
+        _ = f.trigger_events(
+            &[
+                TriggerEvent::PaddingSent {
+                    machine: MachineId(0),
+                },
+                TriggerEvent::NormalRecv,
+            ],
+            at(53),
+        );
+        assert_eq!(f.actions[0], padding(200));
+
+        // without a spacing, all actions are returned
+        f.set_min_spacing(None);
+        _ = f.trigger_events(&[TriggerEvent::NormalRecv], at(54));
+        assert_eq!(f.actions[0], padding(200));
+    }
+
+    #[test]
+    fn framework_block_incoming() {
+        // block incoming traffic for 10 us on every normal packet sent
+        let mut s0 = State::new(enum_map! {
+            Event::NormalSent => vec![Trans(0, 1.0)],
+            _ => vec![],
+        });
+        s0.action = Some(Action::BlockIncoming {
+            replace: false,
+            timeout: Dist::constant(0.0),
+            duration: Dist::constant(10.0),
+            limit: None,
+        });
+        let m = Machine::new(0, 0.0, 0, 0.5, vec![s0]).unwrap();
+
+        let start = Instant::now();
+        let at = |micros| start.add(Duration::from_micros(micros));
+        let machines = vec![m];
+        let mut f = Framework::new(&machines, 0.0, 0.0, start, rand::thread_rng()).unwrap();
+        let block = Some(TriggerAction::BlockIncoming {
+            timeout: Duration::ZERO,
+            duration: Duration::from_micros(10),
+            replace: false,
+            machine: MachineId(0),
+        });
+
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], at(0));
+        assert_eq!(f.actions[0], block);
+        _ = f.trigger_events(
+            &[TriggerEvent::IncomingBlockingBegin {
+                machine: MachineId(0),
+            }],
+            at(0),
+        );
+        assert!(f.incoming_blocking_active);
+        assert!(!f.blocking_active);
+        assert_eq!(f.actions[0], None);
+        _ = f.trigger_events(&[TriggerEvent::IncomingBlockingEnd], at(10));
+        assert!(!f.incoming_blocking_active);
+
+        // accounted for separately from blocking outgoing traffic
+        assert_eq!(f.incoming_blocking_duration, Duration::from_micros(10));
+        assert_eq!(
+            f.runtime[0].incoming_blocking_duration,
+            Duration::from_micros(10)
+        );
+        assert_eq!(f.blocking_duration, Duration::ZERO);
+        assert_eq!(f.runtime[0].blocking_duration, Duration::ZERO);
+
+        // but against the same limits: blocked 10 of 15 us
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], at(15));
+        assert_eq!(f.actions[0], None);
+        // 10 of 30 us
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], at(30));
+        assert_eq!(f.actions[0], block);
+    }
+
+    #[test]
+    fn framework_hold_outgoing() {
+        // hold outgoing traffic for 10 us on every normal packet sent, with a
+        // probability clamped to 1.0
+        let mut s0 = State::new(enum_map! {
+            Event::NormalSent => vec![Trans(0, 1.0)],
+            _ => vec![],
+        });
+        s0.action = Some(Action::HoldOutgoing {
+            probability: Dist::constant(2.0),
+            timeout: Dist::constant(0.0),
+            duration: Dist::constant(10.0),
+            limit: None,
+        });
+        let m = Machine::new(0, 0.0, 0, 0.5, vec![s0]).unwrap();
+
+        let start = Instant::now();
+        let at = |micros| start.add(Duration::from_micros(micros));
+        let machines = vec![m];
+        let mut f = Framework::new(&machines, 0.0, 0.0, start, rand::thread_rng()).unwrap();
+        let hold = Some(TriggerAction::HoldOutgoing {
+            timeout: Duration::ZERO,
+            duration: Duration::from_micros(10),
+            probability: 1.0,
+            machine: MachineId(0),
+        });
+
+        // never reported, so never pending
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], at(0));
+        assert_eq!(f.actions[0], hold);
+        assert!(!f.has_pending_action(MachineId(0)));
+
+        // not accounted for as blocking, but checked against the blocking
+        // limits: blocked 10 of 15 us
+        assert_eq!(f.blocking_duration, Duration::ZERO);
+        _ = f.trigger_events(
+            &[TriggerEvent::BlockingBegin {
+                machine: MachineId(0),
+                lane: 0,
+            }],
+            at(0),
+        );
+        _ = f.trigger_events(&[TriggerEvent::BlockingEnd { lane: 0 }], at(10));
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], at(15));
+        assert_eq!(f.actions[0], None);
+        assert_eq!(
+            f.last_suppression(MachineId(0)),
+            Some(SuppressReason::MachineBlockingLimit)
+        );
+        // 10 of 30 us
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], at(30));
+        assert_eq!(f.actions[0], hold);
+    }
+
+    #[test]
+    fn framework_last_suppression() {
+        let padding = Action::SendPadding {
+            bypass: false,
+            replace: false,
+            timeout: Dist::constant(2.0),
+            limit: None,
+        };
+        let block = Action::BlockOutgoing {
+            bypass: false,
+            replace: false,
+            lane: 0,
+            timeout: Dist::constant(0.0),
+            duration: Dist::constant(100.0),
+            limit: None,
+        };
+        // a single state with the given action, looping on sent packets
+        let machine = |action: Action, max_padding_frac: f64, max_blocking_frac: f64| {
+            let mut s0 = State::new(enum_map! {
+                Event::NormalSent | Event::PaddingSent => vec![Trans(0, 1.0)],
+                _ => vec![],
+            });
+            s0.action = Some(action);
+            Machine::new(0, max_padding_frac, 0, max_blocking_frac, vec![s0]).unwrap()
+        };
+        let current_time = Instant::now();
+        let padding_sent = [TriggerEvent::PaddingSent {
+            machine: MachineId(0),
+        }];
+        let blocking_begin = [TriggerEvent::BlockingBegin {
+            machine: MachineId(0),
+            lane: 0,
+        }];
+
+        // nothing suppressed, and no such machine
+        let machines = vec![machine(padding, 0.0, 0.0)];
+        let mut f = Framework::new(&machines, 0.0, 0.0, current_time, rand::thread_rng()).unwrap();
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+        assert!(f.actions[0].is_some());
+        assert_eq!(f.last_suppression(MachineId(0)), None);
+        assert_eq!(f.last_suppression(MachineId(1)), None);
+
+        // state limit of zero
+        let mut m = machine(padding, 0.0, 0.0);
+        m.states[0].action = Some(Action::SendPadding {
+            bypass: false,
+            replace: false,
+            timeout: Dist::constant(2.0),
+            limit: Some(Dist::constant(0.0)),
+        });
+        let machines = vec![m];
+        let mut f = Framework::new(&machines, 0.0, 0.0, current_time, rand::thread_rng()).unwrap();
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+        assert_eq!(f.actions[0], None);
+        assert_eq!(
+            f.last_suppression(MachineId(0)),
+            Some(SuppressReason::StateLimit)
+        );
+
+        // machine padding limit: one padding per normal packet
+        let machines = vec![machine(padding, 0.5, 0.0)];
+        let mut f = Framework::new(&machines, 0.0, 0.0, current_time, rand::thread_rng()).unwrap();
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+        assert!(f.actions[0].is_some());
+        _ = f.trigger_events(&padding_sent, current_time);
+        assert_eq!(f.actions[0], None);
+        assert_eq!(
+            f.last_suppression(MachineId(0)),
+            Some(SuppressReason::MachinePaddingLimit)
+        );
+
+        // framework padding limit, the same
+        let machines = vec![machine(padding, 0.0, 0.0)];
+        let mut f = Framework::new(&machines, 0.5, 0.0, current_time, rand::thread_rng()).unwrap();
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+        assert!(f.actions[0].is_some());
+        _ = f.trigger_events(&padding_sent, current_time);
+        assert_eq!(f.actions[0], None);
+        assert_eq!(
+            f.last_suppression(MachineId(0)),
+            Some(SuppressReason::FrameworkPaddingLimit)
+        );
+
+        // machine blocking limit: blocking for all of the machine's lifetime
+        let machines = vec![machine(block, 0.0, 0.5)];
+        let mut f = Framework::new(&machines, 0.0, 0.0, current_time, rand::thread_rng()).unwrap();
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+        assert!(f.actions[0].is_some());
+        _ = f.trigger_events(&blocking_begin, current_time);
+        _ = f.trigger_events(
+            &[TriggerEvent::NormalSent],
+            current_time.add(Duration::from_micros(10)),
+        );
+        assert_eq!(f.actions[0], None);
+        assert_eq!(
+            f.last_suppression(MachineId(0)),
+            Some(SuppressReason::MachineBlockingLimit)
+        );
+
+        // framework blocking limit, the same
+        let machines = vec![machine(block, 0.0, 0.0)];
+        let mut f = Framework::new(&machines, 0.0, 0.5, current_time, rand::thread_rng()).unwrap();
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+        assert!(f.actions[0].is_some());
+        _ = f.trigger_events(&blocking_begin, current_time);
+        _ = f.trigger_events(
+            &[TriggerEvent::NormalSent],
+            current_time.add(Duration::from_micros(10)),
+        );
+        assert_eq!(f.actions[0], None);
+        assert_eq!(
+            f.last_suppression(MachineId(0)),
+            Some(SuppressReason::FrameworkBlockingLimit)
+        );
+
+        // shared limit of zero
+        let mut m = machine(padding, 0.0, 0.0);
+        m.states[0].shared_limit = Some(0);
+        m.shared_limits = vec![Dist::constant(0.0)];
+        let machines = vec![m];
+        let mut f = Framework::new(&machines, 0.0, 0.0, current_time, rand::thread_rng()).unwrap();
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+        assert_eq!(f.actions[0], None);
+        assert_eq!(
+            f.last_suppression(MachineId(0)),
+            Some(SuppressReason::SharedLimit)
+        );
+
+        // blocking condition not met
+        let mut m = machine(padding, 0.0, 0.0);
+        m.states[0].blocking_condition = Some(true);
+        let machines = vec![m];
+        let mut f = Framework::new(&machines, 0.0, 0.0, current_time, rand::thread_rng()).unwrap();
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+        assert_eq!(f.actions[0], None);
+        assert_eq!(
+            f.last_suppression(MachineId(0)),
+            Some(SuppressReason::BlockingCondition)
+        );
+    }
+
+    #[test]
+    fn framework_has_pending_action() {
+        // state 0, wait for a sent packet
+        let s0 = State::new(enum_map! {
+            Event::NormalSent => vec![Trans(1, 1.0)],
+            _ => vec![],
+        });
+        // state 1, pad
+        let mut s1 = State::new(enum_map! {
+            Event::NormalRecv => vec![Trans(2, 1.0)],
+            Event::TunnelRecv => vec![Trans(4, 1.0)],
+            _ => vec![],
+        });
+        s1.action = Some(Action::SendPadding {
+            bypass: false,
+            replace: false,
+            timeout: Dist::constant(10.0),
+            limit: None,
+        });
+        // state 2, cancel any pending blocking
+        let mut s2 = State::new(enum_map! {
+            Event::TunnelSent => vec![Trans(3, 1.0)],
+            _ => vec![],
+        });
+        s2.action = Some(Action::Cancel {
+            timer: Timer::ActionBlocking,
+        });
+        // state 3, cancel any pending action
+        let mut s3 = State::new(enum_map! {
+            Event::NormalSent => vec![Trans(1, 1.0)],
+            _ => vec![],
+        });
+        s3.action = Some(Action::Cancel {
+            timer: Timer::Action,
+        });
+        // state 4, block
+        let mut s4 = State::new(enum_map! {
+            Event::TunnelSent => vec![Trans(3, 1.0)],
+            _ => vec![],
+        });
+        s4.action = Some(Action::BlockOutgoing {
+            bypass: false,
+            replace: false,
+            lane: 0,
+            timeout: Dist::constant(10.0),
+            duration: Dist::constant(100.0),
+            limit: None,
+        });
+
+        let machines = vec![Machine::new(1000, 1.0, 1000, 1.0, vec![s0, s1, s2, s3, s4]).unwrap()];
+        let current_time = Instant::now();
+        let mut f = Framework::new(&machines, 0.0, 0.0, current_time, rand::thread_rng()).unwrap();
+        assert!(!f.has_pending_action(MachineId(0)));
+        assert!(!f.has_pending_action(MachineId(1)));
+
+        // scheduled padding is pending
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+        assert!(f.has_pending_action(MachineId(0)));
+
+        // cancelling blocking keeps the padding
+        _ = f.trigger_events(&[TriggerEvent::NormalRecv], current_time);
+        assert_eq!(
+            f.actions[0],
+            Some(TriggerAction::Cancel {
+                machine: MachineId(0),
+                timer: Timer::ActionBlocking,
+            })
+        );
+        assert!(f.has_pending_action(MachineId(0)));
+
+        // cancelling the action timer does not
+        _ = f.trigger_events(&[TriggerEvent::TunnelSent], current_time);
+        assert!(!f.has_pending_action(MachineId(0)));
+
+        // padding again, until sent
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+        assert!(f.has_pending_action(MachineId(0)));
+        _ = f.trigger_events(
+            &[TriggerEvent::PaddingSent {
+                machine: MachineId(0),
+            }],
+            current_time,
+        );
+        assert!(!f.has_pending_action(MachineId(0)));
+
+        // blocking replaces the padding, until blocking begins
+        _ = f.trigger_events(&[TriggerEvent::TunnelRecv], current_time);
+        assert!(f.has_pending_action(MachineId(0)));
+        _ = f.trigger_events(
+            &[TriggerEvent::BlockingBegin {
+                machine: MachineId(0),
+                lane: 0,
+            }],
+            current_time,
+        );
+        assert!(!f.has_pending_action(MachineId(0)));
+
+        // scheduling and cancelling in the same call
+        _ = f.trigger_events(&[TriggerEvent::TunnelSent], current_time);
+        _ = f.trigger_events(
+            &[
+                TriggerEvent::NormalSent,
+                TriggerEvent::TunnelRecv,
+                TriggerEvent::TunnelSent,
+            ],
+            current_time,
+        );
+        assert!(!f.has_pending_action(MachineId(0)));
+    }
+
+    #[test]
+    fn framework_cancel_block_incoming() {
+        // state 0, wait for a sent packet
+        let s0 = State::new(enum_map! {
+            Event::NormalSent => vec![Trans(1, 1.0)],
+            _ => vec![],
+        });
+        // state 1, block incoming
+        let mut s1 = State::new(enum_map! {
+            Event::NormalRecv => vec![Trans(2, 1.0)],
+            _ => vec![],
+        });
+        s1.action = Some(Action::BlockIncoming {
+            replace: false,
+            timeout: Dist::constant(10.0),
+            duration: Dist::constant(100.0),
+            limit: None,
+        });
+        // state 2, cancel any pending padding
+        let mut s2 = State::new(enum_map! {
+            Event::TunnelSent => vec![Trans(3, 1.0)],
+            _ => vec![],
+        });
+        s2.action = Some(Action::Cancel {
+            timer: Timer::ActionPadding,
+        });
+        // state 3, cancel any pending blocking
+        let mut s3 = State::new(enum_map! {
+            Event::NormalSent => vec![Trans(1, 1.0)],
+            _ => vec![],
+        });
+        s3.action = Some(Action::Cancel {
+            timer: Timer::ActionBlocking,
+        });
+
+        let machines = vec![Machine::new(0, 0.0, 1000, 1.0, vec![s0, s1, s2, s3]).unwrap()];
+        let current_time = Instant::now();
+        let mut f = Framework::new(&machines, 0.0, 0.0, current_time, rand::thread_rng()).unwrap();
+
+        // scheduled blocking of incoming traffic is pending
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+        assert!(f.has_pending_action(MachineId(0)));
+
+        // cancelling padding keeps the blocking
+        _ = f.trigger_events(&[TriggerEvent::NormalRecv], current_time);
+        assert!(f.has_pending_action(MachineId(0)));
+
+        // cancelling blocking does not
+        _ = f.trigger_events(&[TriggerEvent::TunnelSent], current_time);
+        assert_eq!(
+            f.actions[0],
+            Some(TriggerAction::Cancel {
+                machine: MachineId(0),
+                timer: Timer::ActionBlocking,
+            })
+        );
+        assert!(!f.has_pending_action(MachineId(0)));
+    }
+
+    #[test]
+    fn framework_machine_start() {
+        // state 0, pad immediately on start
+        let mut s0 = State::new(enum_map! {
+            Event::MachineStart => vec![Trans(0, 1.0)],
+            _ => vec![],
+        });
+        s0.action = Some(Action::SendPadding {
+            bypass: false,
+            replace: false,
+            timeout: Dist {
+                dist: DistType::Uniform {
+                    low: 0.0,
+                    high: 0.0,
+                },
+                start: 0.0,
+                max: 0.0,
+                ..Default::default()
+            },
+            limit: None,
+        });
+        let m = Machine::new(1000, 1.0, 0, 0.0, vec![s0]).unwrap();
+        let machines = vec![m.clone(), m];
+        let current_time = Instant::now();
+        let mut f = Framework::new(&machines, 0.0, 0.0, current_time, rand::thread_rng()).unwrap();
+        let padding = |mi| TriggerAction::SendPadding {
+            timeout: Duration::ZERO,
+            bypass: false,
+            replace: false,
+            machine: MachineId(mi),
+        };
+
+        // nothing happens before the machines are started
+        let snapshot = f.snapshot();
+        assert_eq!(f.trigger_events(&[], current_time).count(), 0);
+
+        // starting a disabled machine is deferred
+        _ = f.set_enabled(MachineId(1), false);
+        let actions: Vec<_> = f.start(current_time).cloned().collect();
+        assert_eq!(actions, vec![padding(0)]);
+
+        // delivered exactly once per machine
+        _ = f.set_enabled(MachineId(1), true);
+        let actions: Vec<_> = f.start(current_time).cloned().collect();
+        assert_eq!(actions, vec![padding(1)]);
+        assert_eq!(f.start(current_time).count(), 0);
+
+        // and again after restoring a snapshot taken before the start
+        f.restore(snapshot, current_time).unwrap();
+        let actions: Vec<_> = f.start(current_time).cloned().collect();
+        assert_eq!(actions, vec![padding(0), padding(1)]);
+    }
+
+    #[test]
+    fn framework_idle() {
+        // state 0, wait for the application to go idle
+        let s0 = State::new(enum_map! {
+            Event::Idle => vec![Trans(1, 1.0)],
+            _ => vec![],
+        });
+        // state 1, cover traffic until normal traffic resumes
+        let mut s1 = State::new(enum_map! {
+            Event::PaddingSent => vec![Trans(1, 1.0)],
+            Event::NormalSent | Event::NormalRecv => vec![Trans(0, 1.0)],
+            _ => vec![],
+        });
+        s1.action = Some(Action::SendPadding {
+            bypass: false,
+            replace: false,
+            timeout: Dist {
+                dist: DistType::Uniform {
+                    low: 5.0,
+                    high: 5.0,
+                },
+                start: 0.0,
+                max: 0.0,
+                ..Default::default()
+            },
+            limit: None,
+        });
+        let machines = vec![Machine::new(1000, 1.0, 0, 0.0, vec![s0, s1]).unwrap()];
+        let current_time = Instant::now();
+        let mut f = Framework::new(&machines, 0.0, 0.0, current_time, rand::thread_rng()).unwrap();
+        let padding = TriggerAction::SendPadding {
+            timeout: Duration::from_micros(5),
+            bypass: false,
+            replace: false,
+            machine: MachineId(0),
+        };
+
+        // no padding while there is traffic
+        _ = f.trigger_events(
+            &[
+                TriggerEvent::NormalSent,
+                TriggerEvent::TunnelSent,
+                TriggerEvent::NormalRecv,
+            ],
+            current_time,
+        );
+        assert_eq!(f.actions[0], None);
+
+        // padding once idle, until traffic resumes
+        assert!(TriggerEvent::Idle.is_event(Event::Idle));
+        _ = f.trigger_events(&[TriggerEvent::Idle], current_time);
+        assert_eq!(f.actions[0], Some(padding.clone()));
+        _ = f.trigger_events(
+            &[TriggerEvent::PaddingSent {
+                machine: MachineId(0),
+            }],
+            current_time,
+        );
+        assert_eq!(f.actions[0], Some(padding));
+        _ = f.trigger_events(&[TriggerEvent::NormalRecv], current_time);
+        assert_eq!(f.actions[0], None);
+        assert_eq!(f.runtime[0].current_state, 0);
+    }
+
+    #[test]
+    fn framework_independent_timers() {
+        // state 0, wait for a sent packet
+        let s0 = State::new(enum_map! {
+            Event::NormalSent => vec![Trans(1, 1.0)],
+            _ => vec![],
+        });
+        // state 1, pad
+        let mut s1 = State::new(enum_map! {
+            Event::NormalRecv => vec![Trans(2, 1.0)],
+            _ => vec![],
+        });
+        s1.action = Some(Action::SendPadding {
+            bypass: false,
+            replace: false,
+            timeout: Dist::constant(10.0),
+            limit: None,
+        });
+        // state 2, set the internal timer
+        let mut s2 = State::new(enum_map! {
+            Event::NormalRecv => vec![Trans(3, 1.0)],
+            Event::TunnelRecv => vec![Trans(4, 1.0)],
+            _ => vec![],
+        });
+        s2.action = Some(Action::UpdateTimer {
+            replace: false,
+            duration: Dist::constant(20.0),
+            limit: None,
+        });
+        // state 3, cancel the internal timer
+        let mut s3 = State::new(enum_map! {
+            Event::NormalRecv => vec![Trans(2, 1.0)],
+            _ => vec![],
+        });
+        s3.action = Some(Action::Cancel {
+            timer: Timer::Internal,
+        });
+        // state 4, cancel all timers
+        let mut s4 = State::new(enum_map! {
+            _ => vec![],
+        });
+        s4.action = Some(Action::Cancel { timer: Timer::All });
+
+        let machines = vec![Machine::new(1000, 1.0, 0, 0.0, vec![s0, s1, s2, s3, s4]).unwrap()];
+        let current_time = Instant::now();
+        let mut f = Framework::new(&machines, 0.0, 0.0, current_time, rand::thread_rng()).unwrap();
+        let cancel = |timer: Timer| {
+            Some(TriggerAction::Cancel {
+                machine: MachineId(0),
+                timer,
+            })
+        };
+
+        // padding, then the internal timer as a separate action: the padding
+        // remains pending on the action timer
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+        assert_eq!(
+            f.actions[0],
+            Some(TriggerAction::SendPadding {
+                timeout: Duration::from_micros(10),
+                bypass: false,
+                replace: false,
+                machine: MachineId(0),
+            })
+        );
+        _ = f.trigger_events(&[TriggerEvent::NormalRecv], current_time);
+        assert_eq!(
+            f.actions[0],
+            Some(TriggerAction::UpdateTimer {
+                duration: Duration::from_micros(20),
+                replace: false,
+                machine: MachineId(0),
+            })
+        );
+        assert!(f.has_pending_action(MachineId(0)));
+
+        // at most one action per machine and call, the last one
+        assert_eq!(
+            f.trigger_events(
+                &[TriggerEvent::NormalRecv, TriggerEvent::NormalRecv],
+                current_time
+            )
+            .count(),
+            1
+        );
+        assert_eq!(f.runtime[0].current_state, 2);
+
+        // cancelling the internal timer keeps the action timer
+        _ = f.trigger_events(&[TriggerEvent::NormalRecv], current_time);
+        assert_eq!(f.actions[0], cancel(Timer::Internal));
+        assert!(f.has_pending_action(MachineId(0)));
+
+        // cancelling all timers does not
+        _ = f.trigger_events(&[TriggerEvent::NormalRecv], current_time);
+        _ = f.trigger_events(&[TriggerEvent::TunnelRecv], current_time);
+        assert_eq!(f.actions[0], cancel(Timer::All));
+        assert!(!f.has_pending_action(MachineId(0)));
+    }
+
+    #[test]
+    fn framework_merge_blocking() {
+        // block after a sent packet
+        let blocking = |timeout: f64, duration: f64, bypass: bool, replace: bool| {
+            let s0 = State::new(enum_map! {
+                Event::NormalSent => vec![Trans(1, 1.0)],
+                _ => vec![],
+            });
+            let s1 = State::new(enum_map! {
+                _ => vec![],
+            });
+            let mut m = Machine::new(0, 0.0, 1000, 0.0, vec![s0, s1]).unwrap();
+            m.states[1].action = Some(Action::BlockOutgoing {
+                bypass,
+                replace,
+                lane: 0,
+                timeout: Dist::constant(timeout),
+                duration: Dist::constant(duration),
+                limit: None,
+            });
+            m
+        };
+        let action = |machine: usize, timeout: u64, duration: u64, bypass, replace| {
+            TriggerAction::BlockOutgoing {
+                timeout: Duration::from_micros(timeout),
+                duration: Duration::from_micros(duration),
+                bypass,
+                replace,
+                lane: 0,
+                machine: MachineId(machine),
+            }
+        };
+        let current_time = Instant::now();
+
+        // two machines blocking at once, and one later
+        let machines = vec![
+            blocking(5.0, 10.0, true, false),
+            blocking(5.0, 20.0, false, false),
+            blocking(6.0, 30.0, false, false),
+        ];
+        let mut f = Framework::new(&machines, 0.0, 0.0, current_time, rand::thread_rng()).unwrap();
+        assert_eq!(
+            f.trigger_events(&[TriggerEvent::NormalSent], current_time)
+                .count(),
+            3
+        );
+        let mut f = Framework::new(&machines, 0.0, 0.0, current_time, rand::thread_rng()).unwrap();
+        f.set_merge_blocking(true);
+        let actions: Vec<_> = f
+            .trigger_events(&[TriggerEvent::NormalSent], current_time)
+            .cloned()
+            .collect();
+        assert_eq!(
+            actions,
+            vec![
+                action(1, 5, 20, false, false),
+                action(2, 6, 30, false, false)
+            ]
+        );
+
+        // a later action with the replace flag overrides a longer duration
+        let machines = vec![
+            blocking(5.0, 20.0, true, false),
+            blocking(5.0, 10.0, true, true),
+        ];
+        let mut f = Framework::new(&machines, 0.0, 0.0, current_time, rand::thread_rng()).unwrap();
+        f.set_merge_blocking(true);
+        let actions: Vec<_> = f
+            .trigger_events(&[TriggerEvent::NormalSent], current_time)
+            .cloned()
+            .collect();
+        assert_eq!(actions, vec![action(1, 5, 10, true, true)]);
+
+        // but not an earlier one
+        let machines = vec![
+            blocking(5.0, 10.0, false, true),
+            blocking(5.0, 20.0, false, false),
+        ];
+        let mut f = Framework::new(&machines, 0.0, 0.0, current_time, rand::thread_rng()).unwrap();
+        f.set_merge_blocking(true);
+        let actions: Vec<_> = f
+            .trigger_events(&[TriggerEvent::NormalSent], current_time)
+            .cloned()
+            .collect();
+        assert_eq!(actions, vec![action(1, 5, 20, false, true)]);
+    }
+
+    #[test]
+    fn framework_state_limit_saturated() {
+        // state 0 pads with a limit that saturates, state 1 with a small limit
+        let limit = |v: f64| {
+            Some(Dist {
+                dist: DistType::Uniform { low: v, high: v },
+                start: 0.0,
+                max: 0.0,
+                ..Default::default()
+            })
+        };
+        let timeout = Dist {
+            dist: DistType::Uniform {
+                low: 1.0,
+                high: 1.0,
+            },
+            start: 0.0,
+            max: 0.0,
+            ..Default::default()
+        };
+        let s0 = State::padding(
+            enum_map! {
+                Event::NormalSent => vec![Trans(1, 1.0)],
+                _ => vec![],
+            },
+            timeout,
+            limit(1e20),
+        );
+        let s1 = State::padding(
+            enum_map! {
+                Event::NormalSent => vec![Trans(0, 1.0)],
+                _ => vec![],
+            },
+            timeout,
+            limit(2.0),
+        );
+        let m = Machine::new(1000, 1.0, 0, 0.0, vec![s0, s1]).unwrap();
+        assert_eq!(m.saturated_limits(), vec![0]);
+        assert_eq!(m.lint(), vec![crate::Lint::SaturatedLimit(0)]);
+
+        let current_time = Instant::now();
+        let machines = vec![m];
+        let mut f = Framework::new(&machines, 0.0, 0.0, current_time, rand::thread_rng()).unwrap();
+        assert!(f.state_limit_saturated(MachineId(0)));
+        assert_eq!(f.runtime[0].state_limit, STATE_LIMIT_MAX);
+
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+        assert!(!f.state_limit_saturated(MachineId(0)));
+        assert_eq!(f.runtime[0].state_limit, 2);
+
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+        assert!(f.state_limit_saturated(MachineId(0)));
+        assert!(!f.state_limit_saturated(MachineId(1)));
+    }
+
+    #[test]
+    fn framework_timer_granularity() {
+        let s0 = State::padding(
+            enum_map! {
+                Event::NormalSent => vec![Trans(1, 1.0)],
+                _ => vec![],
+            },
+            Dist::constant(1200.0),
+            None,
+        );
+        let s1 = State::blocking(
+            enum_map! {
+                Event::NormalSent => vec![Trans(0, 1.0)],
+                _ => vec![],
+            },
+            Dist::constant(0.0),
+            Dist::constant(1.0),
+            None,
+        );
+        let m = Machine::new(1000, 1.0, 1000, 1.0, vec![s0, s1]).unwrap();
+
+        let current_time = Instant::now();
+        let machines = vec![m];
+        let mut f = Framework::new(&machines, 0.0, 0.0, current_time, rand::thread_rng()).unwrap();
+
+        // by default, microsecond precision
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+        assert_eq!(
+            f.actions[0],
+            Some(TriggerAction::SendPadding {
+                timeout: Duration::from_micros(1200),
+                bypass: false,
+                replace: false,
+                machine: MachineId(0),
+            })
+        );
+
+        // with 1 ms granularity, timeouts and durations are rounded up, but
+        // zero stays zero
+        f.set_timer_granularity(Some(Duration::from_millis(1)));
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+        assert_eq!(
+            f.actions[0],
+            Some(TriggerAction::BlockOutgoing {
+                timeout: Duration::from_micros(0),
+                duration: Duration::from_micros(1000),
+                bypass: false,
+                replace: false,
+                lane: 0,
+                machine: MachineId(0),
+            })
+        );
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+        assert_eq!(
+            f.actions[0],
+            Some(TriggerAction::SendPadding {
+                timeout: Duration::from_micros(2000),
+                bypass: false,
+                replace: false,
+                machine: MachineId(0),
+            })
+        );
+
+        // and back to microsecond precision
+        f.set_timer_granularity(None);
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+        assert_eq!(
+            f.actions[0],
+            Some(TriggerAction::SendPadding {
+                timeout: Duration::from_micros(1200),
+                bypass: false,
+                replace: false,
+                machine: MachineId(0),
+            })
+        );
+    }
+
+    #[test]
+    fn framework_fallback_reset() {
+        // state 0, wait for normal traffic
+        let s0 = State::new(enum_map! {
+            Event::NormalSent => vec![Trans(1, 1.0)],
+            _ => vec![],
+        });
+        // state 1, pad while normal traffic is sent, reset on any other event
+        let mut s1 = State::padding(
+            enum_map! {
+                Event::NormalSent | Event::PaddingSent | Event::TunnelSent => vec![Trans(1, 1.0)],
+                _ => vec![],
+            },
+            Dist {
+                dist: DistType::Uniform {
+                    low: 5.0,
+                    high: 5.0,
+                },
+                start: 0.0,
+                max: 0.0,
+                ..Default::default()
+            },
+            None,
+        );
+        s1.fallback = Some(vec![Trans(0, 1.0)]);
+        let machines = vec![Machine::new(1000, 1.0, 0, 0.0, vec![s0, s1]).unwrap()];
+        let current_time = Instant::now();
+        let mut f = Framework::new(&machines, 0.0, 0.0, current_time, rand::thread_rng()).unwrap();
+
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+        assert_eq!(f.runtime[0].current_state, 1);
+        _ = f.trigger_events(
+            &[
+                TriggerEvent::TunnelSent,
+                TriggerEvent::PaddingSent {
+                    machine: MachineId(0),
+                },
+            ],
+            current_time,
+        );
+        assert_eq!(f.runtime[0].current_state, 1);
+
+        // unexpected events reset the machine
+        for event in [
+            TriggerEvent::NormalRecv,
+            TriggerEvent::TunnelRecv,
+            TriggerEvent::BlockingEnd { lane: 0 },
+            TriggerEvent::Idle,
+        ] {
+            _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+            assert_eq!(f.runtime[0].current_state, 1);
+            _ = f.trigger_events(&[event], current_time);
+            assert_eq!(f.runtime[0].current_state, 0);
+        }
+    }
+
+    #[test]
+    fn framework_refresh_limit() {
+        // state 1 pads twice, and may pad twice more after each received
+        // normal packet, without leaving the state
+        let s0 = State::new(enum_map! {
+            Event::NormalSent => vec![Trans(1, 1.0)],
+            _ => vec![],
+        });
+        let mut s1 = State::padding(
+            enum_map! {
+                Event::PaddingSent => vec![Trans(1, 1.0)],
+                Event::NormalRecv => vec![Trans(1, 1.0)],
+                _ => vec![],
+            },
+            Dist::new(
+                DistType::Uniform {
+                    low: 1.0,
+                    high: 1.0,
+                },
+                0.0,
+                0.0,
+            ),
+            None,
+        );
+        s1.fixed_limit = Some(2);
+        s1.refresh_limit_on = Some(Event::NormalRecv);
+        let machines = vec![Machine::new(u64::MAX, 0.0, 0, 0.0, vec![s0, s1]).unwrap()];
+        let current_time = Instant::now();
+        let mut f = Framework::new(&machines, 0.0, 0.0, current_time, rand::thread_rng()).unwrap();
+        let machine = MachineId(0);
+        let padding_sent = [TriggerEvent::PaddingSent { machine }];
+
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+        assert_eq!(f.state_limit(machine), Some(2));
+        assert_eq!(f.trigger_events(&padding_sent, current_time).count(), 1);
+        assert_eq!(f.trigger_events(&padding_sent, current_time).count(), 0);
+        assert_eq!(f.state_limit(machine), Some(0));
+        assert_eq!(f.runtime[0].current_state, 1);
+
+        // exhausted: other self-transitions schedule nothing
+        assert_eq!(f.trigger_events(&padding_sent, current_time).count(), 0);
+
+        // the refresh restores the limit, and the action it schedules is
+        // counted against the refreshed limit once taken
+        assert_eq!(
+            f.trigger_events(&[TriggerEvent::NormalRecv], current_time)
+                .count(),
+            1
+        );
+        assert_eq!(f.state_limit(machine), Some(2));
+        assert_eq!(f.trigger_events(&padding_sent, current_time).count(), 1);
+        assert_eq!(f.state_limit(machine), Some(1));
+        assert_eq!(f.trigger_events(&padding_sent, current_time).count(), 0);
+        assert_eq!(f.state_limit(machine), Some(0));
+    }
+
+    #[test]
+    fn framework_fixed_limit() {
+        // state 1 pads twice, then returns to state 0
+        let s0 = State::new(enum_map! {
+            Event::NormalSent => vec![Trans(1, 1.0)],
+            _ => vec![],
+        });
+        let mut s1 = State::padding(
+            enum_map! {
+                Event::PaddingSent => vec![Trans(1, 1.0)],
+                Event::LimitReached => vec![Trans(0, 1.0)],
+                _ => vec![],
+            },
+            Dist::new(
+                DistType::Uniform {
+                    low: 1.0,
+                    high: 1.0,
+                },
+                0.0,
+                0.0,
+            ),
+            None,
+        );
+        s1.fixed_limit = Some(2);
+        let machines = vec![Machine::new(u64::MAX, 0.0, 0, 0.0, vec![s0, s1]).unwrap()];
+        let current_time = Instant::now();
+        let mut f = Framework::new(&machines, 0.0, 0.0, current_time, rand::thread_rng()).unwrap();
+        let machine = MachineId(0);
+        let padding_sent = [TriggerEvent::PaddingSent { machine }];
+
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+        assert_eq!(f.runtime[0].current_state, 1);
+        assert_eq!(f.state_limit(machine), Some(2));
+        assert_eq!(f.trigger_events(&padding_sent, current_time).count(), 1);
+        assert_eq!(f.state_limit(machine), Some(1));
+        assert_eq!(f.trigger_events(&padding_sent, current_time).count(), 0);
+        assert_eq!(f.runtime[0].current_state, 0);
+
+        // the limit is fixed again on entering the state, unless overridden
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+        assert_eq!(f.state_limit(machine), Some(2));
+        f.set_state_limit(machine, 1);
+        assert_eq!(f.state_limit(machine), Some(1));
+        assert_eq!(f.trigger_events(&padding_sent, current_time).count(), 0);
+        assert_eq!(f.runtime[0].current_state, 0);
+
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+        f.set_state_limit(machine, 5);
+        for _ in 0..4 {
+            assert_eq!(f.trigger_events(&padding_sent, current_time).count(), 1);
+        }
+        assert_eq!(f.trigger_events(&padding_sent, current_time).count(), 0);
+        assert_eq!(f.runtime[0].current_state, 0);
+
+        // no such machine
+        assert_eq!(f.state_limit(MachineId(1)), None);
+        f.set_state_limit(MachineId(1), 1);
+    }
+
+    #[test]
+    fn framework_time_units() {
+        // pads on NormalSent with a constant timeout in the given unit, and
+        // updates its timer on NormalRecv with a constant duration of a second
+        let machine = |timeout: f64, unit| {
+            let mut s0 = State::new(enum_map! {
+                Event::NormalSent => vec![Trans(0, 1.0)],
+                Event::NormalRecv => vec![Trans(1, 1.0)],
+                _ => vec![],
+            });
+            s0.action = Some(Action::SendPadding {
+                bypass: false,
+                replace: false,
+                timeout: Dist::new(
+                    DistType::Uniform {
+                        low: timeout,
+                        high: timeout,
+                    },
+                    0.0,
+                    0.0,
+                )
+                .with_unit(unit),
+                limit: None,
+            });
+            let mut s1 = State::new(enum_map! {
+                Event::NormalSent => vec![Trans(0, 1.0)],
+                _ => vec![],
+            });
+            s1.action = Some(Action::UpdateTimer {
+                replace: false,
+                duration: Dist::new(
+                    DistType::Uniform {
+                        low: 1.0,
+                        high: 1.0,
+                    },
+                    0.0,
+                    0.0,
+                )
+                .with_unit(TimeUnit::Seconds),
+                limit: None,
+            });
+            Machine::new(u64::MAX, 0.0, 0, 0.0, vec![s0, s1]).unwrap()
+        };
+
+        let current_time = Instant::now();
+        for (timeout, unit, expected) in [
+            (2.0, TimeUnit::Milliseconds, Duration::from_millis(2)),
+            (2.0, TimeUnit::Microseconds, Duration::from_micros(2)),
+            // rounded to microseconds
+            (1500.0, TimeUnit::Nanoseconds, Duration::from_micros(2)),
+            (0.25, TimeUnit::Seconds, Duration::from_millis(250)),
+        ] {
+            let machines = vec![machine(timeout, unit)];
+            let mut f =
+                Framework::new(&machines, 0.0, 0.0, current_time, rand::thread_rng()).unwrap();
+            assert_eq!(
+                f.trigger_events(&[TriggerEvent::NormalSent], current_time)
+                    .collect::<Vec<_>>(),
+                vec![&TriggerAction::SendPadding {
+                    timeout: expected,
+                    bypass: false,
+                    replace: false,
+                    machine: MachineId(0),
+                }]
+            );
+            assert_eq!(
+                f.trigger_events(&[TriggerEvent::NormalRecv], current_time)
+                    .collect::<Vec<_>>(),
+                vec![&TriggerAction::UpdateTimer {
+                    duration: Duration::from_secs(1),
+                    replace: false,
+                    machine: MachineId(0),
+                }]
+            );
+        }
+
+        // the caps apply after conversion
+        let a = machine(2.0, TimeUnit::Seconds).states[0].action.unwrap();
+        assert_eq!(a.expected_timeout().unwrap(), 2_000_000.0);
+        assert!(!a.exceeds_caps());
+        let machines = vec![machine(2.0 * 24.0 * 60.0 * 60.0, TimeUnit::Seconds)];
+        let a = machines[0].states[0].action.unwrap();
+        assert_eq!(
+            a.expected_timeout().unwrap(),
+            crate::constants::MAX_SAMPLED_TIMEOUT
+        );
+        assert!(a.exceeds_caps());
+        let mut f = Framework::new(&machines, 0.0, 0.0, current_time, rand::thread_rng()).unwrap();
+        assert_eq!(
+            f.trigger_events(&[TriggerEvent::NormalSent], current_time)
+                .collect::<Vec<_>>(),
+            vec![&TriggerAction::SendPadding {
+                timeout: Duration::from_micros(crate::constants::MAX_SAMPLED_TIMEOUT as u64),
+                bypass: false,
+                replace: false,
+                machine: MachineId(0),
+            }]
+        );
+    }
+
+    #[test]
+    fn framework_padding_recv_limit() {
+        // pads on NormalSent, with an allowance of one padding packet
+        let s0 = State::padding(
+            enum_map! {
+                Event::NormalSent => vec![Trans(0, 1.0)],
+                _ => vec![],
+            },
+            Dist {
+                dist: DistType::Uniform {
+                    low: 1.0,
+                    high: 1.0,
+                },
+                start: 0.0,
+                max: 0.0,
+                ..Default::default()
+            },
+            None,
+        );
+        let machines = vec![Machine::new(1, 0.0, 0, 0.0, vec![s0]).unwrap()];
+        let current_time = Instant::now();
+        let mut f = Framework::new(&machines, 0.0, 0.0, current_time, rand::thread_rng()).unwrap();
+        assert_eq!(f.max_padding_recv_frac(), 0.0);
+        assert!(f.set_max_padding_recv_frac(-0.1).is_err());
+        assert!(f.set_max_padding_recv_frac(1.1).is_err());
+        f.set_max_padding_recv_frac(0.5).unwrap();
+        assert_eq!(f.max_padding_recv_frac(), 0.5);
+
+        // nothing received yet, so no limit
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+        assert!(f.actions[0].is_some());
+
+        // the allowed padding packet bypasses the limit
+        _ = f.trigger_events(&[TriggerEvent::PaddingRecv], current_time);
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+        assert!(f.actions[0].is_some());
+        _ = f.trigger_events(
+            &[TriggerEvent::PaddingSent {
+                machine: MachineId(0),
+            }],
+            current_time,
+        );
+
+        // all received packets are padding
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+        assert_eq!(f.actions[0], None);
+        assert_eq!(
+            f.last_suppression(MachineId(0)),
+            Some(SuppressReason::FrameworkPaddingRecvLimit)
+        );
+
+        // below the limit with more normal packets received, then at the limit
+        _ = f.trigger_events(&[TriggerEvent::NormalRecv], current_time);
+        _ = f.trigger_events(&[TriggerEvent::NormalRecv], current_time);
+        assert_eq!(f.recv_packets(), (2, 1));
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+        assert!(f.actions[0].is_some());
+        _ = f.trigger_events(&[TriggerEvent::PaddingRecv], current_time);
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+        assert_eq!(f.actions[0], None);
+
+        // sent padding does not count, and no limit lifts the suppression
+        assert_eq!(f.recv_packets(), (2, 2));
+        f.set_max_padding_recv_frac(0.0).unwrap();
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+        assert!(f.actions[0].is_some());
+    }
+
+    #[test]
+    fn framework_last_active_machines() {
+        // machine 0 pads on NormalSent, machine 1 on NormalRecv, and machine 2
+        // never does anything
+        let padding = |event| {
+            State::padding(
+                enum_map! {
+                    e if e == event => vec![Trans(0, 1.0)],
+                    _ => vec![],
+                },
+                Dist {
+                    dist: DistType::Uniform {
+                        low: 1.0,
+                        high: 1.0,
+                    },
+                    start: 0.0,
+                    max: 0.0,
+                    ..Default::default()
+                },
+                None,
+            )
+        };
+        let machines = vec![
+            Machine::new(u64::MAX, 0.0, 0, 0.0, vec![padding(Event::NormalSent)]).unwrap(),
+            Machine::new(u64::MAX, 0.0, 0, 0.0, vec![padding(Event::NormalRecv)]).unwrap(),
+            Machine::noop(),
+        ];
+        let current_time = Instant::now();
+        let mut f = Framework::new(&machines, 0.0, 0.0, current_time, rand::thread_rng()).unwrap();
+        assert_eq!(f.last_active_machines().count(), 0);
+
+        for (events, active) in [
+            (vec![TriggerEvent::NormalSent], vec![0]),
+            (vec![TriggerEvent::NormalRecv], vec![1]),
+            (
+                vec![TriggerEvent::NormalRecv, TriggerEvent::NormalSent],
+                vec![0, 1],
+            ),
+            (vec![TriggerEvent::TunnelSent], vec![]),
+        ] {
+            let returned: Vec<MachineId> = f
+                .trigger_events(&events, current_time)
+                .map(|action| match action {
+                    TriggerAction::SendPadding { machine, .. } => *machine,
+                    _ => panic!("unexpected action {action:?}"),
+                })
+                .collect();
+            let active: Vec<MachineId> = active.into_iter().map(MachineId).collect();
+            assert_eq!(returned, active);
+            assert_eq!(f.last_active_machines().collect::<Vec<_>>(), active);
+        }
+    }
+
+    #[test]
+    fn framework_previous_state() {
+        // 0 -> 1 on NormalSent, 1 -> 2 on NormalRecv, and both 1 and 2 bounce
+        // back to their previous state on PaddingRecv
+        let s0 = State::new(enum_map! {
+            Event::NormalSent => vec![Trans(1, 1.0)],
+            Event::PaddingRecv => vec![Trans(STATE_PREVIOUS, 1.0)],
+            _ => vec![],
+        });
+        let s1 = State::new(enum_map! {
+            Event::NormalRecv => vec![Trans(2, 1.0)],
+            Event::PaddingRecv => vec![Trans(STATE_PREVIOUS, 1.0)],
+            _ => vec![],
+        });
+        let s2 = State::new(enum_map! {
+            Event::PaddingRecv => vec![Trans(STATE_PREVIOUS, 1.0)],
+            _ => vec![],
+        });
+        let machines = vec![Machine::new(0, 0.0, 0, 0.0, vec![s0, s1, s2]).unwrap()];
+        let current_time = Instant::now();
+        let mut f = Framework::new(&machines, 0.0, 0.0, current_time, rand::thread_rng()).unwrap();
+
+        // before any change of state, the previous state is the first
+        _ = f.trigger_events(&[TriggerEvent::PaddingRecv], current_time);
+        assert_eq!(f.runtime[0].current_state, 0);
+
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+        assert_eq!(f.runtime[0].current_state, 1);
+        _ = f.trigger_events(&[TriggerEvent::PaddingRecv], current_time);
+        assert_eq!(f.runtime[0].current_state, 0);
+        _ = f.trigger_events(&[TriggerEvent::PaddingRecv], current_time);
+        assert_eq!(f.runtime[0].current_state, 1);
+
+        // bounces between the last two states
+        _ = f.trigger_events(&[TriggerEvent::NormalRecv], current_time);
+        assert_eq!(f.runtime[0].current_state, 2);
+        for _ in 0..3 {
+            _ = f.trigger_events(&[TriggerEvent::PaddingRecv], current_time);
+            assert_eq!(f.runtime[0].current_state, 1);
+            _ = f.trigger_events(&[TriggerEvent::PaddingRecv], current_time);
+            assert_eq!(f.runtime[0].current_state, 2);
+        }
+    }
+
+    #[test]
+    fn framework_random_state() {
+        // every state jumps to a random state on NormalSent
+        let s = State::new(enum_map! {
+            Event::NormalSent => vec![Trans(STATE_RANDOM, 1.0)],
+            _ => vec![],
+        });
+        let machines = vec![Machine::new(0, 0.0, 0, 0.0, vec![s; 4]).unwrap()];
+        let current_time = Instant::now();
+        let mut f = Framework::new(&machines, 0.0, 0.0, current_time, rand::thread_rng()).unwrap();
+
+        let mut visits = [0; 4];
+        for _ in 0..4000 {
+            _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+            visits[f.runtime[0].current_state] += 1;
+        }
+        for v in visits {
+            assert!((800..1200).contains(&v), "{visits:?}");
+        }
+    }
+
+    #[test]
+    fn framework_padding_sent_after_end() {
+        // machine 0 pads once with a limit and shared limit, then ends on
+        // PaddingRecv, keeping its pending padding
+        let s1 = State::padding(
+            enum_map! {
+                Event::PaddingRecv => vec![Trans(STATE_END, 1.0)],
+                _ => vec![],
+            },
+            Dist {
+                dist: DistType::Uniform {
+                    low: 5.0,
+                    high: 5.0,
+                },
+                start: 0.0,
+                max: 0.0,
+                ..Default::default()
+            },
+            Some(Dist {
+                dist: DistType::Uniform {
+                    low: 1.0,
+                    high: 1.0,
+                },
+                start: 0.0,
+                max: 0.0,
+                ..Default::default()
+            }),
+        );
+        let s0 = State::new(enum_map! {
+            Event::NormalSent => vec![Trans(1, 1.0)],
+            _ => vec![],
+        });
+        let mut m = Machine::new(1000, 1.0, 0, 0.0, vec![s0, s1]).unwrap();
+        m.shared_limits = vec![Dist {
+            dist: DistType::Uniform {
+                low: 1.0,
+                high: 1.0,
+            },
+            start: 0.0,
+            max: 0.0,
+            ..Default::default()
+        }];
+        m.states[1].shared_limit = Some(0);
+        let machines = vec![m, Machine::noop()];
+        let current_time = Instant::now();
+        let mut f = Framework::new(&machines, 0.0, 0.0, current_time, rand::thread_rng()).unwrap();
+
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+        assert!(f.actions[0].is_some());
+        _ = f.trigger_events(&[TriggerEvent::PaddingRecv], current_time);
+        assert_eq!(f.runtime[0].current_state, STATE_END);
+        let limits = (f.runtime[0].state_limit, f.runtime[0].shared_limits.clone());
+
+        // the pending padding is sent after the machine ended, and reported
+        // (also more than once): it counts globally, without affecting the
+        // limits of the terminated machine or causing any actions
+        for n in 1..=3 {
+            let actions = f
+                .trigger_events(
+                    &[TriggerEvent::PaddingSent {
+                        machine: MachineId(0),
+                    }],
+                    current_time,
+                )
+                .count();
+            assert_eq!(actions, 0);
+            assert_eq!(f.padding_sent_packets, n);
+            assert_eq!(f.runtime[0].current_state, STATE_END);
+            assert_eq!(
+                (f.runtime[0].state_limit, f.runtime[0].shared_limits.clone()),
+                limits
+            );
+        }
+        assert_eq!(f.runtime[1].padding_sent, 0);
+    }
+
+    #[test]
+    fn framework_clock_regression() {
+        // a machine that blocks on each packet sent, for accounting blocking
+        let mut s0 = State::new(enum_map! {
+            Event::NormalSent => vec![Trans(0, 1.0)],
+            _ => vec![],
+        });
+        s0.action = Some(Action::BlockOutgoing {
+            bypass: false,
+            replace: false,
+            lane: 0,
+            timeout: Dist::new(
+                DistType::Uniform {
+                    low: 0.0,
+                    high: 0.0,
+                },
+                0.0,
+                0.0,
+            ),
+            duration: Dist::new(
+                DistType::Uniform {
+                    low: 100.0,
+                    high: 100.0,
+                },
+                0.0,
+                0.0,
+            ),
+            limit: None,
+        });
+        let m = Machine::new(0, 0.0, 0, 0.5, vec![s0]).unwrap();
+        let machines = vec![m];
+        let start = Instant::now();
+        let mut f = Framework::new(&machines, 0.0, 0.5, start, rand::thread_rng()).unwrap();
+        assert_eq!(f.now(), start);
+
+        let later = start + Duration::from_micros(1000);
+        _ = f.trigger_events(
+            &[TriggerEvent::BlockingBegin {
+                machine: MachineId(0),
+                lane: 0,
+            }],
+            later,
+        );
+        assert_eq!(f.now(), later);
+        assert_eq!(f.clock_regressions(), 0);
+
+        // the clock goes backwards: the time is ignored, and accounting is
+        // done as of the latest time
+        let earlier = start + Duration::from_micros(500);
+        let actions: Vec<_> = f
+            .trigger_events(&[TriggerEvent::NormalSent], earlier)
+            .cloned()
+            .collect();
+        assert_eq!(f.now(), later);
+        assert_eq!(f.clock_regressions(), 1);
+        assert_eq!(
+            f.machine_elapsed(MachineId(0)),
+            Some(Duration::from_micros(1000))
+        );
+        // blocking for no time, so not suppressed by the limit
+        assert_eq!(actions.len(), 1);
+
+        // once the clock catches up, time passes as usual
+        let latest = start + Duration::from_micros(1500);
+        _ = f.trigger_events(&[TriggerEvent::BlockingEnd { lane: 0 }], latest);
+        assert_eq!(f.now(), latest);
+        assert_eq!(f.clock_regressions(), 1);
+    }
+
+    #[test]
+    fn framework_set_enabled() {
+        // pad on each packet sent, ending on a signal
+        let mut s0 = State::new(enum_map! {
+            Event::NormalSent => vec![Trans(0, 1.0)],
+            Event::Signal => vec![Trans(STATE_END, 1.0)],
+            _ => vec![],
+        });
+        s0.action = Some(Action::SendPadding {
+            bypass: false,
+            replace: false,
+            timeout: Dist::new(
+                DistType::Uniform {
+                    low: 10.0,
+                    high: 10.0,
+                },
+                0.0,
+                0.0,
+            ),
+            limit: None,
+        });
+        let m = Machine::new(1000, 1.0, 0, 0.0, vec![s0]).unwrap();
+        let machines = vec![m.clone(), m];
+        let start = Instant::now();
+        let mut f = Framework::new(&machines, 0.0, 0.0, start, rand::thread_rng()).unwrap();
+        let machines_of = |f: &mut Framework<_, _>, events: &[TriggerEvent]| -> Vec<usize> {
+            f.trigger_events(events, start)
+                .map(|a| match a {
+                    TriggerAction::SendPadding { machine, .. } => machine.into_raw(),
+                    _ => panic!("unexpected action {:?}", a),
+                })
+                .collect()
+        };
+        assert_eq!(machines_of(&mut f, &[TriggerEvent::NormalSent]), [0, 1]);
+        assert!(f.has_pending_action(MachineId(1)));
+
+        // disabling cancels pending actions, once
+        assert!(f.is_enabled(MachineId(1)));
+        assert_eq!(
+            f.set_enabled(MachineId(1), false),
+            Some(TriggerAction::Cancel {
+                machine: MachineId(1),
+                timer: Timer::All,
+            })
+        );
+        assert_eq!(f.set_enabled(MachineId(1), false), None);
+        assert_eq!(f.set_enabled(MachineId(2), false), None);
+        assert!(!f.is_enabled(MachineId(1)));
+        assert!(!f.is_enabled(MachineId(2)));
+        assert!(!f.has_pending_action(MachineId(1)));
+
+        // a disabled machine processes no events, not even signals
+        assert_eq!(machines_of(&mut f, &[TriggerEvent::NormalSent]), [0]);
+        assert_eq!(
+            machines_of(
+                &mut f,
+                &[TriggerEvent::PaddingSent {
+                    machine: MachineId(1),
+                }]
+            ),
+            [] as [usize; 0]
+        );
+        assert_eq!(f.runtime[1].normal_sent, 1);
+        assert_eq!(f.runtime[1].padding_sent, 0);
+        // global accounting continues
+        assert_eq!(f.normal_sent_packets, 2);
+        assert_eq!(f.padding_sent_packets, 1);
+        f.signal_pending = Some(SignalTarget::All);
+        _ = f.trigger_events(&[], start);
+        assert_eq!(f.runtime[0].current_state, STATE_END);
+        assert_eq!(f.runtime[1].current_state, 0);
+
+        // enabled again, continuing in the same state
+        assert_eq!(f.set_enabled(MachineId(1), true), None);
+        assert!(f.is_enabled(MachineId(1)));
+        assert_eq!(machines_of(&mut f, &[TriggerEvent::NormalSent]), [1]);
+        assert_eq!(f.runtime[1].normal_sent, 2);
+    }
+
+    #[test]
+    fn framework_gap_condition() {
+        // two sends within a millisecond go to the burst state, padding
+        let mut s0 = State::new(enum_map! {
+            Event::NormalSent => vec![Trans(1, 1.0)],
+            _ => vec![],
+        });
+        s0.gap_condition = Some(GapCondition {
+            event: Event::NormalSent,
+            threshold: 1000,
+            below: true,
+        });
+        let mut s1 = State::new(enum_map! {
+            Event::NormalSent => vec![Trans(0, 1.0)],
+            _ => vec![],
+        });
+        s1.action = Some(Action::SendPadding {
+            bypass: false,
+            replace: false,
+            timeout: Dist::new(
+                DistType::Uniform {
+                    low: 0.0,
+                    high: 0.0,
+                },
+                0.0,
+                0.0,
+            ),
+            limit: None,
+        });
+        // and back on a send after a pause of at least a millisecond
+        s1.gap_condition = Some(GapCondition {
+            event: Event::NormalSent,
+            threshold: 1000,
+            below: false,
+        });
+        let m = Machine::new(1000, 1.0, 0, 0.0, vec![s0, s1]).unwrap();
+        let machines = vec![m, Machine::noop()];
+        let start = Instant::now();
+        let mut f = Framework::new(&machines, 0.0, 0.0, start, rand::thread_rng()).unwrap();
+        assert_eq!(f.runtime[0].last_event.len(), EVENT_NUM);
+        assert!(f.runtime[1].last_event.is_empty());
+
+        let send = |f: &mut Framework<_, _>, micros: u64| -> usize {
+            let t = start + Duration::from_micros(micros);
+            _ = f.trigger_events(&[TriggerEvent::NormalSent], t);
+            f.runtime[0].current_state
+        };
+
+        // spaced sends, including the first without a previous send
+        assert_eq!(send(&mut f, 0), 0);
+        assert_eq!(send(&mut f, 1000), 0);
+        assert_eq!(send(&mut f, 2500), 0);
+        // rapid sends, staying in the burst state while rapid
+        assert_eq!(send(&mut f, 3000), 1);
+        assert_eq!(send(&mut f, 3100), 1);
+        assert_eq!(send(&mut f, 4099), 1);
+        // a pause, back to the first state
+        assert_eq!(send(&mut f, 5099), 0);
+        assert_eq!(send(&mut f, 5100), 1);
+
+        // other events do not affect the gap
+        _ = f.trigger_events(
+            &[TriggerEvent::NormalRecv],
+            start + Duration::from_micros(9000),
+        );
+        assert_eq!(send(&mut f, 9100), 0);
+
+        // two sends in the same call have no gap
+        let t = start + Duration::from_micros(20_000);
+        _ = f.trigger_events(&[TriggerEvent::NormalSent, TriggerEvent::NormalSent], t);
+        assert_eq!(f.runtime[0].current_state, 1);
+    }
+
+    #[test]
+    fn framework_blocking_bypassable() {
+        // block without bypass, and on blocking begin replace the blocking
+        // with a shorter bypassable one
+        let block = |bypass: bool, replace: bool, duration: f64| {
+            Some(Action::BlockOutgoing {
+                bypass,
+                replace,
+                lane: 0,
+                timeout: Dist {
+                    dist: DistType::Uniform {
+                        low: 0.0,
+                        high: 0.0,
+                    },
+                    start: 0.0,
+                    max: 0.0,
+                    ..Default::default()
+                },
+                duration: Dist {
+                    dist: DistType::Uniform {
+                        low: duration,
+                        high: duration,
+                    },
+                    start: 0.0,
+                    max: 0.0,
+                    ..Default::default()
+                },
+                limit: None,
+            })
+        };
+        let s0 = State::new(enum_map! {
+            Event::NormalSent => vec![Trans(1, 1.0)],
+            _ => vec![],
+        });
+        let mut s1 = State::new(enum_map! {
+            Event::BlockingBegin => vec![Trans(2, 1.0)],
+            _ => vec![],
+        });
+        s1.action = block(false, false, 1000.0);
+        let mut s2 = State::new(enum_map! {
+            Event::BlockingBegin => vec![Trans(3, 1.0)],
+            _ => vec![],
+        });
+        s2.action = block(true, true, 10.0);
+        // a shorter blocking that neither replaces nor extends the blocking
+        let mut s3 = State::new(enum_map! {
+            _ => vec![],
+        });
+        s3.action = block(false, false, 1.0);
+        let m = Machine::new(0, 0.0, 0, 0.0, vec![s0, s1, s2, s3]).unwrap();
+
+        let mut current_time = Instant::now();
+        let mut f = Framework::new([m], 0.0, 0.0, current_time, rand::thread_rng()).unwrap();
+        assert!(!f.blocking_bypassable(0));
+
+        _ = f.trigger_events(&[TriggerEvent::NormalSent], current_time);
+        assert!(!f.blocking_bypassable(0));
+        _ = f.trigger_events(
+            &[TriggerEvent::BlockingBegin {
+                machine: MachineId(0),
+                lane: 0,
+            }],
+            current_time,
+        );
+        assert!(!f.blocking_bypassable(0));
+
+        // the overwrite with the replace flag makes the blocking bypassable
+        current_time = current_time.add(Duration::from_micros(5));
+        _ = f.trigger_events(
+            &[TriggerEvent::BlockingBegin {
+                machine: MachineId(0),
+                lane: 0,
+            }],
+            current_time,
+        );
+        assert!(f.blocking_bypassable(0));
+
+        // the shorter blocking does not overwrite it
+        current_time = current_time.add(Duration::from_micros(1));
+        _ = f.trigger_events(
+            &[TriggerEvent::BlockingBegin {
+                machine: MachineId(0),
+                lane: 0,
+            }],
+            current_time,
+        );
+        assert!(f.blocking_bypassable(0));
+
+        current_time = current_time.add(Duration::from_micros(10));
+        _ = f.trigger_events(&[TriggerEvent::BlockingEnd { lane: 0 }], current_time);
+        assert!(!f.blocking_bypassable(0));
+    }
+
+    #[test]
+    fn framework_blocking_lanes() {
+        // one machine blocking each of lanes 0 and 1 on normal sent
+        let block = |lane: u8, bypass: bool| {
+            let s0 = State::new(enum_map! {
+                Event::NormalSent => vec![Trans(1, 1.0)],
+                _ => vec![],
+            });
+            let mut s1 = State::new(enum_map! {
+                _ => vec![],
+            });
+            s1.action = Some(Action::BlockOutgoing {
+                bypass,
+                replace: false,
+                lane,
+                timeout: Dist {
+                    dist: DistType::Uniform {
+                        low: 0.0,
+                        high: 0.0,
+                    },
+                    start: 0.0,
+                    max: 0.0,
+                    ..Default::default()
+                },
+                duration: Dist {
+                    dist: DistType::Uniform {
+                        low: 1000.0,
+                        high: 1000.0,
+                    },
+                    start: 0.0,
+                    max: 0.0,
+                    ..Default::default()
+                },
+                limit: None,
+            });
+            Machine::new(0, 0.0, 0, 0.0, vec![s0, s1]).unwrap()
+        };
+        let machines = vec![block(0, false), block(1, true)];
+
+        let start = Instant::now();
+        let mut f = Framework::new(&machines, 0.0, 0.0, start, rand::thread_rng()).unwrap();
+        let actions: Vec<_> = f
+            .trigger_events(&[TriggerEvent::NormalSent], start)
+            .cloned()
+            .collect();
+        assert_eq!(actions.len(), 2);
+        for (i, action) in actions.iter().enumerate() {
+            match action {
+                TriggerAction::BlockOutgoing { lane, machine, .. } => {
+                    assert_eq!(*lane as usize, i);
+                    assert_eq!(*machine, MachineId(i));
+                }
+                _ => panic!("unexpected action {:?}", action),
+            }
+        }
+
+        // lane 0 blocks at 0 us, lane 1 at 5 us
+        _ = f.trigger_events(
+            &[TriggerEvent::BlockingBegin {
+                machine: MachineId(0),
+                lane: 0,
+            }],
+            start,
+        );
+        _ = f.trigger_events(
+            &[TriggerEvent::BlockingBegin {
+                machine: MachineId(1),
+                lane: 1,
+            }],
+            start.add(Duration::from_micros(5)),
+        );
+        assert_eq!(f.blocking_owner(0), Some(MachineId(0)));
+        assert_eq!(f.blocking_owner(1), Some(MachineId(1)));
+        assert_eq!(f.blocking_owner(2), None);
+        assert!(!f.blocking_bypassable(0));
+        assert!(f.blocking_bypassable(1));
+
+        // lane 0 ends at 10 us, the aggregate blocking is still ongoing
+        _ = f.trigger_events(
+            &[TriggerEvent::BlockingEnd { lane: 0 }],
+            start.add(Duration::from_micros(10)),
+        );
+        assert_eq!(f.blocking_owner(0), None);
+        assert_eq!(f.blocking_owner(1), Some(MachineId(1)));
+        assert!(f.blocking_active);
+        assert_eq!(f.blocking_duration, Duration::ZERO);
+        assert_eq!(f.lane_blocking_duration(0), Duration::from_micros(10));
+        assert_eq!(f.lane_blocking_duration(1), Duration::from_micros(5));
+
+        // lane 1 ends at 20 us, ending the aggregate blocking
+        _ = f.trigger_events(
+            &[TriggerEvent::BlockingEnd { lane: 1 }],
+            start.add(Duration::from_micros(20)),
+        );
+        assert!(!f.blocking_active);
+        assert_eq!(f.blocking_duration, Duration::from_micros(20));
+        assert_eq!(f.lane_blocking_duration(0), Duration::from_micros(10));
+        assert_eq!(f.lane_blocking_duration(1), Duration::from_micros(15));
+        for runtime in f.runtime.iter() {
+            assert_eq!(runtime.blocking_duration, Duration::from_micros(20));
+        }
+
+        // events for unsupported lanes are ignored
+        _ = f.trigger_events(
+            &[TriggerEvent::BlockingBegin {
+                machine: MachineId(0),
+                lane: BLOCKING_LANES as u8,
+            }],
+            start.add(Duration::from_micros(30)),
+        );
+        assert!(!f.blocking_active);
+        assert_eq!(
+            f.lane_blocking_duration(BLOCKING_LANES as u8),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn framework_blocking_lane_limits() {
+        // one machine blocking each of lanes 0 and 1 for 10 us on every
+        // normal packet sent, with a machine blocking limit of max_frac
+        let block = |lane: u8, max_frac: f64| {
+            let mut s0 = State::new(enum_map! {
+                Event::NormalSent => vec![Trans(0, 1.0)],
+                _ => vec![],
+            });
+            s0.action = Some(Action::BlockOutgoing {
+                bypass: false,
+                replace: false,
+                lane,
+                timeout: Dist::constant(0.0),
+                duration: Dist::constant(10.0),
+                limit: None,
+            });
+            Machine::new(0, 0.0, 0, max_frac, vec![s0]).unwrap()
+        };
+
+        // the limits of the machines, then of the framework
+        for (machines, max_frac, reason) in [
+            (
+                vec![block(0, 0.5), block(1, 0.5)],
+                0.0,
+                SuppressReason::MachineBlockingLimit,
+            ),
+            (
+                vec![block(0, 0.0), block(1, 0.0)],
+                0.5,
+                SuppressReason::FrameworkBlockingLimit,
+            ),
+        ] {
+            let start = Instant::now();
+            let at = |micros| start.add(Duration::from_micros(micros));
+            let mut f =
+                Framework::new(&machines, 0.0, max_frac, start, rand::thread_rng()).unwrap();
+
+            // only lane 0 blocks, for 10 us
+            _ = f.trigger_events(&[TriggerEvent::NormalSent], at(0));
+            _ = f.trigger_events(
+                &[TriggerEvent::BlockingBegin {
+                    machine: MachineId(0),
+                    lane: 0,
+                }],
+                at(0),
+            );
+            _ = f.trigger_events(&[TriggerEvent::BlockingEnd { lane: 0 }], at(10));
+            assert_eq!(
+                f.runtime[0].lane_blocking_duration[0],
+                Duration::from_micros(10)
+            );
+            assert_eq!(f.runtime[0].lane_blocking_duration[1], Duration::ZERO);
+
+            // lane 0 is at its cap, blocked 10 of 15 us, while lane 1 keeps
+            // blocking, as does the aggregate of the lanes
+            _ = f.trigger_events(&[TriggerEvent::NormalSent], at(15));
+            assert_eq!(f.actions[0], None);
+            assert_eq!(f.last_suppression(MachineId(0)), Some(reason));
+            assert!(matches!(
+                f.actions[1],
+                Some(TriggerAction::BlockOutgoing { lane: 1, .. })
+            ));
+            _ = f.trigger_events(
+                &[TriggerEvent::BlockingBegin {
+                    machine: MachineId(1),
+                    lane: 1,
+                }],
+                at(15),
+            );
+            // in aggregate, blocked 13 of 18 us
+            _ = f.trigger_events(&[TriggerEvent::NormalSent], at(18));
+            assert_eq!(f.actions[0], None);
+            assert!(matches!(
+                f.actions[1],
+                Some(TriggerAction::BlockOutgoing { lane: 1, .. })
+            ));
+            _ = f.trigger_events(&[TriggerEvent::BlockingEnd { lane: 1 }], at(25));
+            assert_eq!(f.blocking_duration, Duration::from_micros(20));
+            assert_eq!(
+                f.runtime[1].lane_blocking_duration[1],
+                Duration::from_micros(10)
+            );
+
+            // lane 0 blocks again once below its cap: 10 of 30 us
+            _ = f.trigger_events(&[TriggerEvent::NormalSent], at(30));
+            assert!(matches!(
+                f.actions[0],
+                Some(TriggerAction::BlockOutgoing { lane: 0, .. })
+            ));
+        }
+    }
+
+    mod properties {
+        use crate::framework::*;
+        use proptest::collection::vec;
+        use proptest::prelude::*;
+        use std::time::{Duration, Instant};
+
+        proptest! {
+            // accounting invariants for arbitrary machines and events
+            #[test]
+            fn limit_accounting(
+                machines in vec(any::<Machine>(), 1..4),
+                max_padding_frac in prop_oneof![Just(0.0), 0.0..=1.0],
+                max_blocking_frac in prop_oneof![Just(0.0), 0.0..=1.0],
+                steps in vec((0..10u8, 0..4usize, 0..1000u64), 0..200),
+            ) {
+                let start = Instant::now();
+                let mut now = start;
+                let mut f = Framework::new(
+                    &machines,
+                    max_padding_frac,
+                    max_blocking_frac,
+                    now,
+                    rand::thread_rng(),
+                )
+                .unwrap();
+
+                for (kind, machine, delay) in steps {
+                    now += Duration::from_micros(delay);
+                    let machine = MachineId(machine % machines.len());
+                    let event = match kind {
+                        0 => TriggerEvent::NormalRecv,
+                        1 => TriggerEvent::PaddingRecv,
+                        2 => TriggerEvent::TunnelRecv,
+                        3 => TriggerEvent::NormalSent,
+                        4 => TriggerEvent::PaddingSent { machine },
+                        5 => TriggerEvent::TunnelSent,
+                        6 => TriggerEvent::BlockingBegin { machine, lane: 0 },
+                        7 => TriggerEvent::BlockingEnd { lane: 0 },
+                        8 => TriggerEvent::TimerBegin { machine },
+                        _ => TriggerEvent::TimerEnd { machine },
+                    };
+                    // one event at a time, so that any action is scheduled
+                    // with the accounting as it is after the call
+                    let actions: Vec<_> = f.trigger_events(&[event], now).cloned().collect();
+
+                    // padding sent by machines adds up to the total
+                    prop_assert_eq!(
+                        f.runtime.iter().map(|r| r.padding_sent).sum::<u64>(),
+                        f.padding_sent_packets
+                    );
+
+                    // never blocked longer than the elapsed time
+                    prop_assert!(f.blocking_duration <= now - start);
+                    for r in &f.runtime {
+                        prop_assert!(r.blocking_duration <= now - start);
+                    }
+
+                    // padding is only scheduled below the padding limits, so
+                    // sending it exceeds the limits by at most one packet.
+                    // Tolerance: before any normal packets have been sent, a
+                    // machine with its own limit may pad despite the
+                    // framework limit.
+                    for action in actions {
+                        let TriggerAction::SendPadding { machine, .. } = action else {
+                            continue;
+                        };
+                        let (r, m) = (&f.runtime[machine.0], &machines[machine.0]);
+                        if r.padding_sent < m.allowed_padding_packets {
+                            continue;
+                        }
+                        let total = r.padding_sent + r.normal_sent;
+                        if m.max_padding_frac > 0.0 && total > 0 {
+                            let frac = r.padding_sent as f64 / total as f64;
+                            prop_assert!(frac < m.max_padding_frac);
+                        }
+                        let total = f.padding_sent_packets + f.normal_sent_packets;
+                        if max_padding_frac > 0.0
+                            && total > 0
+                            && (f.normal_sent_packets > 0 || m.max_padding_frac == 0.0)
+                        {
+                            let frac = f.padding_sent_packets as f64 / total as f64;
+                            prop_assert!(frac < max_padding_frac);
+                        }
+                    }
+                }
+            }
+
+            // the noop machine never acts, regardless of events
+            #[test]
+            fn noop_no_actions(steps in vec((0..13u8, 0..1000u64), 0..200)) {
+                let machines = vec![Machine::noop()];
+                let mut now = Instant::now();
+                let mut f =
+                    Framework::new(&machines, 0.0, 0.0, now, rand::thread_rng()).unwrap();
+
+                let machine = MachineId(0);
+                for (kind, delay) in steps {
+                    now += Duration::from_micros(delay);
+                    let event = match kind {
+                        0 => TriggerEvent::NormalRecv,
+                        1 => TriggerEvent::PaddingRecv,
+                        2 => TriggerEvent::TunnelRecv,
+                        3 => TriggerEvent::NormalSent,
+                        4 => TriggerEvent::PaddingSent { machine },
+                        5 => TriggerEvent::TunnelSent,
+                        6 => TriggerEvent::BlockingBegin { machine, lane: 0 },
+                        7 => TriggerEvent::BlockingEnd { lane: 0 },
+                        8 => TriggerEvent::TimerBegin { machine },
+                        9 => TriggerEvent::TimerEnd { machine },
+                        10 => TriggerEvent::IncomingBlockingBegin { machine },
+                        11 => TriggerEvent::IncomingBlockingEnd,
+                        _ => TriggerEvent::Idle,
+                    };
+                    prop_assert_eq!(f.trigger_events(&[event], now).count(), 0);
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,136 @@
+//! Histograms of the timeouts and durations of actions returned by a
+//! [`Framework`](crate::Framework), see
+//! [`Framework::action_histograms()`](crate::Framework::action_histograms).
+
+/// The number of buckets of a [`Histogram`].
+pub const HISTOGRAM_BUCKETS: usize = 65;
+
+/// A histogram of values in microseconds, in buckets by powers of two: bucket
+/// 0 counts zero, and bucket `i > 0` counts values in `[2^(i-1), 2^i)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Histogram {
+    buckets: [u64; HISTOGRAM_BUCKETS],
+    count: u64,
+    sum: u64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self {
+            buckets: [0; HISTOGRAM_BUCKETS],
+            count: 0,
+            sum: 0,
+        }
+    }
+}
+
+impl Histogram {
+    /// Record a value in microseconds.
+    pub fn record(&mut self, micros: u64) {
+        self.buckets[Self::bucket(micros)] += 1;
+        self.count += 1;
+        self.sum = self.sum.saturating_add(micros);
+    }
+
+    /// Returns the index of the bucket counting the value.
+    pub fn bucket(micros: u64) -> usize {
+        (u64::BITS - micros.leading_zeros()) as usize
+    }
+
+    /// Returns the range of values counted by the bucket with the given
+    /// index, as the inclusive lowest and highest value.
+    pub fn bucket_range(index: usize) -> (u64, u64) {
+        match index {
+            0 => (0, 0),
+            i if i >= HISTOGRAM_BUCKETS - 1 => (1 << (HISTOGRAM_BUCKETS - 2), u64::MAX),
+            i => (1 << (i - 1), (1 << i) - 1),
+        }
+    }
+
+    /// Returns the count of each bucket.
+    pub fn buckets(&self) -> &[u64; HISTOGRAM_BUCKETS] {
+        &self.buckets
+    }
+
+    /// Returns the number of recorded values.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Returns the mean of the recorded values, or None if there are none.
+    pub fn mean(&self) -> Option<f64> {
+        if self.count == 0 {
+            return None;
+        }
+        Some(self.sum as f64 / self.count as f64)
+    }
+
+    /// Remove all recorded values.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Histograms of the actions returned for a machine.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ActionHistograms {
+    /// The timeouts of [`TriggerAction::SendPadding`],
+    /// [`TriggerAction::BlockOutgoing`], [`TriggerAction::BlockIncoming`], and
+    /// [`TriggerAction::HoldOutgoing`].
+    ///
+    /// [`TriggerAction::SendPadding`]: crate::TriggerAction::SendPadding
+    /// [`TriggerAction::BlockOutgoing`]: crate::TriggerAction::BlockOutgoing
+    /// [`TriggerAction::BlockIncoming`]: crate::TriggerAction::BlockIncoming
+    /// [`TriggerAction::HoldOutgoing`]: crate::TriggerAction::HoldOutgoing
+    pub timeouts: Histogram,
+    /// The durations of [`TriggerAction::BlockOutgoing`],
+    /// [`TriggerAction::BlockIncoming`], and [`TriggerAction::HoldOutgoing`].
+    ///
+    /// [`TriggerAction::BlockOutgoing`]: crate::TriggerAction::BlockOutgoing
+    /// [`TriggerAction::BlockIncoming`]: crate::TriggerAction::BlockIncoming
+    /// [`TriggerAction::HoldOutgoing`]: crate::TriggerAction::HoldOutgoing
+    pub block_durations: Histogram,
+    /// The durations of [`TriggerAction::UpdateTimer`].
+    ///
+    /// [`TriggerAction::UpdateTimer`]: crate::TriggerAction::UpdateTimer
+    pub timer_durations: Histogram,
+}
+
+impl ActionHistograms {
+    /// Remove all recorded values.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::histogram::*;
+
+    #[test]
+    fn histogram_buckets() {
+        let mut h = Histogram::default();
+        assert_eq!(h.mean(), None);
+        for v in [0, 1, 2, 3, 4, 1000, u64::MAX] {
+            h.record(v);
+        }
+        assert_eq!(h.count(), 7);
+        assert_eq!(h.buckets()[0], 1);
+        assert_eq!(h.buckets()[1], 1);
+        assert_eq!(h.buckets()[2], 2);
+        assert_eq!(h.buckets()[3], 1);
+        assert_eq!(h.buckets()[10], 1);
+        assert_eq!(h.buckets()[64], 1);
+        assert_eq!(h.buckets().iter().sum::<u64>(), 7);
+
+        // each value is within the range of its bucket
+        for v in [0, 1, 2, 3, 4, 1000, 1 << 63, u64::MAX] {
+            let (low, high) = Histogram::bucket_range(Histogram::bucket(v));
+            assert!(low <= v && v <= high);
+        }
+        assert_eq!(Histogram::bucket_range(10), (512, 1023));
+
+        h.reset();
+        assert_eq!(h, Histogram::default());
+    }
+}
@@ -145,6 +145,7 @@
 //!                 duration: _,
 //!                 bypass: _,
 //!                 replace: _,
+//!                 lane: _,
 //!                 machine: _,
 //!             } => {
 //!                 // Set an action timer with the specified timeout,
@@ -152,10 +153,11 @@
 //!                 // (be it to block or to send padding). On expiry, do
 //!                 // the following (all or nothing):
 //!                 //
-//!                 // 1. If no blocking is currently taking place (globally
-//!                 //    across all machines, so for this instance of the
-//!                 //    framework), start blocking all outgoing traffic
-//!                 //    for the specified duration. If blocking is already
+//!                 // 1. If no blocking of the lane is currently taking
+//!                 //    place (globally across all machines, so for this
+//!                 //    instance of the framework), start blocking all
+//!                 //    outgoing traffic of the lane for the specified
+//!                 //    duration. If blocking of the lane is already
 //!                 //    taking place (due to any machine), there are two
 //!                 //    cases. If replace is true, replace the existing
 //!                 //    blocking duration with the specified duration in
@@ -163,20 +165,23 @@
 //!                 //    duration of the specified duration and the
 //!                 //    *remaining* duration to block already in place.
 //!                 // 2. Trigger TriggerEvent::BlockingBegin { machine:
-//!                 //    machine } regardless of logic outcome in 1. (From
-//!                 //    the point of view of the machine, blocking is now
-//!                 //    taking place).
+//!                 //    machine, lane: lane } regardless of logic outcome
+//!                 //    in 1. (From the point of view of the machine,
+//!                 //    blocking is now taking place).
 //!                 //
 //!                 // Note that blocking is global across all machines,
-//!                 // since the intent is to block all outgoing traffic.
-//!                 // Further, you MUST ensure that when blocking ends, you
-//!                 // trigger TriggerEvent::BlockingEnd.
+//!                 // since the intent is to block all outgoing traffic of
+//!                 // the lane. The integration maps each lane to a class
+//!                 // of outgoing traffic, with traffic without a class of
+//!                 // its own in lane 0. Further, you MUST ensure that when
+//!                 // blocking of a lane ends, you trigger
+//!                 // TriggerEvent::BlockingEnd { lane: lane }.
 //!                 //
 //!                 // If bypass is true and blocking was activated,
 //!                 // extended, or replaced in step 1, then a bypass flag
-//!                 // MUST be set and be available to check as part of
-//!                 // dealing with TriggerAction::SendPadding actions (see
-//!                 // above).
+//!                 // of the lane MUST be set and be available to check as
+//!                 // part of dealing with TriggerAction::SendPadding
+//!                 // actions (see above).
 //!             }
 //!             TriggerAction::UpdateTimer {
 //!                 duration: _,
@@ -195,6 +200,34 @@
 //!                 // Trigger TriggerEvent::TimerEnd { machine: machine }
 //!                 // when the timer expires.
 //!             }
+//!             TriggerAction::BlockIncoming {
+//!                 timeout: _,
+//!                 duration: _,
+//!                 replace: _,
+//!                 machine: _,
+//!             } => {
+//!                 // Like TriggerAction::BlockOutgoing, but for incoming
+//!                 // traffic, with its own global blocking state: while
+//!                 // blocking, delay delivering received packets to the
+//!                 // application. Trigger NormalRecv and PaddingRecv once
+//!                 // packets are delivered.
+//!                 //
+//!                 // Trigger TriggerEvent::IncomingBlockingBegin { machine:
+//!                 // machine } on expiry of the timeout, and
+//!                 // TriggerEvent::IncomingBlockingEnd when blocking of
+//!                 // incoming traffic ends.
+//!             }
+//!             TriggerAction::HoldOutgoing {
+//!                 timeout: _,
+//!                 duration: _,
+//!                 probability: _,
+//!                 machine: _,
+//!             } => {
+//!                 // On expiry of the timeout, replace any ongoing holding
+//!                 // of outgoing traffic: for the duration, hold each
+//!                 // outgoing non-padding packet with the probability,
+//!                 // until the duration ends. Nothing is triggered.
+//!             }
 //!         }
 //!     }
 //!
@@ -204,21 +237,33 @@
 //! }
 //! ```
 pub mod action;
+pub mod blocking;
 pub mod constants;
 pub mod counter;
 pub mod dist;
 mod error;
 pub mod event;
 mod framework;
+#[cfg(feature = "metrics")]
+pub mod histogram;
 mod machine;
+pub mod replay;
 pub mod state;
+pub mod sync;
 pub mod time;
+mod v2;
 
 pub use crate::action::{Timer, TriggerAction};
 pub use crate::error::Error;
 pub use crate::event::TriggerEvent;
-pub use framework::{Framework, MachineId};
-pub use machine::Machine;
+pub use framework::{Framework, FrameworkSnapshot, MachineId, SuppressReason};
+pub use machine::{DistRef, Lint, Machine, OnEnd, Param};
+pub use sync::SyncFramework;
+
+#[cfg(feature = "metrics")]
+pub use framework::Metrics;
+#[cfg(feature = "metrics")]
+pub use histogram::{ActionHistograms, Histogram};
 
 #[cfg(feature = "parsing")]
 pub mod parsing;
@@ -228,7 +273,7 @@ mod tests {
 
     #[test]
     fn constants_set() {
-        assert_eq!(crate::constants::VERSION, 2);
+        assert_eq!(crate::constants::VERSION, 3);
     }
 
     #[test]
@@ -358,6 +403,7 @@ mod tests {
                         duration: _,
                         bypass: _,
                         replace: _,
+                        lane: _,
                         machine: _,
                     } => {
                         // Set an action timer with the specified timeout,
@@ -365,10 +411,11 @@ mod tests {
                         // (be it to block or to send padding). On expiry, do
                         // the following (all or nothing):
                         //
-                        // 1. If no blocking is currently taking place (globally
-                        //    across all machines, so for this instance of the
-                        //    framework), start blocking all outgoing traffic
-                        //    for the specified duration. If blocking is already
+                        // 1. If no blocking of the lane is currently taking
+                        //    place (globally across all machines, so for this
+                        //    instance of the framework), start blocking all
+                        //    outgoing traffic of the lane for the specified
+                        //    duration. If blocking of the lane is already
                         //    taking place (due to any machine), there are two
                         //    cases. If replace is true, replace the existing
                         //    blocking duration with the specified duration in
@@ -376,20 +423,23 @@ mod tests {
                         //    duration of the specified duration and the
                         //    *remaining* duration to block already in place.
                         // 2. Trigger TriggerEvent::BlockingBegin { machine:
-                        //    machine } regardless of logic outcome in 1. (From
-                        //    the point of view of the machine, blocking is now
-                        //    taking place).
+                        //    machine, lane: lane } regardless of logic outcome
+                        //    in 1. (From the point of view of the machine,
+                        //    blocking is now taking place).
                         //
                         // Note that blocking is global across all machines,
-                        // since the intent is to block all outgoing traffic.
-                        // Further, you MUST ensure that when blocking ends, you
-                        // trigger TriggerEvent::BlockingEnd.
+                        // since the intent is to block all outgoing traffic of
+                        // the lane. The integration maps each lane to a class
+                        // of outgoing traffic, with traffic without a class of
+                        // its own in lane 0. Further, you MUST ensure that when
+                        // blocking of a lane ends, you trigger
+                        // TriggerEvent::BlockingEnd { lane: lane }.
                         //
                         // If bypass is true and blocking was activated,
                         // extended, or replaced in step 1, then a bypass flag
-                        // MUST be set and be available to check as part of
-                        // dealing with TriggerAction::SendPadding actions (see
-                        // above).
+                        // of the lane MUST be set and be available to check as
+                        // part of dealing with TriggerAction::SendPadding
+                        // actions (see above).
                     }
                     TriggerAction::UpdateTimer {
                         duration: _,
@@ -408,6 +458,34 @@ mod tests {
                         // Trigger TriggerEvent::TimerEnd { machine: machine }
                         // when the timer expires.
                     }
+                    TriggerAction::BlockIncoming {
+                        timeout: _,
+                        duration: _,
+                        replace: _,
+                        machine: _,
+                    } => {
+                        // Like TriggerAction::BlockOutgoing, but for incoming
+                        // traffic, with its own global blocking state: while
+                        // blocking, delay delivering received packets to the
+                        // application. Trigger NormalRecv and PaddingRecv once
+                        // packets are delivered.
+                        //
+                        // Trigger TriggerEvent::IncomingBlockingBegin { machine:
+                        // machine } on expiry of the timeout, and
+                        // TriggerEvent::IncomingBlockingEnd when blocking of
+                        // incoming traffic ends.
+                    }
+                    TriggerAction::HoldOutgoing {
+                        timeout: _,
+                        duration: _,
+                        probability: _,
+                        machine: _,
+                    } => {
+                        // On expiry of the timeout, replace any ongoing holding
+                        // of outgoing traffic: for the duration, hold each
+                        // outgoing non-padding packet with the probability,
+                        // until the duration ends. Nothing is triggered.
+                    }
                 }
             }
 
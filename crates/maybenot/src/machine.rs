@@ -4,17 +4,106 @@
 use crate::constants::*;
 use crate::*;
 use base64::prelude::*;
-use bincode::Options;
 use flate2::read::ZlibDecoder;
 use flate2::write::ZlibEncoder;
 use flate2::Compression;
 use serde::{Deserialize, Serialize};
 use sha256::digest;
+use std::collections::HashMap;
 use std::fmt;
 use std::io::prelude::*;
 use std::str::FromStr;
 
-use self::state::State;
+use self::action::Action;
+use self::dist::{Dist, DistParam, DistType};
+use self::event::Event;
+use self::replay::{EventLog, Fixture};
+use self::state::{State, Trans};
+
+/// What happens to any pending action of a [`Machine`] when it transitions to
+/// [`STATE_END`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OnEnd {
+    /// Keep any pending action: scheduled padding or blocking is still
+    /// taken, and the internal timer still expires.
+    #[default]
+    KeepPending,
+    /// Cancel all pending actions, including the internal timer, such that
+    /// the machine goes quiet immediately. The framework outputs a
+    /// [`TriggerAction::Cancel`] for [`Timer::All`] on transitioning to
+    /// [`STATE_END`], regardless of whether any timer is running.
+    CancelPending,
+}
+
+/// A distribution of a [`Machine`] that a [`Param`] can be bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DistRef {
+    /// The timeout of the action of the state with the given index.
+    Timeout(usize),
+    /// The duration of the action of the state with the given index: of
+    /// blocking or the timer, the period of an aligned timer, or the interval
+    /// of a padding burst.
+    Duration(usize),
+    /// The limit of the action of the state with the given index.
+    Limit(usize),
+    /// The distribution of counter A of the state with the given index.
+    CounterA(usize),
+    /// The distribution of counter B of the state with the given index.
+    CounterB(usize),
+    /// The shared limit with the given index.
+    SharedLimit(usize),
+    /// The probability of holding of the action of the state with the given
+    /// index.
+    Probability(usize),
+}
+
+/// A named parameter of a machine template, see [`Machine::instantiate()`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Param {
+    /// The name of the parameter.
+    pub name: String,
+    /// The distribution parameters set to the value of the parameter.
+    pub targets: Vec<(DistRef, DistParam)>,
+}
+
+/// A likely mistake in a valid [`Machine`], found by [`Machine::lint()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lint {
+    /// The limit of the action of the state with the given index saturates,
+    /// making the action effectively unlimited, see
+    /// [`Action::limit_saturates()`](crate::action::Action::limit_saturates).
+    SaturatedLimit(usize),
+    /// The state with the given index blocks outgoing traffic, but no
+    /// reachable state transitions on [`Event::BlockingBegin`] or
+    /// [`Event::BlockingEnd`], so the machine cannot react to its own
+    /// blocking.
+    UnhandledBlocking(usize),
+    /// A timeout, interval, duration, or period of the action of the state
+    /// with the given index often exceeds the cap it is clamped to when
+    /// sampled, see
+    /// [`Action::exceeds_caps()`](crate::action::Action::exceeds_caps).
+    ExceedsCap(usize),
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Lint::SaturatedLimit(state) => {
+                write!(f, "state {}: limit saturates, effectively unlimited", state)
+            }
+            Lint::UnhandledBlocking(state) => write!(
+                f,
+                "state {}: blocks, but no reachable state transitions on BlockingBegin or BlockingEnd",
+                state
+            ),
+            Lint::ExceedsCap(state) => write!(
+                f,
+                "state {}: timeout or duration often exceeds its cap, effectively constant",
+                state
+            ),
+        }
+    }
+}
 
 /// A probabilistic state machine (Rabin automaton) consisting of one or more
 /// [`State`] that determine when to inject and/or block outgoing traffic.
@@ -26,12 +115,40 @@ pub struct Machine {
     /// The maximum fraction of padding packets to allow as actions.
     pub max_padding_frac: f64,
     /// The number of microseconds of blocking a machine is allowed to generate
-    /// as actions before other limits apply.
+    /// as actions before other limits apply, per lane of outgoing traffic.
     pub allowed_blocked_microsec: u64,
-    /// The maximum fraction of blocking (microseconds) to allow as actions.
+    /// The maximum fraction of blocking (microseconds) to allow as actions,
+    /// per lane of outgoing traffic.
     pub max_blocking_frac: f64,
     /// The states that make up the machine.
     pub states: Vec<State>,
+    /// Limits shared by the actions of one or more states, referenced by index
+    /// from [`State::shared_limit`]. Each limit is sampled once when the
+    /// machine is added to a framework and decremented whenever an action of
+    /// any state referencing it is taken.
+    #[serde(default)]
+    pub shared_limits: Vec<Dist>,
+    /// What happens to any pending action when the machine transitions to
+    /// [`STATE_END`].
+    #[serde(default)]
+    pub on_end: OnEnd,
+    /// Named parameters that make the machine a template, to be instantiated
+    /// with values for all parameters before use. The values in the
+    /// distributions of a template are placeholders.
+    #[serde(default)]
+    pub params: Vec<Param>,
+    /// Optional features of the framework that the machine requires, as a
+    /// bitmask of feature bits (e.g., [`FEATURE_PORTABLE_SAMPLE`]). A machine
+    /// requiring a feature that the framework was not built with (see
+    /// [`SUPPORTED_FEATURES`]) fails validation with
+    /// [`Error::UnsupportedFeature`].
+    #[serde(default)]
+    pub requires: u32,
+    /// If set, the machine transitions on [`Event::TimeThreshold`] once it
+    /// has been running for this many microseconds. Machines without a
+    /// threshold cannot have transitions on the event.
+    #[serde(default)]
+    pub time_threshold: Option<u64>,
 }
 
 impl Machine {
@@ -50,40 +167,611 @@ impl Machine {
             allowed_blocked_microsec,
             max_blocking_frac,
             states,
+            shared_limits: vec![],
+            on_end: OnEnd::KeepPending,
+            params: vec![],
+            requires: 0,
+            time_threshold: None,
         };
         machine.validate()?;
 
         Ok(machine)
     }
 
+    /// Create a machine that does nothing: it has a single state without any
+    /// action or transitions.
+    pub fn noop() -> Machine {
+        Machine {
+            allowed_padding_packets: 0,
+            max_padding_frac: 0.0,
+            allowed_blocked_microsec: 0,
+            max_blocking_frac: 0.0,
+            states: vec![State::new(enum_map::enum_map! { _ => vec![] })],
+            shared_limits: vec![],
+            on_end: OnEnd::KeepPending,
+            params: vec![],
+            requires: 0,
+            time_threshold: None,
+        }
+    }
+
+    /// Create a constant-rate padding machine. Once the first normal packet
+    /// is sent or received, it schedules padding every `period` microseconds,
+    /// forever. The padding has the replace flag set, such that a normal
+    /// packet queued when padding is due is sent in its place. The machine
+    /// has no limits of its own, only those of the framework apply. Returns
+    /// an error if the period is zero.
+    pub fn constant_rate(period: u64) -> Result<Machine, Error> {
+        if period == 0 {
+            Err(Error::Machine(
+                "constant-rate period must be positive".to_string(),
+            ))?;
+        }
+        let start = State::new(enum_map::enum_map! {
+            Event::NormalSent | Event::NormalRecv => vec![Trans(1, 1.0)],
+            _ => vec![],
+        });
+        let mut pad = State::new(enum_map::enum_map! {
+            Event::PaddingSent => vec![Trans(1, 1.0)],
+            _ => vec![],
+        });
+        let period = period as f64;
+        pad.action = Some(Action::SendPadding {
+            bypass: false,
+            replace: true,
+            timeout: Dist::new(
+                DistType::Uniform {
+                    low: period,
+                    high: period,
+                },
+                0.0,
+                0.0,
+            ),
+            limit: None,
+        });
+        Machine::new(0, 0.0, 0, 0.0, vec![start, pad])
+    }
+
+    /// Create a fail-closed constant-rate machine. Once the first normal
+    /// packet is sent or received, it blocks all outgoing traffic and
+    /// schedules padding every `period` microseconds, forever. The blocking
+    /// has the bypass and replace flags set, as does the padding, such that
+    /// padding bypasses the blocking and a queued normal packet is sent in
+    /// place of padding when due. Outgoing packets are therefore only sent on
+    /// a fixed grid, regardless of the traffic. Blocking lasts for
+    /// [`MAX_SAMPLED_BLOCK_DURATION`] and is then renewed.
+    ///
+    /// The framework has no notion of packet sizes: for a constant-rate
+    /// schedule in bytes, the integration must also send all packets, padding
+    /// or not, with the same size. The machine has no limits of its own, only
+    /// those of the framework apply. Returns an error if the period is zero.
+    pub fn fail_closed_constant_rate(period: u64) -> Result<Machine, Error> {
+        if period == 0 {
+            Err(Error::Machine(
+                "constant-rate period must be positive".to_string(),
+            ))?;
+        }
+        let start = State::new(enum_map::enum_map! {
+            Event::NormalSent | Event::NormalRecv => vec![Trans(1, 1.0)],
+            _ => vec![],
+        });
+        let mut block = State::new(enum_map::enum_map! {
+            Event::BlockingBegin => vec![Trans(2, 1.0)],
+            _ => vec![],
+        });
+        block.action = Some(Action::BlockOutgoing {
+            bypass: true,
+            replace: true,
+            lane: 0,
+            timeout: Dist::constant(0.0),
+            duration: Dist::constant(MAX_SAMPLED_BLOCK_DURATION),
+            limit: None,
+        });
+        let mut pad = State::new(enum_map::enum_map! {
+            Event::PaddingSent => vec![Trans(2, 1.0)],
+            Event::BlockingEnd => vec![Trans(1, 1.0)],
+            _ => vec![],
+        });
+        pad.action = Some(Action::SendPadding {
+            bypass: true,
+            replace: true,
+            timeout: Dist::constant(period as f64),
+            limit: None,
+        });
+        Machine::new(0, 0.0, 0, 0.0, vec![start, block, pad])
+    }
+
+    /// Parse many serialized machines (see [`Machine::from_str()`]), e.g.,
+    /// from a config, without stopping at the first invalid one. Returns the
+    /// machines that were parsed, in order, and the index and error of each
+    /// string that failed. Machine templates (see [`Self::params`]) fail, since
+    /// they cannot be used in a framework before being instantiated.
+    pub fn load_many(machines: &[&str]) -> (Vec<Machine>, Vec<(usize, Error)>) {
+        let mut parsed = Vec::with_capacity(machines.len());
+        let mut errors = vec![];
+        for (i, s) in machines.iter().enumerate() {
+            match Machine::from_str(s).and_then(|m| m.validate().map(|_| m)) {
+                Ok(m) => parsed.push(m),
+                Err(e) => errors.push((i, e)),
+            }
+        }
+        (parsed, errors)
+    }
+
+    /// Read a machine from a file, containing either a serialized machine (see
+    /// [`Machine::from_str()`]) or a machine as JSON. The format is detected
+    /// from the content: JSON machines are objects, starting with `{`. The
+    /// machine is validated, so machine templates (see [`Self::params`]) fail.
+    /// Requires the `fs` feature.
+    #[cfg(feature = "fs")]
+    pub fn from_file<P: AsRef<std::path::Path>>(path: P) -> Result<Machine, Error> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| Error::Machine(format!("failed to read {}: {}", path.display(), e)))?;
+        let content = content.trim();
+        let m = if content.starts_with('{') {
+            serde_json::from_str::<Machine>(content)
+                .map_err(|e| Error::Machine(format!("failed to parse {}: {}", path.display(), e)))?
+        } else {
+            Machine::from_str(content)?
+        };
+        m.validate()?;
+        Ok(m)
+    }
+
+    /// Read all machines in a directory, one per file (see
+    /// [`Self::from_file()`]), keyed by file name and sorted by it.
+    /// Subdirectories are ignored. Fails on the first file that is not a valid
+    /// machine. Requires the `fs` feature.
+    #[cfg(feature = "fs")]
+    pub fn load_dir<P: AsRef<std::path::Path>>(path: P) -> Result<Vec<(String, Machine)>, Error> {
+        let path = path.as_ref();
+        let read_err =
+            |e: std::io::Error| Error::Machine(format!("failed to read {}: {}", path.display(), e));
+
+        let mut files = vec![];
+        for entry in std::fs::read_dir(path).map_err(read_err)? {
+            let entry = entry.map_err(read_err)?;
+            if entry.file_type().map_err(read_err)?.is_file() {
+                files.push((
+                    entry.file_name().to_string_lossy().into_owned(),
+                    entry.path(),
+                ));
+            }
+        }
+        files.sort();
+
+        files
+            .into_iter()
+            .map(|(name, file)| Ok((name, Machine::from_file(file)?)))
+            .collect()
+    }
+
     /// Get a unique and deterministic string that represents the machine. The
-    /// string is 32 characters long, hex-encoded.
+    /// string is 32 characters long, hex-encoded. The name is a digest of the
+    /// encoding of the machine, also for machines too large to be serialized
+    /// (see [`Self::serialize()`]).
     pub fn name(&self) -> String {
-        let s = digest(self.serialize());
+        // encoding a machine does not fail in practice (see encode()), but if
+        // it does, the name is still deterministic
+        let s = match self.encode() {
+            Ok(encoded) => digest(encoded),
+            Err(e) => digest(e.to_string()),
+        };
         s[0..32].to_string()
     }
 
-    pub fn serialize(&self) -> String {
-        let bincoder = bincode::DefaultOptions::new().with_limit(MAX_DECOMPRESSED_SIZE as u64);
-        let encoded = bincoder.serialize(&self).unwrap();
-        let mut e = ZlibEncoder::new(Vec::new(), Compression::best());
-        e.write_all(encoded.as_slice()).unwrap();
-        let s = BASE64_STANDARD.encode(e.finish().unwrap());
-        // version as first 2 characters, then base64 compressed bincoded
-        format!("{:02}{}", VERSION, s)
+    /// Get a deterministic string that represents the structure of the
+    /// machine: the number of states, and the events each state transitions
+    /// on and to which states (including [`State::fallback`]). Probabilities,
+    /// actions, counters, distributions, and limits are not included, so
+    /// machines with the same structure but, e.g., different timings share
+    /// the same topology hash. Like [`Self::name()`], the string is 32
+    /// characters long, hex-encoded.
+    pub fn topology_hash(&self) -> String {
+        let mut topology = format!("{}", self.states.len());
+        for state in &self.states {
+            topology.push(';');
+            for (event, transitions) in state.get_transitions() {
+                if !transitions.is_empty() {
+                    topology.push_str(&format!(" {}:{}", event, targets(&transitions)));
+                }
+            }
+            if let Some(fallback) = &state.fallback {
+                topology.push_str(&format!(" *:{}", targets(fallback)));
+            }
+        }
+        let s = digest(topology);
+        s[0..32].to_string()
+    }
+
+    /// Returns a canonical form of the machine, where the transitions of each
+    /// state are in a fixed order. Machines that only differ in the order of
+    /// their transitions behave the same, and have the same canonical form
+    /// (and therefore [`Self::name()`]). Note that the order of transitions
+    /// affects which state is sampled for a given random value, so the
+    /// canonical machine is only equivalent in distribution.
+    pub fn canonicalize(&self) -> Machine {
+        let mut m = self.clone();
+        for state in m.states.iter_mut() {
+            state.canonicalize();
+        }
+        m
+    }
+
+    /// Returns the indices of the states whose action has a limit that
+    /// saturates, see
+    /// [`Action::limit_saturates()`](crate::action::Action::limit_saturates).
+    /// Such states are valid, but effectively unlimited, so a non-empty result
+    /// is a warning that the machine is probably not as intended.
+    pub fn saturated_limits(&self) -> Vec<usize> {
+        self.states
+            .iter()
+            .enumerate()
+            .filter(|(_, state)| state.action.is_some_and(|a| a.limit_saturates()))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Returns the indices of the states that are reachable from the first
+    /// state by transitions on any event (including [`State::fallback`]), in
+    /// increasing order. The first state is always reachable.
+    pub fn reachable_states(&self) -> Vec<usize> {
+        let mut reachable = vec![false; self.states.len()];
+        let mut stack = vec![0];
+        while let Some(i) = stack.pop() {
+            if i >= self.states.len() || reachable[i] {
+                continue;
+            }
+            reachable[i] = true;
+            let state = &self.states[i];
+            let mut targets: Vec<usize> = state
+                .get_transitions()
+                .values()
+                .flatten()
+                .chain(state.fallback.iter().flatten())
+                .map(|t| t.0)
+                .collect();
+            // a random state may be any state, while the previous state has
+            // already been reached
+            if targets.contains(&STATE_RANDOM) {
+                targets = (0..self.states.len()).collect();
+            }
+            stack.extend(targets);
+        }
+        (0..self.states.len()).filter(|i| reachable[*i]).collect()
+    }
+
+    /// Returns the machine without the states that are not reachable (see
+    /// [`Self::reachable_states()`]), with the remaining states renumbered in
+    /// order, e.g., to shrink machines generated by tools that leave dead
+    /// states. Parameters bound to distributions of removed states are
+    /// unbound from them, while shared limits are kept. Unreachable states
+    /// never affect a running machine, so the pruned machine behaves exactly
+    /// the same, also sampling the same random values.
+    pub fn prune(&self) -> Machine {
+        let reachable = self.reachable_states();
+        // the new index of each state, if kept
+        let mut index = vec![STATE_END; self.states.len()];
+        for (new, old) in reachable.iter().enumerate() {
+            index[*old] = new;
+        }
+
+        let mut m = self.clone();
+        m.states = reachable
+            .iter()
+            .map(|i| {
+                let mut state = self.states[*i].clone();
+                state.renumber(&index);
+                state
+            })
+            .collect();
+        for param in m.params.iter_mut() {
+            param.targets.retain_mut(|(dist, _)| {
+                let i = match dist {
+                    DistRef::Timeout(i)
+                    | DistRef::Duration(i)
+                    | DistRef::Limit(i)
+                    | DistRef::CounterA(i)
+                    | DistRef::CounterB(i)
+                    | DistRef::Probability(i) => i,
+                    DistRef::SharedLimit(_) => return true,
+                };
+                match index.get(*i) {
+                    Some(&new) if new != STATE_END => {
+                        *i = new;
+                        true
+                    }
+                    _ => false,
+                }
+            });
+        }
+        m
+    }
+
+    /// Returns the probability of transitioning between states on an
+    /// [`Event`], as a square matrix with a row and column per state, followed
+    /// by one for [`STATE_END`]. The value at row `i` and column `j` is the
+    /// probability of being in state `j` after the event in state `i`, so each
+    /// row sums to one (up to floating point error).
+    ///
+    /// The pseudo-states are represented as follows. Since a machine remains
+    /// in its state when it does not transition, the probability of no
+    /// transition is on the diagonal, as is the probability of transitioning
+    /// to [`STATE_SIGNAL`] (the machine signals, but does not change state).
+    /// [`STATE_END`] is absorbing: its row has a one in its own column. The
+    /// probability of [`STATE_RANDOM`] is split evenly over all states. Since
+    /// [`STATE_PREVIOUS`] depends on the history of the machine, and not only
+    /// on its current state, it is also represented on the diagonal.
+    /// Transitions of [`State::fallback`] are used for events without
+    /// transitions of their own, as when running the machine.
+    pub fn transition_matrix(&self, event: Event) -> Vec<Vec<f32>> {
+        let n = self.states.len();
+        let mut matrix = vec![vec![0.0; n + 1]; n + 1];
+        for (i, state) in self.states.iter().enumerate() {
+            let row = &mut matrix[i];
+            let mut remaining = 1.0;
+            for t in state.transitions_on(event).into_iter().flatten() {
+                remaining -= t.1;
+                let j = match t.0 {
+                    STATE_END => n,
+                    STATE_SIGNAL | STATE_PREVIOUS => i,
+                    STATE_RANDOM => {
+                        row[..n].iter_mut().for_each(|p| *p += t.1 / n as f32);
+                        continue;
+                    }
+                    j => j,
+                };
+                row[j] += t.1;
+            }
+            row[i] += remaining.max(0.0);
+        }
+        matrix[n][n] = 1.0;
+        matrix
+    }
+
+    /// Check the reachable states (see [`Self::reachable_states()`]) of the
+    /// machine for likely mistakes that validation allows, such as blocking
+    /// without transitions on the blocking events. Returns an empty vector if
+    /// nothing was found. Lints are warnings: the machine may well be as
+    /// intended.
+    pub fn lint(&self) -> Vec<Lint> {
+        let reachable = self.reachable_states();
+        let handles_blocking = reachable.iter().any(|i| {
+            let state = &self.states[*i];
+            let t = state.get_transitions();
+            state.fallback.is_some()
+                || !t[Event::BlockingBegin].is_empty()
+                || !t[Event::BlockingEnd].is_empty()
+        });
+
+        let mut lints = vec![];
+        for i in reachable {
+            let Some(action) = self.states[i].action else {
+                continue;
+            };
+            if action.limit_saturates() {
+                lints.push(Lint::SaturatedLimit(i));
+            }
+            if matches!(action, Action::BlockOutgoing { .. }) && !handles_blocking {
+                lints.push(Lint::UnhandledBlocking(i));
+            }
+            if action.exceeds_caps() {
+                lints.push(Lint::ExceedsCap(i));
+            }
+        }
+        lints
+    }
+
+    /// Instantiate a machine template (see [`Self::params`]) with a value for
+    /// each of its parameters, returning a machine without parameters. Values
+    /// for parameters not in the template are ignored, such that the same
+    /// values can be used to instantiate several templates. Returns an error
+    /// if a parameter has no value, or if the instantiated machine is invalid.
+    pub fn instantiate(&self, params: HashMap<String, f64>) -> Result<Machine, Error> {
+        let mut m = self.clone();
+        for param in std::mem::take(&mut m.params) {
+            let Some(value) = params.get(&param.name) else {
+                Err(Error::Machine(format!("unbound parameter {}", param.name)))?
+            };
+            m.bind(&param, *value)?;
+        }
+        m.validate()?;
+        Ok(m)
+    }
+
+    fn bind(&mut self, param: &Param, value: f64) -> Result<(), Error> {
+        for (dist, dist_param) in param.targets.iter() {
+            self.dist_mut(*dist)
+                .ok_or_else(|| {
+                    Error::Machine(format!(
+                        "parameter {} bound to missing distribution {:?}",
+                        param.name, dist
+                    ))
+                })?
+                .set_param(*dist_param, value)?;
+        }
+        Ok(())
+    }
+
+    fn dist_mut(&mut self, dist: DistRef) -> Option<&mut Dist> {
+        match dist {
+            DistRef::Timeout(i) => match self.states.get_mut(i)?.action.as_mut()? {
+                Action::SendPadding { timeout, .. }
+                | Action::BlockOutgoing { timeout, .. }
+                | Action::BlockIncoming { timeout, .. }
+                | Action::HoldOutgoing { timeout, .. }
+                | Action::SendPaddingBurst { timeout, .. } => Some(timeout),
+                _ => None,
+            },
+            DistRef::Duration(i) => match self.states.get_mut(i)?.action.as_mut()? {
+                Action::BlockOutgoing { duration, .. }
+                | Action::BlockIncoming { duration, .. }
+                | Action::HoldOutgoing { duration, .. }
+                | Action::UpdateTimer { duration, .. }
+                | Action::UpdateTimerAligned {
+                    period: duration, ..
+                }
+                | Action::SendPaddingBurst {
+                    interval: duration, ..
+                } => Some(duration),
+                _ => None,
+            },
+            DistRef::Limit(i) => match self.states.get_mut(i)?.action.as_mut()? {
+                Action::Cancel { .. } => None,
+                Action::SendPadding { limit, .. }
+                | Action::BlockOutgoing { limit, .. }
+                | Action::BlockIncoming { limit, .. }
+                | Action::UpdateTimer { limit, .. }
+                | Action::UpdateTimerAligned { limit, .. }
+                | Action::SendPaddingBurst { limit, .. }
+                | Action::HoldOutgoing { limit, .. } => limit.as_mut(),
+            },
+            DistRef::Probability(i) => match self.states.get_mut(i)?.action.as_mut()? {
+                Action::HoldOutgoing { probability, .. } => Some(probability),
+                _ => None,
+            },
+            DistRef::CounterA(i) => self.states.get_mut(i)?.counter.0.as_mut()?.dist.as_mut(),
+            DistRef::CounterB(i) => self.states.get_mut(i)?.counter.1.as_mut()?.dist.as_mut(),
+            DistRef::SharedLimit(i) => self.shared_limits.get_mut(i),
+        }
+    }
+
+    /// Serialize the machine into a string that can be parsed with
+    /// [`Machine::from_str()`](std::str::FromStr). Returns an error if the
+    /// encoded machine exceeds [`MAX_DECOMPRESSED_SIZE`], since such a machine
+    /// could not be parsed again.
+    ///
+    /// The serialization is deterministic: the same machine always serializes
+    /// to the same string, since all its collections are ordered (vectors and
+    /// arrays indexed by [`Event`]), never hash maps or sets.
+    pub fn serialize(&self) -> Result<String, Error> {
+        let bytes = self.to_bytes()?;
+        let s = BASE64_STANDARD.encode(&bytes[1..]);
+        // version as first 2 characters, then base64 compressed encoding
+        Ok(format!("{:02}{}", VERSION, s))
+    }
+
+    /// Serialize the machine into bytes that can be parsed with
+    /// [`Machine::from_bytes()`]: the same as [`Self::serialize()`], but
+    /// without base64, for binary transports. The version is the first byte,
+    /// followed by the compressed encoding of the machine.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let encoded = self.encode()?;
+        if encoded.len() > MAX_DECOMPRESSED_SIZE {
+            Err(Error::Machine(format!(
+                "encoded machine is {} bytes, max is {}",
+                encoded.len(),
+                MAX_DECOMPRESSED_SIZE
+            )))?;
+        }
+        let mut e = ZlibEncoder::new(vec![VERSION], Compression::best());
+        e.write_all(encoded.as_slice())
+            .map_err(|e| Error::Machine(e.to_string()))?;
+        e.finish().map_err(|e| Error::Machine(e.to_string()))
+    }
+
+    // The encoding of the machine since version 3: JSON, where fields are
+    // tagged by name. Fields added later have defaults (#[serde(default)]), so
+    // machines encoded before still decode, without changing the version.
+    fn encode(&self) -> Result<Vec<u8>, Error> {
+        // only maps with keys that are not strings fail to encode, and the
+        // only map of a machine, the transitions of a state, is keyed by the
+        // names of events
+        serde_json::to_vec(self).map_err(|e| Error::Machine(e.to_string()))
+    }
+
+    /// Attempt to create a machine from bytes serialized with
+    /// [`Self::to_bytes()`]. Like [`Machine::from_str()`](std::str::FromStr),
+    /// the machine is validated, possibly as a template.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let Some((&version, compressed)) = bytes.split_first() else {
+            Err(Error::Machine("bytes too short".to_string()))?
+        };
+        check_version(version)?;
+        from_compressed(version, compressed)
+    }
+
+    /// Record the actions of the machine when replaying the log of events with
+    /// a random number generator seeded with the seed, as a [`Fixture`] to
+    /// later check with [`Self::assert_fixture()`] that the machine still
+    /// behaves the same, e.g., after a refactor. The machine runs alone,
+    /// without framework limits. Returns an error if the machine is invalid.
+    ///
+    /// ```
+    /// use maybenot::{replay::{EventLog, Fixture}, Machine, MachineId, TriggerEvent};
+    /// use std::time::Duration;
+    ///
+    /// // a normal packet, and then the padding of the machine every 1 ms
+    /// let mut log = EventLog::new();
+    /// log.record(&[TriggerEvent::NormalSent], Duration::ZERO);
+    /// for i in 1..10 {
+    ///     let padding = TriggerEvent::PaddingSent {
+    ///         machine: MachineId::from_raw(0),
+    ///     };
+    ///     log.record(&[padding], Duration::from_millis(i));
+    /// }
+    ///
+    /// let m = Machine::constant_rate(1000).unwrap();
+    /// let fixture = m.record_fixture(&log, 0).unwrap();
+    /// assert_eq!(fixture.len(), 10);
+    ///
+    /// // stored as text, e.g., in a file next to the tests, and checked later
+    /// let stored = fixture.to_string();
+    /// m.assert_fixture(&stored.parse::<Fixture>().unwrap());
+    /// ```
+    pub fn record_fixture(&self, log: &EventLog, seed: u64) -> Result<Fixture, Error> {
+        Fixture::record(self, log, seed)
+    }
+
+    /// Replay the log of the fixture with its seed, as in
+    /// [`Self::record_fixture()`], and panic if the actions of the machine
+    /// differ from those recorded, describing the first difference.
+    pub fn assert_fixture(&self, fixture: &Fixture) {
+        let actual = self
+            .record_fixture(fixture.log(), fixture.seed())
+            .expect("fixture recorded for an invalid machine");
+        actual.assert_matches(fixture);
     }
 
     /// Validates that the machine is in a valid state (machines that are
-    /// mutated may get into an invalid state).
+    /// mutated may get into an invalid state). Machine templates do not
+    /// validate, since their parameters are unbound: see
+    /// [`Self::validate_template()`].
     pub fn validate(&self) -> Result<(), Error> {
-        // sane limits
-        if self.max_padding_frac < 0.0 || self.max_padding_frac > 1.0 {
+        self.validate_template()?;
+        if let Some(param) = self.params.first() {
+            Err(Error::Machine(format!("unbound parameter {}", param.name)))?;
+        }
+        Ok(())
+    }
+
+    /// Validates the machine like [`Self::validate()`], but allows it to be a
+    /// template (see [`Self::params`]) as long as all parameters are bound to
+    /// distributions of the machine.
+    pub fn validate_template(&self) -> Result<(), Error> {
+        // before anything else, since an unsupported feature may explain why
+        // the machine is otherwise invalid
+        let missing = self.requires & !SUPPORTED_FEATURES;
+        if missing & FEATURE_PORTABLE_SAMPLE != 0 {
+            Err(Error::UnsupportedFeature("portable-sample".to_string()))?;
+        }
+        if missing != 0 {
+            Err(Error::UnsupportedFeature(format!(
+                "unknown feature bits {:#x}",
+                missing
+            )))?;
+        }
+
+        // sane limits, also finite: NaN is encoded as null, which would not
+        // decode again
+        if !(0.0..=1.0).contains(&self.max_padding_frac) {
             return Err(Error::Machine(format!(
                 "max_padding_frac has to be [0.0, 1.0], got {}",
                 self.max_padding_frac
             )));
         }
-        if self.max_blocking_frac < 0.0 || self.max_blocking_frac > 1.0 {
+        if !(0.0..=1.0).contains(&self.max_blocking_frac) {
             return Err(Error::Machine(format!(
                 "max_blocking_frac has to be [0.0, 1.0], got {}",
                 self.max_blocking_frac
@@ -110,12 +798,52 @@ impl Machine {
             state
                 .validate(num_states)
                 .map_err(|e| Error::Machine(e.to_string()))?;
+
+            if self.time_threshold.is_none()
+                && !state.get_transitions()[Event::TimeThreshold].is_empty()
+            {
+                Err(Error::Machine(
+                    "found transitions on TimeThreshold without a time threshold".to_string(),
+                ))?;
+            }
+            if let Some(index) = state.shared_limit {
+                if index >= self.shared_limits.len() {
+                    Err(Error::Machine(format!(
+                        "found out-of-bounds shared limit index {}",
+                        index
+                    )))?;
+                }
+            }
+        }
+
+        // validate all shared limits
+        for limit in self.shared_limits.iter() {
+            limit.validate()?;
+        }
+
+        // validate that all parameters can be bound, on a copy
+        if !self.params.is_empty() {
+            let mut m = self.clone();
+            for param in self.params.iter() {
+                m.bind(param, 0.0)?;
+            }
         }
 
         Ok(())
     }
 }
 
+// the sorted target states of transitions, for Machine::topology_hash()
+fn targets(transitions: &[Trans]) -> String {
+    let mut targets: Vec<usize> = transitions.iter().map(|t| t.0).collect();
+    targets.sort_unstable();
+    targets
+        .iter()
+        .map(|t| t.to_string())
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
 /// From a serialized string, attempt to create a machine.
 impl FromStr for Machine {
     type Err = Error;
@@ -125,13 +853,16 @@ impl FromStr for Machine {
         if s.len() < 3 {
             Err(Error::Machine("string too short".to_string()))?;
         }
-        let version = &s[0..2];
-        if version != format!("{:02}", VERSION) {
+        // not necessarily on a char boundary for invalid input
+        let version = s.get(0..2).unwrap_or_default();
+        let digits = version.bytes().all(|b| b.is_ascii_digit());
+        let Some(v) = version.parse::<u8>().ok().filter(|_| digits) else {
             Err(Error::Machine(format!(
                 "version mismatch, expected {}, got {}",
                 VERSION, version
-            )))?;
-        }
+            )))?
+        };
+        check_version(v)?;
         let s = &s[2..];
 
         // base64 decoding has a fixed ratio of ~4:3
@@ -139,27 +870,67 @@ impl FromStr for Machine {
         if compressed.is_err() {
             Err(Error::Machine("base64 decoding failed".to_string()))?;
         }
-        let compressed = compressed.unwrap();
-        // decompress, but scared of exceeding memory limits / zlib bombs
-        let mut decoder = ZlibDecoder::new(compressed.as_slice());
-        let mut buf = vec![0; MAX_DECOMPRESSED_SIZE];
-        let bytes_read = decoder
-            .read(&mut buf)
-            .map_err(|e| Error::Machine(e.to_string()))?;
+        from_compressed(v, &compressed.unwrap())
+    }
+}
 
-        // With binencode, note that "The size of the encoded object will be the
-        // same or smaller than the size that the object takes up in memory in a
-        // running Rust program".
-        let bincoder = bincode::DefaultOptions::new().with_limit(MAX_DECOMPRESSED_SIZE as u64);
-        let r = bincoder.deserialize(&buf[..bytes_read]);
+/// From serialized bytes, attempt to create a machine, see
+/// [`Machine::from_bytes()`].
+impl TryFrom<&[u8]> for Machine {
+    type Error = Error;
 
-        // ensure that the machine is valid
-        let m: Machine = r.map_err(|e| Error::Machine(e.to_string()))?;
-        m.validate()?;
-        Ok(m)
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        Machine::from_bytes(bytes)
+    }
+}
+
+// Version 2 is the only older version still accepted: it was the published
+// format before field tags, and its machines are in use, so rejecting them
+// would break every deployed machine for no gain. Version 2 machines decode
+// exactly as before (see v2.rs), with the defaults of all fields added since.
+// Versions before 2 are rejected with an error saying so.
+fn check_version(version: u8) -> Result<(), Error> {
+    match version {
+        V2 | VERSION => Ok(()),
+        v if v < VERSION => Err(Error::Machine(format!(
+            "unsupported old version {}, expected {} or {}: the serialization format has changed",
+            v, V2, VERSION
+        ))),
+        v => Err(Error::Machine(format!(
+            "version mismatch, expected {}, got {}",
+            VERSION, v
+        ))),
     }
 }
 
+// The last version bincoded without field tags, still decoded, see v2.rs.
+const V2: u8 = 2;
+
+fn from_compressed(version: u8, compressed: &[u8]) -> Result<Machine, Error> {
+    // decompress, but scared of exceeding memory limits / zlib bombs
+    let mut buf = vec![];
+    ZlibDecoder::new(compressed)
+        .take(MAX_DECOMPRESSED_SIZE as u64 + 1)
+        .read_to_end(&mut buf)
+        .map_err(|e| Error::Machine(e.to_string()))?;
+    if buf.len() > MAX_DECOMPRESSED_SIZE {
+        Err(Error::Machine(format!(
+            "decompressed machine exceeds {} bytes",
+            MAX_DECOMPRESSED_SIZE
+        )))?;
+    }
+
+    let m = if version == V2 {
+        v2::decode(&buf)?
+    } else {
+        serde_json::from_slice::<Machine>(&buf).map_err(|e| Error::Machine(e.to_string()))?
+    };
+
+    // ensure that the machine is valid, possibly a template
+    m.validate_template()?;
+    Ok(m)
+}
+
 impl fmt::Display for Machine {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
@@ -169,6 +940,11 @@ impl fmt::Display for Machine {
             - max_padding_frac: {}\n\
             - allowed_blocked_microsec: {}\n\
             - max_blocking_frac: {}\n\
+            - shared_limits: [{}]\n\
+            - on_end: {:?}\n\
+            - params: [{}]\n\
+            - requires: {:#x}\n\
+            - time_threshold: {:?}\n\
             States:\n\
             {}",
             self.name(),
@@ -176,6 +952,19 @@ impl fmt::Display for Machine {
             self.max_padding_frac,
             self.allowed_blocked_microsec,
             self.max_blocking_frac,
+            self.shared_limits
+                .iter()
+                .map(|d| format!("{}", d))
+                .collect::<Vec<String>>()
+                .join(", "),
+            self.on_end,
+            self.params
+                .iter()
+                .map(|p| p.name.as_str())
+                .collect::<Vec<&str>>()
+                .join(", "),
+            self.requires,
+            self.time_threshold,
             self.states
                 .iter()
                 .map(|s| format!("{}", s))
@@ -185,12 +974,62 @@ impl fmt::Display for Machine {
     }
 }
 
+#[cfg(test)]
+impl proptest::arbitrary::Arbitrary for Machine {
+    type Parameters = ();
+    type Strategy = proptest::strategy::BoxedStrategy<Self>;
+
+    /// Arbitrary valid machines with up to five states.
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        use proptest::prelude::*;
+
+        (1..=5usize)
+            .prop_flat_map(|num_states| {
+                (
+                    0..100u64,
+                    prop_oneof![Just(0.0), 0.0..=1.0],
+                    0..10000u64,
+                    prop_oneof![Just(0.0), 0.0..=1.0],
+                    proptest::collection::vec(any_with::<State>(num_states), num_states),
+                    prop_oneof![Just(OnEnd::KeepPending), Just(OnEnd::CancelPending)],
+                    proptest::option::of(0..10000u64),
+                )
+            })
+            .prop_filter_map(
+                "invalid machine",
+                |(
+                    allowed_padding,
+                    max_padding,
+                    allowed_blocked,
+                    max_blocking,
+                    states,
+                    on_end,
+                    time_threshold,
+                )| {
+                    let mut m = Machine::noop();
+                    m.allowed_padding_packets = allowed_padding;
+                    m.max_padding_frac = max_padding;
+                    m.allowed_blocked_microsec = allowed_blocked;
+                    m.max_blocking_frac = max_blocking;
+                    m.states = states;
+                    m.on_end = on_end;
+                    m.time_threshold = time_threshold;
+                    m.validate().ok()?;
+                    Some(m)
+                },
+            )
+            .boxed()
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use crate::counter::{Counter, Operation};
     use crate::event::Event;
     use crate::machine::*;
     use crate::state::Trans;
     use enum_map::enum_map;
+    use std::time::Instant;
 
     #[test]
     fn machine_name_generation() {
@@ -206,6 +1045,336 @@ mod tests {
         assert_eq!(m.name(), m.name());
     }
 
+    #[test]
+    fn machine_time_threshold() {
+        let s0 = State::new(enum_map! {
+                 Event::TimeThreshold => vec![Trans(0, 1.0)],
+             _ => vec![],
+        });
+
+        // transitions on the event require a threshold
+        let r = Machine::new(1000, 1.0, 0, 0.0, vec![s0.clone()]);
+        assert!(r.is_err());
+        let mut m = Machine::noop();
+        m.states = vec![s0];
+        m.time_threshold = Some(1000);
+        assert!(m.validate().is_ok());
+        let s = m.serialize().unwrap();
+        assert_eq!(Machine::from_str(&s).unwrap().time_threshold, Some(1000));
+    }
+
+    #[test]
+    fn machine_serialize_too_large() {
+        let s0 = State::new(enum_map! {
+                 Event::PaddingSent => vec![Trans(0, 1.0)],
+             _ => vec![],
+        });
+        let mut m = Machine::new(1000, 1.0, 0, 0.0, vec![s0]).unwrap();
+        let s = m.serialize().unwrap();
+        assert_eq!(Machine::from_str(&s).unwrap().name(), m.name());
+
+        // a machine too large to be parsed again is an error, not a panic
+        m.states = vec![m.states[0].clone(); MAX_DECOMPRESSED_SIZE / 8];
+        assert!(m.serialize().is_err());
+        assert_eq!(m.name().len(), 32);
+
+        // and such machines still have names of their own
+        let mut m2 = m.clone();
+        m2.allowed_padding_packets += 1;
+        assert!(m2.serialize().is_err());
+        assert_ne!(m2.name(), m.name());
+    }
+
+    #[test]
+    fn machine_canonicalize() {
+        let s0 = State::new(enum_map! {
+            Event::PaddingSent => vec![Trans(0, 0.2), Trans(1, 0.3), Trans(STATE_END, 0.5)],
+            Event::NormalSent => vec![Trans(1, 1.0)],
+            _ => vec![],
+        });
+        let s1 = State::new(enum_map! {
+            Event::NormalSent => vec![Trans(STATE_SIGNAL, 0.1), Trans(0, 0.9)],
+            _ => vec![],
+        });
+        let a = Machine::new(1000, 1.0, 0, 0.0, vec![s0, s1]).unwrap();
+
+        // same transitions, different order
+        let s0 = State::new(enum_map! {
+            Event::PaddingSent => vec![Trans(STATE_END, 0.5), Trans(0, 0.2), Trans(1, 0.3)],
+            Event::NormalSent => vec![Trans(1, 1.0)],
+            _ => vec![],
+        });
+        let s1 = State::new(enum_map! {
+            Event::NormalSent => vec![Trans(0, 0.9), Trans(STATE_SIGNAL, 0.1)],
+            _ => vec![],
+        });
+        let b = Machine::new(1000, 1.0, 0, 0.0, vec![s0, s1]).unwrap();
+
+        assert_ne!(a.name(), b.name());
+        assert_eq!(a.canonicalize().name(), b.canonicalize().name());
+        assert_eq!(
+            a.canonicalize().serialize().unwrap(),
+            b.canonicalize().serialize().unwrap()
+        );
+        // canonicalization is idempotent
+        assert_eq!(
+            a.canonicalize().canonicalize().name(),
+            a.canonicalize().name()
+        );
+        assert_eq!(
+            b.canonicalize().states[0].get_transitions()[Event::PaddingSent],
+            vec![Trans(0, 0.2), Trans(1, 0.3), Trans(STATE_END, 0.5)]
+        );
+
+        // different transitions remain different
+        let mut c = b.clone();
+        c.states[1] = State::new(enum_map! {
+            Event::NormalSent => vec![Trans(0, 0.8), Trans(STATE_SIGNAL, 0.2)],
+            _ => vec![],
+        });
+        assert_ne!(a.canonicalize().name(), c.canonicalize().name());
+    }
+
+    #[test]
+    fn from_str_invalid() {
+        // too short, wrong version, and a multi-byte char in the version
+        // (found by fuzzing)
+        assert!(Machine::from_str("02").is_err());
+        assert!(Machine::from_str("01eNpjYEAHjBgiDAAAOAAC").is_err());
+        assert!(Machine::from_str("\u{ca}\u{9a};h").is_err());
+        assert!(Machine::from_str("0\u{ca}eNpjYEAHjBgiDAAAOAAC").is_err());
+    }
+
+    #[test]
+    fn from_str_versions() {
+        // the noop machine of version 2, bincoded without field tags
+        let v2 = "02eNpjYEAHjOgCAAA0AAI=";
+        let m = Machine::from_str(v2).unwrap();
+        assert_eq!(m.name(), Machine::noop().name());
+        assert!(m.serialize().unwrap().starts_with("03"));
+
+        // a version 2 payload is not a version 3 one, and vice versa
+        assert!(Machine::from_str(&v2.replacen("02", "03", 1)).is_err());
+        let v3 = Machine::noop().serialize().unwrap();
+        assert!(Machine::from_str(&v3.replacen("03", "02", 1))
+            .unwrap_err()
+            .to_string()
+            .contains("invalid version 2 machine"));
+
+        // older versions are rejected
+        let r = Machine::from_str("01eNpjYEAHjAxYAAAAPAAC");
+        assert_eq!(
+            r.unwrap_err().to_string(),
+            "invalid machine: unsupported old version 1, expected 2 or 3: \
+            the serialization format has changed"
+        );
+
+        // newer versions are not supported either
+        let r = Machine::from_str(&v3.replacen("03", "04", 1));
+        assert_eq!(
+            r.unwrap_err().to_string(),
+            "invalid machine: version mismatch, expected 3, got 4"
+        );
+
+        // nor are versions that are not two digits
+        assert!(Machine::from_str(&v3.replacen("03", "+3", 1)).is_err());
+
+        // the same for bytes, where only version 2 is decoded of the older
+        // versions
+        assert!(check_version(V2).is_ok());
+        assert!(check_version(VERSION).is_ok());
+        for v in 0..V2 {
+            assert!(check_version(v)
+                .unwrap_err()
+                .to_string()
+                .contains(&format!("unsupported old version {}", v)));
+        }
+        let r = Machine::from_bytes(&[1, 0x78, 0x9c]);
+        assert!(r
+            .unwrap_err()
+            .to_string()
+            .contains("unsupported old version 1"));
+    }
+
+    #[test]
+    fn from_str_v2() {
+        // a version 2 machine with all actions, counters, and transitions on
+        // both the first and last event of version 2
+        let v2 = "02eNqFT7ENwkAMvPsQEVFTUrIB1HkXLEFPxRSUdAzDIpFYgBEoI4EA8/ZHfCi44s9v+3z2DgnXeO+mFnaxBgP9NxfjWzRe2l8b9I0BOLRwkI+XQlORJJiSm5MJZC3FgONZ6LrGKwvBGGRmfsZaGFKobs/khla3AfEPjbPki4vqvpcv18qOQDYFK86s2Ltu4v3bQcd6lXCJP2eg2G8YTIRR5xsHKiHq";
+        let m = Machine::from_str(v2).unwrap();
+        assert_eq!(m.allowed_padding_packets, 100);
+        assert_eq!(m.max_padding_frac, 0.5);
+        assert_eq!(m.allowed_blocked_microsec, 2000);
+        assert_eq!(m.max_blocking_frac, 0.25);
+        assert_eq!(m.states.len(), 5);
+
+        let t = m.states[0].get_transitions();
+        assert_eq!(t[Event::NormalSent], vec![Trans(1, 0.5), Trans(2, 0.25)]);
+        assert_eq!(t[Event::Signal], vec![Trans(STATE_END, 1.0)]);
+        assert_eq!(
+            m.states[0].counter.0,
+            Some(Counter::new_dist(
+                Operation::Set,
+                Dist::new(DistType::Poisson { lambda: 5.0 }, 1.0, 10.0)
+            ))
+        );
+        assert_eq!(
+            m.states[1].action,
+            Some(Action::SendPadding {
+                bypass: true,
+                replace: false,
+                timeout: Dist::new(
+                    DistType::Normal {
+                        mean: 1000.5,
+                        stdev: 20.25
+                    },
+                    10.0,
+                    5000.0
+                ),
+                limit: Some(Dist::new(
+                    DistType::Uniform {
+                        low: 3.0,
+                        high: 7.0
+                    },
+                    0.0,
+                    0.0
+                )),
+            })
+        );
+        assert_eq!(
+            m.states[1].get_transitions()[Event::TimerEnd],
+            vec![Trans(STATE_SIGNAL, 0.125)]
+        );
+        assert_eq!(
+            m.states[1].counter.1,
+            Some(Counter::new(Operation::Decrement))
+        );
+        assert_eq!(
+            m.states[2].action,
+            Some(Action::BlockOutgoing {
+                bypass: false,
+                replace: true,
+                lane: 0,
+                timeout: Dist::constant(0.0),
+                duration: Dist::new(
+                    DistType::Weibull {
+                        scale: 2.0,
+                        shape: 0.5
+                    },
+                    0.0,
+                    100000.0
+                ),
+                limit: None,
+            })
+        );
+        assert_eq!(
+            m.states[3].action,
+            Some(Action::UpdateTimer {
+                replace: true,
+                duration: Dist::new(
+                    DistType::Beta {
+                        alpha: 1.5,
+                        beta: 2.5
+                    },
+                    100.0,
+                    0.0
+                ),
+                limit: Some(Dist::new(
+                    DistType::Geometric { probability: 0.3 },
+                    0.0,
+                    0.0
+                )),
+            })
+        );
+        assert!(m.states[3].counter.0.unwrap().copy);
+        assert_eq!(
+            m.states[4].action,
+            Some(Action::Cancel { timer: Timer::All })
+        );
+
+        // fields added since version 2 have their defaults
+        assert!(m.shared_limits.is_empty());
+        assert_eq!(m.on_end, OnEnd::KeepPending);
+        assert!(m.params.is_empty());
+        assert_eq!(m.requires, 0);
+
+        // and the machine round-trips with the current version
+        let m2 = Machine::from_str(&m.serialize().unwrap()).unwrap();
+        assert_eq!(m2.name(), m.name());
+    }
+
+    #[test]
+    fn from_bytes_without_added_fields() {
+        // a machine encoded before shared limits, blocking conditions, and
+        // OnEnd were added decodes with their defaults
+        let json = r#"{
+            "allowed_padding_packets": 10,
+            "max_padding_frac": 0.5,
+            "allowed_blocked_microsec": 0,
+            "max_blocking_frac": 0.0,
+            "states": [{
+                "action": null,
+                "counter": [null, null],
+                "transitions": {"NormalSent": [[0, 1.0]]}
+            }]
+        }"#;
+        let mut e = ZlibEncoder::new(vec![VERSION], Compression::best());
+        e.write_all(json.as_bytes()).unwrap();
+        let m = Machine::from_bytes(&e.finish().unwrap()).unwrap();
+        assert_eq!(m.allowed_padding_packets, 10);
+        assert!(m.shared_limits.is_empty());
+        assert_eq!(m.on_end, OnEnd::KeepPending);
+        assert_eq!(m.states[0].shared_limit, None);
+        assert_eq!(m.states[0].blocking_condition, None);
+        assert_eq!(
+            m.states[0].get_transitions()[Event::NormalSent],
+            vec![Trans(0, 1.0)]
+        );
+
+        // the same holds for version 2, which had no tags to go by
+        let m = Machine::from_str("02eNpjYEAHjOgCAAA0AAI=").unwrap();
+        assert!(m.shared_limits.is_empty());
+        assert_eq!(m.on_end, OnEnd::KeepPending);
+        assert_eq!(m.states[0].shared_limit, None);
+        assert_eq!(m.states[0].blocking_condition, None);
+    }
+
+    #[test]
+    fn from_bytes() {
+        let m = Machine::new(
+            1000,
+            1.0,
+            0,
+            0.0,
+            vec![State::new(enum_map! {
+                Event::PaddingSent => vec![Trans(0, 1.0)],
+                _ => vec![],
+            })],
+        )
+        .unwrap();
+        let b = m.to_bytes().unwrap();
+        assert_eq!(Machine::from_bytes(&b).unwrap().name(), m.name());
+        assert_eq!(Machine::try_from(b.as_slice()).unwrap().name(), m.name());
+        let s = m.serialize().unwrap();
+        assert_eq!(Machine::from_str(&s).unwrap().name(), m.name());
+
+        // empty, only the version, and old or new versions
+        assert!(Machine::from_bytes(&[]).is_err());
+        assert!(Machine::from_bytes(&b[..1]).is_err());
+        let mut old = b.clone();
+        old[0] = 1;
+        assert!(Machine::from_bytes(&old)
+            .unwrap_err()
+            .to_string()
+            .contains("unsupported old version"));
+        let mut new = b.clone();
+        new[0] = VERSION + 1;
+        assert!(Machine::from_bytes(&new)
+            .unwrap_err()
+            .to_string()
+            .contains("version mismatch"));
+    }
+
     #[test]
     fn validate_machine_limits() {
         let s0 = State::new(enum_map! {
@@ -226,6 +1395,12 @@ mod tests {
         println!("{:?}", r.as_ref().err());
         assert!(r.is_err());
 
+        // NaN is serialized as null, so would not round-trip
+        m.max_padding_frac = f64::NAN;
+        let r = m.validate();
+        println!("{:?}", r.as_ref().err());
+        assert!(r.is_err());
+
         m.max_padding_frac = 0.5;
         let r = m.validate();
         assert!(r.is_ok());
@@ -241,9 +1416,16 @@ mod tests {
         println!("{:?}", r.as_ref().err());
         assert!(r.is_err());
 
+        m.max_blocking_frac = f64::NAN;
+        let r = m.validate();
+        println!("{:?}", r.as_ref().err());
+        assert!(r.is_err());
+
         m.max_blocking_frac = 0.5;
         let r = m.validate();
         assert!(r.is_ok());
+        let parsed = Machine::from_str(&m.serialize().unwrap()).unwrap();
+        assert_eq!(parsed.name(), m.name());
     }
 
     #[test]
@@ -275,4 +1457,653 @@ mod tests {
         let r = Machine::new(1000, 1.0, 0, 0.0, vec![s0]);
         assert!(r.is_ok());
     }
+
+    #[test]
+    fn validate_machine_shared_limits() {
+        let mut s0 = State::new(enum_map! {
+                 Event::PaddingSent => vec![Trans(0, 1.0)],
+             _ => vec![],
+        });
+        s0.shared_limit = Some(0);
+
+        // out of bounds shared limit index
+        let r = Machine::new(1000, 1.0, 0, 0.0, vec![s0.clone()]);
+        println!("{:?}", r.as_ref().err());
+        assert!(r.is_err());
+
+        // invalid shared limit dist
+        let mut m = Machine::new(
+            1000,
+            1.0,
+            0,
+            0.0,
+            vec![State::new(enum_map! {
+                 _ => vec![],
+            })],
+        )
+        .unwrap();
+        m.states = vec![s0];
+        m.shared_limits = vec![Dist::new(
+            crate::dist::DistType::Uniform {
+                low: 15.0, // NOTE low > high
+                high: 5.0,
+            },
+            0.0,
+            0.0,
+        )];
+        let r = m.validate();
+        println!("{:?}", r.as_ref().err());
+        assert!(r.is_err());
+
+        // valid shared limit
+        m.shared_limits = vec![Dist::new(
+            crate::dist::DistType::Uniform {
+                low: 5.0,
+                high: 5.0,
+            },
+            0.0,
+            0.0,
+        )];
+        assert!(m.validate().is_ok());
+    }
+
+    #[test]
+    fn machine_template_instantiate() {
+        use crate::dist::DistType;
+
+        // a padding machine with placeholder timeout and limit
+        let mut s0 = State::new(enum_map! {
+                 Event::PaddingSent => vec![Trans(0, 1.0)],
+             _ => vec![],
+        });
+        s0.action = Some(Action::SendPadding {
+            bypass: false,
+            replace: false,
+            timeout: Dist::new(
+                DistType::Uniform {
+                    low: 0.0,
+                    high: 0.0,
+                },
+                0.0,
+                0.0,
+            ),
+            limit: Some(Dist::new(
+                DistType::Uniform {
+                    low: 1.0,
+                    high: 1.0,
+                },
+                0.0,
+                0.0,
+            )),
+        });
+        let mut template = Machine::new(1000, 1.0, 0, 0.0, vec![s0]).unwrap();
+        template.params = vec![
+            Param {
+                name: "base_delay".to_string(),
+                targets: vec![
+                    (DistRef::Timeout(0), DistParam::First),
+                    (DistRef::Timeout(0), DistParam::Second),
+                ],
+            },
+            Param {
+                name: "spread".to_string(),
+                targets: vec![(DistRef::Timeout(0), DistParam::Max)],
+            },
+            Param {
+                name: "packets".to_string(),
+                targets: vec![(DistRef::Limit(0), DistParam::Start)],
+            },
+        ];
+
+        // a template has unbound parameters, but can be parsed
+        let r = template.validate();
+        assert_eq!(
+            r.unwrap_err().to_string(),
+            "invalid machine: unbound parameter base_delay"
+        );
+        template.validate_template().unwrap();
+        let parsed = Machine::from_str(&template.serialize().unwrap()).unwrap();
+        assert_eq!(parsed.params, template.params);
+
+        // same topology, two sets of parameters
+        let mut params = HashMap::new();
+        params.insert("base_delay".to_string(), 10.0);
+        params.insert("spread".to_string(), 20.0);
+        params.insert("packets".to_string(), 5.0);
+        let m1 = template.instantiate(params.clone()).unwrap();
+        params.insert("base_delay".to_string(), 100.0);
+        params.insert("spread".to_string(), 200.0);
+        params.insert("unused".to_string(), 1.0);
+        let m2 = template.instantiate(params).unwrap();
+
+        for (m, delay, max) in [(&m1, 10.0, 20.0), (&m2, 100.0, 200.0)] {
+            m.validate().unwrap();
+            assert!(m.params.is_empty());
+            let Some(Action::SendPadding { timeout, limit, .. }) = m.states[0].action else {
+                panic!("expected padding action");
+            };
+            assert_eq!(
+                timeout,
+                Dist::new(
+                    DistType::Uniform {
+                        low: delay,
+                        high: delay
+                    },
+                    0.0,
+                    max
+                )
+            );
+            assert_eq!(limit.unwrap().start, 5.0);
+        }
+        assert_ne!(m1.name(), m2.name());
+
+        // all parameters have to be bound
+        let mut params = HashMap::new();
+        params.insert("base_delay".to_string(), 10.0);
+        let r = template.instantiate(params);
+        assert_eq!(
+            r.unwrap_err().to_string(),
+            "invalid machine: unbound parameter spread"
+        );
+
+        // the instantiated machine has to be valid
+        let mut params = HashMap::new();
+        params.insert("base_delay".to_string(), f64::NAN);
+        params.insert("spread".to_string(), 0.0);
+        params.insert("packets".to_string(), 0.0);
+        assert!(template.instantiate(params).is_err());
+
+        // parameters must be bound to existing distributions
+        template.params[2].targets = vec![(DistRef::Duration(0), DistParam::Start)];
+        assert!(template.validate_template().is_err());
+        template.params[2].targets = vec![(DistRef::Timeout(0), DistParam::Third)];
+        assert!(template.validate_template().is_err());
+    }
+
+    #[test]
+    fn machine_load_many() {
+        let s0 = State::new(enum_map! {
+                 Event::PaddingSent => vec![Trans(0, 1.0)],
+             _ => vec![],
+        });
+        let m = Machine::new(1000, 1.0, 0, 0.0, vec![s0]).unwrap();
+        let valid = m.serialize().unwrap();
+        let mut template = m.clone();
+        template.params = vec![Param {
+            name: "limit".to_string(),
+            targets: vec![],
+        }];
+        let template = template.serialize().unwrap();
+
+        let (machines, errors) = Machine::load_many(&[
+            &valid,
+            "01eNpjYEAHjAxYAAAAPAAC",
+            "",
+            &valid,
+            &template,
+            "04not base64",
+        ]);
+        assert_eq!(machines.len(), 2);
+        assert!(machines.iter().all(|parsed| parsed.name() == m.name()));
+        assert_eq!(
+            errors.iter().map(|(i, _)| *i).collect::<Vec<_>>(),
+            vec![1, 2, 4, 5]
+        );
+        assert!(errors[0].1.to_string().contains("unsupported old version"));
+        assert!(errors[2].1.to_string().contains("unbound parameter limit"));
+
+        // nothing to load
+        let (machines, errors) = Machine::load_many(&[]);
+        assert!(machines.is_empty() && errors.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn machine_load_dir() {
+        let s0 = State::new(enum_map! {
+                 Event::PaddingSent => vec![Trans(0, 1.0)],
+             _ => vec![],
+        });
+        let m = Machine::new(1000, 1.0, 0, 0.0, vec![s0]).unwrap();
+
+        let dir = std::env::temp_dir().join(format!("maybenot-load-dir-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("subdir")).unwrap();
+        std::fs::write(dir.join("b.json"), serde_json::to_string(&m).unwrap()).unwrap();
+        std::fs::write(dir.join("a"), format!("{}\n", m.serialize().unwrap())).unwrap();
+
+        // both formats, sorted by file name
+        let machines = Machine::load_dir(&dir).unwrap();
+        assert_eq!(
+            machines.iter().map(|(n, _)| n.as_str()).collect::<Vec<_>>(),
+            vec!["a", "b.json"]
+        );
+        assert!(machines.iter().all(|(_, parsed)| parsed.name() == m.name()));
+        assert_eq!(
+            Machine::from_file(dir.join("b.json")).unwrap().name(),
+            m.name()
+        );
+
+        // any invalid file fails
+        let mut invalid = m.clone();
+        invalid.max_padding_frac = 2.0;
+        std::fs::write(dir.join("c.json"), serde_json::to_string(&invalid).unwrap()).unwrap();
+        assert!(Machine::load_dir(&dir).is_err());
+        std::fs::write(dir.join("c.json"), "{").unwrap();
+        assert!(Machine::load_dir(&dir).is_err());
+        assert!(Machine::from_file(dir.join("missing")).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn machine_requires_features() {
+        let mut m = Machine::noop();
+        assert!(m.validate().is_ok());
+
+        // requiring a feature that the framework was not built with fails to
+        // load, naming the feature
+        m.requires = FEATURE_PORTABLE_SAMPLE;
+        let s = m.serialize().unwrap();
+        let r = Machine::from_str(&s);
+        if cfg!(feature = "portable-sample") {
+            assert!(r.is_ok());
+            assert!(Framework::new(&[m], 0.0, 0.0, Instant::now(), rand::thread_rng()).is_ok());
+        } else {
+            assert_eq!(
+                r.unwrap_err().to_string(),
+                "unsupported feature required by machine: portable-sample"
+            );
+            let machines = [m];
+            let r = Framework::new(&machines, 0.0, 0.0, Instant::now(), rand::thread_rng());
+            assert!(matches!(r, Err(Error::UnsupportedFeature(_))));
+        }
+
+        // unknown features, e.g., of newer frameworks, are never supported
+        let mut m = Machine::noop();
+        m.requires = 1 << 7;
+        let r = Machine::from_str(&m.serialize().unwrap());
+        assert_eq!(
+            r.unwrap_err().to_string(),
+            "unsupported feature required by machine: unknown feature bits 0x80"
+        );
+    }
+
+    #[test]
+    fn machine_topology_hash() {
+        let machine = |p: f32, timeout: f64, limit: f64| {
+            let mut s0 = State::padding(
+                enum_map! {
+                    Event::NormalSent => vec![Trans(1, p), Trans(0, 1.0 - p)],
+                    _ => vec![],
+                },
+                Dist::new(
+                    DistType::Uniform {
+                        low: timeout,
+                        high: timeout,
+                    },
+                    0.0,
+                    0.0,
+                ),
+                Some(Dist::new(
+                    DistType::Uniform {
+                        low: limit,
+                        high: limit,
+                    },
+                    0.0,
+                    0.0,
+                )),
+            );
+            s0.fallback = Some(vec![Trans(STATE_END, 1.0)]);
+            let s1 = State::new(enum_map! {
+                Event::PaddingSent => vec![Trans(0, 1.0)],
+                _ => vec![],
+            });
+            Machine::new(1000, 0.5, 0, 0.0, vec![s0, s1]).unwrap()
+        };
+
+        let m = machine(0.5, 10.0, 5.0);
+        let hash = m.topology_hash();
+        assert_eq!(hash.len(), 32);
+
+        // parameter-only changes keep the topology hash, not the name
+        for other in [
+            machine(0.2, 10.0, 5.0),
+            machine(0.5, 1000.0, 5.0),
+            machine(0.5, 10.0, 1.0),
+            Machine::new(10, 1.0, 100, 0.5, m.states.clone()).unwrap(),
+        ] {
+            assert_ne!(other.name(), m.name());
+            assert_eq!(other.topology_hash(), hash);
+        }
+        let mut other = m.clone();
+        other.states[0].action = None;
+        other.states[1].counter = (
+            Some(crate::counter::Counter::new(
+                crate::counter::Operation::Increment,
+            )),
+            None,
+        );
+        assert_eq!(other.topology_hash(), hash);
+
+        // structural changes do not
+        let mut other = m.clone();
+        other.states[1] = State::new(enum_map! {
+            Event::PaddingRecv => vec![Trans(0, 1.0)],
+            _ => vec![],
+        });
+        assert_ne!(other.topology_hash(), hash);
+        let mut other = m.clone();
+        other.states[1] = State::new(enum_map! {
+            Event::PaddingSent => vec![Trans(1, 1.0)],
+            _ => vec![],
+        });
+        assert_ne!(other.topology_hash(), hash);
+        let mut other = m.clone();
+        other.states[0].fallback = None;
+        assert_ne!(other.topology_hash(), hash);
+        let mut other = m.clone();
+        other.states.push(Machine::noop().states[0].clone());
+        assert_ne!(other.topology_hash(), hash);
+    }
+
+    #[test]
+    fn machine_lint_blocking() {
+        let block = Action::BlockOutgoing {
+            bypass: false,
+            replace: false,
+            lane: 0,
+            timeout: Dist::new(
+                DistType::Uniform {
+                    low: 0.0,
+                    high: 0.0,
+                },
+                0.0,
+                0.0,
+            ),
+            duration: Dist::new(
+                DistType::Uniform {
+                    low: 1000.0,
+                    high: 1000.0,
+                },
+                0.0,
+                0.0,
+            ),
+            limit: None,
+        };
+
+        // blocks on normal traffic, but never learns that it is blocking
+        let s0 = State::new(enum_map! {
+            Event::NormalSent => vec![Trans(1, 1.0)],
+            _ => vec![],
+        });
+        let mut s1 = State::new(enum_map! {
+            Event::NormalRecv => vec![Trans(0, 1.0)],
+            _ => vec![],
+        });
+        s1.action = Some(block);
+        let m = Machine::new(0, 0.0, 1000, 1.0, vec![s0.clone(), s1.clone()]).unwrap();
+        assert_eq!(m.reachable_states(), vec![0, 1]);
+        assert_eq!(m.lint(), vec![Lint::UnhandledBlocking(1)]);
+        assert_eq!(
+            m.lint()[0].to_string(),
+            "state 1: blocks, but no reachable state transitions on BlockingBegin or BlockingEnd"
+        );
+
+        // transitions on the blocking events handle the lifecycle, also in
+        // another reachable state
+        let s2 = State::new(enum_map! {
+            Event::BlockingEnd => vec![Trans(0, 1.0)],
+            _ => vec![],
+        });
+        let mut s1_to_2 = State::new(enum_map! {
+            Event::BlockingBegin => vec![Trans(2, 1.0)],
+            _ => vec![],
+        });
+        s1_to_2.action = Some(block);
+        let m = Machine::new(0, 0.0, 1000, 1.0, vec![s0.clone(), s1_to_2, s2.clone()]).unwrap();
+        assert!(m.lint().is_empty());
+
+        // but not in an unreachable state
+        let m = Machine::new(0, 0.0, 1000, 1.0, vec![s0.clone(), s1.clone(), s2]).unwrap();
+        assert_eq!(m.reachable_states(), vec![0, 1]);
+        assert_eq!(m.lint(), vec![Lint::UnhandledBlocking(1)]);
+
+        // and an unreachable blocking state is not linted
+        let m = Machine::new(
+            0,
+            0.0,
+            1000,
+            1.0,
+            vec![Machine::noop().states[0].clone(), s1],
+        )
+        .unwrap();
+        assert_eq!(m.reachable_states(), vec![0]);
+        assert!(m.lint().is_empty());
+    }
+
+    #[test]
+    fn machine_prune() {
+        use crate::dist::{DistParam, DistType};
+        use crate::replay::EventLog;
+        use crate::{MachineId, TriggerEvent};
+        use std::time::Duration;
+
+        // pads with a random timeout in state 1, and with a fixed one in
+        // state 3, while state 2 is dead: it only transitions to itself and
+        // to the live states
+        let padding = |low, high| {
+            Some(Action::SendPadding {
+                bypass: false,
+                replace: false,
+                timeout: Dist::new(DistType::Uniform { low, high }, 0.0, 0.0),
+                limit: Some(Dist::constant(5.0)),
+            })
+        };
+        let s0 = State::new(enum_map! {
+            Event::NormalSent => vec![Trans(1, 0.5), Trans(3, 0.5)],
+            _ => vec![],
+        });
+        let mut s1 = State::new(enum_map! {
+            Event::PaddingSent => vec![Trans(1, 0.9), Trans(STATE_PREVIOUS, 0.1)],
+            Event::LimitReached => vec![Trans(0, 1.0)],
+            _ => vec![],
+        });
+        s1.action = padding(10.0, 100.0);
+        let mut s2 = State::new(enum_map! {
+            Event::PaddingSent => vec![Trans(2, 0.5), Trans(3, 0.5)],
+            _ => vec![],
+        });
+        s2.action = padding(1.0, 1.0);
+        s2.fallback = Some(vec![Trans(0, 1.0)]);
+        let mut s3 = State::new(enum_map! {
+            Event::PaddingSent => vec![Trans(3, 1.0)],
+            _ => vec![],
+        });
+        s3.action = padding(50.0, 50.0);
+        s3.fallback = Some(vec![Trans(1, 0.5), Trans(STATE_END, 0.5)]);
+        let mut m = Machine::new(u64::MAX, 0.0, 0, 0.0, vec![s0, s1, s2, s3]).unwrap();
+        m.shared_limits = vec![Dist::constant(100.0)];
+
+        let pruned = m.prune();
+        assert_eq!(pruned.states.len(), 3);
+        assert_eq!(pruned.reachable_states(), vec![0, 1, 2]);
+        assert_eq!(pruned.shared_limits, m.shared_limits);
+        assert_eq!(
+            pruned.states[0].get_transitions()[Event::NormalSent],
+            vec![Trans(1, 0.5), Trans(2, 0.5)]
+        );
+        assert_eq!(
+            pruned.states[1].get_transitions()[Event::PaddingSent],
+            vec![Trans(1, 0.9), Trans(STATE_PREVIOUS, 0.1)]
+        );
+        assert_eq!(
+            pruned.states[2].fallback,
+            Some(vec![Trans(1, 0.5), Trans(STATE_END, 0.5)])
+        );
+        assert!(pruned.validate().is_ok());
+        assert!(pruned.serialize().unwrap().len() < m.serialize().unwrap().len());
+        // pruning again changes nothing
+        assert_eq!(pruned.prune().name(), pruned.name());
+
+        // the same actions, with the same random values
+        let mut log = EventLog::new();
+        let machine = MachineId::from_raw(0);
+        for i in 0..100 {
+            log.record(&[TriggerEvent::NormalSent], Duration::from_micros(i * 100));
+            log.record(
+                &[TriggerEvent::PaddingSent { machine }],
+                Duration::from_micros(i * 100 + 50),
+            );
+            log.record(
+                &[TriggerEvent::NormalRecv],
+                Duration::from_micros(i * 100 + 60),
+            );
+        }
+        for seed in 0..10 {
+            let fixture = m.record_fixture(&log, seed).unwrap();
+            assert!(!fixture.is_empty());
+            pruned.assert_fixture(&fixture);
+        }
+
+        // parameters follow their states, or are unbound with them
+        let mut template = m.clone();
+        template.params = vec![Param {
+            name: "timeout".to_string(),
+            targets: vec![
+                (DistRef::Timeout(3), DistParam::First),
+                (DistRef::Timeout(2), DistParam::First),
+                (DistRef::SharedLimit(0), DistParam::First),
+            ],
+        }];
+        assert_eq!(
+            template.prune().params[0].targets,
+            vec![
+                (DistRef::Timeout(2), DistParam::First),
+                (DistRef::SharedLimit(0), DistParam::First),
+            ]
+        );
+
+        // nothing is pruned when all states are reachable
+        assert_eq!(Machine::noop().prune().name(), Machine::noop().name());
+    }
+
+    #[test]
+    fn machine_transition_matrix() {
+        let s0 = State::new(enum_map! {
+            Event::NormalSent => vec![Trans(1, 0.5), Trans(STATE_END, 0.25)],
+            _ => vec![],
+        });
+        let s1 = State::new(enum_map! {
+            Event::NormalSent => vec![Trans(STATE_SIGNAL, 0.5), Trans(2, 0.5)],
+            Event::NormalRecv => vec![Trans(0, 0.3), Trans(STATE_SIGNAL, 0.2)],
+            _ => vec![],
+        });
+        let mut s2 = State::new(enum_map! {
+            Event::NormalRecv => vec![Trans(1, 1.0)],
+            _ => vec![],
+        });
+        s2.fallback = Some(vec![Trans(0, 1.0)]);
+        let m = Machine::new(0, 0.0, 0, 0.0, vec![s0, s1, s2]).unwrap();
+
+        assert_eq!(
+            m.transition_matrix(Event::NormalSent),
+            vec![
+                vec![0.25, 0.5, 0.0, 0.25],
+                vec![0.0, 0.5, 0.5, 0.0],
+                vec![1.0, 0.0, 0.0, 0.0],
+                vec![0.0, 0.0, 0.0, 1.0],
+            ]
+        );
+        let recv = m.transition_matrix(Event::NormalRecv);
+        assert_eq!(recv[0], vec![1.0, 0.0, 0.0, 0.0]);
+        assert!((recv[1][0] - 0.3).abs() < 1e-6);
+        assert!((recv[1][1] - 0.7).abs() < 1e-6);
+        assert_eq!(recv[2], vec![0.0, 1.0, 0.0, 0.0]);
+        assert_eq!(recv[3], vec![0.0, 0.0, 0.0, 1.0]);
+        for row in m.transition_matrix(Event::TimerEnd) {
+            assert!((row.iter().sum::<f32>() - 1.0).abs() < 1e-6);
+        }
+
+        // a random state is any state, reachable or not before, while the
+        // previous state stays on the diagonal
+        let s0 = State::new(enum_map! {
+            Event::NormalSent => vec![Trans(STATE_RANDOM, 0.5), Trans(STATE_PREVIOUS, 0.5)],
+            _ => vec![],
+        });
+        let s1 = State::new(enum_map! {
+            Event::NormalSent => vec![Trans(STATE_PREVIOUS, 1.0)],
+            _ => vec![],
+        });
+        let m = Machine::new(0, 0.0, 0, 0.0, vec![s0, s1]).unwrap();
+        assert_eq!(m.reachable_states(), vec![0, 1]);
+        assert_eq!(
+            m.transition_matrix(Event::NormalSent),
+            vec![
+                vec![0.75, 0.25, 0.0],
+                vec![0.0, 1.0, 0.0],
+                vec![0.0, 0.0, 1.0],
+            ]
+        );
+    }
+
+    #[test]
+    fn machine_lint_exceeds_cap() {
+        // pads with an over-dispersed timeout
+        let mut s0 = State::new(enum_map! {
+            Event::NormalSent => vec![Trans(0, 1.0)],
+            _ => vec![],
+        });
+        s0.action = Some(Action::SendPadding {
+            bypass: false,
+            replace: false,
+            timeout: Dist::new(
+                DistType::Normal {
+                    mean: 1e9,
+                    stdev: 1e11,
+                },
+                0.0,
+                0.0,
+            ),
+            limit: None,
+        });
+        let m = Machine::new(1000, 1.0, 0, 0.0, vec![s0]).unwrap();
+        assert_eq!(m.lint(), vec![Lint::ExceedsCap(0)]);
+        assert_eq!(
+            m.lint()[0].to_string(),
+            "state 0: timeout or duration often exceeds its cap, effectively constant"
+        );
+    }
+
+    mod properties {
+        use crate::machine::*;
+        use proptest::prelude::*;
+
+        proptest! {
+            // no serialized collection depends on iteration order, so neither
+            // repeated serialization, clones, nor parsing change the bytes
+            #[test]
+            fn machine_serialize_deterministic(m in any::<Machine>()) {
+                let s = m.serialize().unwrap();
+                for _ in 0..100 {
+                    prop_assert_eq!(&m.serialize().unwrap(), &s);
+                }
+                prop_assert_eq!(&m.clone().serialize().unwrap(), &s);
+                let parsed = Machine::from_str(&s).unwrap();
+                prop_assert_eq!(&parsed.serialize().unwrap(), &s);
+                prop_assert_eq!(parsed.name(), m.name());
+            }
+
+            // the byte form is the string form without base64
+            #[test]
+            fn machine_bytes_round_trip(m in any::<Machine>()) {
+                let b = m.to_bytes().unwrap();
+                let s = m.serialize().unwrap();
+                prop_assert_eq!(b[0], VERSION);
+                prop_assert_eq!(BASE64_STANDARD.encode(&b[1..]), &s[2..]);
+                let parsed = Machine::from_bytes(&b).unwrap();
+                prop_assert_eq!(&parsed.to_bytes().unwrap(), &b);
+                prop_assert_eq!(parsed.name(), m.name());
+            }
+        }
+    }
 }
@@ -147,6 +147,7 @@ pub fn parse_state(buf: Vec<u8>, num_states: usize) -> Result<State, Error> {
             action = Some(Action::BlockOutgoing {
                 bypass,
                 replace,
+                lane: 0,
                 timeout,
                 duration,
                 limit,
@@ -175,27 +176,39 @@ pub fn parse_state(buf: Vec<u8>, num_states: usize) -> Result<State, Error> {
             r += 8; // for f64
 
             if v != 0.0 {
-                let state = match i.cmp(&(num_states)) {
-                    Ordering::Less => i,
-                    // FIXME: if someone really needs this, it can be supported
-                    // by dynamically creating a new state with the cancel
-                    // action and adjusting transitions accordingly
-                    Ordering::Equal => Err(Error::Machine(
-                        "invalid state, not supported in v2".to_string(),
-                    ))?,
-                    Ordering::Greater => STATE_END,
-                };
-
-                transitions[*event].push(Trans(state, v as f32));
+                transitions[*event].push(Trans(v1_target(i, num_states)?, v as f32));
             }
         }
     }
 
     let mut s = State::new(transitions);
     s.action = action;
+    // the probabilities of a v1 vector, including the pseudo-states, must
+    // not be over-allocated
+    s.validate(num_states)?;
     Ok(s)
 }
 
+// In v1, the probability vector of an event has a slot per state followed by
+// the two pseudo-states, cancel and end, while transitions now name their
+// target explicitly.
+fn v1_target(slot: usize, num_states: usize) -> Result<usize, Error> {
+    match slot.cmp(&num_states) {
+        Ordering::Less => Ok(slot),
+        // FIXME: if someone really needs this, it can be supported by
+        // dynamically creating a new state with the cancel action and
+        // adjusting transitions accordingly
+        Ordering::Equal => Err(Error::Machine(
+            "invalid state, not supported in v2".to_string(),
+        )),
+        Ordering::Greater if slot == num_states + 1 => Ok(STATE_END),
+        Ordering::Greater => Err(Error::Machine(format!(
+            "found out-of-bounds v1 state slot {}",
+            slot
+        ))),
+    }
+}
+
 fn parse_dist(buf: Vec<u8>) -> Result<Option<Dist>, Error> {
     if buf.len() < SERIALIZED_DIST_SIZE {
         Err(Error::Machine("too small".to_string()))?;
@@ -216,6 +229,7 @@ fn parse_dist(buf: Vec<u8>) -> Result<Option<Dist>, Error> {
         dist: dist_type.unwrap(),
         start,
         max,
+        ..Default::default()
     }))
 }
 
@@ -268,6 +282,48 @@ mod tests {
 
     use super::*;
 
+    // a v1 state without action, with the given (event, slot, probability)
+    // transitions
+    fn v1_state(num_states: usize, transitions: &[(usize, usize, f64)]) -> Vec<u8> {
+        let mut buf =
+            vec![
+                0;
+                3 * SERIALIZED_DIST_SIZE + 4 + (num_states + 2) * 8 * (v1_events_iter().len() + 1)
+            ];
+        for &(event, slot, p) in transitions {
+            let r = 3 * SERIALIZED_DIST_SIZE + 4 + (event * (num_states + 2) + slot) * 8;
+            LittleEndian::write_f64(&mut buf[r..r + 8], p);
+        }
+        buf
+    }
+
+    #[test]
+    fn test_parse_v1_pseudo_states() {
+        // NormalRecv (event 0) to state 1, the end slot, or both
+        let s = parse_state(v1_state(2, &[(0, 1, 1.0)]), 2).unwrap();
+        assert_eq!(s.get_transitions()[Event::NormalRecv], vec![Trans(1, 1.0)]);
+        let s = parse_state(v1_state(2, &[(0, 3, 1.0)]), 2).unwrap();
+        assert_eq!(
+            s.get_transitions()[Event::NormalRecv],
+            vec![Trans(STATE_END, 1.0)]
+        );
+        let s = parse_state(v1_state(2, &[(0, 0, 0.25), (0, 3, 0.75)]), 2).unwrap();
+        assert_eq!(
+            s.get_transitions()[Event::NormalRecv],
+            vec![Trans(0, 0.25), Trans(STATE_END, 0.75)]
+        );
+        // PaddingRecv (event 1) is not affected by NormalRecv
+        assert!(s.get_transitions()[Event::PaddingRecv].is_empty());
+
+        // the cancel slot is not supported
+        let r = parse_state(v1_state(2, &[(0, 2, 1.0)]), 2);
+        assert!(r.unwrap_err().to_string().contains("not supported"));
+
+        // over-allocated to a pseudo-state
+        assert!(parse_state(v1_state(2, &[(0, 1, 0.5), (0, 3, 0.75)]), 2).is_err());
+        assert!(parse_state(v1_state(2, &[(0, 3, 1.5)]), 2).is_err());
+    }
+
     #[test]
     fn test_parse_v1_machine() {
         // some examples machines, from noop to manually more complex and two
@@ -283,7 +339,7 @@ mod tests {
             println!("{:?}", machine);
             assert_eq!(
                 machine.name(),
-                Machine::from_str(machine.serialize().as_str())
+                Machine::from_str(machine.serialize().unwrap().as_str())
                     .unwrap()
                     .name()
             );
@@ -0,0 +1,616 @@
+//! A compact binary log of the events triggered in a [`Framework`], for
+//! replaying them later against the same machines, e.g., to reproduce an
+//! incident.
+//!
+//! Together with a seeded random number generator, replaying a log returns
+//! exactly the same actions as when the events were recorded. A [`Fixture`]
+//! records the actions of a machine for a log and seed, to check that the
+//! machine still behaves the same later.
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use base64::prelude::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+use rand_core::RngCore;
+
+use crate::{Error, Framework, Machine, MachineId, TriggerAction, TriggerEvent};
+
+// the version of the binary format, the first byte of an encoded log
+const LOG_VERSION: u8 = 2;
+
+/// A log of the events of each call to [`Framework::trigger_events()`], with
+/// the time of the call.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EventLog {
+    // the time of each call since the framework was created, in microseconds,
+    // and the events
+    calls: Vec<(u64, Vec<TriggerEvent>)>,
+}
+
+impl EventLog {
+    /// Create a new, empty, log.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the events of a call to [`Framework::trigger_events()`], at the
+    /// given time since the framework was created (i.e., `current_time` minus
+    /// the time given to [`Framework::new()`]). The time is recorded with
+    /// microsecond precision, and SHOULD be nondecreasing.
+    pub fn record(&mut self, events: &[TriggerEvent], since_start: Duration) {
+        self.calls
+            .push((since_start.as_micros() as u64, events.to_vec()));
+    }
+
+    /// Returns the number of recorded calls.
+    pub fn len(&self) -> usize {
+        self.calls.len()
+    }
+
+    /// Returns true if no calls have been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.calls.is_empty()
+    }
+
+    /// Encode the log in a compact binary format: a version byte, followed by
+    /// each call as the time since the previous call and the number of events,
+    /// and each event as a byte for its type and, if any, the machine and the
+    /// blocking lane. Numbers are variable-length encoded.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = vec![LOG_VERSION];
+        let mut last = 0;
+        for (time, events) in &self.calls {
+            write_varint(&mut out, time.saturating_sub(last));
+            last = *time;
+            write_varint(&mut out, events.len() as u64);
+            for event in events {
+                let (code, machine, lane) = encode_event(event);
+                out.push(code);
+                if let Some(machine) = machine {
+                    write_varint(&mut out, machine.into_raw() as u64);
+                }
+                if let Some(lane) = lane {
+                    out.push(lane);
+                }
+            }
+        }
+        out
+    }
+
+    /// Decode a log encoded with [`Self::to_bytes()`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader = Reader { bytes, pos: 0 };
+        let version = reader.byte()?;
+        if version != LOG_VERSION {
+            Err(Error::EventLog(format!(
+                "unsupported version {}, expected {}",
+                version, LOG_VERSION
+            )))?;
+        }
+
+        let mut calls = vec![];
+        let mut time: u64 = 0;
+        while !reader.is_done() {
+            time = time.saturating_add(reader.varint()?);
+            let n = reader.varint()?;
+            // each event takes at least a byte
+            if n > reader.remaining() as u64 {
+                Err(Error::EventLog(format!("too many events {}", n)))?;
+            }
+            let mut events = Vec::with_capacity(n as usize);
+            for _ in 0..n {
+                events.push(reader.event()?);
+            }
+            calls.push((time, events));
+        }
+        Ok(Self { calls })
+    }
+
+    /// Replay the log against the given machines, creating a framework with
+    /// the given limits and random number generator as by
+    /// [`Framework::new()`]. Returns the actions of all calls, in order.
+    pub fn replay<M, R>(
+        &self,
+        machines: M,
+        max_padding_frac: f64,
+        max_blocking_frac: f64,
+        rng: R,
+    ) -> Result<Vec<TriggerAction>, Error>
+    where
+        M: AsRef<[Machine]>,
+        R: RngCore,
+    {
+        Ok(self
+            .replay_calls(machines, max_padding_frac, max_blocking_frac, rng)?
+            .into_iter()
+            .flatten()
+            .collect())
+    }
+
+    // like replay(), but with the actions of each call separately
+    fn replay_calls<M, R>(
+        &self,
+        machines: M,
+        max_padding_frac: f64,
+        max_blocking_frac: f64,
+        rng: R,
+    ) -> Result<Vec<Vec<TriggerAction>>, Error>
+    where
+        M: AsRef<[Machine]>,
+        R: RngCore,
+    {
+        let start = Instant::now();
+        let mut f = Framework::new(machines, max_padding_frac, max_blocking_frac, start, rng)?;
+        let mut actions = vec![];
+        for (time, events) in &self.calls {
+            let now = start + Duration::from_micros(*time);
+            actions.push(f.trigger_events(events, now).cloned().collect());
+        }
+        Ok(actions)
+    }
+}
+
+/// The actions of a machine when replaying an [`EventLog`] with a seeded
+/// random number generator, created by [`Machine::record_fixture()`] and
+/// checked by [`Machine::assert_fixture()`].
+///
+/// A fixture is stored as text (see its [`fmt::Display`] and [`FromStr`]):
+/// the seed, the encoded log, and then a line per action with the index of
+/// the call to [`Framework::trigger_events()`] it was returned by. Timeouts
+/// and durations are in microseconds. The random number generator is
+/// `ChaCha12Rng`, whose stream is stable across versions and platforms, and
+/// the machine runs alone without framework limits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fixture {
+    seed: u64,
+    log: EventLog,
+    actions: Vec<String>,
+}
+
+impl Fixture {
+    pub(crate) fn record(machine: &Machine, log: &EventLog, seed: u64) -> Result<Self, Error> {
+        let calls = log.replay_calls(
+            std::slice::from_ref(machine),
+            0.0,
+            0.0,
+            ChaCha12Rng::seed_from_u64(seed),
+        )?;
+        let actions = calls
+            .iter()
+            .enumerate()
+            .flat_map(|(i, actions)| actions.iter().map(move |a| encode_action(i, a)))
+            .collect();
+        Ok(Self {
+            seed,
+            log: log.clone(),
+            actions,
+        })
+    }
+
+    /// Returns the seed of the random number generator.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns the log of events replayed.
+    pub fn log(&self) -> &EventLog {
+        &self.log
+    }
+
+    /// Returns the number of recorded actions.
+    pub fn len(&self) -> usize {
+        self.actions.len()
+    }
+
+    /// Returns true if no actions were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    // panics on the first action that differs from the expected fixture
+    pub(crate) fn assert_matches(&self, expected: &Fixture) {
+        for (i, (got, want)) in self.actions.iter().zip(&expected.actions).enumerate() {
+            assert!(
+                got == want,
+                "fixture mismatch at action {}: expected `{}`, got `{}`",
+                i,
+                want,
+                got
+            );
+        }
+        assert!(
+            self.actions.len() == expected.actions.len(),
+            "fixture mismatch: expected {} actions, got {}",
+            expected.actions.len(),
+            self.actions.len()
+        );
+    }
+}
+
+impl fmt::Display for Fixture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "seed {}", self.seed)?;
+        writeln!(f, "log {}", BASE64_STANDARD.encode(self.log.to_bytes()))?;
+        for action in &self.actions {
+            writeln!(f, "{}", action)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Fixture {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines();
+        let seed = lines
+            .next()
+            .and_then(|l| l.strip_prefix("seed "))
+            .and_then(|seed| seed.parse().ok())
+            .ok_or(Error::EventLog("fixture without a seed".to_string()))?;
+        let log = lines
+            .next()
+            .and_then(|l| l.strip_prefix("log "))
+            .and_then(|log| BASE64_STANDARD.decode(log).ok())
+            .ok_or(Error::EventLog("fixture without a log".to_string()))?;
+        Ok(Self {
+            seed,
+            log: EventLog::from_bytes(&log)?,
+            actions: lines.map(|l| l.to_string()).collect(),
+        })
+    }
+}
+
+fn encode_action(call: usize, action: &TriggerAction) -> String {
+    let us = |d: &Duration| d.as_micros();
+    match action {
+        TriggerAction::Cancel { machine, timer } => {
+            format!("{} {} Cancel timer={:?}", call, machine.into_raw(), timer)
+        }
+        TriggerAction::SendPadding {
+            timeout,
+            bypass,
+            replace,
+            machine,
+        } => format!(
+            "{} {} SendPadding timeout={} bypass={} replace={}",
+            call,
+            machine.into_raw(),
+            us(timeout),
+            bypass,
+            replace
+        ),
+        TriggerAction::BlockOutgoing {
+            timeout,
+            duration,
+            bypass,
+            replace,
+            lane,
+            machine,
+        } => format!(
+            "{} {} BlockOutgoing timeout={} duration={} bypass={} replace={} lane={}",
+            call,
+            machine.into_raw(),
+            us(timeout),
+            us(duration),
+            bypass,
+            replace,
+            lane
+        ),
+        TriggerAction::UpdateTimer {
+            duration,
+            replace,
+            machine,
+        } => format!(
+            "{} {} UpdateTimer duration={} replace={}",
+            call,
+            machine.into_raw(),
+            us(duration),
+            replace
+        ),
+        TriggerAction::BlockIncoming {
+            timeout,
+            duration,
+            replace,
+            machine,
+        } => format!(
+            "{} {} BlockIncoming timeout={} duration={} replace={}",
+            call,
+            machine.into_raw(),
+            us(timeout),
+            us(duration),
+            replace
+        ),
+        TriggerAction::HoldOutgoing {
+            timeout,
+            duration,
+            probability,
+            machine,
+        } => format!(
+            "{} {} HoldOutgoing timeout={} duration={} probability={}",
+            call,
+            machine.into_raw(),
+            us(timeout),
+            us(duration),
+            probability
+        ),
+    }
+}
+
+fn encode_event(event: &TriggerEvent) -> (u8, Option<MachineId>, Option<u8>) {
+    match event {
+        TriggerEvent::NormalRecv => (0, None, None),
+        TriggerEvent::PaddingRecv => (1, None, None),
+        TriggerEvent::TunnelRecv => (2, None, None),
+        TriggerEvent::NormalSent => (3, None, None),
+        TriggerEvent::PaddingSent { machine } => (4, Some(*machine), None),
+        TriggerEvent::TunnelSent => (5, None, None),
+        TriggerEvent::BlockingBegin { machine, lane } => (6, Some(*machine), Some(*lane)),
+        TriggerEvent::BlockingEnd { lane } => (7, None, Some(*lane)),
+        TriggerEvent::TimerBegin { machine } => (8, Some(*machine), None),
+        TriggerEvent::TimerEnd { machine } => (9, Some(*machine), None),
+        TriggerEvent::IncomingBlockingBegin { machine } => (10, Some(*machine), None),
+        TriggerEvent::IncomingBlockingEnd => (11, None, None),
+        TriggerEvent::Idle => (12, None, None),
+    }
+}
+
+fn write_varint(out: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        out.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    out.push(v as u8);
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn is_done(&self) -> bool {
+        self.pos >= self.bytes.len()
+    }
+
+    fn remaining(&self) -> usize {
+        self.bytes.len().saturating_sub(self.pos)
+    }
+
+    fn byte(&mut self) -> Result<u8, Error> {
+        let b = *self
+            .bytes
+            .get(self.pos)
+            .ok_or(Error::EventLog("unexpected end of log".to_string()))?;
+        self.pos += 1;
+        Ok(b)
+    }
+
+    fn varint(&mut self) -> Result<u64, Error> {
+        let mut v: u64 = 0;
+        for shift in (0..64).step_by(7) {
+            let b = self.byte()?;
+            v |= ((b & 0x7f) as u64) << shift;
+            if b & 0x80 == 0 {
+                return Ok(v);
+            }
+        }
+        Err(Error::EventLog("too long varint".to_string()))
+    }
+
+    fn event(&mut self) -> Result<TriggerEvent, Error> {
+        let code = self.byte()?;
+        let mut machine =
+            || -> Result<MachineId, Error> { Ok(MachineId::from_raw(self.varint()? as usize)) };
+        Ok(match code {
+            0 => TriggerEvent::NormalRecv,
+            1 => TriggerEvent::PaddingRecv,
+            2 => TriggerEvent::TunnelRecv,
+            3 => TriggerEvent::NormalSent,
+            4 => TriggerEvent::PaddingSent {
+                machine: machine()?,
+            },
+            5 => TriggerEvent::TunnelSent,
+            6 => TriggerEvent::BlockingBegin {
+                machine: machine()?,
+                lane: self.byte()?,
+            },
+            7 => TriggerEvent::BlockingEnd { lane: self.byte()? },
+            8 => TriggerEvent::TimerBegin {
+                machine: machine()?,
+            },
+            9 => TriggerEvent::TimerEnd {
+                machine: machine()?,
+            },
+            10 => TriggerEvent::IncomingBlockingBegin {
+                machine: machine()?,
+            },
+            11 => TriggerEvent::IncomingBlockingEnd,
+            12 => TriggerEvent::Idle,
+            _ => Err(Error::EventLog(format!("unknown event type {}", code)))?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::action::Action;
+    use crate::dist::{Dist, DistType};
+    use crate::event::Event;
+    use crate::replay::*;
+    use crate::state::{State, Trans};
+    use enum_map::enum_map;
+    use rand::SeedableRng;
+    use rand_xoshiro::Xoshiro256StarStar;
+
+    #[test]
+    fn record_and_replay() {
+        // pad and block with random timeouts and durations
+        let uniform = |low: f64, high: f64| Dist {
+            dist: DistType::Uniform { low, high },
+            start: 0.0,
+            max: 0.0,
+            ..Default::default()
+        };
+        let mut s0 = State::new(enum_map! {
+            Event::NormalSent | Event::PaddingSent => vec![Trans(0, 0.5), Trans(1, 0.5)],
+            _ => vec![],
+        });
+        s0.action = Some(Action::SendPadding {
+            bypass: false,
+            replace: false,
+            timeout: uniform(0.0, 100.0),
+            limit: None,
+        });
+        let mut s1 = State::new(enum_map! {
+            Event::BlockingBegin | Event::NormalRecv => vec![Trans(0, 1.0)],
+            _ => vec![],
+        });
+        s1.action = Some(Action::BlockOutgoing {
+            bypass: false,
+            replace: false,
+            lane: 0,
+            timeout: uniform(0.0, 100.0),
+            duration: uniform(0.0, 1000.0),
+            limit: None,
+        });
+        let machines = vec![Machine::new(1000, 1.0, 1000, 1.0, vec![s0, s1]).unwrap(); 2];
+
+        // record a run, driven by its actions
+        let start = Instant::now();
+        let mut f = Framework::new(
+            &machines,
+            0.0,
+            0.0,
+            start,
+            Xoshiro256StarStar::seed_from_u64(42),
+        )
+        .unwrap();
+        let mut log = EventLog::new();
+        let mut recorded = vec![];
+        let mut events = vec![TriggerEvent::NormalSent];
+        for i in 0..200u64 {
+            let since_start = Duration::from_micros(i * 7);
+            log.record(&events, since_start);
+            let actions: Vec<_> = f
+                .trigger_events(&events, start + since_start)
+                .cloned()
+                .collect();
+            events = actions
+                .iter()
+                .map(|a| match a {
+                    TriggerAction::SendPadding { machine, .. } => {
+                        TriggerEvent::PaddingSent { machine: *machine }
+                    }
+                    TriggerAction::BlockOutgoing { machine, .. } => TriggerEvent::BlockingBegin {
+                        machine: *machine,
+                        lane: 0,
+                    },
+                    _ => TriggerEvent::TunnelRecv,
+                })
+                .collect();
+            events.push(match i % 3 {
+                0 => TriggerEvent::NormalSent,
+                1 => TriggerEvent::NormalRecv,
+                _ => TriggerEvent::Idle,
+            });
+            recorded.extend(actions);
+        }
+        assert!(!recorded.is_empty());
+
+        // replaying the decoded log results in identical actions
+        let bytes = log.to_bytes();
+        let decoded = EventLog::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded, log);
+        assert_eq!(decoded.len(), 200);
+        let replayed = decoded
+            .replay(&machines, 0.0, 0.0, Xoshiro256StarStar::seed_from_u64(42))
+            .unwrap();
+        assert_eq!(replayed, recorded);
+    }
+
+    #[test]
+    fn from_bytes_invalid() {
+        let mut log = EventLog::new();
+        log.record(
+            &[
+                TriggerEvent::NormalSent,
+                TriggerEvent::PaddingSent {
+                    machine: MachineId::from_raw(300),
+                },
+            ],
+            Duration::from_micros(1000),
+        );
+        let bytes = log.to_bytes();
+        assert_eq!(EventLog::from_bytes(&bytes).unwrap(), log);
+
+        // empty, truncated, unknown version, and unknown event
+        assert!(EventLog::from_bytes(&[]).is_err());
+        assert!(EventLog::from_bytes(&[LOG_VERSION]).unwrap().is_empty());
+        assert!(EventLog::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        let mut invalid = bytes.clone();
+        invalid[0] = LOG_VERSION + 1;
+        assert!(EventLog::from_bytes(&invalid).is_err());
+        assert!(EventLog::from_bytes(&[LOG_VERSION, 0, 1, 13]).is_err());
+        assert!(EventLog::from_bytes(&[LOG_VERSION, 0, 100, 0]).is_err());
+    }
+
+    // a normal packet, then padding every 10 us, and padding of the peer
+    fn fixture_log() -> EventLog {
+        let mut log = EventLog::new();
+        log.record(&[TriggerEvent::NormalSent], Duration::ZERO);
+        for i in 1..20 {
+            log.record(
+                &[
+                    TriggerEvent::PaddingSent {
+                        machine: MachineId::from_raw(0),
+                    },
+                    TriggerEvent::PaddingRecv,
+                ],
+                Duration::from_micros(i * 10),
+            );
+        }
+        log
+    }
+
+    #[test]
+    fn fixture_round_trip() {
+        let m = Machine::constant_rate(10).unwrap();
+        let log = fixture_log();
+        let fixture = m.record_fixture(&log, 7).unwrap();
+        assert_eq!(fixture.len(), 20);
+        assert_eq!(fixture.seed(), 7);
+        assert_eq!(fixture.log(), &log);
+        assert_eq!(
+            fixture.to_string().lines().nth(2),
+            Some("0 0 SendPadding timeout=10 bypass=false replace=true")
+        );
+
+        let parsed: Fixture = fixture.to_string().parse().unwrap();
+        assert_eq!(parsed, fixture);
+        m.assert_fixture(&parsed);
+        // recording again is deterministic
+        assert_eq!(m.record_fixture(&log, 7).unwrap(), fixture);
+
+        assert!(Fixture::from_str("").is_err());
+        assert!(Fixture::from_str("seed 1\n").is_err());
+        assert!(Fixture::from_str("seed x\nlog Ag==\n").is_err());
+        assert!(Fixture::from_str("seed 1\nlog AQ==\n").is_err());
+        assert!(Fixture::from_str("seed 1\nlog Ag==\n").unwrap().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "fixture mismatch at action 0: expected \
+        `0 0 SendPadding timeout=10 bypass=false replace=true`, \
+        got `0 0 SendPadding timeout=20 bypass=false replace=true`")]
+    fn fixture_mismatch() {
+        let fixture = Machine::constant_rate(10)
+            .unwrap()
+            .record_fixture(&fixture_log(), 0)
+            .unwrap();
+        Machine::constant_rate(20).unwrap().assert_fixture(&fixture);
+    }
+}
@@ -14,6 +14,7 @@ use std::fmt;
 
 use self::action::Action;
 use self::counter::Counter;
+use self::dist::Dist;
 use self::event::Event;
 
 use enum_map::enum_map;
@@ -22,6 +23,73 @@ use enum_map::enum_map;
 #[derive(PartialEq, Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct Trans(pub usize, pub f32);
 
+impl Trans {
+    /// Create transitions with probabilities proportional to the given integer
+    /// weights, as pairs of state index and weight, e.g., `[(1, 1), (2, 2)]`
+    /// for transitioning to state 1 with probability 1/3 and to state 2 with
+    /// probability 2/3.
+    ///
+    /// Probabilities such as 1/3 are not representable as `f32`, and summing
+    /// their closest approximations may exceed 1.0, failing validation. The
+    /// probabilities are therefore rounded down, such that they sum to at most
+    /// 1.0, and the transitions are sorted by state index (as in
+    /// [`Machine::canonicalize()`]). Returns an error if there are no weights or
+    /// any weight is zero.
+    ///
+    /// Example:
+    /// ```
+    /// use maybenot::state::*;
+    /// use maybenot::event::*;
+    /// use enum_map::enum_map;
+    /// let state = State::new(enum_map! {
+    ///     Event::PaddingSent => Trans::weighted(&[(0, 1), (1, 1), (2, 1)]).unwrap(),
+    ///     _ => vec![],
+    /// });
+    /// assert!(state.validate(3).is_ok());
+    /// ```
+    pub fn weighted(weights: &[(usize, u32)]) -> Result<Vec<Trans>, Error> {
+        if weights.is_empty() {
+            Err(Error::Machine("no weights for transitions".to_string()))?;
+        }
+        if weights.iter().any(|(_, w)| *w == 0) {
+            Err(Error::Machine(
+                "found zero weight for transition".to_string(),
+            ))?;
+        }
+        Ok(normalize(
+            weights.iter().map(|(state, w)| (*state, *w as f64)),
+        ))
+    }
+}
+
+// transitions with probabilities proportional to positive weights, rounded
+// down to sum to at most 1.0, see Trans::weighted()
+fn normalize(weights: impl Iterator<Item = (usize, f64)> + Clone) -> Vec<Trans> {
+    let total: f64 = weights.clone().map(|(_, w)| w).sum();
+
+    let mut transitions: Vec<Trans> = weights
+        .map(|(state, w)| {
+            let exact = w / total;
+            let mut p = exact as f32;
+            if p as f64 > exact {
+                // next smaller f32, p is positive
+                p = f32::from_bits(p.to_bits() - 1);
+            }
+            Trans(state, p)
+        })
+        .collect();
+    transitions.sort_by_key(|t| t.0);
+
+    // rounding down each probability may not be enough for their sum,
+    // computed as when sampling, to be at most 1.0
+    while transitions.iter().map(|t| t.1).sum::<f32>() > 1.0 {
+        let last = transitions.last_mut().unwrap();
+        last.1 = f32::from_bits(last.1.to_bits() - 1);
+    }
+
+    transitions
+}
+
 impl fmt::Display for Trans {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         if self.1 == 1.0 {
@@ -32,6 +100,85 @@ impl fmt::Display for Trans {
     }
 }
 
+/// A condition on the time since the previous occurrence of an [`Event`] for
+/// a machine, see [`State::gap_condition`].
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct GapCondition {
+    /// The event whose transitions are conditioned on the gap.
+    pub event: Event,
+    /// The threshold in microseconds.
+    pub threshold: u64,
+    /// If true, the condition holds if the gap is below the threshold,
+    /// otherwise if it is at or above the threshold.
+    pub below: bool,
+}
+
+impl GapCondition {
+    /// Returns true if the condition holds for the gap since the previous
+    /// occurrence of the event, in microseconds, or None if the event has not
+    /// occurred before (an infinite gap).
+    pub fn holds(&self, gap: Option<u64>) -> bool {
+        match gap {
+            Some(gap) => (gap < self.threshold) == self.below,
+            None => !self.below,
+        }
+    }
+}
+
+impl fmt::Display for GapCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = if self.below { "<" } else { ">=" };
+        write!(f, "{} gap {} {}us", self.event, op, self.threshold)
+    }
+}
+
+/// A condition on the value of one of the two counters of a machine, see
+/// [`State::counter_condition`].
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct CounterCondition {
+    /// The counter of the condition: 0 for counter A, 1 for counter B, as in
+    /// [`State::counter`].
+    pub counter: usize,
+    /// The threshold to compare the value of the counter with.
+    pub threshold: u64,
+    /// If true, the condition holds if the counter is below the threshold,
+    /// otherwise if it is at or above the threshold.
+    pub below: bool,
+}
+
+impl CounterCondition {
+    /// Returns true if the condition holds for the given value of the
+    /// counter.
+    pub fn holds(&self, value: u64) -> bool {
+        (value < self.threshold) == self.below
+    }
+
+    /// Validate that the condition is on one of the two counters and can
+    /// hold.
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.counter > 1 {
+            Err(Error::Machine(format!(
+                "counter condition on counter {}, expected 0 (A) or 1 (B)",
+                self.counter
+            )))?;
+        }
+        if self.below && self.threshold == 0 {
+            Err(Error::Machine(
+                "counter condition below 0 never holds".to_string(),
+            ))?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for CounterCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = if self.below { "<" } else { ">=" };
+        let counter = if self.counter == 0 { "A" } else { "B" };
+        write!(f, "counter {} {} {}", counter, op, self.threshold)
+    }
+}
+
 /// A state as part of a [`Machine`].
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct State {
@@ -39,10 +186,105 @@ pub struct State {
     pub action: Option<Action>,
     /// On transition to this state, update the machine's two counters (A,B).
     pub counter: (Option<Counter>, Option<Counter>),
+    /// If set, the action of this state is also limited by the
+    /// [`Machine::shared_limits`] with the given index. The shared limit is
+    /// decremented when the action is taken, regardless of which state
+    /// referencing it took the action. Once the shared limit reaches zero, no
+    /// further actions are scheduled by states referencing it.
+    #[serde(default)]
+    pub shared_limit: Option<usize>,
+    /// If set, the action of this state is only scheduled if outgoing traffic
+    /// is blocked (true) or not blocked (false) at the time of transitioning
+    /// to this state. For [`Action::BlockOutgoing`](crate::action::Action::BlockOutgoing),
+    /// this is the blocking of the lane the action targets; for all other
+    /// actions, outgoing traffic is blocked if any lane is. The blocking status is updated before machines
+    /// transition on [`Event::BlockingBegin`] and [`Event::BlockingEnd`], so
+    /// transitions on these events are evaluated with the new status. Note
+    /// that an action already scheduled is not cancelled when the blocking
+    /// status changes: use transitions to a state with
+    /// [`Action::Cancel`](crate::action::Action::Cancel) for that.
+    #[serde(default)]
+    pub blocking_condition: Option<bool>,
+    /// If set, the action of this state is only scheduled if the counter of
+    /// the condition satisfies it at the time of transitioning to this state,
+    /// e.g., to pad only while a budget in a counter remains. The condition is
+    /// evaluated before the counters are updated by [`Self::counter`]. If it
+    /// does not hold, the machine still transitions to the state, and only the
+    /// action is suppressed.
+    #[serde(default)]
+    pub counter_condition: Option<CounterCondition>,
+    /// If set, the state only transitions on the event of the condition if the
+    /// time since the previous occurrence of the event for the machine (the
+    /// gap) satisfies the condition, e.g., to move to a burst state only on
+    /// two [`Event::NormalSent`] within a millisecond. The first occurrence
+    /// of an event has no previous occurrence, and only satisfies a condition
+    /// that is not below the threshold. If the condition does not hold, the
+    /// state does not transition on the event, also not by
+    /// [`Self::fallback`]. A running machine with a gap condition in any state
+    /// keeps the time of the last occurrence of each event as runtime state.
+    #[serde(default)]
+    pub gap_condition: Option<GapCondition>,
+    /// If set, the state transitions for any [`Event`] without transitions of
+    /// its own, e.g., to reset the machine on unexpected events. Transitions
+    /// for a specific event always take precedence, also if none of them is
+    /// sampled, so the fallback never shadows them.
+    #[serde(default)]
+    pub fallback: Option<Vec<Trans>>,
+    /// If set, the limit of the action of this state, instead of a limit
+    /// sampled from the limit distribution of the action on entering the
+    /// state. The action must have no limit distribution: a fixed limit is the
+    /// explicit form of a constant distribution, e.g., for deterministic
+    /// machines.
+    #[serde(default)]
+    pub fixed_limit: Option<u64>,
+    /// If set, a transition from this state to itself on the given [`Event`]
+    /// samples the limit of the action anew, as on entering the state, e.g.,
+    /// to reset a padding budget on each [`Event::NormalRecv`]. The limit is
+    /// refreshed before the action of the transition is scheduled, so taking
+    /// that action decrements the refreshed limit. A state without
+    /// transitions on [`Event::LimitReached`] stays in the state once its
+    /// limit is exhausted, and schedules actions again after the next refresh.
+    #[serde(default)]
+    pub refresh_limit_on: Option<Event>,
     /// For each possible [`Event`], a vector of state transitions.
+    #[serde(with = "transitions_by_event")]
     transitions: [Option<Vec<Trans>>; EVENT_NUM],
 }
 
+// The transitions of a state are encoded by event, for events with
+// transitions, such that machines encoded before an event was added still
+// decode.
+mod transitions_by_event {
+    use super::*;
+    use serde::ser::SerializeMap;
+    use serde::{Deserializer, Serializer};
+    use std::collections::HashMap;
+
+    pub fn serialize<S: Serializer>(
+        transitions: &[Option<Vec<Trans>>; EVENT_NUM],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(transitions.iter().flatten().count()))?;
+        for (event, t) in Event::iter().zip(transitions.iter()) {
+            if let Some(t) = t {
+                map.serialize_entry(event, t)?;
+            }
+        }
+        map.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<[Option<Vec<Trans>>; EVENT_NUM], D::Error> {
+        const ARRAY_NO_TRANS: Option<Vec<Trans>> = None;
+        let mut transitions = [ARRAY_NO_TRANS; EVENT_NUM];
+        for (event, t) in HashMap::<Event, Vec<Trans>>::deserialize(deserializer)? {
+            transitions[event.to_usize()] = Some(t);
+        }
+        Ok(transitions)
+    }
+}
+
 impl State {
     /// Create a new [`State`] that transitions on the given [`Event`]s.
     ///
@@ -73,11 +315,94 @@ impl State {
 
         State {
             transitions,
+            fallback: None,
             action: None,
             counter: (None, None),
+            shared_limit: None,
+            blocking_condition: None,
+            counter_condition: None,
+            gap_condition: None,
+            fixed_limit: None,
+            refresh_limit_on: None,
         }
     }
 
+    /// Create a new [`State`] that transitions on the given [`Event`]s (see
+    /// [`Self::new()`]), where the probabilities of the transitions of each
+    /// event are relative: they are normalized to sum to 1.0, e.g.,
+    /// `vec![Trans(0, 2.0), Trans(1, 1.0)]` transitions to state 0 with
+    /// probability 2/3 and to state 1 with probability 1/3. Probabilities are
+    /// rounded down and sorted by state index as by [`Trans::weighted()`].
+    /// Returns an error if any relative probability is not positive and
+    /// finite.
+    pub fn new_normalized(t: EnumMap<Event, Vec<Trans>>) -> Result<Self, Error> {
+        let mut normalized = enum_map! { _ => vec![] };
+        for (event, vector) in t {
+            if let Some(t) = vector.iter().find(|t| !(t.1 > 0.0 && t.1.is_finite())) {
+                Err(Error::Machine(format!(
+                    "found relative probability {} for {}, has to be positive and finite",
+                    t.1, event
+                )))?;
+            }
+            if !vector.is_empty() {
+                normalized[event] = normalize(vector.iter().map(|t| (t.0, t.1 as f64)));
+            }
+        }
+        Ok(Self::new(normalized))
+    }
+
+    /// Create a new [`State`] that transitions on the given [`Event`]s (see
+    /// [`Self::new()`]) and schedules padding after a timeout sampled from
+    /// `timeout`, up to a `limit` if set, with an [`Action::SendPadding`]
+    /// without the bypass and replace flags.
+    pub fn padding(t: EnumMap<Event, Vec<Trans>>, timeout: Dist, limit: Option<Dist>) -> Self {
+        let mut s = State::new(t);
+        s.action = Some(Action::SendPadding {
+            bypass: false,
+            replace: false,
+            timeout,
+            limit,
+        });
+        s
+    }
+
+    /// Create a new [`State`] that transitions on the given [`Event`]s (see
+    /// [`Self::new()`]) and schedules blocking of outgoing traffic after a
+    /// timeout sampled from `timeout`, for a duration sampled from `duration`,
+    /// up to a `limit` if set, with an [`Action::BlockOutgoing`] without the
+    /// bypass and replace flags.
+    pub fn blocking(
+        t: EnumMap<Event, Vec<Trans>>,
+        timeout: Dist,
+        duration: Dist,
+        limit: Option<Dist>,
+    ) -> Self {
+        let mut s = State::new(t);
+        s.action = Some(Action::BlockOutgoing {
+            bypass: false,
+            replace: false,
+            lane: 0,
+            timeout,
+            duration,
+            limit,
+        });
+        s
+    }
+
+    /// Create a new [`State`] that transitions on the given [`Event`]s (see
+    /// [`Self::new()`]) and sets the internal timer of the machine to a
+    /// duration sampled from `duration`, up to a `limit` if set, with an
+    /// [`Action::UpdateTimer`] without the replace flag.
+    pub fn timer(t: EnumMap<Event, Vec<Trans>>, duration: Dist, limit: Option<Dist>) -> Self {
+        let mut s = State::new(t);
+        s.action = Some(Action::UpdateTimer {
+            replace: false,
+            duration,
+            limit,
+        });
+        s
+    }
+
     /// Validate that this state has acceptable transitions and that the
     /// distributions, if set, are valid. Note that num_states is the number of
     /// states in the machine, not the number of states in this state's
@@ -85,48 +410,16 @@ impl State {
     pub fn validate(&self, num_states: usize) -> Result<(), Error> {
         // validate transition probabilities
         for (event, transitions) in self.transitions.iter().enumerate() {
-            let Some(transitions) = transitions else {
-                continue;
-            };
-            if self.transitions.is_empty() {
-                Err(Error::Machine(format!(
-                    "found empty transition vector for {}",
-                    &event
-                )))?;
-            }
-
-            let mut sum: f32 = 0.0;
-            let mut seen: HashSet<usize> = HashSet::new();
-
-            for t in transitions.iter() {
-                if t.0 >= num_states && t.0 != STATE_END && t.0 != STATE_SIGNAL {
-                    Err(Error::Machine(format!(
-                        "found out-of-bounds state index {}",
-                        t.0
-                    )))?;
-                }
-                if seen.contains(&t.0) {
-                    Err(Error::Machine(format!(
-                        "found duplicate state index {}",
-                        t.0
-                    )))?;
-                }
-                seen.insert(t.0);
-
-                if t.1 <= 0.0 || t.1 > 1.0 {
-                    Err(Error::Machine(format!(
-                        "found probability {}, has to be (0.0, 1.0]",
-                        t.1
-                    )))?;
-                }
-                sum += t.1;
+            if let Some(transitions) = transitions {
+                validate_transitions(transitions, num_states, event)?;
             }
-
-            if sum <= 0.0 || sum > 1.0 {
-                Err(Error::Machine(format!(
-                    "found invalid total probability vector {} for {}, must be (0.0, 1.0]",
-                    &sum, &event
-                )))?;
+        }
+        if let Some(fallback) = &self.fallback {
+            validate_transitions(fallback, num_states, "fallback")?;
+            if self.transitions.iter().all(|t| t.is_some()) {
+                Err(Error::Machine(
+                    "found fallback transitions, but all events have transitions".to_string(),
+                ))?;
             }
         }
 
@@ -135,6 +428,25 @@ impl State {
         if let Some(action) = &self.action {
             action.validate()?;
         }
+        if self.fixed_limit.is_some() {
+            match self.action {
+                None => Err(Error::Machine(
+                    "found fixed limit, but no action".to_string(),
+                ))?,
+                Some(action) if action.has_limit() => Err(Error::Machine(
+                    "found both a fixed limit and a limit distribution".to_string(),
+                ))?,
+                _ => {}
+            }
+        }
+        if let Some(condition) = &self.counter_condition {
+            condition.validate()?;
+        }
+        if self.refresh_limit_on.is_some() && !self.has_limit() {
+            Err(Error::Machine(
+                "found limit refresh event, but no limit".to_string(),
+            ))?;
+        }
         if let Some(counter) = &self.counter.0 {
             counter.validate()?;
         }
@@ -145,10 +457,26 @@ impl State {
         Ok(())
     }
 
+    /// Sample the limit of the action of this state: the fixed limit, if set,
+    /// or else sampled from the action (see [`Action::sample_limit()`]).
+    pub(crate) fn sample_limit<R: RngCore>(&self, rng: &mut R) -> (u64, bool) {
+        match (self.fixed_limit, self.action) {
+            (Some(limit), _) => (limit, false),
+            (None, Some(action)) => action.sample_limit(rng),
+            (None, None) => (STATE_LIMIT_MAX, false),
+        }
+    }
+
+    /// Returns true if the action of this state is limited, by a fixed limit
+    /// or a limit distribution.
+    pub(crate) fn has_limit(&self) -> bool {
+        self.fixed_limit.is_some() || self.action.is_some_and(|a| a.has_limit())
+    }
+
     /// Sample a state to transition to given an [`Event`].
     pub fn sample_state<R: RngCore>(&self, event: Event, rng: &mut R) -> Option<usize> {
         use rand::Rng;
-        if let Some(vector) = &self.transitions[event.to_usize()] {
+        if let Some(vector) = self.transitions_on(event) {
             let mut sum = 0.0;
             let r = rng.gen_range(0.0..1.0);
             for t in vector.iter() {
@@ -161,6 +489,49 @@ impl State {
         None
     }
 
+    /// The transitions taken on an [`Event`]: those of the event, or the
+    /// fallback if the event has none.
+    pub(crate) fn transitions_on(&self, event: Event) -> Option<&Vec<Trans>> {
+        self.transitions[event.to_usize()]
+            .as_ref()
+            .or(self.fallback.as_ref())
+    }
+
+    /// Sort the transitions for each [`Event`] (and the fallback) by state
+    /// index, and remove any empty transition vectors. This does not change
+    /// the probability of transitioning to any state.
+    pub(crate) fn canonicalize(&mut self) {
+        for transitions in self
+            .transitions
+            .iter_mut()
+            .chain(std::iter::once(&mut self.fallback))
+        {
+            if let Some(vector) = transitions {
+                if vector.is_empty() {
+                    *transitions = None;
+                } else {
+                    vector.sort_by_key(|t| t.0);
+                }
+            }
+        }
+    }
+
+    /// Renumber the states that this state transitions to (including by the
+    /// fallback), from state `i` to state `index[i]`. Pseudo-states are not
+    /// renumbered.
+    pub(crate) fn renumber(&mut self, index: &[usize]) {
+        for vector in self
+            .transitions
+            .iter_mut()
+            .chain(std::iter::once(&mut self.fallback))
+            .flatten()
+        {
+            for t in vector.iter_mut().filter(|t| t.0 < index.len()) {
+                t.0 = index[t.0];
+            }
+        }
+    }
+
     /// Get the transitions for this state as an [`EnumMap`] of [`Event`] to
     /// vectors of [`Trans`].
     pub fn get_transitions(&self) -> EnumMap<Event, Vec<Trans>> {
@@ -201,6 +572,22 @@ impl fmt::Display for State {
             }
         };
 
+        if let Some(index) = self.shared_limit {
+            writeln!(f, "shared limit: {}", index)?;
+        }
+        if let Some(blocked) = self.blocking_condition {
+            writeln!(f, "blocking condition: {}", blocked)?;
+        }
+        if let Some(condition) = self.counter_condition {
+            writeln!(f, "counter condition: {}", condition)?;
+        }
+        if let Some(gap) = self.gap_condition {
+            writeln!(f, "gap condition: {}", gap)?;
+        }
+        if let Some(event) = self.refresh_limit_on {
+            writeln!(f, "refresh limit on: {}", event)?;
+        }
+
         writeln!(f, "transitions: ")?;
         for event in Event::iter() {
             if let Some(vector) = &self.transitions[event.to_usize()] {
@@ -217,11 +604,177 @@ impl fmt::Display for State {
                 writeln!(f)?;
             }
         }
+        if let Some(vector) = &self.fallback {
+            let vector: Vec<String> = vector.iter().map(|t| t.to_string()).collect();
+            writeln!(f, "\tfallback: {}", vector.join(", "))?;
+        }
 
         Ok(())
     }
 }
 
+// validates a vector of transitions, of the given event or fallback
+fn validate_transitions(
+    transitions: &[Trans],
+    num_states: usize,
+    event: impl fmt::Display,
+) -> Result<(), Error> {
+    if transitions.is_empty() {
+        Err(Error::Machine(format!(
+            "found empty transition vector for {}",
+            &event
+        )))?;
+    }
+
+    let mut sum: f32 = 0.0;
+    let mut seen: HashSet<usize> = HashSet::new();
+
+    for t in transitions.iter() {
+        if t.0 >= num_states
+            && !matches!(
+                t.0,
+                STATE_END | STATE_SIGNAL | STATE_PREVIOUS | STATE_RANDOM
+            )
+        {
+            Err(Error::Machine(format!(
+                "found out-of-bounds state index {}",
+                t.0
+            )))?;
+        }
+        if seen.contains(&t.0) {
+            Err(Error::Machine(format!(
+                "found duplicate state index {}",
+                t.0
+            )))?;
+        }
+        seen.insert(t.0);
+
+        if t.1 <= 0.0 || t.1 > 1.0 {
+            Err(Error::Machine(format!(
+                "found probability {}, has to be (0.0, 1.0]",
+                t.1
+            )))?;
+        }
+        sum += t.1;
+    }
+
+    if sum <= 0.0 || sum > 1.0 {
+        Err(Error::Machine(format!(
+            "found invalid total probability vector {} for {}, must be (0.0, 1.0]",
+            &sum, &event
+        )))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+impl proptest::arbitrary::Arbitrary for State {
+    /// The number of states in the machine.
+    type Parameters = usize;
+    type Strategy = proptest::strategy::BoxedStrategy<Self>;
+
+    /// Arbitrary states with transitions to any of the states in the
+    /// machine, including the pseudo-states.
+    fn arbitrary_with(num_states: Self::Parameters) -> Self::Strategy {
+        use crate::counter::Operation;
+        use crate::dist::Dist;
+        use proptest::collection::vec;
+        use proptest::prelude::*;
+
+        let vector = vec((0..num_states + 4, 0.0f32..1.0), 0..3).prop_map(move |v| {
+            let sum: f32 = v.iter().map(|(_, p)| p).sum();
+            v.into_iter()
+                .map(|(s, p)| {
+                    let s = match s {
+                        s if s < num_states => s,
+                        s if s == num_states => STATE_END,
+                        s if s == num_states + 1 => STATE_SIGNAL,
+                        s if s == num_states + 2 => STATE_PREVIOUS,
+                        _ => STATE_RANDOM,
+                    };
+                    Trans(s, if sum > 1.0 { p / sum } else { p })
+                })
+                .collect::<Vec<_>>()
+        });
+        let trans = vec(vector.clone(), EVENT_NUM);
+        let fallback =
+            proptest::option::weighted(0.1, vector.prop_filter("empty", |v| !v.is_empty()));
+        let gap = proptest::option::weighted(
+            0.1,
+            (0..EVENT_NUM, 0..10_000u64, any::<bool>()).prop_map(|(event, threshold, below)| {
+                GapCondition {
+                    event: Event::from_usize(event),
+                    threshold,
+                    below,
+                }
+            }),
+        );
+        let condition = proptest::option::weighted(
+            0.1,
+            (0..2usize, 1..10u64, any::<bool>()).prop_map(|(counter, threshold, below)| {
+                CounterCondition {
+                    counter,
+                    threshold,
+                    below,
+                }
+            }),
+        );
+        let refresh = proptest::option::weighted(0.1, (0..EVENT_NUM).prop_map(Event::from_usize));
+        let operation = prop_oneof![
+            Just(Operation::Increment),
+            Just(Operation::Decrement),
+            Just(Operation::Set),
+        ];
+        let counter = proptest::option::of(prop_oneof![
+            operation.clone().prop_map(Counter::new),
+            operation.clone().prop_map(Counter::new_copy),
+            (operation, any::<Dist>()).prop_map(|(op, dist)| Counter::new_dist(op, dist)),
+        ]);
+
+        (
+            trans,
+            any::<Option<Action>>(),
+            counter.clone(),
+            counter,
+            any::<Option<bool>>(),
+            condition,
+            fallback,
+            gap,
+            refresh,
+        )
+            .prop_map(
+                |(
+                    trans,
+                    action,
+                    counter_a,
+                    counter_b,
+                    blocking,
+                    condition,
+                    fallback,
+                    gap,
+                    refresh,
+                )| {
+                    let mut map: EnumMap<Event, Vec<Trans>> = EnumMap::default();
+                    for (i, t) in trans.into_iter().enumerate() {
+                        map[Event::from_usize(i)] = t;
+                    }
+                    let mut s = State::new(map);
+                    s.action = action;
+                    s.counter = (counter_a, counter_b);
+                    s.blocking_condition = blocking;
+                    s.counter_condition = condition;
+                    s.fallback = fallback;
+                    s.gap_condition = gap;
+                    // only valid for a limited action
+                    s.refresh_limit_on = refresh.filter(|_| s.has_limit());
+                    s
+                },
+            )
+            .boxed()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::counter::{Counter, Operation};
@@ -295,6 +848,131 @@ mod tests {
         });
         let r = s.validate(num_states);
         assert!(r.is_ok());
+
+        // as should the other pseudo-states
+        let s = State::new(enum_map! {
+                 Event::PaddingSent => vec![Trans(STATE_PREVIOUS, 0.4), Trans(STATE_RANDOM, 0.3)],
+                 Event::NormalSent => vec![Trans(STATE_SIGNAL, 1.0)],
+             _ => vec![],
+        });
+        let r = s.validate(num_states);
+        assert!(r.is_ok());
+    }
+
+    #[test]
+    fn validate_state_fixed_limit() {
+        let padding = |limit| {
+            State::padding(
+                enum_map! {
+                    Event::PaddingSent => vec![Trans(0, 1.0)],
+                    _ => vec![],
+                },
+                Dist::new(
+                    DistType::Uniform {
+                        low: 1.0,
+                        high: 1.0,
+                    },
+                    0.0,
+                    0.0,
+                ),
+                limit,
+            )
+        };
+
+        let mut s = padding(None);
+        s.fixed_limit = Some(3);
+        assert!(s.validate(1).is_ok());
+        assert_eq!(s.sample_limit(&mut rand::thread_rng()), (3, false));
+        assert!(s.has_limit());
+
+        // not along with a limit distribution
+        let mut s = padding(Some(Dist::new(
+            DistType::Uniform {
+                low: 3.0,
+                high: 3.0,
+            },
+            0.0,
+            0.0,
+        )));
+        assert!(s.has_limit());
+        s.fixed_limit = Some(3);
+        assert_eq!(
+            s.validate(1).unwrap_err().to_string(),
+            "invalid machine: found both a fixed limit and a limit distribution"
+        );
+
+        // nor without an action to limit
+        let mut s = State::new(enum_map! {
+            _ => vec![],
+        });
+        assert!(!s.has_limit());
+        assert_eq!(
+            s.sample_limit(&mut rand::thread_rng()),
+            (STATE_LIMIT_MAX, false)
+        );
+        s.fixed_limit = Some(3);
+        assert!(s.validate(1).is_err());
+    }
+
+    #[test]
+    fn validate_state_counter_condition() {
+        let mut s = State::new(enum_map! {
+            _ => vec![],
+        });
+        let condition = |counter, threshold, below| {
+            Some(CounterCondition {
+                counter,
+                threshold,
+                below,
+            })
+        };
+
+        s.counter_condition = condition(1, 0, false);
+        assert!(s.validate(1).is_ok());
+        assert_eq!(s.counter_condition.unwrap().to_string(), "counter B >= 0");
+        s.counter_condition = condition(0, 5, true);
+        assert!(s.validate(1).is_ok());
+        assert!(s.counter_condition.unwrap().holds(4));
+        assert!(!s.counter_condition.unwrap().holds(5));
+
+        // only two counters
+        s.counter_condition = condition(2, 5, true);
+        assert_eq!(
+            s.validate(1).unwrap_err().to_string(),
+            "invalid machine: counter condition on counter 2, expected 0 (A) or 1 (B)"
+        );
+        // and a counter is never below 0
+        s.counter_condition = condition(0, 0, true);
+        assert_eq!(
+            s.validate(1).unwrap_err().to_string(),
+            "invalid machine: counter condition below 0 never holds"
+        );
+    }
+
+    #[test]
+    fn validate_state_refresh_limit() {
+        let mut s = State::padding(
+            enum_map! {
+                Event::NormalRecv => vec![Trans(0, 1.0)],
+                _ => vec![],
+            },
+            Dist::new(
+                DistType::Uniform {
+                    low: 1.0,
+                    high: 1.0,
+                },
+                0.0,
+                0.0,
+            ),
+            None,
+        );
+        s.refresh_limit_on = Some(Event::NormalRecv);
+        assert_eq!(
+            s.validate(1).unwrap_err().to_string(),
+            "invalid machine: found limit refresh event, but no limit"
+        );
+        s.fixed_limit = Some(3);
+        assert!(s.validate(1).is_ok());
     }
 
     #[test]
@@ -317,6 +995,7 @@ mod tests {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             },
             limit: None,
         });
@@ -336,6 +1015,7 @@ mod tests {
                 },
                 start: 0.0,
                 max: 0.0,
+                ..Default::default()
             },
             limit: None,
         });
@@ -373,6 +1053,7 @@ mod tests {
                     },
                     start: 0.0,
                     max: 0.0,
+                    ..Default::default()
                 },
             )),
         );
@@ -381,4 +1062,235 @@ mod tests {
         println!("{:?}", r.as_ref().err());
         assert!(r.is_err());
     }
+
+    #[test]
+    fn sample_state_boundaries() {
+        // an RNG that makes gen_range(0.0..1.0) for f32 return exactly the
+        // given value, a multiple of 2^-23
+        struct Fixed(f32);
+        impl RngCore for Fixed {
+            fn next_u32(&mut self) -> u32 {
+                ((self.0 * (1 << 23) as f32) as u32) << 9
+            }
+            fn next_u64(&mut self) -> u64 {
+                rand_core::impls::next_u64_via_u32(self)
+            }
+            fn fill_bytes(&mut self, dest: &mut [u8]) {
+                rand_core::impls::fill_bytes_via_next(self, dest)
+            }
+            fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+                self.fill_bytes(dest);
+                Ok(())
+            }
+        }
+        let below = |p: f32| p - 1.0 / (1 << 23) as f32;
+
+        // the pseudo-states are explicit targets, routed like any other
+        let s = State::new(enum_map! {
+            Event::NormalSent => vec![
+                Trans(STATE_END, 0.25),
+                Trans(STATE_SIGNAL, 0.25),
+                Trans(1, 0.5),
+            ],
+            Event::PaddingSent => vec![Trans(STATE_SIGNAL, 0.25), Trans(STATE_END, 0.5)],
+            _ => vec![],
+        });
+        let sample = |event, r| s.sample_state(event, &mut Fixed(r));
+
+        assert_eq!(sample(Event::NormalSent, 0.0), Some(STATE_END));
+        assert_eq!(sample(Event::NormalSent, below(0.25)), Some(STATE_END));
+        assert_eq!(sample(Event::NormalSent, 0.25), Some(STATE_SIGNAL));
+        assert_eq!(sample(Event::NormalSent, below(0.5)), Some(STATE_SIGNAL));
+        assert_eq!(sample(Event::NormalSent, 0.5), Some(1));
+        assert_eq!(sample(Event::NormalSent, below(1.0)), Some(1));
+
+        // with a total probability below 1.0, the remainder is no transition
+        assert_eq!(sample(Event::PaddingSent, below(0.25)), Some(STATE_SIGNAL));
+        assert_eq!(sample(Event::PaddingSent, 0.25), Some(STATE_END));
+        assert_eq!(sample(Event::PaddingSent, below(0.75)), Some(STATE_END));
+        assert_eq!(sample(Event::PaddingSent, 0.75), None);
+        assert_eq!(sample(Event::PaddingSent, below(1.0)), None);
+        assert_eq!(sample(Event::BlockingBegin, 0.0), None);
+    }
+
+    #[test]
+    fn weighted_transitions() {
+        // some sevenths and tenths as f32 add up to more than 1.0
+        let sevenths = vec![
+            Trans(0, 2.0 / 7.0),
+            Trans(1, 4.0 / 7.0),
+            Trans(2, 1.0 / 7.0),
+        ];
+        let tenths = (0..10).map(|i| Trans(i, 0.1)).collect::<Vec<_>>();
+        for t in [sevenths, tenths] {
+            let s = State::new(enum_map! {
+                Event::PaddingSent => t.clone(),
+                _ => vec![],
+            });
+            assert!(s.validate(10).is_err());
+        }
+
+        // but not as weights
+        for weights in [
+            vec![(0, 2), (1, 4), (2, 1)],
+            (0..10).map(|i| (i, 1)).collect(),
+            vec![(0, 1), (1, 1), (2, 1)],
+            vec![(2, 3), (0, 3), (1, 1)],
+            vec![(STATE_END, 2), (1, 5), (STATE_SIGNAL, 7)],
+        ] {
+            let t = Trans::weighted(&weights).unwrap();
+            assert_eq!(t.len(), weights.len());
+            assert!(t.windows(2).all(|w| w[0].0 < w[1].0));
+            let sum: f32 = t.iter().map(|t| t.1).sum();
+            assert!(sum <= 1.0 && sum > 1.0 - 1e-6);
+            let s = State::new(enum_map! {
+                Event::PaddingSent => t.clone(),
+                _ => vec![],
+            });
+            s.validate(10).unwrap();
+        }
+
+        // proportional to the weights
+        let t = Trans::weighted(&[(1, 1), (0, 3)]).unwrap();
+        assert_eq!(t, vec![Trans(0, 0.75), Trans(1, 0.25)]);
+
+        // invalid weights
+        assert!(Trans::weighted(&[]).is_err());
+        assert!(Trans::weighted(&[(0, 1), (1, 0)]).is_err());
+    }
+
+    #[test]
+    fn new_normalized_state() {
+        let s = State::new_normalized(enum_map! {
+            Event::PaddingSent => vec![Trans(0, 2.0), Trans(1, 1.0)],
+            Event::NormalSent => vec![Trans(1, 0.25)],
+            Event::BlockingEnd => vec![Trans(1, 1.0), Trans(0, 3.0)],
+            Event::Idle => (0..10).map(|i| Trans(i, 0.1)).collect(),
+            _ => vec![],
+        })
+        .unwrap();
+        s.validate(10).unwrap();
+
+        let t = s.get_transitions();
+        // rounded down, to sum to at most 1.0
+        let thirds = &t[Event::PaddingSent];
+        assert_eq!((thirds[0].0, thirds[1].0), (0, 1));
+        assert!((thirds[0].1 - 2.0 / 3.0).abs() < 1e-6);
+        assert!((thirds[1].1 - 1.0 / 3.0).abs() < 1e-6);
+        assert!(thirds[0].1 + thirds[1].1 <= 1.0);
+        assert_eq!(t[Event::NormalSent], vec![Trans(1, 1.0)]);
+        assert_eq!(t[Event::BlockingEnd], vec![Trans(0, 0.75), Trans(1, 0.25)]);
+        assert!(t[Event::NormalRecv].is_empty());
+        let sum: f32 = t[Event::Idle].iter().map(|t| t.1).sum();
+        assert!(sum <= 1.0 && sum > 1.0 - 1e-6);
+
+        // the strict constructor keeps the probabilities as they are
+        let s = State::new(enum_map! {
+            Event::PaddingSent => vec![Trans(0, 2.0), Trans(1, 1.0)],
+            _ => vec![],
+        });
+        assert!(s.validate(2).is_err());
+
+        // invalid relative probabilities
+        for p in [0.0, -1.0, f32::NAN, f32::INFINITY] {
+            let r = State::new_normalized(enum_map! {
+                Event::PaddingSent => vec![Trans(0, 1.0), Trans(1, p)],
+                _ => vec![],
+            });
+            assert!(r.is_err());
+        }
+    }
+
+    #[test]
+    fn state_action_helpers() {
+        let t = || {
+            enum_map! {
+                Event::PaddingSent => vec![Trans(0, 1.0)],
+                _ => vec![],
+            }
+        };
+        let d = |v: f64| Dist {
+            dist: DistType::Uniform { low: v, high: v },
+            start: 0.0,
+            max: 0.0,
+            ..Default::default()
+        };
+
+        let s = State::padding(t(), d(1.0), Some(d(2.0)));
+        assert_eq!(
+            s.action,
+            Some(Action::SendPadding {
+                bypass: false,
+                replace: false,
+                timeout: d(1.0),
+                limit: Some(d(2.0)),
+            })
+        );
+        assert_eq!(s.get_transitions(), t());
+        s.validate(1).unwrap();
+
+        let s = State::blocking(t(), d(1.0), d(3.0), None);
+        assert_eq!(
+            s.action,
+            Some(Action::BlockOutgoing {
+                bypass: false,
+                replace: false,
+                lane: 0,
+                timeout: d(1.0),
+                duration: d(3.0),
+                limit: None,
+            })
+        );
+        assert_eq!(s.get_transitions(), t());
+        s.validate(1).unwrap();
+
+        let s = State::timer(t(), d(4.0), Some(d(5.0)));
+        assert_eq!(
+            s.action,
+            Some(Action::UpdateTimer {
+                replace: false,
+                duration: d(4.0),
+                limit: Some(d(5.0)),
+            })
+        );
+        assert_eq!(s.get_transitions(), t());
+        s.validate(1).unwrap();
+
+        // the actions are validated like any other
+        assert!(State::padding(t(), d(f64::NAN), None).validate(1).is_err());
+    }
+
+    #[test]
+    fn fallback_transitions() {
+        let num_states = 3;
+        let mut s = State::new(enum_map! {
+            Event::NormalSent => vec![Trans(1, 0.5)],
+            _ => vec![],
+        });
+        s.fallback = Some(vec![Trans(2, 1.0)]);
+        assert!(s.validate(num_states).is_ok());
+
+        // the fallback applies only to events without transitions of their own
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            assert_ne!(s.sample_state(Event::NormalSent, &mut rng), Some(2));
+            assert_eq!(s.sample_state(Event::PaddingRecv, &mut rng), Some(2));
+            assert_eq!(s.sample_state(Event::Idle, &mut rng), Some(2));
+        }
+
+        // the fallback is validated like any transitions
+        s.fallback = Some(vec![]);
+        assert!(s.validate(num_states).is_err());
+        s.fallback = Some(vec![Trans(3, 1.0)]);
+        assert!(s.validate(num_states).is_err());
+        s.fallback = Some(vec![Trans(1, 0.6), Trans(2, 0.6)]);
+        assert!(s.validate(num_states).is_err());
+
+        // a fallback is pointless if all events have transitions
+        let mut s = State::new(enum_map! {
+            _ => vec![Trans(0, 1.0)],
+        });
+        s.fallback = Some(vec![Trans(2, 1.0)]);
+        assert!(s.validate(num_states).is_err());
+    }
 }
@@ -0,0 +1,190 @@
+//! A thread-safe wrapper of the [`Framework`], for integrations that trigger
+//! events from multiple threads.
+
+use rand_core::RngCore;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use crate::*;
+
+/// A [`Framework`] behind a [`Mutex`], that can be shared between threads
+/// (e.g., in an `Arc`) to trigger events concurrently.
+///
+/// Each call locks the framework for its duration, so calls are serialized:
+/// the framework processes the events of one call at a time, exactly as if
+/// the calls were made in that order from a single thread. Since the per-call
+/// work of the framework is small, the lock is held briefly. Unlike
+/// [`Framework::trigger_events()`], actions are returned as an owned
+/// [`Vec`], such that the lock is released before the caller takes the
+/// actions. This allocates on the heap for each call with actions.
+///
+/// The wrapper is [`Send`] and [`Sync`] if the machines, random number
+/// generator, and time type are [`Send`]. Note that `current_time` taken
+/// before a call may arrive out of order across threads: an earlier time than
+/// that of a previous call is ignored by the framework, see
+/// [`Framework::clock_regressions()`].
+///
+/// A panic while the lock is held does not poison the wrapper: the framework
+/// never panics on events from misbehaving integrations, so the framework is
+/// used as is by later calls.
+pub struct SyncFramework<M, R, T = std::time::Instant>
+where
+    T: crate::time::Instant,
+{
+    framework: Mutex<Framework<M, R, T>>,
+}
+
+impl<M, R, T> SyncFramework<M, R, T>
+where
+    M: AsRef<[Machine]>,
+    R: RngCore,
+    T: crate::time::Instant,
+{
+    /// Create a new framework instance behind a lock, see
+    /// [`Framework::new()`].
+    pub fn new(
+        machines: M,
+        max_padding_frac: f64,
+        max_blocking_frac: f64,
+        current_time: T,
+        rng: R,
+    ) -> Result<Self, Error> {
+        Ok(Framework::new(
+            machines,
+            max_padding_frac,
+            max_blocking_frac,
+            current_time,
+            rng,
+        )?
+        .into())
+    }
+
+    /// Trigger zero or more [`TriggerEvent`] for all machines running in the
+    /// framework, see [`Framework::trigger_events()`]. Returns the zero or
+    /// more [`TriggerAction`] that MUST be taken by the caller.
+    pub fn trigger_events(
+        &self,
+        events: &[TriggerEvent],
+        current_time: T,
+    ) -> Vec<TriggerAction<T>> {
+        self.lock()
+            .trigger_events(events, current_time)
+            .cloned()
+            .collect()
+    }
+
+    /// Start the machines in the framework, see [`Framework::start()`].
+    /// Returns the zero or more [`TriggerAction`] that MUST be taken by the
+    /// caller.
+    pub fn start(&self, current_time: T) -> Vec<TriggerAction<T>> {
+        self.lock().start(current_time).cloned().collect()
+    }
+
+    /// Lock the framework, e.g., to use any other method of the
+    /// [`Framework`]. Other threads block on the lock until the returned
+    /// guard is dropped.
+    pub fn lock(&self) -> MutexGuard<'_, Framework<M, R, T>> {
+        self.framework
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Consume the wrapper, returning the framework.
+    pub fn into_inner(self) -> Framework<M, R, T> {
+        self.framework
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<M, R, T> From<Framework<M, R, T>> for SyncFramework<M, R, T>
+where
+    T: crate::time::Instant,
+{
+    fn from(framework: Framework<M, R, T>) -> Self {
+        Self {
+            framework: Mutex::new(framework),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::action::Action;
+    use crate::dist::{Dist, DistType};
+    use crate::event::Event;
+    use crate::state::{State, Trans};
+    use crate::sync::*;
+    use enum_map::enum_map;
+    use rand_core::SeedableRng;
+    use rand_xoshiro::Xoshiro256StarStar;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn sync_framework_threads() {
+        // pad once per normal packet sent
+        let mut s1 = State::new(enum_map! {
+            Event::NormalSent => vec![Trans(1, 1.0)],
+            _ => vec![],
+        });
+        s1.action = Some(Action::SendPadding {
+            bypass: false,
+            replace: false,
+            timeout: Dist {
+                dist: DistType::Uniform {
+                    low: 1.0,
+                    high: 1.0,
+                },
+                start: 0.0,
+                max: 0.0,
+                ..Default::default()
+            },
+            limit: None,
+        });
+        let s0 = State::new(enum_map! {
+            Event::NormalSent => vec![Trans(1, 1.0)],
+            _ => vec![],
+        });
+        let machines = vec![Machine::new(0, 0.0, 0, 0.0, vec![s0, s1]).unwrap()];
+
+        let start = Instant::now();
+        let f = Arc::new(
+            SyncFramework::new(
+                machines,
+                0.0,
+                0.0,
+                start,
+                Xoshiro256StarStar::seed_from_u64(0),
+            )
+            .unwrap(),
+        );
+        let threads: Vec<_> = (0..4)
+            .map(|i| {
+                let f = Arc::clone(&f);
+                thread::spawn(move || {
+                    for j in 0..100 {
+                        let now = start + Duration::from_micros(i * 100 + j);
+                        let actions = f.trigger_events(&[TriggerEvent::NormalSent], now);
+                        assert_eq!(
+                            actions,
+                            vec![TriggerAction::SendPadding {
+                                timeout: Duration::from_micros(1),
+                                bypass: false,
+                                replace: false,
+                                machine: MachineId::from_raw(0),
+                            }]
+                        );
+                    }
+                })
+            })
+            .collect();
+        for t in threads {
+            t.join().unwrap();
+        }
+
+        // the latest time of any thread is the time of the framework
+        let f = Arc::into_inner(f).unwrap().into_inner();
+        assert_eq!(f.now(), start + Duration::from_micros(399));
+    }
+}
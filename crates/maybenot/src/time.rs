@@ -11,6 +11,17 @@ pub trait Instant: Clone + Copy {
     ///
     /// Should return a zero duration if `earlier` is later than `self`
     fn saturating_duration_since(&self, earlier: Self) -> Self::Duration;
+
+    /// Returns the instant the given duration before this one, or None if it
+    /// cannot be represented.
+    ///
+    /// Used to restore snapshots of a framework (see
+    /// [`Framework::restore()`](crate::Framework::restore)). The default only
+    /// subtracts zero durations, returning None otherwise, so restoring
+    /// snapshots with earlier times fails unless this is implemented.
+    fn checked_sub(&self, duration: Self::Duration) -> Option<Self> {
+        duration.is_zero().then_some(*self)
+    }
 }
 
 pub trait Duration: Clone + Copy + AddAssign + PartialOrd {
@@ -34,6 +45,11 @@ impl Instant for std::time::Instant {
     fn saturating_duration_since(&self, earlier: Self) -> Self::Duration {
         self.saturating_duration_since(earlier)
     }
+
+    #[inline(always)]
+    fn checked_sub(&self, duration: Self::Duration) -> Option<Self> {
+        self.checked_sub(duration)
+    }
 }
 
 impl Duration for std::time::Duration {
@@ -59,3 +75,27 @@ impl Duration for std::time::Duration {
         self.as_secs_f64() / rhs.as_secs_f64()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // an instant in microseconds, relying on the default checked_sub()
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct Micros(u64);
+
+    impl Instant for Micros {
+        type Duration = std::time::Duration;
+
+        fn saturating_duration_since(&self, earlier: Self) -> Self::Duration {
+            std::time::Duration::from_micros(self.0.saturating_sub(earlier.0))
+        }
+    }
+
+    #[test]
+    fn default_checked_sub() {
+        let now = Micros(10);
+        assert_eq!(now.checked_sub(std::time::Duration::ZERO), Some(now));
+        assert_eq!(now.checked_sub(std::time::Duration::from_micros(1)), None);
+    }
+}
@@ -0,0 +1,167 @@
+//! Decoding of machines serialized with version 2 of the format, before
+//! machines were encoded with the names of their fields. Version 2 machines
+//! are bincoded without any tags, so the types of version 2 are kept here as
+//! they were, and converted to the current types with the defaults of all
+//! fields added since.
+
+use bincode::Options;
+use serde::Deserialize;
+
+use crate::*;
+
+use self::action::{Action, Timer};
+use self::constants::MAX_DECOMPRESSED_SIZE;
+use self::counter::{Counter, Operation};
+use self::dist::{Dist, DistType};
+use self::event::Event;
+use self::state::{State, Trans};
+
+// The number of events in version 2, in the order of Event.
+const V2_EVENT_NUM: usize = 13;
+
+#[derive(Deserialize)]
+struct V2Machine {
+    allowed_padding_packets: u64,
+    max_padding_frac: f64,
+    allowed_blocked_microsec: u64,
+    max_blocking_frac: f64,
+    states: Vec<V2State>,
+}
+
+#[derive(Deserialize)]
+struct V2State {
+    action: Option<V2Action>,
+    counter: (Option<V2Counter>, Option<V2Counter>),
+    transitions: [Option<Vec<Trans>>; V2_EVENT_NUM],
+}
+
+// the timers of version 2 are the first variants of Timer
+#[derive(Deserialize)]
+enum V2Action {
+    Cancel {
+        timer: Timer,
+    },
+    SendPadding {
+        bypass: bool,
+        replace: bool,
+        timeout: V2Dist,
+        limit: Option<V2Dist>,
+    },
+    BlockOutgoing {
+        bypass: bool,
+        replace: bool,
+        timeout: V2Dist,
+        duration: V2Dist,
+        limit: Option<V2Dist>,
+    },
+    UpdateTimer {
+        replace: bool,
+        duration: V2Dist,
+        limit: Option<V2Dist>,
+    },
+}
+
+#[derive(Deserialize)]
+struct V2Counter {
+    operation: Operation,
+    dist: Option<V2Dist>,
+    copy: bool,
+}
+
+// the distribution types of version 2 are the first variants of DistType
+#[derive(Deserialize, Clone, Copy)]
+struct V2Dist {
+    dist: DistType,
+    start: f64,
+    max: f64,
+}
+
+impl From<V2Dist> for Dist {
+    fn from(d: V2Dist) -> Self {
+        Dist::new(d.dist, d.start, d.max)
+    }
+}
+
+impl From<V2Counter> for Counter {
+    fn from(c: V2Counter) -> Self {
+        Counter {
+            operation: c.operation,
+            dist: c.dist.map(Dist::from),
+            copy: c.copy,
+        }
+    }
+}
+
+impl From<V2Action> for Action {
+    fn from(a: V2Action) -> Self {
+        match a {
+            V2Action::Cancel { timer } => Action::Cancel { timer },
+            V2Action::SendPadding {
+                bypass,
+                replace,
+                timeout,
+                limit,
+            } => Action::SendPadding {
+                bypass,
+                replace,
+                timeout: timeout.into(),
+                limit: limit.map(Dist::from),
+            },
+            V2Action::BlockOutgoing {
+                bypass,
+                replace,
+                timeout,
+                duration,
+                limit,
+            } => Action::BlockOutgoing {
+                bypass,
+                replace,
+                lane: 0,
+                timeout: timeout.into(),
+                duration: duration.into(),
+                limit: limit.map(Dist::from),
+            },
+            V2Action::UpdateTimer {
+                replace,
+                duration,
+                limit,
+            } => Action::UpdateTimer {
+                replace,
+                duration: duration.into(),
+                limit: limit.map(Dist::from),
+            },
+        }
+    }
+}
+
+impl From<V2State> for State {
+    fn from(s: V2State) -> Self {
+        let mut transitions = enum_map::enum_map! { _ => vec![] };
+        for (event, t) in Event::iter().zip(s.transitions) {
+            transitions[*event] = t.unwrap_or_default();
+        }
+        let mut state = State::new(transitions);
+        state.action = s.action.map(Action::from);
+        state.counter = (
+            s.counter.0.map(Counter::from),
+            s.counter.1.map(Counter::from),
+        );
+        state
+    }
+}
+
+/// Decode a decompressed version 2 machine. The machine is not validated.
+pub(crate) fn decode(buf: &[u8]) -> Result<Machine, Error> {
+    let bincoder = bincode::DefaultOptions::new().with_limit(MAX_DECOMPRESSED_SIZE as u64);
+    let m: V2Machine = bincoder
+        .deserialize(buf)
+        .map_err(|e| Error::Machine(format!("invalid version 2 machine: {}", e)))?;
+
+    let mut machine = Machine::noop();
+    machine.allowed_padding_packets = m.allowed_padding_packets;
+    machine.max_padding_frac = m.max_padding_frac;
+    machine.allowed_blocked_microsec = m.allowed_blocked_microsec;
+    machine.max_blocking_frac = m.max_blocking_frac;
+    machine.states = m.states.into_iter().map(State::from).collect();
+    Ok(machine)
+}